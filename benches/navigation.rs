@@ -0,0 +1,41 @@
+//! Navegación por un menú grande: `cargo bench --bench navigation`.
+//!
+//! Cada iteración entra tres niveles (con 5000 ítems por nivel) y vuelve a la
+//! raíz, que es lo que paga el usuario al recorrer un menú profundo.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use tmenu::app::App;
+
+/// Menú con `depth` niveles de submenús anidados; el submenú siguiente es
+/// siempre el primer ítem de cada nivel, seguido de `width` comandos.
+fn large_menu(width: usize, depth: usize) -> String {
+    fn level(toon: &mut String, current: usize, width: usize, depth: usize) {
+        let indent = "    ".repeat(current + 1);
+        toon.push_str(&format!("{}\"Nivel {}\":\n", indent, current));
+        if current + 1 < depth {
+            level(toon, current + 1, width, depth);
+        }
+        for i in 0..width {
+            toon.push_str(&format!("{}    \"Item {}\": \"echo {}\"\n", indent, i, i));
+        }
+    }
+    let mut toon = String::from("\"Bench\":\n");
+    level(&mut toon, 0, width, depth);
+    toon
+}
+
+fn enter_back(c: &mut Criterion) {
+    let mut app = App::from_toon_str(&large_menu(5_000, 4), false);
+    c.bench_function("enter x3 / back, 4 niveles x 5000 ítems", |b| {
+        b.iter(|| {
+            for _ in 0..3 {
+                app.enter_selected().unwrap();
+                black_box(app.filtered_items());
+            }
+            while app.back() {}
+        })
+    });
+}
+
+criterion_group!(benches, enter_back);
+criterion_main!(benches);
@@ -0,0 +1,65 @@
+/*
+ * API C del motor de menús de tmenu. La biblioteca se compila con
+ *
+ *   cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * y queda como libtmenu.so / libtmenu.dylib / tmenu.dll en target/release;
+ * con `--crate-type staticlib`, como libtmenu.a. Las convenciones están en
+ * src/ffi.rs:
+ * - tmenu_load retorna un handle opaco que se libera con tmenu_free.
+ * - Los strings retornados son UTF-8 terminados en NUL, propiedad del
+ *   llamador, y se liberan con tmenu_string_free.
+ * - Las funciones que retornan int usan 0 = ok / no, 1 = sí, -1 = error.
+ */
+#ifndef TMENU_H
+#define TMENU_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Menú cargado: nivel actual, selección e historial de navegación. */
+typedef struct TmenuApp TmenuApp;
+
+/* Carga un archivo de menú .toon. Retorna NULL si no se pudo cargar. */
+TmenuApp *tmenu_load(const char *path);
+
+/* Libera un handle obtenido con tmenu_load. Acepta NULL. */
+void tmenu_free(TmenuApp *app);
+
+/* Libera un string retornado por esta API. Acepta NULL. */
+void tmenu_string_free(char *s);
+
+/* Mueve la selección al ítem siguiente o anterior (con wrap-around). */
+void tmenu_next(TmenuApp *app);
+void tmenu_prev(TmenuApp *app);
+
+/* Entra al submenú seleccionado: 1 si entró, 0 si el ítem no es un submenú,
+ * -1 si un submenú generado no se pudo construir. */
+int tmenu_enter(TmenuApp *app);
+
+/* Vuelve al menú anterior: 1 si volvió, 0 si ya estaba en la raíz. */
+int tmenu_back(TmenuApp *app);
+
+/* Cantidad de ítems del nivel actual. */
+size_t tmenu_item_count(const TmenuApp *app);
+
+/* Índice del ítem seleccionado, o -1 si no hay selección. */
+intptr_t tmenu_selected(const TmenuApp *app);
+
+/* Etiqueta del ítem index del nivel actual, o NULL si no existe. */
+char *tmenu_item_label(const TmenuApp *app, size_t index);
+
+/* Ejecuta el comando del ítem seleccionado heredando la terminal y espera a
+ * que termine. Retorna el código de salida, o -1 si el ítem no es un comando,
+ * el comando no es seguro o no se pudo lanzar. */
+int tmenu_run_selected(const TmenuApp *app);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TMENU_H */
@@ -1,110 +1,845 @@
-use ratatui::{Terminal, backend::CrosstermBackend, widgets::ListState};
+use ratatui::{
+    Terminal,
+    backend::Backend,
+    layout::{Position, Rect},
+    widgets::ListState,
+};
 
 use crossterm::{
-    event::DisableMouseCapture,
-    event::EnableMouseCapture,
+    cursor::SetCursorStyle,
+    event::{self, DisableMouseCapture, EnableMouseCapture},
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    style::{Attribute, ResetColor, SetAttribute},
+    terminal::{
+        EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
+        enable_raw_mode,
+    },
 };
-use std::io::{self, Stdout};
-use std::process::Command;
+use log::{Level, debug, log_enabled, warn};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
+use crate::builder::Menu;
+use crate::control::ControlSocket;
+use crate::hints::Hints;
+use crate::jobs::{JobStatus, Jobs};
+use crate::keymap::Keymap;
 use crate::model::{
-    CommandParam, ConfirmationState, ExecutionMode, HistoryEntry, MenuAction, MenuItem,
+    Columns, CommandParam, ConfirmationState, Detach, ExecutionMode, FollowUp, MenuAction,
+    MenuItem, MenuItems, QuickSelect, Script, Shell, SortOrder, SourceLocation, TmuxTarget,
+    item_path,
+};
+use crate::parser::{GlobalConfig, parse_toon_file_with, parse_toon_str_with};
+use crate::search::{TreeMatch, filter_level, filter_recursive, filter_tree, find_first_command};
+use crate::theme::Theme;
+use crate::timefmt::TimeFormat;
+use crate::tui::{EventSource, TerminalEvents};
+use crate::vars::{SessionVars, VarSource};
+use crate::widget::truncate_to_width;
+use crate::window::Window;
+use crate::{
+    clipboard, detach, error::TmenuError, history, notify, parser, plugin, position, process,
+    provider, saved, script, stats, tmux, writer,
 };
-use crate::parser::parse_toon_file;
-use crate::search::{filter_recursive, find_first_command};
-use crate::{error::AppError, history, parser};
+use unicode_width::UnicodeWidthStr;
+
+/// Etiquetas de los submenús de favoritos y recientes que se agregan al menú raíz.
+const FAVORITES_LABEL: &str = "Favoritos";
+const RECENT_LABEL: &str = "Recientes";
+
+/// Estado de un ítem con `[status=...]`, junto a la etiqueta.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusBadge {
+    pub text: String,
+    /// El comando terminó bien
+    pub ok: bool,
+}
+
+/// Entrada del historial de navegación para poder volver atrás.
+pub struct HistoryEntry {
+    pub title: String,
+    pub items: MenuItems,
+    pub state: ListState,
+}
 
 /// Estado principal de la aplicación TUI.
 pub struct App {
     pub history: Vec<HistoryEntry>,
     pub current_title: String,
-    pub current_items: Vec<MenuItem>,
+    pub current_items: MenuItems,
     /// Ítems del menú raíz, guardados al inicio para que `go_home` sea exacto.
     pub root_title: String,
-    pub root_items: Vec<MenuItem>,
+    pub root_items: MenuItems,
+    /// Árbol completo tal como se parseó, con los ítems `[hidden=true]` que
+    /// `root_items` deja afuera mientras no se muestren.
+    pub menu_items: MenuItems,
+    /// Mostrar los ítems ocultos (`o`).
+    pub show_hidden: bool,
     pub state: ListState,
     pub search_text: String,
     pub search_mode: bool,
+    /// La búsqueda se abrió con `/`: filtra solo el nivel actual, submenús
+    /// incluidos, en lugar de buscar comandos en todo el árbol (Tab).
+    pub filter_level: bool,
+    /// La búsqueda se abrió con Ctrl+F: recorre todo el árbol desde la raíz,
+    /// submenús incluidos, y cada resultado muestra dónde está.
+    pub global_search: bool,
     pub show_preview: bool,
     pub show_help: bool,
+    /// Modo `--debug`: lo que pasa se anota en el registro de [`crate::debuglog`]
     pub debug: bool,
     pub wizard: Option<WizardState>,
     /// Modal de confirmación: Some(cmd) = usuario debe confirmar; None = no hay confirmación pendiente
     pub confirmation: Option<ConfirmationState>,
     /// Modo de ejecución global (Clean o Inherit)
     pub execution_mode: ExecutionMode,
+    /// Ruta del archivo de menú, usada para recargarlo cuando cambia en disco.
+    /// `None` si el menú se leyó de la entrada estándar.
+    pub menu_path: Option<PathBuf>,
+    /// Configuración de partida del menú (la de `[defaults]` en `config.toml`),
+    /// para volver a aplicarla al recargarlo.
+    defaults: GlobalConfig,
+    /// Fecha de modificación del archivo en la última carga.
+    menu_mtime: Option<SystemTime>,
+    /// Registrar estadísticas de uso locales (`stats: true` en `config:`).
+    pub stats: bool,
+    /// Shell global para los ítems sin `[shell=...]` propio.
+    pub shell: Shell,
+    /// Pausa "Presioná Enter" tras los comandos sin `[wait=...]` propio.
+    pub wait: bool,
+    /// Qué hacen `1`–`9` en los menús sin teclas de acceso rápido.
+    pub quick_select: QuickSelect,
+    /// Columnas de la lista de ítems (`columns:`).
+    pub columns: Columns,
+    /// `next`/`previous` dan la vuelta en los extremos (`wrap:`).
+    pub wrap: bool,
+    /// Los submenús del menú raíz son pestañas (`tabs:`).
+    pub tabs: bool,
+    /// Orden de los ítems de los submenús generados sin `[sort=...]` propio
+    /// (`sort:`); los del árbol ya los ordena el parser.
+    pub sort: SortOrder,
+    /// Mostrar la barra inferior con el comando seleccionado (`status_bar:`).
+    pub status_bar: bool,
+    /// La barra inferior muestra el comando completo en varias líneas.
+    pub status_expanded: bool,
+    /// Notificar en el escritorio cuando termina un trabajo en segundo plano.
+    pub notify: bool,
+    /// Guardar los comandos ejecutados en el historial (`history: false` en `config:`).
+    pub record_history: bool,
+    /// Guardar y recuperar la última posición del menú (`restore: false` en `config:`).
+    pub restore: bool,
+    /// Programa de `elevate_with:` para los ítems con `[elevate=true]`.
+    pub elevate_with: Option<Vec<String>>,
+    /// Emulador de `terminal:` para los ítems con `[detach=terminal]`.
+    pub terminal: Option<Vec<String>>,
+    /// Hooks de `before_exec:` y `after_exec:`, alrededor de cada comando.
+    pub before_exec: Option<String>,
+    pub after_exec: Option<String>,
+    /// Teclas de las acciones, con las de `keys:` aplicadas.
+    pub keymap: Keymap,
+    /// Colores y símbolos de la interfaz (`[theme]` de `config.toml`).
+    pub theme: Theme,
+    /// Textos del pie del menú (`[hints]` de `config.toml`).
+    pub hints: Hints,
+    /// Tamaño y ubicación del menú (`[window]` de `config.toml`).
+    pub window: Window,
+    /// Variables de entorno del bloque `env:`, para todos los comandos.
+    pub env: Vec<(String, String)>,
+    /// Formato de las fechas y horas mostradas (`date_format`/`time_format`).
+    pub time_format: TimeFormat,
+    /// Intervalo de las tareas periódicas (recarga del menú, animaciones).
+    pub tick_rate: Duration,
+    /// Comandos lanzados en segundo plano en esta sesión.
+    pub jobs: Jobs,
+    /// Se presionó `g` y se espera la segunda para `gg` (ir al primer ítem).
+    pub pending_g: bool,
+    /// Dónde quedaron la lista, sus columnas y el `[<-] Volver` en el último
+    /// dibujado, para los clics del mouse y el movimiento entre columnas.
+    pub layout: MenuLayout,
+    /// Último clic sobre un ítem, para reconocer el doble clic.
+    last_click: Option<(Instant, usize)>,
+    /// Vista de trabajos (`J`): `Some` con la fila seleccionada mientras está abierta.
+    pub jobs_view: Option<ListState>,
+    /// Variables de sesión: respuestas del wizard y salidas capturadas.
+    pub vars: SessionVars,
+    /// Ítems favoritos (claves de `item_key`), en el orden en que se marcaron.
+    pub favorites: Vec<String>,
+    /// Ítems ejecutados hace poco (claves de `item_key`), el último primero.
+    pub recent: Vec<String>,
+    /// Vista de variables (`v`), `Some` mientras está abierta.
+    pub vars_view: Option<VarsView>,
+    /// Vista de árbol (`t`), `Some` mientras está abierta.
+    pub tree_view: Option<TreeView>,
+    /// Popup de texto abierto: la ayuda del ítem seleccionado (`?`) o el
+    /// comando que se hubiera ejecutado en modo simulación.
+    pub popup: Option<TextPopup>,
+    /// Origen del ítem seleccionado (`s`), `Some` mientras está abierto.
+    pub source_view: Option<SourceView>,
+    /// Comando de `on_success`/`on_failure` que el loop principal tiene que
+    /// ejecutar (necesita la terminal, que no siempre está a mano al terminar).
+    pub follow_up: Option<MenuItem>,
+    /// Modo simulación (`--dry-run`, `F3`): los comandos se muestran en lugar
+    /// de ejecutarse.
+    pub dry_run: bool,
+    /// Modo selección (`--print`): no se ejecuta nada, la elección se guarda
+    /// en `selection` y la app se cierra.
+    pub print: Option<PrintTarget>,
+    /// Lo elegido en modo selección, para imprimirlo al salir.
+    pub selection: Option<String>,
+    /// Último comando ejecutado, ya resuelto, para repetirlo con `.`.
+    pub last_run: Option<(MenuItem, String)>,
+    /// Panel con la salida del último comando ejecutado en modo `capture`.
+    pub output: Option<CapturedOutput>,
+    /// Hasta cuándo queda bloqueado cada ítem con `[cooldown=...]` que se ejecutó.
+    cooldowns: HashMap<String, Instant>,
+    /// Resultado de cada `[enabled_if=...]` y `[status=...]` ya evaluado en
+    /// el nivel `probes_level`, para no ejecutar los comandos en cada dibujado.
+    conditions: HashMap<String, bool>,
+    statuses: HashMap<String, StatusBadge>,
+    probes_level: Option<MenuItems>,
+    /// Mensaje no fatal para el usuario (por ejemplo un submenú que no se pudo generar).
+    /// Se borra con la próxima tecla.
+    pub status_message: Option<String>,
+    /// De dónde lee eventos el bucle de la interfaz: la terminal, salvo en pruebas.
+    pub events: Box<dyn EventSource>,
+    /// Socket de control (`--listen`) cuyos pedidos atiende el bucle de la interfaz.
+    pub control: Option<ControlSocket>,
 }
 
 impl App {
     /// Crea una instancia de `App` cargando el menú desde un archivo `.toon`.
-    pub fn from_toon(path: &std::path::Path, debug: bool) -> Result<Self, AppError> {
-        let (config, main_title, root_items) = parse_toon_file(path)?;
+    pub fn from_toon(path: &Path, debug: bool) -> Result<Self, TmenuError> {
+        Self::from_toon_with(path, debug, GlobalConfig::default())
+    }
+
+    /// Como [`App::from_toon`], con `defaults` como configuración de partida
+    /// que el `config:` del menú completa o reemplaza, también al recargarlo.
+    pub fn from_toon_with(
+        path: &Path,
+        debug: bool,
+        defaults: GlobalConfig,
+    ) -> Result<Self, TmenuError> {
+        let (config, main_title, root_items) = parse_toon_file_with(path, defaults.clone())?;
+        debug!("menú leído de {}", path.display());
+        let mut app = Self::new(config, main_title, root_items, debug);
+        app.menu_path = Some(path.to_path_buf());
+        app.menu_mtime = file_mtime(path);
+        app.defaults = defaults;
+        Ok(app)
+    }
+
+    /// Crea una instancia de `App` a partir del contenido de un menú `.toon`
+    /// ya leído (por ejemplo desde la entrada estándar). Sin archivo no hay recarga.
+    pub fn from_toon_str(content: &str, debug: bool) -> Self {
+        Self::from_toon_str_with(content, debug, GlobalConfig::default())
+    }
+
+    /// Como [`App::from_toon_str`], con `defaults` como configuración de partida.
+    pub fn from_toon_str_with(content: &str, debug: bool, defaults: GlobalConfig) -> Self {
+        let (config, main_title, root_items) =
+            parse_toon_str_with(content, Path::new("."), defaults);
+        Self::new(config, main_title, root_items, debug)
+    }
+
+    /// Crea una instancia de `App` con un menú armado desde código
+    /// ([`Menu::builder`](crate::builder::Menu::builder)). Sin archivo no hay recarga.
+    pub fn from_menu(menu: Menu, debug: bool) -> Self {
+        Self::new(menu.config, menu.title, menu.items, debug)
+    }
 
+    fn new(config: GlobalConfig, main_title: String, root_items: MenuItems, debug: bool) -> Self {
         let mut state = ListState::default();
         state.select(Some(0));
+        let time_format = TimeFormat::from_config(&config);
+        let (keymap, key_errors) = Keymap::from_config(&config.keys);
+        let visible = Rc::new(without_hidden(&root_items));
+        log_menu(&main_title, &root_items, &config, &key_errors);
 
-        Ok(App {
+        App {
             history: Vec::new(),
             current_title: main_title.clone(),
-            current_items: root_items.clone(),
+            current_items: visible.clone(),
             root_title: main_title,
-            root_items,
+            root_items: visible,
+            menu_items: root_items,
+            show_hidden: false,
             state,
             search_text: String::new(),
             search_mode: false,
+            filter_level: false,
+            global_search: false,
+            pending_g: false,
+            layout: MenuLayout::default(),
+            last_click: None,
             show_preview: false,
             show_help: false,
             debug,
             wizard: None,
             confirmation: None,
             execution_mode: config.execution_mode,
-        })
+            tick_rate: config.tick_rate,
+            stats: config.stats,
+            shell: config.shell,
+            wait: config.wait,
+            env: config.env,
+            quick_select: config.quick_select,
+            columns: config.columns,
+            wrap: config.wrap,
+            status_bar: config.status_bar,
+            tabs: config.tabs,
+            sort: config.sort,
+            status_expanded: false,
+            notify: config.notify,
+            record_history: config.history,
+            restore: config.restore,
+            elevate_with: config.elevate_with,
+            terminal: config.terminal,
+            before_exec: config.before_exec,
+            after_exec: config.after_exec,
+            keymap,
+            theme: Theme::default(),
+            hints: Hints::default(),
+            window: Window::default(),
+            time_format,
+            menu_path: None,
+            defaults: GlobalConfig::default(),
+            menu_mtime: None,
+            jobs: Jobs::default(),
+            jobs_view: None,
+            vars: SessionVars::default(),
+            favorites: Vec::new(),
+            recent: Vec::new(),
+            vars_view: None,
+            tree_view: None,
+            popup: None,
+            source_view: None,
+            follow_up: None,
+            last_run: None,
+            dry_run: false,
+            print: None,
+            selection: None,
+            output: None,
+            cooldowns: HashMap::new(),
+            conditions: HashMap::new(),
+            statuses: HashMap::new(),
+            probes_level: None,
+            status_message: key_errors_message(&key_errors),
+            events: Box::new(TerminalEvents),
+            control: None,
+        }
+    }
+
+    /// Tareas periódicas, llamadas en cada tick sin eventos de teclado.
+    /// Retorna `true` si el estado cambió y hay que redibujar.
+    pub fn on_tick(&mut self) -> bool {
+        let reloaded = self.check_reload();
+        // Con trabajos en curso se redibuja para animar su indicador
+        let running = self.jobs.any_running();
+        for index in self.jobs.poll() {
+            let Some(job) = self.jobs.get(index) else {
+                continue;
+            };
+            let (item, cmd, started) = (job.item.clone(), job.cmd.clone(), job.started);
+            let (success, timed_out) = (
+                job.status == JobStatus::Finished(true),
+                job.status == JobStatus::TimedOut,
+            );
+            if self.notify {
+                notify::send(&item.label, &job_outcome(success, timed_out, job.code));
+            }
+            debug!(
+                "trabajo en segundo plano {:?} terminó: {}",
+                cmd,
+                job_outcome(success, timed_out, job.code)
+            );
+            self.log_history(&cmd, job.code);
+            self.record_run(&item, &cmd, success, started);
+            self.after_run(&item, success);
+            self.status_message = Some(if timed_out {
+                timeout_message(&item)
+            } else {
+                format!(
+                    "\"{}\" terminó {}",
+                    item.label,
+                    if success { "bien" } else { "con error" }
+                )
+            });
+        }
+        // Mientras haya ítems en cooldown se redibuja para actualizar la cuenta regresiva
+        let counting = !self.cooldowns.is_empty();
+        let now = Instant::now();
+        self.cooldowns.retain(|_, until| *until > now);
+        reloaded || counting || running
+    }
+
+    /// Tiempo que falta para poder volver a ejecutar `item`, si está en cooldown.
+    pub fn cooldown_remaining(&self, item: &MenuItem) -> Option<Duration> {
+        item.cooldown?;
+        let until = self.cooldowns.get(&item_key(item)?)?;
+        until
+            .checked_duration_since(Instant::now())
+            .filter(|d| !d.is_zero())
+    }
+
+    /// Si `item` está deshabilitado, por `[disabled=true]` o porque su
+    /// `[enabled_if=...]` no terminó bien (o, con `script:`, no retornó
+    /// `true`). Cada condición se evalúa una vez por nivel: al entrar de
+    /// nuevo al nivel se vuelve a evaluar.
+    pub fn is_disabled(&mut self, item: &MenuItem) -> bool {
+        if item.disabled {
+            return true;
+        }
+        let Some(condition) = &item.enabled_if else {
+            return false;
+        };
+        self.sync_probes();
+        if let Some(&holds) = self.conditions.get(condition) {
+            return !holds;
+        }
+        // Una condición que no arranca, tarda demasiado o falla cuenta como falsa
+        let holds = match condition.strip_prefix("script:") {
+            Some(code) => script::condition(&Script::parse(code)).unwrap_or_else(|e| {
+                warn!(
+                    "enabled_if de '{}': {}",
+                    item.label,
+                    e.to_string().trim_end()
+                );
+                false
+            }),
+            None => matches!(self.run_probe(item, condition), Some((true, _))),
+        };
+        self.conditions.insert(condition.clone(), holds);
+        !holds
+    }
+
+    /// Estado de `item` según su `[status=...]`: la primera línea de la
+    /// salida, o ✓/✗ según cómo terminó si no escribe nada. Se evalúa una vez
+    /// por nivel, como las condiciones, o de nuevo con `refresh_probes`.
+    pub fn status_badge(&mut self, item: &MenuItem) -> Option<StatusBadge> {
+        const MAX_BADGE_WIDTH: usize = 16;
+
+        let probe = item.status.as_ref()?;
+        self.sync_probes();
+        if let Some(badge) = self.statuses.get(probe) {
+            return Some(badge.clone());
+        }
+        let (ok, text) = self
+            .run_probe(item, probe)
+            .unwrap_or((false, "?".to_string()));
+        let text = match text.trim() {
+            "" => match (ok, self.theme.ascii) {
+                (true, false) => "✓".to_string(),
+                (false, false) => "✗".to_string(),
+                (true, true) => "ok".to_string(),
+                (false, true) => "x".to_string(),
+            },
+            line => truncate_to_width(line, MAX_BADGE_WIDTH, "…"),
+        };
+        let badge = StatusBadge { text, ok };
+        self.statuses.insert(probe.clone(), badge.clone());
+        Some(badge)
+    }
+
+    /// Descarta las condiciones y estados evaluados para que se vuelvan a
+    /// ejecutar en el próximo dibujado.
+    pub fn refresh_probes(&mut self) {
+        self.conditions.clear();
+        self.statuses.clear();
+    }
+
+    /// Al cambiar de nivel se olvidan las condiciones y estados del anterior.
+    fn sync_probes(&mut self) {
+        let same_level = self
+            .probes_level
+            .as_ref()
+            .is_some_and(|items| Rc::ptr_eq(items, &self.current_items));
+        if !same_level {
+            self.refresh_probes();
+            self.probes_level = Some(Rc::clone(&self.current_items));
+        }
+    }
+
+    /// Ejecuta `cmd` (una condición o un estado de `item`) con el shell, el
+    /// directorio y las variables del ítem, sin entrada. Retorna si terminó
+    /// bien y la primera línea no vacía de la salida, o `None` si no arrancó
+    /// o tardó demasiado.
+    fn run_probe(&self, item: &MenuItem, cmd: &str) -> Option<(bool, String)> {
+        const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+        let shell = item.shell.as_ref().unwrap_or(&self.shell);
+        let mut command = build_command(item, &shell.command_line(cmd), &self.env)?;
+        command.stdin(Stdio::null());
+        let (stdout, _, status) =
+            process::output_timeout(&mut command, Some(PROBE_TIMEOUT)).ok()?;
+        let line = String::from_utf8_lossy(&stdout)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string();
+        Some((status?.success(), line))
+    }
+
+    /// Recarga el menú si el archivo cambió en disco desde la última carga.
+    /// Retorna `true` si el menú fue recargado.
+    ///
+    /// Si el archivo quedó inválido (por ejemplo a mitad de un guardado),
+    /// se conserva el menú actual y se reintenta en el próximo cambio.
+    pub fn check_reload(&mut self) -> bool {
+        let Some(path) = &self.menu_path else {
+            return false;
+        };
+        let mtime = file_mtime(path);
+        if mtime.is_none() || mtime == self.menu_mtime {
+            return false;
+        }
+        self.menu_mtime = mtime;
+
+        match self.reload() {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("no se pudo recargar el menú: {}", e.to_string().trim());
+                false
+            }
+        }
+    }
+
+    /// Vuelve a parsear el archivo de menú y reconstruye el árbol de ítems,
+    /// intentando conservar el submenú abierto y el ítem seleccionado.
+    pub fn reload(&mut self) -> Result<(), TmenuError> {
+        let Some(path) = &self.menu_path else {
+            return Ok(());
+        };
+        let (config, main_title, root_items) = parse_toon_file_with(path, self.defaults.clone())?;
+        debug!("menú recargado de {}", path.display());
+        log_menu(&main_title, &root_items, &config, &[]);
+
+        self.execution_mode = config.execution_mode;
+        self.stats = config.stats;
+        self.time_format = TimeFormat::from_config(&config);
+        self.env = config.env;
+        self.quick_select = config.quick_select;
+        self.columns = config.columns;
+        self.wrap = config.wrap;
+        self.status_bar = config.status_bar;
+        self.tabs = config.tabs;
+        self.sort = config.sort;
+        self.notify = config.notify;
+        self.record_history = config.history;
+        self.restore = config.restore;
+        self.elevate_with = config.elevate_with;
+        self.terminal = config.terminal;
+        self.before_exec = config.before_exec;
+        self.after_exec = config.after_exec;
+        let (keymap, key_errors) = Keymap::from_config(&config.keys);
+        self.keymap = keymap;
+        if let Some(message) = key_errors_message(&key_errors) {
+            self.status_message = Some(message);
+        }
+        self.root_title = main_title;
+        self.replace_tree(root_items);
+        Ok(())
+    }
+
+    /// Agrega el comando al historial, salvo con `history: false`; si falla
+    /// solo se avisa.
+    fn log_history(&self, cmd: &str, code: Option<i32>) {
+        if !self.record_history {
+            return;
+        }
+        if let Err(e) = history::log_command(cmd, code) {
+            eprintln!("[warn] no se pudo guardar en historial: {}", e);
+        }
+    }
+
+    /// Muestra o esconde los ítems `[hidden=true]`, conservando el lugar del
+    /// menú salvo que esté dentro de uno que se esconde.
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+        self.replace_tree(self.menu_items.clone());
+        self.status_message = Some(if self.show_hidden {
+            "Mostrando los ítems ocultos".to_string()
+        } else {
+            "Ítems ocultos escondidos".to_string()
+        });
+    }
+
+    /// Cambia el árbol del menú por `menu_items` y vuelve a entrar en los
+    /// submenús abiertos mientras sigan existiendo, intentando conservar el
+    /// ítem seleccionado.
+    fn replace_tree(&mut self, menu_items: MenuItems) {
+        let path = self.selection_path();
+        let selected_label = self
+            .state
+            .selected()
+            .and_then(|i| self.current_items.get(i))
+            .map(|item| item.label.clone());
+        let selected_index = self.state.selected().unwrap_or(0);
+
+        self.root_items = if self.show_hidden {
+            menu_items.clone()
+        } else {
+            Rc::new(without_hidden(&menu_items))
+        };
+        self.menu_items = menu_items;
+        self.pin_submenus();
+        self.history.clear();
+        self.current_title = self.root_title.clone();
+        self.current_items = self.root_items.clone();
+        self.state = ListState::default();
+
+        // Volver a entrar en los submenús del camino mientras sigan existiendo
+        for label in path {
+            let found = self.current_items.iter().position(|i| i.label == label);
+            let Some(index) = found else { break };
+            let Some(Ok(sub_items)) = self.submenu_items(&self.current_items[index]) else {
+                break;
+            };
+            self.state.select(Some(index));
+            self.enter_submenu(label, sub_items);
+        }
+        if self.history.is_empty() {
+            self.open_first_tab();
+        }
+
+        // Reseleccionar por etiqueta; si ya no existe, mantener la posición acotada
+        let index = selected_label
+            .and_then(|label| self.current_items.iter().position(|i| i.label == label))
+            .unwrap_or_else(|| selected_index.min(self.current_items.len().saturating_sub(1)));
+        self.state.select(Some(index));
+    }
+
+    /// Etiquetas de los submenús abiertos desde la raíz hasta el nivel actual.
+    fn selection_path(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .state
+                    .selected()
+                    .and_then(|i| entry.items.get(i))
+                    .map(|item| item.label.clone())
+            })
+            .collect()
     }
 
     /// Devuelve los ítems filtrados según el texto de búsqueda actual.
     /// Si no hay búsqueda activa, retorna todos los ítems del nivel actual.
-    pub fn filtered_items(&self) -> Vec<MenuItem> {
+    pub fn filtered_items(&self) -> MenuItems {
         if !self.search_mode || self.search_text.is_empty() {
-            return self.current_items.clone();
+            return Rc::clone(&self.current_items);
         }
 
-        let mut results = filter_recursive(&self.current_items, &self.search_text, 0);
+        let mut results = self.search_results();
 
-        if results.is_empty() {
-            if let Some(fallback) = find_first_command(&self.current_items) {
-                results.push(fallback);
-            }
+        // El filtro de `/` muestra solo lo que coincide, sin fallback
+        if results.is_empty()
+            && !self.filter_level
+            && !self.global_search
+            && let Some(fallback) = find_first_command(&self.current_items)
+        {
+            results.push(fallback);
+        }
+        Rc::new(results)
+    }
+
+    /// Ítems que coinciden con la búsqueda actual, según se abrió con Tab,
+    /// `/` o Ctrl+F.
+    pub fn search_results(&self) -> Vec<MenuItem> {
+        if self.global_search {
+            self.tree_results()
+                .into_iter()
+                .map(|found| found.item)
+                .collect()
+        } else if self.filter_level {
+            filter_level(&self.current_items, &self.search_text)
+        } else {
+            filter_recursive(&self.current_items, &self.search_text, 0)
+        }
+    }
+
+    /// Resultados de la búsqueda global con el camino de cada uno, en el
+    /// orden de `search_results`.
+    pub fn tree_results(&self) -> Vec<TreeMatch> {
+        filter_tree(&self.root_items, &self.search_text)
+    }
+
+    /// Va al lugar del menú donde está el resultado seleccionado de la
+    /// búsqueda global y lo deja seleccionado. Retorna `false` si no hay
+    /// resultado o ya no se encuentra.
+    pub fn locate_result(&mut self) -> bool {
+        let Some(found) = self
+            .state
+            .selected()
+            .and_then(|i| self.tree_results().into_iter().nth(i))
+        else {
+            return false;
+        };
+        let mut path = found.path;
+        path.push(found.item.label);
+        self.global_search = false;
+        self.jump_to(&path)
+    }
+
+    /// Selecciona el ítem visible cuya tecla de acceso rápido es `key`.
+    /// Retorna `false` si ninguno la tiene.
+    pub fn select_hotkey(&mut self, key: char) -> bool {
+        let items = self.filtered_items();
+        let Some(index) = items.iter().position(|item| item.hotkey == Some(key)) else {
+            return false;
+        };
+        self.state.select(Some(index));
+        true
+    }
+
+    /// Selecciona el ítem visible número `n` (desde 1) con `quick_select`. No
+    /// aplica en los niveles con teclas de acceso rápido propias, para no
+    /// mezclar dos numeraciones. Retorna `true` si hay que ejecutarlo.
+    pub fn quick_select(&mut self, n: usize) -> bool {
+        if self.quick_select == QuickSelect::Off {
+            return false;
+        }
+        let items = self.filtered_items();
+        if n == 0 || n > items.len() || items.iter().any(|item| item.hotkey.is_some()) {
+            return false;
         }
-        results
+        self.state.select(Some(n - 1));
+        self.quick_select == QuickSelect::Run
     }
 
-    /// Avanza la selección al siguiente ítem (con wrap-around).
+    /// Avanza la selección al siguiente ítem; desde el último pasa al
+    /// primero salvo `wrap: false`.
     pub fn next(&mut self) {
-        let len = self.current_items.len();
-        if len == 0 {
+        let items = Rc::clone(&self.current_items);
+        self.step(&items, true, self.wrap);
+    }
+
+    /// Retrocede la selección al ítem anterior; desde el primero pasa al
+    /// último salvo `wrap: false`.
+    pub fn previous(&mut self) {
+        let items = Rc::clone(&self.current_items);
+        self.step(&items, false, self.wrap);
+    }
+
+    /// Mueve la selección de `items` al siguiente ítem habilitado (o al
+    /// anterior), dando la vuelta en los extremos con `wrap`. Si no hay otro
+    /// habilitado en esa dirección la selección no cambia.
+    pub fn step(&mut self, items: &[MenuItem], forward: bool, wrap: bool) {
+        let len = items.len();
+        let Some(mut i) = self.state.selected() else {
+            self.state.select((len > 0).then_some(0));
             return;
+        };
+        for _ in 1..len {
+            i = match (forward, wrap) {
+                (true, _) if i + 1 < len => i + 1,
+                (true, true) => 0,
+                (false, _) if i > 0 => i - 1,
+                (false, true) => len - 1,
+                _ => return,
+            };
+            if !self.is_disabled(&items[i]) {
+                self.state.select(Some(i));
+                return;
+            }
         }
-        let i = self.state.selected().map_or(0, |i| (i + 1) % len);
-        self.state.select(Some(i));
     }
 
-    /// Retrocede la selección al ítem anterior (con wrap-around).
-    pub fn previous(&mut self) {
-        let len = self.current_items.len();
-        if len == 0 {
+    /// Si el ítem seleccionado de `items` está deshabilitado, pasa al primer
+    /// habilitado después de él (o antes, si no hay). Así la selección nunca
+    /// queda sobre uno deshabilitado al entrar a un nivel o al filtrar.
+    pub fn skip_disabled(&mut self, items: &[MenuItem]) {
+        let Some(selected) = self.state.selected().filter(|&i| i < items.len()) else {
+            return;
+        };
+        if !self.is_disabled(&items[selected]) {
             return;
         }
-        let i = self
-            .state
-            .selected()
-            .map_or(0, |i| if i == 0 { len - 1 } else { i - 1 });
-        self.state.select(Some(i));
+        let after = (selected + 1..items.len()).find(|&i| !self.is_disabled(&items[i]));
+        let found = after.or_else(|| (0..selected).rev().find(|&i| !self.is_disabled(&items[i])));
+        if let Some(i) = found {
+            self.state.select(Some(i));
+        }
+    }
+
+    /// Qué hay en la celda `(column, row)` del último dibujado.
+    pub fn click_target(&self, column: u16, row: u16) -> Option<ClickTarget> {
+        let position = Position::new(column, row);
+        if self.layout.back.is_some_and(|area| area.contains(position)) {
+            return Some(ClickTarget::Back);
+        }
+        let list = self.layout.list;
+        if !list.contains(position) {
+            return None;
+        }
+        // En la grilla cada columna suma un alto completo de ítems
+        let column_width = (list.width / self.layout.columns as u16).max(1);
+        let column = ((column - list.x) / column_width) as usize;
+        let index = self.state.offset() + column * list.height as usize + (row - list.y) as usize;
+        (index < self.filtered_items().len()).then_some(ClickTarget::Item(index))
+    }
+
+    /// Clic sobre el ítem `index`: lo selecciona y retorna `true` si es el
+    /// segundo clic de un doble clic, que lo ejecuta.
+    pub fn click_item(&mut self, index: usize) -> bool {
+        const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+        let now = Instant::now();
+        let double = self
+            .last_click
+            .is_some_and(|(at, last)| last == index && now.duration_since(at) < DOUBLE_CLICK);
+        self.state.select(Some(index));
+        // Un tercer clic empieza otro doble clic en lugar de ejecutar de nuevo
+        self.last_click = (!double).then_some((now, index));
+        double
+    }
+
+    /// Mueve la selección `delta` ítems, sin dar la vuelta en los extremos.
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.filtered_items().len().saturating_sub(1);
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(selected.saturating_add_signed(delta).min(last)));
+    }
+
+    /// Selecciona el primer ítem visible habilitado, o el último con `last`.
+    pub fn select_edge(&mut self, last: bool) {
+        let items = self.filtered_items();
+        let index = if last {
+            (0..items.len())
+                .rev()
+                .find(|&i| !self.is_disabled(&items[i]))
+        } else {
+            (0..items.len()).find(|&i| !self.is_disabled(&items[i]))
+        };
+        self.state.select(index);
+    }
+
+    /// Avanza (`1`) o retrocede (`-1`) una página de la lista dibujada.
+    pub fn move_page(&mut self, direction: isize) {
+        let rows = self.layout.list.height.max(1) as usize;
+        self.move_selection(direction * (rows * self.layout.columns) as isize);
+    }
+
+    /// Pasa a la columna siguiente (`1`) o anterior (`-1`) de la grilla.
+    /// Retorna `false` con la lista de una sola columna.
+    pub fn move_column(&mut self, direction: isize) -> bool {
+        if self.layout.columns < 2 {
+            return false;
+        }
+        self.move_selection(direction * self.layout.list.height.max(1) as isize);
+        true
     }
 
     /// Vuelve al menú anterior en el historial.
     pub fn back(&mut self) -> bool {
+        if self.at_root() {
+            return false;
+        }
         if let Some(entry) = self.history.pop() {
             self.current_title = entry.title;
             self.current_items = entry.items;
@@ -115,7 +850,8 @@ impl App {
         }
     }
 
-    /// Vuelve directamente al menú raíz usando los ítems guardados al inicio.
+    /// Vuelve directamente al menú raíz usando los ítems guardados al inicio
+    /// (con pestañas, a la primera).
     /// Fix: el código original usaba `history.drain().next()` que descartaba
     /// el estado real del root (guardaba el estado al entrar al primer submenú).
     pub fn go_home(&mut self) {
@@ -127,6 +863,95 @@ impl App {
         self.current_items = self.root_items.clone();
         self.state = ListState::default();
         self.state.select(Some(0));
+        self.open_first_tab();
+    }
+
+    /// Si no hay un menú al que volver. Con pestañas y sin ítems sueltos en
+    /// el menú raíz, la lista de submenús raíz no se muestra: el primer nivel
+    /// de una pestaña cuenta como la raíz.
+    pub fn at_root(&self) -> bool {
+        match self.history.len() {
+            0 => true,
+            1 => !self.tab_labels().is_empty() && !self.has_root_tab(),
+            _ => false,
+        }
+    }
+
+    /// Pestañas con `tabs:`: el menú raíz, si tiene ítems que no son
+    /// submenús, y después cada submenú del menú raíz. Vacía sin `tabs:`.
+    pub fn tab_labels(&self) -> Vec<String> {
+        if !self.tabs {
+            return Vec::new();
+        }
+        let mut labels = Vec::new();
+        if self.has_root_tab() {
+            labels.push(self.root_title.clone());
+        }
+        labels.extend(
+            self.root_items
+                .iter()
+                .filter(|item| item.action.is_submenu())
+                .map(|item| item.label.clone()),
+        );
+        labels
+    }
+
+    fn has_root_tab(&self) -> bool {
+        self.root_items.iter().any(|item| !item.action.is_submenu())
+    }
+
+    /// Pestaña del nivel actual: la del submenú raíz del que se viene.
+    pub fn active_tab(&self) -> usize {
+        let label = match self.history.get(1) {
+            Some(entry) => &entry.title,
+            None if self.history.is_empty() => &self.root_title,
+            None => &self.current_title,
+        };
+        self.tab_labels()
+            .iter()
+            .position(|tab| tab == label)
+            .unwrap_or(0)
+    }
+
+    /// Pasa a la pestaña siguiente o a la anterior, dando la vuelta. Cada
+    /// pestaña se abre en su primer nivel.
+    pub fn switch_tab(&mut self, forward: bool) {
+        let count = self.tab_labels().len();
+        if count == 0 {
+            return;
+        }
+        let active = self.active_tab();
+        let index = if forward {
+            (active + 1) % count
+        } else {
+            (active + count - 1) % count
+        };
+        self.open_tab(index);
+    }
+
+    /// Abre la pestaña `index` de `tab_labels`.
+    pub fn open_tab(&mut self, index: usize) {
+        let Some(label) = self.tab_labels().into_iter().nth(index) else {
+            return;
+        };
+        if index == 0 && self.has_root_tab() {
+            self.go_home();
+            return;
+        }
+        if !self.jump_to(&[label]) {
+            return;
+        }
+        if let Err(e) = self.enter_selected() {
+            self.status_message = Some(e.to_string().trim().to_string());
+        }
+    }
+
+    /// Con pestañas y sin ítems sueltos en el menú raíz, el menú empieza en
+    /// la primera pestaña en lugar de la lista de submenús.
+    pub fn open_first_tab(&mut self) {
+        if self.history.is_empty() && !self.tab_labels().is_empty() && !self.has_root_tab() {
+            self.open_tab(0);
+        }
     }
 
     /// Guarda el estado actual en el historial antes de navegar a un submenú.
@@ -134,23 +959,27 @@ impl App {
         self.history.push(HistoryEntry {
             title: self.current_title.clone(),
             items: self.current_items.clone(),
-            state: self.state.clone(),
+            state: self.state,
         });
     }
 
     /// Activa el ítem en el índice seleccionado de `list`.
     /// Retorna `true` si la aplicación debe cerrarse (comando "exit").
-    pub fn activate_item(
+    pub fn activate_item<B: Backend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        terminal: &mut Terminal<B>,
         list: &[MenuItem],
-    ) -> Result<bool, AppError> {
+    ) -> Result<bool, TmenuError> {
         let Some(index) = self.state.selected() else {
             return Ok(false);
         };
         let Some(item) = list.get(index) else {
             return Ok(false);
         };
+        if self.is_disabled(item) {
+            self.status_message = Some(format!("\"{}\" no está disponible ahora", item.label));
+            return Ok(false);
+        }
 
         match &item.action.clone() {
             MenuAction::Quit => return Ok(true),
@@ -159,266 +988,1993 @@ impl App {
                 if cmd == "exit" {
                     return Ok(true);
                 }
+                // Con pasos, la secuencia entera (un comando por línea) pasa por
+                // el wizard, la confirmación y la ejecución
+                let cmd = &item.command_sequence(cmd);
+
+                if self.is_blocked(item) {
+                    return Ok(false);
+                }
 
                 let params = parser::extract_params(cmd);
                 if params.is_empty() {
                     // Sin interpolación: pedir confirmación solo si el ítem lo requiere
-                    if item.require_confirmation {
-                        return self.request_command_confirmation(terminal, cmd);
+                    if item.needs_confirmation() {
+                        return self.request_command_confirmation(terminal, item, cmd);
                     } else {
                         // Ejecutar directo sin confirmación
-                        self.execute_external_command(terminal, cmd)?;
+                        return self.execute_external_command(terminal, item, cmd);
                     }
                 } else {
                     // Con interpolación: iniciar wizard (no ejecutar todavía)
-                    self.wizard = Some(WizardState::new(
-                        params,
-                        cmd.to_string(),
-                        item.require_confirmation,
-                    ));
+                    let mut wizard = WizardState::new(params, cmd.to_string(), item.clone());
+                    wizard.prefill(&self.vars);
+                    self.wizard = Some(wizard);
+                    self.prepare_wizard_step();
                 }
             }
-            MenuAction::OpenSubmenu(sub_items) => {
-                self.search_text.clear();
-                self.search_mode = false;
-                self.push_history();
-                self.current_title = item.label.clone();
-                self.current_items = sub_items.clone();
-                self.state = ListState::default();
-                self.state.select(Some(0));
-            }
+            _ => match self.submenu_items(item) {
+                Some(Ok(sub_items)) => self.enter_submenu(item.label.clone(), sub_items),
+                // Un submenú generado que falla no cierra la app: se informa y se sigue
+                Some(Err(e)) => self.status_message = Some(e.to_string().trim().to_string()),
+                None => {}
+            },
         }
         Ok(false)
     }
 
-    /// Intenta ejecutar un comando, mostrando primero un modal de confirmación.
-    /// Si el usuario confirma (Sí), se ejecuta y se registra en el historial.
-    /// Retorna true si la app debe cerrarse.
-    pub fn request_command_confirmation(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-        cmd: &str,
-    ) -> Result<bool, AppError> {
-        if !Self::is_safe_command(cmd) {
-            return Err(AppError::ForbiddenCommand(cmd.to_string()));
+    /// Si `item` no se puede ejecutar ahora (ya corre en segundo plano o está
+    /// en cooldown). El motivo queda en la barra de estado.
+    fn is_blocked(&mut self, item: &MenuItem) -> bool {
+        // Una sola instancia a la vez de cada comando en segundo plano
+        if item.background && self.is_running(item) {
+            self.status_message = Some(format!(
+                "\"{}\" ya se está ejecutando en segundo plano",
+                item.label
+            ));
+            return true;
         }
 
-        // Mostrar modal de confirmación
-        self.confirmation = Some(ConfirmationState::new(cmd.to_string()));
-
-        // Ejecutar el modal bloqueante — devuelve true si se ejecutó, false si se canceló
-        let should_execute = crate::run_confirmation_modal(terminal, self)?;
-
-        if should_execute {
-            self.execute_command_internal(terminal, cmd)?;
+        if let Some(remaining) = self.cooldown_remaining(item) {
+            self.status_message = Some(format!(
+                "\"{}\" se ejecutó hace poco; disponible en {}s",
+                item.label,
+                remaining.as_secs() + 1
+            ));
+            return true;
         }
-
-        Ok(false)
+        false
     }
 
-    /// Ejecuta un comando externo SIN pedir confirmación.
-    /// Si `execution_mode` es `Clean`, ejecuta `clear` antes del comando.
-    /// (Usado internamente después de que el usuario confirma).
-    fn execute_command_internal(
-        &self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-        cmd: &str,
-    ) -> Result<(), AppError> {
-        if self.debug {
-            eprintln!("[debug] ejecutando: {:?}", cmd);
-        }
-
-        // Restaurar terminal a modo normal
-        let _ = disable_raw_mode();
-        if let Err(e) = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture) {
-            eprintln!("[warn] no se pudo restaurar la terminal: {}", e);
+    /// Vuelve a ejecutar el último comando (`.`) con los mismos valores del
+    /// wizard, sin navegar hasta él. La confirmación, si el ítem la pide, se
+    /// repite. Retorna `true` si la app debe cerrarse.
+    pub fn rerun_last<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> Result<bool, TmenuError> {
+        let Some((item, cmd)) = self.last_run.clone() else {
+            self.status_message = Some("Todavía no se ejecutó ningún comando".to_string());
+            return Ok(false);
+        };
+        if self.is_blocked(&item) {
+            return Ok(false);
         }
-
-        // Si el modo es Clean, limpiar pantalla antes de ejecutar
-        if self.execution_mode == crate::model::ExecutionMode::Clean {
-            let _ = std::process::Command::new("clear").spawn();
+        if item.needs_confirmation() {
+            self.request_command_confirmation(terminal, &item, &cmd)
+        } else {
+            self.execute_external_command(terminal, &item, &cmd)
         }
+    }
 
-        let parts: Vec<String> = shlex::split(cmd)
-            .unwrap_or_else(|| cmd.split_whitespace().map(str::to_string).collect());
+    /// Ejecuta el comando de `on_success`/`on_failure` pendiente, si hay. No
+    /// cuenta como último comando para `rerun_last`. Retorna `true` si la app
+    /// debe cerrarse.
+    pub fn run_follow_up<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> Result<bool, TmenuError> {
+        let Some(item) = self.follow_up.take() else {
+            return Ok(false);
+        };
+        let MenuAction::Execute(cmd) = &item.action else {
+            return Ok(false);
+        };
+        let last_run = self.last_run.take();
+        let result = self.execute_external_command(terminal, &item, cmd);
+        self.last_run = last_run;
+        result
+    }
 
-        if let Some((bin, args)) = parts.split_first() {
-            let mut command = Command::new(bin);
-            command.args(args);
-            match command.spawn() {
-                Ok(mut child) => {
-                    let _ = child.wait();
-                    // Registrar en historial solo si la ejecución fue exitosa
-                    if let Err(e) = history::log_command(cmd) {
-                        eprintln!("[warn] no se pudo guardar en historial: {}", e);
-                    }
-                }
-                Err(e) => eprintln!("[error] no se pudo ejecutar '{}': {}", bin, e),
+    /// Ejecuta sin interfaz el ítem al final del camino de etiquetas `path`
+    /// (`tmenu run Deploy/Staging/Restart`), con su directorio, su entorno, su
+    /// shell, sus pasos y sus hooks, y retorna el código de salida del comando.
+    /// Los ítems ocultos también se alcanzan.
+    ///
+    /// Los campos del wizard y la confirmación se preguntan en `prompt` y se
+    /// responden por `input`, una línea cada uno; `assume_yes` da la
+    /// confirmación por aceptada. Los modos `background`, `capture`, `detach` y
+    /// `tmux` no aplican: el comando corre en primer plano y se espera a que
+    /// termine. Un `on_success`/`on_failure` con comando también se ejecuta.
+    pub fn run_path(
+        &mut self,
+        path: &[String],
+        assume_yes: bool,
+        input: &mut impl io::BufRead,
+        prompt: &mut impl Write,
+    ) -> Result<i32, TmenuError> {
+        if !self.show_hidden {
+            self.show_hidden = true;
+            self.replace_tree(self.menu_items.clone());
+        }
+        let found = self.jump_to(path);
+        let item = self
+            .state
+            .selected()
+            .and_then(|i| self.current_items.get(i));
+        let Some(item) = item.filter(|_| found).cloned() else {
+            return Err(TmenuError::RunError(format!(
+                "no se encontró '{}'",
+                item_path(path)
+            )));
+        };
+        if self.is_disabled(&item) {
+            return Err(TmenuError::RunError(format!(
+                "\"{}\" no está disponible ahora",
+                item.label
+            )));
+        }
+        let cmd = match &item.action {
+            MenuAction::Execute(cmd) => cmd.trim().trim_matches('"'),
+            MenuAction::Quit => return Ok(0),
+            _ => {
+                return Err(TmenuError::RunError(format!(
+                    "\"{}\" es un submenú, no ejecuta un comando",
+                    item.label
+                )));
             }
+        };
+        if cmd == "exit" {
+            return Ok(0);
         }
-
-        println!("\nPresioná Enter para volver al menú...");
-        let _ = io::stdin().read_line(&mut String::new());
-
-        // Volver a modo TUI
-        if let Err(e) = enable_raw_mode() {
-            eprintln!("[warn] no se pudo activar raw mode: {}", e);
+        let mut cmd = item.command_sequence(cmd);
+        let params = parser::extract_params(&cmd);
+        if !params.is_empty() {
+            cmd = self.ask_params(params, &cmd, &item, input, prompt)?;
         }
-        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture) {
-            eprintln!("[warn] no se pudo restaurar pantalla alternativa: {}", e);
+        if !self.allows_command(&item, &cmd) {
+            return Err(TmenuError::ForbiddenCommand(cmd));
         }
-        terminal
-            .clear()
-            .map_err(|e| AppError::TerminalError(e.to_string()))?;
-
-        Ok(())
-    }
-
-    /// Ejecuta un comando externo en el shell del sistema operativo.
-    ///
-    /// Si `execution_mode` es `Clean`, ejecuta `clear` antes del comando.
-    /// Antes de ejecutar, restaura la terminal a modo normal y la reconfigura
-    /// en modo TUI al finalizar.
-    ///
-    /// # Seguridad
-    /// - Rechaza comandos con path traversal (`..`).
-    /// - Usa `shlex::split` para respetar quoting correctamente en lugar de
-    ///   `split_whitespace`, que parte argumentos con espacios.
-    /// - Los valores interpolados por el wizard se validan aquí también,
-    ///   ya que `finish_wizard` llama a este método con el comando resuelto.
-    pub fn execute_external_command(
-        &self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-        cmd: &str,
-    ) -> Result<(), AppError> {
-        if !Self::is_safe_command(cmd) {
-            return Err(AppError::ForbiddenCommand(cmd.to_string()));
+        if item.needs_confirmation() && !assume_yes && !confirm_line(&item, input, prompt)? {
+            return Err(TmenuError::RunError("cancelado".to_string()));
         }
 
-        if self.debug {
-            eprintln!("[debug] ejecutando: {:?}", cmd);
+        debug!("ejecutando \"{}\" sin interfaz: {:?}", item.label, cmd);
+        if let Some(hook) = &self.before_exec
+            && !self.run_hook(hook, &item, &cmd, None)
+        {
+            return Err(TmenuError::RunError(format!(
+                "before_exec falló: \"{}\" no se ejecutó",
+                item.label
+            )));
         }
-
-        // Restaurar terminal a modo normal
-        let _ = disable_raw_mode();
-        if let Err(e) = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture) {
-            eprintln!("[warn] no se pudo restaurar la terminal: {}", e);
+        let code = self.run_steps(&item, &cmd);
+        self.after_run(&item, code == 0);
+        if let Some(follow_up) = self.follow_up.take()
+            && let MenuAction::Execute(cmd) = &follow_up.action
+        {
+            self.run_steps(&follow_up, cmd);
         }
+        Ok(code)
+    }
 
-        // Si el modo es Clean, limpiar pantalla antes de ejecutar
-        if self.execution_mode == crate::model::ExecutionMode::Clean {
-            let _ = std::process::Command::new("clear").spawn();
+    /// Ejecuta en primer plano los pasos de `cmd`, hasta el primero que falla,
+    /// y registra la ejecución. Retorna el código de salida de ese paso (1 si
+    /// no terminó solo), o 0.
+    fn run_steps(&mut self, item: &MenuItem, cmd: &str) -> i32 {
+        let started = Instant::now();
+        let steps = self.command_steps(item, cmd);
+        let mut code = 0;
+        for (i, (step, parts)) in steps.iter().enumerate() {
+            if steps.len() > 1 {
+                eprintln!("[{}/{}] {}", i + 1, steps.len(), step);
+            }
+            code = self.run_foreground_code(item, step, parts).unwrap_or(1);
+            if code != 0 {
+                break;
+            }
         }
+        self.record_run(item, cmd, code == 0, started);
+        code
+    }
 
-        // Parsear respetando quoting ("arg con espacios" se trata como un solo arg).
-        // Fallback a split_whitespace si shlex falla (comillas desbalanceadas, etc).
-        let parts: Vec<String> = shlex::split(cmd)
-            .unwrap_or_else(|| cmd.split_whitespace().map(str::to_string).collect());
-
-        if let Some((bin, args)) = parts.split_first() {
-            let mut command = Command::new(bin);
-            command.args(args);
-            match command.spawn() {
-                Ok(mut child) => {
-                    let _ = child.wait();
-                    // Registrar en historial solo si la ejecución fue exitosa
-                    if let Err(e) = history::log_command(cmd) {
-                        eprintln!("[warn] no se pudo guardar en historial: {}", e);
-                    }
+    /// Pregunta línea por línea los campos del wizard de `cmd`, con la
+    /// respuesta guardada como valor propuesto, y retorna el comando resuelto.
+    /// Un `{pick:...}` muestra sus opciones numeradas y acepta el número o la
+    /// opción tal cual.
+    fn ask_params(
+        &mut self,
+        params: Vec<CommandParam>,
+        cmd: &str,
+        item: &MenuItem,
+        input: &mut impl io::BufRead,
+        prompt: &mut impl Write,
+    ) -> Result<String, TmenuError> {
+        let mut wizard = WizardState::new(params, cmd.to_string(), item.clone());
+        wizard.prefill(&self.vars);
+        self.wizard = Some(wizard);
+        let count = self.wizard.as_ref().map_or(0, |w| w.params.len());
+        for current in 0..count {
+            if let Some(wizard) = self.wizard.as_mut() {
+                wizard.current = current;
+            }
+            self.prepare_wizard_step();
+            let Some(wizard) = self.wizard.as_mut() else {
+                let reason = self.status_message.take().unwrap_or_default();
+                return Err(TmenuError::RunError(reason));
+            };
+            let label = &wizard.params[current].label;
+            if wizard.is_picking() {
+                for (n, option) in wizard.options.iter().enumerate() {
+                    writeln!(prompt, "  {}) {}", n + 1, option)?;
                 }
-                Err(e) => eprintln!("[error] no se pudo ejecutar '{}': {}", bin, e),
+                write!(prompt, "{} [1-{}]: ", label, wizard.options.len())?;
+            } else if wizard.values[current].is_empty() {
+                write!(prompt, "{}: ", label)?;
+            } else {
+                write!(prompt, "{} [{}]: ", label, wizard.values[current])?;
+            }
+            prompt.flush()?;
+            let Some(answer) = read_answer(input)? else {
+                self.wizard = None;
+                return Err(TmenuError::RunError("cancelado".to_string()));
+            };
+            if wizard.is_picking() {
+                let chosen = answer
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|n| wizard.options.get(n))
+                    .or_else(|| wizard.options.iter().find(|option| **option == answer))
+                    .cloned();
+                let Some(chosen) = chosen else {
+                    let reason = format!("'{}' no es una de las opciones de {}", answer, label);
+                    self.wizard = None;
+                    return Err(TmenuError::RunError(reason));
+                };
+                wizard.values[current] = chosen;
+            } else if !answer.is_empty() {
+                wizard.values[current] = answer;
             }
         }
+        let Some(wizard) = self.wizard.take() else {
+            return Ok(cmd.to_string());
+        };
+        for (param, value) in wizard.params.iter().zip(&wizard.values) {
+            self.vars.set(&param.label, value, VarSource::Prompt);
+        }
+        Ok(wizard.resolve())
+    }
+
+    /// Entra al submenú del ítem seleccionado, sin usar la terminal.
+    /// Retorna `Ok(false)` si el ítem seleccionado no abre un submenú.
+    pub fn enter_selected(&mut self) -> Result<bool, TmenuError> {
+        let Some(item) = self
+            .state
+            .selected()
+            .and_then(|i| self.current_items.get(i))
+            .cloned()
+        else {
+            return Ok(false);
+        };
+        match self.submenu_items(&item) {
+            Some(items) => {
+                self.enter_submenu(item.label, items?);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Entra en un submenú guardando el nivel actual en el historial.
+    fn enter_submenu(&mut self, title: String, items: MenuItems) {
+        self.search_text.clear();
+        self.search_mode = false;
+        self.push_history();
+        self.current_title = title;
+        self.current_items = items;
+        self.state = ListState::default();
+        self.state.select(Some(0));
+    }
+
+    /// Intenta ejecutar un comando, mostrando primero un modal de confirmación.
+    /// Si el usuario confirma (Sí), se ejecuta y se registra en el historial.
+    /// Retorna true si la app debe cerrarse.
+    pub fn request_command_confirmation<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        item: &MenuItem,
+        cmd: &str,
+    ) -> Result<bool, TmenuError> {
+        if !self.allows_command(item, cmd) {
+            return Err(TmenuError::ForbiddenCommand(cmd.to_string()));
+        }
+        // En modo selección no se ejecuta nada: no hay qué confirmar
+        if self.print.is_some() {
+            return self.execute_external_command(terminal, item, cmd);
+        }
+
+        // Mostrar modal de confirmación
+        self.confirmation = Some(ConfirmationState::new(
+            cmd.to_string(),
+            item.confirm_message.clone(),
+            item.confirm_text.clone(),
+        ));
+
+        // Ejecutar el modal bloqueante — devuelve true si se ejecutó, false si se canceló
+        let should_execute = crate::tui::run_confirmation_modal(terminal, self)?;
+
+        if should_execute {
+            return self.execute_external_command(terminal, item, cmd);
+        }
+
+        Ok(false)
+    }
+
+    /// Ejecuta un comando externo en el shell del sistema operativo.
+    ///
+    /// Si `execution_mode` es `Clean`, ejecuta `clear` antes del comando.
+    /// Antes de ejecutar, restaura la terminal a modo normal y la reconfigura
+    /// en modo TUI al finalizar. Los atributos de `item` (directorio de trabajo,
+    /// etc.) se aplican al proceso lanzado. Retorna `true` si la app debe
+    /// cerrarse porque el ítem tiene `[exit=true]`.
+    ///
+    /// # Seguridad
+    /// - Rechaza comandos con path traversal (`..`).
+    /// - Sin shell (default) usa `shlex::split` para respetar quoting correctamente
+    ///   en lugar de `split_whitespace`, que parte argumentos con espacios. Con un
+    ///   shell configurado el comando se le pasa entero, sin esa validación (ver
+    ///   [`App::allows_command`]).
+    /// - Los valores interpolados por el wizard se validan aquí también,
+    ///   ya que `finish_wizard` llama a este método con el comando resuelto.
+    ///
+    /// Los ítems con `[elevate=true]` se ejecutan siempre en primer plano con la
+    /// terminal restaurada, aunque sean `background` o `capture`: el programa de
+    /// elevación necesita la terminal para pedir la contraseña.
+    pub fn execute_external_command<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        item: &MenuItem,
+        cmd: &str,
+    ) -> Result<bool, TmenuError> {
+        if !self.allows_command(item, cmd) {
+            return Err(TmenuError::ForbiddenCommand(cmd.to_string()));
+        }
+
+        // Modo selección: nada se ejecuta, la elección se imprime al salir
+        if let Some(target) = self.print {
+            self.selection = Some(match target {
+                // Los pasos de una secuencia se encadenan para poder usarla con `eval`
+                PrintTarget::Command => cmd.lines().collect::<Vec<_>>().join(" && "),
+                PrintTarget::Label => item.label.clone(),
+            });
+            return Ok(true);
+        }
+
+        debug!("ejecutando \"{}\": {:?}", item.label, cmd);
+        self.last_run = Some((item.clone(), cmd.to_string()));
+
+        let steps = self.command_steps(item, cmd);
+        let mode = item.execution_mode.unwrap_or(self.execution_mode);
+
+        // Simulación: mostrar lo que se ejecutaría y volver al menú
+        if self.dry_run {
+            self.show_dry_run(item, &steps);
+            return Ok(false);
+        }
+
+        if let Some(hook) = &self.before_exec
+            && !self.run_hook(hook, item, cmd, None)
+        {
+            self.status_message = Some(format!(
+                "before_exec falló: \"{}\" no se ejecutó",
+                item.label
+            ));
+            return Ok(false);
+        }
+
+        // En tmux: a una ventana o panel nuevo, tmenu sigue a la vista
+        if let Some(target) = item.tmux.filter(|_| tmux::inside()) {
+            if let [(_, parts)] = steps.as_slice() {
+                self.spawn_in_tmux(item, cmd, parts, target);
+            } else {
+                self.status_message = Some(format!(
+                    "\"{}\" tiene varios pasos: no se puede ejecutar en tmux",
+                    item.label
+                ));
+            }
+            return Ok(item.exit_after);
+        }
+
+        // Separado: se lanza en su sesión (o en otra terminal) y no se sigue.
+        // Sin terminal la elevación no podría pedir la contraseña.
+        if let Some(detach) = item
+            .detach
+            .filter(|detach| *detach == Detach::Terminal || !item.elevate)
+        {
+            if let [(_, parts)] = steps.as_slice() {
+                self.spawn_detached(item, cmd, parts, detach);
+            } else {
+                self.status_message = Some(format!(
+                    "\"{}\" tiene varios pasos: no se puede ejecutar aparte",
+                    item.label
+                ));
+            }
+            return Ok(item.exit_after);
+        }
+
+        // Background: se lanza y se vuelve al menú sin esperar
+        if item.background && !item.elevate {
+            if let [(_, parts)] = steps.as_slice() {
+                self.spawn_background(item, cmd, parts);
+            } else {
+                self.status_message = Some(format!(
+                    "\"{}\" tiene varios pasos: no se puede ejecutar en segundo plano",
+                    item.label
+                ));
+            }
+            return Ok(item.exit_after);
+        }
+
+        // Capture: la salida se muestra en un panel sin salir de la TUI
+        if mode == ExecutionMode::Capture && !item.elevate {
+            self.run_captured(item, cmd, &steps);
+            return Ok(item.exit_after);
+        }
+
+        // Restaurar terminal a modo normal
+        suspend_tui();
+
+        // Si el modo es Clean, limpiar pantalla antes de ejecutar
+        if mode == ExecutionMode::Clean {
+            let _ = std::process::Command::new("clear").spawn();
+        }
+
+        let started = Instant::now();
+        let mut success = true;
+        for (i, (step, parts)) in steps.iter().enumerate() {
+            if steps.len() > 1 {
+                println!("\n[{}/{}] {}", i + 1, steps.len(), step);
+            }
+            if !self.run_foreground(item, step, parts) {
+                success = false;
+                if i + 1 < steps.len() {
+                    eprintln!(
+                        "[error] el paso {}/{} falló; no se ejecutan los siguientes",
+                        i + 1,
+                        steps.len()
+                    );
+                }
+                break;
+            }
+        }
+        self.record_run(item, cmd, success, started);
+        self.after_run(item, success);
+
+        // Sin menú al que volver no tiene sentido la pausa
+        if item.wait.unwrap_or(self.wait) && !item.exit_after {
+            println!("\nPresioná Enter para volver al menú...");
+            let _ = io::stdin().read_line(&mut String::new());
+        }
 
-        println!("\nPresioná Enter para volver al menú...");
-        let _ = io::stdin().read_line(&mut String::new());
-
         // Volver a modo TUI
-        if let Err(e) = enable_raw_mode() {
-            eprintln!("[warn] no se pudo activar raw mode: {}", e);
+        resume_tui(terminal)?;
+        Ok(item.exit_after)
+    }
+
+    /// Programa y argumentos de cada paso de `cmd` (uno por línea). Sin
+    /// shell se parsea respetando quoting ("arg con espacios" es un solo
+    /// arg); con shell, el paso completo se le pasa como argumento. Con
+    /// `[elevate=true]` se antepone el programa de elevación.
+    fn command_steps<'c>(&self, item: &MenuItem, cmd: &'c str) -> Vec<(&'c str, Vec<String>)> {
+        let shell = item.shell.as_ref().unwrap_or(&self.shell);
+        let elevator = item
+            .elevate
+            .then(|| self.elevate_with.clone().unwrap_or_else(default_elevator));
+        cmd.lines()
+            .map(|step| {
+                let parts = shell.command_line(step);
+                let parts = match &elevator {
+                    Some(elevator) => {
+                        elevated_command_line(elevator, parts, self.env.iter().chain(&item.env))
+                    }
+                    None => parts,
+                };
+                (step, parts)
+            })
+            .collect()
+    }
+
+    /// Líneas `$ ...` de cada paso, el directorio y el entorno con que se
+    /// ejecutaría `item`: lo que muestran la simulación y los detalles.
+    fn run_summary(&self, item: &MenuItem, steps: &[(&str, Vec<String>)]) -> Vec<String> {
+        let mut lines: Vec<String> = steps
+            .iter()
+            .map(|(_, parts)| {
+                let line = shlex::try_join(parts.iter().map(String::as_str))
+                    .unwrap_or_else(|_| parts.join(" "));
+                format!("$ {}", line)
+            })
+            .collect();
+        let mut details = Vec::new();
+        if let Some(cwd) = &item.cwd {
+            details.push(format!("Directorio: {}", cwd.display()));
+        }
+        // Las del ítem pisan a las globales del mismo nombre
+        let env: Vec<(&str, &str)> = self
+            .env
+            .iter()
+            .filter(|(name, _)| !item.env.iter().any(|(own, _)| own == name))
+            .chain(&item.env)
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        if !env.is_empty() {
+            details.push(format!("Entorno: {}", writer::env_assignments(env)));
+        }
+        if !details.is_empty() {
+            lines.push(String::new());
+            lines.extend(details);
+        }
+        lines
+    }
+
+    /// Muestra en un popup lo que se ejecutaría (`--dry-run`): cada paso con
+    /// sus argumentos ya resueltos (shell y elevación incluidos), el directorio
+    /// y las variables de entorno.
+    fn show_dry_run(&mut self, item: &MenuItem, steps: &[(&str, Vec<String>)]) {
+        self.popup = Some(TextPopup {
+            title: format!("Simulación: {}", item.label),
+            lines: self.run_summary(item, steps),
+            scroll: 0,
+        });
+    }
+
+    /// Popup con el comando completo del ítem seleccionado entre `items` tal
+    /// como se ejecutaría, con los campos del wizard que ya tienen una
+    /// variable de sesión reemplazados, su directorio, su entorno y el resto
+    /// de sus atributos.
+    pub fn open_command_details(&mut self, items: &[MenuItem]) {
+        let Some(item) = self.state.selected().and_then(|i| items.get(i)) else {
+            return;
+        };
+        let MenuAction::Execute(cmd) = &item.action else {
+            self.status_message = Some(format!("\"{}\" no ejecuta un comando", item.label));
+            return;
+        };
+        let mut cmd = item.command_sequence(cmd.trim().trim_matches('"'));
+        for param in parser::extract_params(&cmd) {
+            if let Some(value) = self.vars.get(&param.label).filter(|_| param.pick.is_none()) {
+                cmd = cmd.replace(&param.placeholder, value);
+            }
+        }
+        let mut lines = self.run_summary(item, &self.command_steps(item, &cmd));
+        // Los que quedan los pide el wizard al ejecutar
+        let pending: Vec<String> = parser::extract_params(&cmd)
+            .into_iter()
+            .map(|param| param.label)
+            .collect();
+        if !pending.is_empty() {
+            lines.push(format!("Se pedirán al ejecutar: {}", pending.join(", ")));
+        }
+        // El directorio, el entorno y el shell ya están arriba
+        let flags: Vec<String> = writer::item_flags(item)
+            .into_iter()
+            .filter(|flag| {
+                !["cwd=", "env=", "shell="]
+                    .iter()
+                    .any(|key| flag.starts_with(key))
+            })
+            .collect();
+        if !flags.is_empty() {
+            lines.push(String::new());
+            lines.push("Atributos:".to_string());
+            lines.extend(flags.iter().map(|flag| format!("  {}", flag)));
+        }
+        self.popup = Some(TextPopup {
+            title: item.label.clone(),
+            lines,
+            scroll: 0,
+        });
+    }
+
+    /// Ejecuta un paso con la terminal del usuario y espera a que termine.
+    /// Retorna `true` si terminó bien. El límite de `[timeout=...]` es por paso.
+    fn run_foreground(&mut self, item: &MenuItem, cmd: &str, parts: &[String]) -> bool {
+        self.run_foreground_code(item, cmd, parts) == Some(0)
+    }
+
+    /// Como [`App::run_foreground`], pero retorna el código de salida del
+    /// paso: `None` si no arrancó, se pasó del timeout o lo cortó una señal.
+    fn run_foreground_code(&mut self, item: &MenuItem, cmd: &str, parts: &[String]) -> Option<i32> {
+        let Some(mut command) = build_command(item, parts, &self.env) else {
+            return Some(0);
+        };
+        if item.timeout.is_some() {
+            process::isolate(&mut command, true);
+        }
+        match command.spawn() {
+            Ok(mut child) => {
+                let status = process::wait_timeout(&mut child, item.timeout);
+                if item.timeout.is_some() {
+                    process::reclaim_terminal();
+                }
+                let code = status
+                    .as_ref()
+                    .ok()
+                    .copied()
+                    .flatten()
+                    .and_then(|s| s.code());
+                log_exit(cmd, &status);
+                self.log_history(cmd, code);
+                if let Ok(None) = status {
+                    let message = timeout_message(item);
+                    eprintln!("\n[timeout] {}", message);
+                    self.status_message = Some(message);
+                }
+                code
+            }
+            Err(e) => {
+                // Queda también en la barra de estado: sin pausa el mensaje
+                // de la terminal no llega a verse
+                let message = spawn_failure(&parts[0], e);
+                eprintln!("[error] {}", message);
+                self.status_message = Some(message);
+                None
+            }
+        }
+    }
+
+    /// Ejecuta el comando capturando stdout y stderr para mostrarlos en el
+    /// panel de salida (`execution_mode: capture`). La entrada estándar queda
+    /// cerrada: es para comandos cortos y no interactivos (`git status`, `df -h`).
+    /// En una secuencia, la salida de cada paso va precedida de su número.
+    fn run_captured(&mut self, item: &MenuItem, cmd: &str, steps: &[(&str, Vec<String>)]) {
+        let started = Instant::now();
+        let mut text = String::new();
+        let mut success = true;
+        for (i, (step, parts)) in steps.iter().enumerate() {
+            let Some(mut command) = build_command(item, parts, &self.env) else {
+                continue;
+            };
+            if steps.len() > 1 {
+                text.push_str(&format!("[{}/{}] {}\n", i + 1, steps.len(), step));
+            }
+            match process::output_timeout(command.stdin(Stdio::null()), item.timeout) {
+                Ok((stdout, stderr, status)) => {
+                    log_exit(step, &Ok(status));
+                    self.log_history(step, status.and_then(|s| s.code()));
+                    // stdout y stderr llegan por separado: stderr se agrega al final
+                    text.push_str(&String::from_utf8_lossy(&stdout));
+                    text.push_str(&String::from_utf8_lossy(&stderr));
+                    if status.is_none() {
+                        text.push_str(&format!("\n[timeout] {}\n", timeout_message(item)));
+                    }
+                    success = status.is_some_and(|s| s.success());
+                }
+                Err(e) => {
+                    text.push_str(&spawn_failure(&parts[0], e));
+                    text.push('\n');
+                    success = false;
+                }
+            }
+            if !success {
+                if i + 1 < steps.len() {
+                    text.push_str(&format!(
+                        "\n[error] el paso {}/{} falló; no se ejecutan los siguientes\n",
+                        i + 1,
+                        steps.len()
+                    ));
+                }
+                break;
+            }
+        }
+        self.record_run(item, cmd, success, started);
+        self.after_run(item, success);
+        if let Some(filter) = &item.output_filter {
+            match script::filter_output(filter, &text, success) {
+                Ok(filtered) => text = filtered,
+                Err(e) => text.push_str(&format!("\n[error] {}", e)),
+            }
+        }
+        self.vars
+            .set(&item.label, text.trim_end(), VarSource::Output);
+        self.output = Some(CapturedOutput::new(&item.label, &text, success));
+    }
+
+    /// Lanza el comando como trabajo en segundo plano. Si no se puede lanzar,
+    /// el error se informa en la barra de estado.
+    fn spawn_background(&mut self, item: &MenuItem, cmd: &str, parts: &[String]) {
+        let (Some(command), Some(key)) = (build_command(item, parts, &self.env), item_key(item))
+        else {
+            return;
+        };
+        match self.jobs.spawn(key, item, cmd, command) {
+            // Se registra en el historial al terminar, con su código de salida
+            Ok(()) => {
+                self.status_message =
+                    Some(format!("\"{}\" se ejecuta en segundo plano", item.label));
+            }
+            Err(e) => {
+                self.status_message = Some(spawn_failure(&parts[0], e));
+            }
+        }
+    }
+
+    /// Lanza `item` separado de tmenu (`[detach=...]`). No se espera ni se
+    /// conoce su resultado: queda en el historial sin código de salida, y
+    /// para las estadísticas y `after_exec` cuenta como bien si arrancó.
+    fn spawn_detached(&mut self, item: &MenuItem, cmd: &str, parts: &[String], detach: Detach) {
+        let line = match detach {
+            Detach::Session => Some(parts.to_vec()),
+            Detach::Terminal => {
+                detach::in_terminal(item.terminal.as_deref().or(self.terminal.as_deref()), parts)
+            }
+        };
+        let Some(line) = line else {
+            self.status_message =
+                Some("No se encontró un emulador de terminal: configurá `terminal:`".to_string());
+            return;
+        };
+        let Some(mut command) = build_command(item, &line, &self.env) else {
+            return;
+        };
+        let started = Instant::now();
+        match detach::spawn_detached(&mut command) {
+            Ok(()) => {
+                self.log_history(cmd, None);
+                self.record_run(item, cmd, true, started);
+                self.status_message = Some(format!("\"{}\" se abrió aparte", item.label));
+            }
+            Err(e) => {
+                self.status_message = Some(spawn_failure(&line[0], e));
+            }
+        }
+    }
+
+    /// Manda `item` a una ventana o panel nuevo de tmux (`[tmux=...]`). Como
+    /// con `[detach=...]`, tmenu no espera el resultado: el historial lo
+    /// registra sin código de salida.
+    fn spawn_in_tmux(&mut self, item: &MenuItem, cmd: &str, parts: &[String], target: TmuxTarget) {
+        let cwd = item
+            .cwd
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+        let line = tmux::command_line(
+            target,
+            &item.label,
+            parts,
+            &cwd,
+            self.env.iter().chain(&item.env),
+            item.wait.unwrap_or(self.wait),
+        );
+        let started = Instant::now();
+        let result = Command::new(&line[0])
+            .args(&line[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output();
+        match result {
+            Ok(output) if output.status.success() => {
+                self.log_history(cmd, None);
+                self.record_run(item, cmd, true, started);
+                self.status_message = Some(format!("\"{}\" se abrió en tmux", item.label));
+            }
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                self.status_message =
+                    Some(format!("tmux: {}", stderr.lines().next().unwrap_or("")));
+            }
+            Err(e) => self.status_message = Some(spawn_failure("tmux", e)),
+        }
+    }
+
+    /// Abre la vista de trabajos con el más reciente seleccionado.
+    pub fn open_jobs_view(&mut self) {
+        if self.jobs.is_empty() {
+            self.status_message = Some("No hay trabajos en segundo plano".to_string());
+            return;
+        }
+        let mut state = ListState::default();
+        state.select(Some(self.jobs.len() - 1));
+        self.jobs_view = Some(state);
+    }
+
+    /// Muestra en el panel de salida lo que lleva escrito el trabajo `index`.
+    pub fn show_job_output(&mut self, index: usize) {
+        let Some(job) = self.jobs.get(index) else {
+            return;
+        };
+        let text = job.output();
+        let success = job.status == JobStatus::Running || job.status == JobStatus::Finished(true);
+        self.output = Some(CapturedOutput::new(&job.item.label, &text, success));
+    }
+
+    /// Termina el trabajo `index`. Cuenta como una ejecución fallida.
+    pub fn kill_job(&mut self, index: usize) {
+        if !self.jobs.kill(index) {
+            return;
+        }
+        let Some(job) = self.jobs.get(index) else {
+            return;
+        };
+        let (item, cmd, started) = (job.item.clone(), job.cmd.clone(), job.started);
+        self.log_history(&cmd, None);
+        self.record_run(&item, &cmd, false, started);
+        self.status_message = Some(format!("\"{}\" terminado", item.label));
+    }
+
+    /// Quita de la vista el trabajo `index` ya terminado, con su salida. La
+    /// vista se cierra cuando no queda ninguno.
+    pub fn dismiss_job(&mut self, index: usize) {
+        if !self.jobs.dismiss(index) {
+            self.status_message = Some("El trabajo sigue en curso: x lo termina".to_string());
+            return;
+        }
+        if self.jobs.is_empty() {
+            self.jobs_view = None;
+        } else if let Some(state) = self.jobs_view.as_mut() {
+            state.select(Some(index.min(self.jobs.len() - 1)));
+        }
+    }
+
+    /// Si el paso actual del wizard es un `{pick:...}` sin cargar, ejecuta su
+    /// comando y ofrece sus líneas. Si falla o no imprime nada, el wizard se
+    /// cancela y se avisa en la barra de estado.
+    pub fn prepare_wizard_step(&mut self) {
+        let Some(wizard) = &self.wizard else { return };
+        let Some(pick) = wizard.pending_pick() else {
+            return;
+        };
+        let item = &wizard.item;
+        let parts = item
+            .shell
+            .as_ref()
+            .unwrap_or(&self.shell)
+            .command_line(pick);
+        let output = build_command(item, &parts, &self.env)
+            .map(|mut command| command.stdin(Stdio::null()).stderr(Stdio::null()).output());
+
+        let options: Vec<String> = match output {
+            Some(Ok(output)) => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Some(Err(e)) => {
+                self.status_message = Some(spawn_failure(&parts[0], e));
+                self.wizard = None;
+                return;
+            }
+            None => Vec::new(),
+        };
+        if options.is_empty() {
+            self.status_message = Some(format!("'{}' no devolvió opciones", pick));
+            self.wizard = None;
+            return;
+        }
+        if let Some(wizard) = self.wizard.as_mut() {
+            wizard.set_options(options);
+        }
+    }
+
+    /// Abre la ayuda extendida del ítem seleccionado entre `items`, si tiene.
+    pub fn open_item_help(&mut self, items: &[MenuItem]) {
+        let Some(item) = self.state.selected().and_then(|i| items.get(i)) else {
+            return;
+        };
+        let Some(help) = &item.help else {
+            self.status_message = Some(format!("\"{}\" no tiene ayuda", item.label));
+            return;
+        };
+        match help.load() {
+            Ok(text) => {
+                self.popup = Some(TextPopup {
+                    title: item.label.clone(),
+                    lines: text.lines().map(str::to_string).collect(),
+                    scroll: 0,
+                })
+            }
+            Err(e) => {
+                self.status_message = Some(format!("no se pudo leer la ayuda: {}", e));
+            }
+        }
+    }
+
+    /// Muestra en qué archivo y línea se definió el ítem seleccionado entre `items`.
+    pub fn open_source_view(&mut self, items: &[MenuItem]) {
+        let Some(item) = self.state.selected().and_then(|i| items.get(i)) else {
+            return;
+        };
+        let Some(location) = item.source.clone() else {
+            self.status_message = Some(format!("\"{}\" no tiene origen conocido", item.label));
+            return;
+        };
+        // La línea se relee del archivo: puede haber cambiado desde que se cargó
+        let snippet = location
+            .file
+            .as_ref()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .and_then(|content| content.lines().nth(location.line - 1).map(str::to_string));
+        self.source_view = Some(SourceView {
+            label: item.label.clone(),
+            location,
+            snippet,
+        });
+    }
+
+    /// Copia al portapapeles el comando del ítem seleccionado, tal como está
+    /// escrito en el menú (con los `{{...}}` sin completar) para ajustarlo en
+    /// el shell. Los pasos de una secuencia se encadenan con `&&`.
+    pub fn copy_command(&mut self, items: &[MenuItem]) {
+        let Some(item) = self.state.selected().and_then(|i| items.get(i)) else {
+            return;
+        };
+        let Some(text) = command_line(item) else {
+            self.status_message = Some(format!("\"{}\" no ejecuta un comando", item.label));
+            return;
+        };
+        self.status_message = Some(match clipboard::copy(&text) {
+            Ok(()) => format!("Copiado: {}", text),
+            Err(e) => format!("no se pudo copiar al portapapeles: {}", e),
+        });
+    }
+
+    /// Abre la vista de variables de sesión con la primera seleccionada.
+    /// Abre la vista de árbol con el camino hasta el nivel actual desplegado
+    /// y el ítem seleccionado marcado.
+    pub fn open_tree_view(&mut self) {
+        let mut path = self.selection_path();
+        let mut expanded = HashSet::new();
+        for depth in 1..=path.len() {
+            expanded.insert(path[..depth].to_vec());
+        }
+        if let Some(item) = self
+            .state
+            .selected()
+            .and_then(|i| self.current_items.get(i))
+        {
+            path.push(item.label.clone());
         }
-        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture) {
-            eprintln!("[warn] no se pudo restaurar pantalla alternativa: {}", e);
+        let mut view = TreeView {
+            state: ListState::default(),
+            expanded,
+        };
+        // Dentro de un submenú generado, que no se despliega, se marca ese submenú
+        let rows = self.tree_rows(&view);
+        let index = (1..=path.len())
+            .rev()
+            .find_map(|n| rows.iter().position(|row| row.full_path() == path[..n]));
+        view.state.select(Some(index.unwrap_or(0)));
+        self.tree_view = Some(view);
+    }
+
+    /// Filas visibles de la vista de árbol: los ítems del menú raíz y, debajo
+    /// de cada submenú desplegado, los suyos. Los submenús generados no se
+    /// despliegan (leerían disco o ejecutarían comandos).
+    pub fn tree_rows(&self, view: &TreeView) -> Vec<TreeRow> {
+        fn collect(
+            items: &[MenuItem],
+            path: &mut Vec<String>,
+            view: &TreeView,
+            rows: &mut Vec<TreeRow>,
+        ) {
+            for item in items {
+                path.push(item.label.clone());
+                let children = match &item.action {
+                    MenuAction::OpenSubmenu(children) if view.expanded.contains(path) => {
+                        Some(children)
+                    }
+                    _ => None,
+                };
+                rows.push(TreeRow {
+                    path: path[..path.len() - 1].to_vec(),
+                    item: item.clone(),
+                    expanded: children.is_some(),
+                });
+                if let Some(children) = children {
+                    collect(children, path, view, rows);
+                }
+                path.pop();
+            }
+        }
+        let mut rows = Vec::new();
+        collect(&self.root_items, &mut Vec::new(), view, &mut rows);
+        rows
+    }
+
+    /// Despliega (`true`) o pliega el submenú seleccionado de la vista de
+    /// árbol. Desplegar uno ya abierto baja a su primer ítem; plegar algo que
+    /// no está desplegado sube al submenú que lo contiene.
+    pub fn tree_expand(&mut self, expand: bool) {
+        let Some(view) = &self.tree_view else { return };
+        let rows = self.tree_rows(view);
+        let Some(index) = view.state.selected().filter(|&i| i < rows.len()) else {
+            return;
+        };
+        let row = &rows[index];
+        let is_tree = matches!(row.item.action, MenuAction::OpenSubmenu(_));
+        let Some(view) = &mut self.tree_view else {
+            return;
+        };
+        match (expand, row.expanded) {
+            (true, false) if is_tree => {
+                view.expanded.insert(row.full_path());
+            }
+            (true, true)
+                if rows
+                    .get(index + 1)
+                    .is_some_and(|next| next.path == row.full_path()) =>
+            {
+                view.state.select(Some(index + 1))
+            }
+            (false, true) => {
+                view.expanded.remove(&row.full_path());
+            }
+            (false, false) => {
+                let parent = rows[..index]
+                    .iter()
+                    .rposition(|r| r.full_path() == row.path);
+                if let Some(parent) = parent {
+                    view.state.select(Some(parent));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Fila seleccionada de la vista de árbol.
+    pub fn tree_selected(&self) -> Option<TreeRow> {
+        let view = self.tree_view.as_ref()?;
+        let index = view.state.selected()?;
+        self.tree_rows(view).into_iter().nth(index)
+    }
+
+    /// Despliega todos los submenús de la vista de árbol.
+    pub fn tree_expand_all(&mut self) {
+        fn collect(items: &[MenuItem], path: &mut Vec<String>, out: &mut HashSet<Vec<String>>) {
+            for item in items {
+                if let MenuAction::OpenSubmenu(children) = &item.action {
+                    path.push(item.label.clone());
+                    out.insert(path.clone());
+                    collect(children, path, out);
+                    path.pop();
+                }
+            }
+        }
+        let mut expanded = HashSet::new();
+        collect(&self.root_items, &mut Vec::new(), &mut expanded);
+        if let Some(view) = &mut self.tree_view {
+            view.expanded = expanded;
+        }
+    }
+
+    /// Cierra la vista de árbol y navega hasta el ítem seleccionado en ella,
+    /// que queda seleccionado en su nivel. Retorna `false` si no se encontró.
+    pub fn leave_tree_view(&mut self) -> bool {
+        let Some(view) = self.tree_view.take() else {
+            return false;
+        };
+        let rows = self.tree_rows(&view);
+        match view.state.selected().and_then(|i| rows.get(i)) {
+            Some(row) => self.jump_to(&row.full_path()),
+            None => false,
+        }
+    }
+
+    pub fn open_vars_view(&mut self) {
+        if self.vars.is_empty() {
+            self.status_message = Some("No hay variables de sesión".to_string());
+            return;
+        }
+        let mut state = ListState::default();
+        state.select(Some(0));
+        self.vars_view = Some(VarsView {
+            state,
+            editing: None,
+        });
+    }
+
+    /// Como [`submenu_items`], más los submenús de favoritos y recientes, y
+    /// con los generados ordenados según `sort` y sin los ocultos.
+    fn submenu_items(&self, item: &MenuItem) -> Option<Result<MenuItems, TmenuError>> {
+        let order = match item.action {
+            MenuAction::Favorites => return Some(Ok(Rc::new(self.saved_items(&self.favorites)))),
+            MenuAction::Recent(limit) => {
+                let mut items = self.saved_items(&self.recent);
+                items.truncate(limit);
+                return Some(Ok(Rc::new(items)));
+            }
+            MenuAction::OpenSubmenu(_) => return submenu_items(item, &self.shell),
+            // El historial va del más reciente al más viejo salvo que el ítem pida otro orden
+            MenuAction::History(_) => item.sort.unwrap_or(SortOrder::File),
+            _ => item.sort.unwrap_or(self.sort),
+        };
+        Some(submenu_items(item, &self.shell)?.map(|items| {
+            let mut items = Rc::unwrap_or_clone(items);
+            // Los de `dir:` y `list:` heredan `hidden` del ítem, visible si se llegó acá
+            items.retain(|child| self.show_hidden || !child.hidden);
+            order.apply(&mut items);
+            Rc::new(items)
+        }))
+    }
+
+    /// Ítems de `keys` (favoritos o recientes) que están en el árbol del menú
+    /// (sin contar los de submenús generados), con todos sus atributos y en
+    /// el orden de `keys`.
+    fn saved_items(&self, keys: &[String]) -> Vec<MenuItem> {
+        fn collect(items: &[MenuItem], found: &mut HashMap<String, MenuItem>) {
+            for item in items {
+                match &item.action {
+                    MenuAction::OpenSubmenu(children) => collect(children, found),
+                    _ => {
+                        if let Some(key) = item_key(item) {
+                            found.entry(key).or_insert_with(|| item.clone());
+                        }
+                    }
+                }
+            }
+        }
+        let mut found = HashMap::new();
+        collect(&self.root_items, &mut found);
+        keys.iter().filter_map(|key| found.remove(key)).collect()
+    }
+
+    /// Agrega al principio del menú raíz los submenús de favoritos y de
+    /// recientes que tienen ítems, salvo que el menú ubique los suyos con
+    /// `favorites:` o `recent:`; los quita si quedan vacíos.
+    pub fn pin_submenus(&mut self) {
+        let is_favorites = |item: &MenuItem| item.action == MenuAction::Favorites;
+        let is_recent = |item: &MenuItem| matches!(item.action, MenuAction::Recent(_));
+        // Los agregados son los únicos sin ubicación en el archivo
+        let mut items: Vec<MenuItem> = self
+            .root_items
+            .iter()
+            .filter(|item| item.source.is_some() || !(is_favorites(item) || is_recent(item)))
+            .cloned()
+            .collect();
+        if !items.iter().any(is_recent) && !self.saved_items(&self.recent).is_empty() {
+            let limit = parser::DEFAULT_RECENT_LIMIT;
+            items.insert(0, MenuItem::new(RECENT_LABEL, MenuAction::Recent(limit)));
+        }
+        if !items.iter().any(is_favorites) && !self.saved_items(&self.favorites).is_empty() {
+            items.insert(0, MenuItem::new(FAVORITES_LABEL, MenuAction::Favorites));
+        }
+        let selected_label = self
+            .state
+            .selected()
+            .and_then(|i| self.current_items.get(i))
+            .map(|item| item.label.clone());
+        self.root_items = Rc::new(items);
+        if self.history.is_empty() {
+            self.current_items = self.root_items.clone();
+            if let Some(index) = selected_label
+                .and_then(|label| self.current_items.iter().position(|i| i.label == label))
+            {
+                self.state.select(Some(index));
+            }
+        }
+    }
+
+    /// Marca o desmarca como favorito el comando seleccionado y guarda la lista.
+    pub fn toggle_favorite(&mut self, items: &[MenuItem]) {
+        let Some(item) = self.state.selected().and_then(|i| items.get(i)) else {
+            return;
+        };
+        let Some(key) = item_key(item) else {
+            self.status_message = Some(format!("\"{}\" no ejecuta un comando", item.label));
+            return;
+        };
+        let message = match self.favorites.iter().position(|k| *k == key) {
+            Some(index) => {
+                self.favorites.remove(index);
+                format!("Quitado de favoritos: {}", item.label)
+            }
+            None => {
+                self.favorites.push(key);
+                format!("Agregado a favoritos: {}", item.label)
+            }
+        };
+        self.status_message = Some(match saved::save(saved::List::Favorites, &self.favorites) {
+            Ok(()) => message,
+            Err(e) => e.to_string().trim().to_string(),
+        });
+        self.pin_submenus();
+        // Dentro del submenú de favoritos, la lista se actualiza en el momento
+        let in_favorites = self.history.last().is_some_and(|entry| {
+            entry
+                .state
+                .selected()
+                .and_then(|i| entry.items.get(i))
+                .is_some_and(|item| item.action == MenuAction::Favorites)
+        });
+        if in_favorites && !self.search_mode {
+            self.current_items = Rc::new(self.saved_items(&self.favorites));
+            let last = self.current_items.len().checked_sub(1);
+            self.state
+                .select(self.state.selected().zip(last).map(|(i, last)| i.min(last)));
+        }
+    }
+
+    /// Si `item` tiene un trabajo en segundo plano todavía en curso.
+    pub fn is_running(&self, item: &MenuItem) -> bool {
+        item_key(item).is_some_and(|key| self.jobs.is_running(&key))
+    }
+
+    /// Registra una ejecución terminada: estadísticas, recientes y cooldown.
+    fn record_run(&mut self, item: &MenuItem, cmd: &str, success: bool, started: Instant) {
+        if let Some(hook) = &self.after_exec
+            && !self.run_hook(hook, item, cmd, Some(success))
+        {
+            self.status_message = Some("after_exec terminó con error".to_string());
+        }
+        if self.stats
+            && let Err(e) = stats::record(&item.label, cmd, success, started.elapsed())
+        {
+            eprintln!("[warn] no se pudieron guardar las estadísticas: {}", e);
+        }
+        if let Some(key) = item_key(item) {
+            saved::touch(&mut self.recent, &key);
+            if let Err(e) = saved::save(saved::List::Recent, &self.recent) {
+                eprintln!("[warn] no se pudieron guardar los recientes: {}", e);
+            }
+        }
+        if let (Some(cooldown), Some(key)) = (item.cooldown, item_key(item)) {
+            self.cooldowns.insert(key, Instant::now() + cooldown);
+        }
+    }
+
+    /// Ejecuta un hook de `before_exec:`/`after_exec:` con el shell global (o
+    /// el del sistema) y el entorno global, sin entrada ni salida en pantalla (puede correr con la
+    /// interfaz activa). Recibe `TMENU_LABEL` y `TMENU_COMMAND`, y después del
+    /// comando `TMENU_STATUS` (`ok` o `fail`). Retorna `true` si terminó bien.
+    fn run_hook(&self, hook: &str, item: &MenuItem, cmd: &str, success: Option<bool>) -> bool {
+        // Los hooks siempre pasan por un shell: suelen usar `$TMENU_*` y redirecciones
+        let parts = match &self.shell {
+            Shell::None => Shell::system().command_line(hook),
+            shell => shell.command_line(hook),
+        };
+        let Some((bin, args)) = parts.split_first() else {
+            return true;
+        };
+        let mut command = new_command(bin, args);
+        command
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .env("TMENU_LABEL", &item.label)
+            .env(
+                "TMENU_COMMAND",
+                cmd.lines().collect::<Vec<_>>().join(" && "),
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(success) = success {
+            command.env("TMENU_STATUS", if success { "ok" } else { "fail" });
+        }
+        command.status().is_ok_and(|status| status.success())
+    }
+
+    /// Aplica `on_success` u `on_failure` según cómo terminó `item`. Los saltos
+    /// se hacen en el momento; los comandos quedan en `follow_up`.
+    fn after_run(&mut self, item: &MenuItem, success: bool) {
+        let follow_up = if success {
+            &item.on_success
+        } else {
+            &item.on_failure
+        };
+        match follow_up {
+            Some(FollowUp::Command(cmd)) => self.follow_up = Some(follow_up_item(item, cmd)),
+            Some(FollowUp::Goto(path)) if !self.jump_to(path) => {
+                self.status_message = Some(format!("no se encontró el ítem '{}'", item_path(path)));
+            }
+            Some(FollowUp::Goto(_)) | None => {}
+        }
+    }
+
+    /// Navega desde la raíz por el camino de etiquetas `path` y deja
+    /// seleccionado el último. Retorna `false` si alguna etiqueta no existe
+    /// (la navegación queda en el último nivel encontrado).
+    pub fn jump_to(&mut self, path: &[String]) -> bool {
+        let Some((target, parents)) = path.split_last() else {
+            return false;
+        };
+        self.search_text.clear();
+        self.search_mode = false;
+        self.history.clear();
+        self.current_title = self.root_title.clone();
+        self.current_items = self.root_items.clone();
+        self.state = ListState::default();
+        self.state.select(Some(0));
+        for label in parents {
+            let found = self.current_items.iter().position(|i| &i.label == label);
+            let Some(index) = found else { return false };
+            let Some(Ok(sub_items)) = self.submenu_items(&self.current_items[index]) else {
+                return false;
+            };
+            self.state.select(Some(index));
+            self.enter_submenu(label.clone(), sub_items);
+        }
+        match self.current_items.iter().position(|i| &i.label == target) {
+            Some(index) => {
+                self.state.select(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Etiquetas de los submenús abiertos y del ítem seleccionado: el camino
+    /// que [`App::jump_to`] recorre para volver al mismo lugar.
+    pub fn position(&self) -> Vec<String> {
+        let mut path = self.selection_path();
+        let items = self.filtered_items();
+        if let Some(item) = self.state.selected().and_then(|i| items.get(i)) {
+            path.push(item.label.clone());
+        }
+        path
+    }
+
+    /// Guarda [`App::position`] como la última del archivo de menú. Un menú
+    /// sin archivo (entrada estándar, armado en código) o con `restore: false`
+    /// no guarda nada.
+    pub fn save_position(&self) -> Result<(), TmenuError> {
+        let Some(menu) = self.menu_path.as_ref().filter(|_| self.restore) else {
+            return Ok(());
+        };
+        let path = self.position();
+        if path.is_empty() {
+            return Ok(());
+        }
+        position::save(menu, &path)
+    }
+
+    /// Vuelve a la última posición guardada del archivo de menú, con los
+    /// submenús del camino en el historial para volver con Esc como si se
+    /// hubiera navegado hasta ahí. Si el menú cambió y el camino ya no existe,
+    /// queda en el último nivel que encontró.
+    pub fn restore_position(&mut self) -> Result<(), TmenuError> {
+        let Some(menu) = self.menu_path.as_ref().filter(|_| self.restore) else {
+            return Ok(());
+        };
+        let Some(path) = position::load(menu)? else {
+            return Ok(());
+        };
+        debug!("última posición: {}", item_path(&path));
+        if !self.jump_to(&path) && self.history.is_empty() {
+            self.open_first_tab();
+        }
+        Ok(())
+    }
+
+    /// Camino desde el menú raíz hasta el nivel actual para el título
+    /// (`Menu › Deploy › Staging`). Si no entra, se omiten los niveles
+    /// intermedios más cercanos a la raíz: `Menu › .. › Staging › Prod`.
+    pub fn breadcrumb(&self) -> String {
+        const MAX_WIDTH: usize = 40;
+
+        if self.history.is_empty() {
+            return self.current_title.clone();
+        }
+
+        let root = self.history[0].title.as_str();
+        let current = self.current_title.as_str();
+
+        // Construir la cadena completa y ver si entra
+        let mut parts: Vec<&str> = self.history.iter().map(|e| e.title.as_str()).collect();
+        parts.push(current);
+        let full = parts.join(" › ");
+
+        if full.width() <= MAX_WIDTH {
+            return full;
+        }
+
+        // Truncar: Raíz › .. › los últimos niveles que entren
+        for skip in 2..parts.len() {
+            let candidate = format!("{} › .. › {}", root, parts[skip..].join(" › "));
+            if candidate.width() <= MAX_WIDTH {
+                return candidate;
+            }
+        }
+
+        // Caso extremo: solo el nivel actual (root o current son muy largos)
+        truncate_to_width(current, MAX_WIDTH, "")
+    }
+
+    /// Finaliza el wizard: si requiere confirmación, muestra modal; sino, ejecuta directo.
+    pub fn finish_wizard<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> Result<bool, TmenuError> {
+        if let Some(wizard) = self.wizard.take() {
+            let cmd = wizard.resolve();
+            for (param, value) in wizard.params.iter().zip(&wizard.values) {
+                self.vars.set(&param.label, value, VarSource::Prompt);
+            }
+
+            if wizard.item.needs_confirmation() {
+                // Pedir confirmación antes de ejecutar el comando resuelto
+                return self.request_command_confirmation(terminal, &wizard.item, &cmd);
+            } else {
+                // Ejecutar directo sin confirmación
+                return self.execute_external_command(terminal, &wizard.item, &cmd);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Si `cmd` se puede ejecutar como `item`. Con un shell (`shell:` o
+    /// `[shell=...]`) se acepta cualquier línea: pipes, redirecciones, globs y
+    /// variables son justamente para lo que se eligió el shell. Sin shell se
+    /// aplica [`App::is_safe_command`].
+    pub fn allows_command(&self, item: &MenuItem, cmd: &str) -> bool {
+        matches!(
+            item.shell.as_ref().unwrap_or(&self.shell),
+            Shell::Program(_)
+        ) || Self::is_safe_command(cmd)
+    }
+
+    /// Valida que el comando no contenga path traversal ni caracteres de shell peligrosos.
+    ///
+    /// Nota: no se usan pipes/shell, así que `|`, `&`, `;` no son vectores de inyección
+    /// en este contexto — pero `..` sí puede usarse para path traversal en argumentos.
+    ///
+    /// En una secuencia de pasos se valida cada línea.
+    pub fn is_safe_command(cmd: &str) -> bool {
+        cmd.lines().all(|step| {
+            // Rechazar path traversal explícito
+            if step.split_whitespace().any(|part| part.contains("..")) {
+                return false;
+            }
+            // Allowlist de caracteres válidos (extendida respecto al original)
+            step.chars().all(|c| {
+                matches!(c,
+                    'a'..='z' | 'A'..='Z' | '0'..='9'
+                    | ' ' | '.' | '/' | '_' | '-' | '='
+                    | ':' | '@' | '+' | '%' | '~' | ','
+                    | '\'' | '"'
+                )
+            })
+        })
+    }
+}
+
+/// Arma el proceso a lanzar a partir de programa y argumentos, con los
+/// atributos del ítem (directorio de trabajo). `None` si el comando está vacío.
+fn build_command(item: &MenuItem, parts: &[String], env: &[(String, String)]) -> Option<Command> {
+    let (bin, args) = parts.split_first()?;
+    let mut command = new_command(bin, args);
+    // Las variables del ítem van después: pisan a las globales del mismo nombre
+    command.envs(env.iter().chain(&item.env).map(|(k, v)| (k, v)));
+    if let Some(cwd) = &item.cwd {
+        command.current_dir(cwd);
+    }
+    Some(command)
+}
+
+/// Proceso para `bin` con `args`. En Windows `cmd /C` no entiende las comillas
+/// con que se escapan los argumentos (las reglas de MSVC, que sí usa
+/// PowerShell): la línea se le pasa tal cual.
+fn new_command(bin: &str, args: &[String]) -> Command {
+    let mut command = Command::new(bin);
+    #[cfg(windows)]
+    if let [flag, line] = args
+        && flag.eq_ignore_ascii_case("/C")
+        && Path::new(bin)
+            .file_stem()
+            .is_some_and(|stem| stem.eq_ignore_ascii_case("cmd"))
+    {
+        use std::os::windows::process::CommandExt;
+        command.arg(flag).raw_arg(line);
+        return command;
+    }
+    command.args(args);
+    command
+}
+
+/// Ítem que ejecuta el comando `cmd` de `on_success`/`on_failure` con el
+/// entorno de `item` (directorio, variables, shell, modo). No hereda lo que
+/// es propio de la acción original: confirmación, pasos, cooldown ni otros
+/// seguimientos, para que no se encadenen.
+fn follow_up_item(item: &MenuItem, cmd: &str) -> MenuItem {
+    MenuItem {
+        label: format!("{} › {}", item.label, cmd),
+        action: MenuAction::Execute(cmd.to_string()),
+        require_confirmation: false,
+        confirm_message: None,
+        confirm_text: None,
+        schedule: None,
+        cooldown: None,
+        background: false,
+        detach: None,
+        output_filter: None,
+        terminal: None,
+        tmux: None,
+        steps: Vec::new(),
+        on_success: None,
+        on_failure: None,
+        help: None,
+        ..item.clone()
+    }
+}
+
+/// Aviso para un ítem que superó su `[timeout=...]`.
+fn timeout_message(item: &MenuItem) -> String {
+    let limit = item.timeout.unwrap_or_default();
+    format!(
+        "\"{}\" superó el tiempo límite de {}s y se terminó",
+        item.label,
+        limit.as_secs_f64()
+    )
+}
+
+/// Programa de elevación por defecto: el primero de la lista que esté en el
+/// PATH (`sudo` o `doas` en Unix, `gsudo` o el `sudo` de Windows 11).
+fn default_elevator() -> Vec<String> {
+    let candidates: &[&str] = if cfg!(windows) {
+        &["gsudo", "sudo"]
+    } else {
+        &["sudo", "doas"]
+    };
+    let found = candidates
+        .iter()
+        .find(|name| in_path(name))
+        .unwrap_or(&candidates[0]);
+    vec![found.to_string()]
+}
+
+/// Cómo terminó un trabajo en segundo plano, para la notificación.
+fn job_outcome(success: bool, timed_out: bool, code: Option<i32>) -> String {
+    match (success, timed_out, code) {
+        (true, _, _) => "Terminó bien".to_string(),
+        (_, true, _) => "Se venció el tiempo límite".to_string(),
+        (_, _, Some(code)) => format!("Terminó con error (código {})", code),
+        _ => "Terminó con error".to_string(),
+    }
+}
+
+/// Si `program` está en algún directorio del PATH.
+pub(crate) fn in_path(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        dir.join(program).is_file()
+            || (cfg!(windows) && dir.join(format!("{program}.exe")).is_file())
+    })
+}
+
+/// Antepone el programa de elevación a `parts`. `sudo` y `doas` limpian el
+/// entorno, así que en Unix las variables de `env:` y `[env=...]` se pasan
+/// explícitamente con `env NOMBRE=valor`.
+fn elevated_command_line<'a>(
+    elevator: &[String],
+    parts: Vec<String>,
+    env: impl Iterator<Item = &'a (String, String)>,
+) -> Vec<String> {
+    let mut line = elevator.to_vec();
+    if cfg!(unix) {
+        let assignments: Vec<String> = env.map(|(k, v)| format!("{}={}", k, v)).collect();
+        if !assignments.is_empty() {
+            line.push("env".to_string());
+            line.extend(assignments);
+        }
+    }
+    line.extend(parts);
+    line
+}
+
+/// Comando de un ítem tal como está escrito en el menú, con los pasos de una
+/// secuencia encadenados con `&&`. `None` si el ítem no ejecuta un comando.
+pub fn command_line(item: &MenuItem) -> Option<String> {
+    let MenuAction::Execute(cmd) = &item.action else {
+        return None;
+    };
+    Some(
+        item.steps
+            .iter()
+            .fold(cmd.clone(), |text, step| text + " && " + step.as_str()),
+    )
+}
+
+/// Identifica un comando (para su cooldown o sus trabajos en segundo plano):
+/// etiqueta y comando original (sin interpolar), para que sobreviva a
+/// búsquedas y recargas del menú.
+pub fn item_key(item: &MenuItem) -> Option<String> {
+    match &item.action {
+        MenuAction::Execute(cmd) => Some(format!("{}\0{}", item.label, cmd)),
+        _ => None,
+    }
+}
+
+/// Ítems que muestra un submenú al abrirlo: los del árbol para submenús estáticos,
+/// o generados en el momento para los dinámicos. `None` si el ítem no abre un submenú.
+///
+/// Las entradas de `dir:`, `list:` y `script:` heredan los atributos del ítem que las genera
+/// (confirmación, directorio de trabajo, etc.).
+fn submenu_items(item: &MenuItem, shell: &Shell) -> Option<Result<MenuItems, TmenuError>> {
+    let generated = match &item.action {
+        // Los arma la app, que conoce los favoritos y los recientes
+        MenuAction::Favorites | MenuAction::Recent(_) => return None,
+        MenuAction::OpenSubmenu(items) => return Some(Ok(Rc::clone(items))),
+        MenuAction::MenuFile(path) => return Some(provider::menu_file_items(path)),
+        MenuAction::Directory(dir) => provider::directory_items(dir),
+        MenuAction::History(limit) => provider::history_items(*limit),
+        MenuAction::Dynamic { source, template } => {
+            let shell = item.shell.as_ref().unwrap_or(shell);
+            provider::command_items(source, template.as_deref(), shell)
+        }
+        MenuAction::Plugin(command) => plugin::plugin_items(command),
+        MenuAction::Script(code) => script::items(code),
+        MenuAction::SshHosts => provider::ssh_items(),
+        MenuAction::Execute(_) | MenuAction::Quit => return None,
+    };
+    Some(generated.map(|items| {
+        Rc::new(
+            items
+                .into_iter()
+                .map(|child| MenuItem {
+                    label: child.label,
+                    action: child.action,
+                    ..item.clone()
+                })
+                .collect(),
+        )
+    }))
+}
+
+/// Copia del árbol sin los ítems `[hidden=true]` en ninguno de sus niveles.
+fn without_hidden(items: &[MenuItem]) -> Vec<MenuItem> {
+    items
+        .iter()
+        .filter(|item| !item.hidden)
+        .map(|item| match &item.action {
+            MenuAction::OpenSubmenu(children) => MenuItem {
+                action: MenuAction::OpenSubmenu(Rc::new(without_hidden(children))),
+                ..item.clone()
+            },
+            _ => item.clone(),
+        })
+        .collect()
+}
+
+/// Sale del modo TUI (raw mode y pantalla alternativa) para ceder la terminal
+/// a un proceso externo.
+pub fn suspend_tui() {
+    let _ = disable_raw_mode();
+    if let Err(e) = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture) {
+        eprintln!("[warn] no se pudo restaurar la terminal: {}", e);
+    }
+}
+
+/// Vuelve al modo TUI después de `suspend_tui` y fuerza un redibujado completo.
+///
+/// Algunos programas a pantalla completa (vim, ncdu, htop) salen dejando la
+/// terminal con modos cambiados: región de scroll, forma del cursor, colores.
+/// Se vuelven a fijar antes de redibujar y se consulta el tamaño de nuevo,
+/// por si la ventana cambió mientras corría el comando.
+pub fn resume_tui<B: Backend>(terminal: &mut Terminal<B>) -> Result<(), TmenuError> {
+    if let Err(e) = enable_raw_mode() {
+        eprintln!("[warn] no se pudo activar raw mode: {}", e);
+    }
+    if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture) {
+        eprintln!("[warn] no se pudo restaurar pantalla alternativa: {}", e);
+    }
+    if let Err(e) = reset_terminal_modes() {
+        eprintln!(
+            "[warn] no se pudieron restablecer los modos de la terminal: {}",
+            e
+        );
+    }
+    discard_pending_input();
+
+    let to_err = |e: B::Error| TmenuError::TerminalError(e.to_string());
+    terminal.hide_cursor().map_err(to_err)?;
+    terminal.autoresize().map_err(to_err)?;
+    terminal.clear().map_err(to_err)
+}
+
+/// Restablece los modos que un programa externo pudo dejar cambiados.
+fn reset_terminal_modes() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    // Región de scroll completa (DECSTBM) y origen absoluto (DECOM): crossterm
+    // no tiene comandos para estos, y con una región propia ratatui dibuja corrido
+    stdout.write_all(b"\x1b[r\x1b[?6l")?;
+    execute!(
+        stdout,
+        ResetColor,
+        SetAttribute(Attribute::Reset),
+        SetCursorStyle::DefaultUserShape,
+        EnableLineWrap
+    )
+}
+
+/// Descarta eventos que quedaron en la entrada (respuestas de la terminal a
+/// consultas del programa externo, teclas a destiempo) para que no se lean
+/// como acciones sobre el menú.
+fn discard_pending_input() {
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        if event::read().is_err() {
+            break;
         }
-        terminal
-            .clear()
-            .map_err(|e| AppError::TerminalError(e.to_string()))?;
+    }
+}
 
-        Ok(())
+/// Anota en el registro de depuración lo que se sacó del menú parseado.
+fn log_menu(title: &str, items: &MenuItems, config: &GlobalConfig, key_errors: &[String]) {
+    if !log_enabled!(Level::Debug) {
+        return;
     }
-    pub fn breadcrumb(&self) -> String {
-        const MAX_WIDTH: usize = 40;
+    debug!(
+        "menú \"{}\": {} ítems en la raíz; shell {:?}, ejecución {:?}, wait {}, sort {:?}, history {}",
+        title,
+        items.len(),
+        config.shell,
+        config.execution_mode,
+        config.wait,
+        config.sort,
+        config.history
+    );
+    for (action, keys) in &config.keys {
+        debug!("tecla configurada: {} = {}", action, keys);
+    }
+    for error in key_errors {
+        warn!("tecla ignorada: {}", error);
+    }
+}
 
-        if self.history.is_empty() {
-            return self.current_title.clone();
-        }
+/// Mensaje de un comando que no arrancó, que también queda en el registro
+/// de depuración.
+fn spawn_failure(program: &str, e: std::io::Error) -> String {
+    let message = TmenuError::SpawnError(program.to_string(), e)
+        .to_string()
+        .trim_end()
+        .to_string();
+    warn!("{}", message);
+    message
+}
 
-        let root = self.history[0].title.as_str();
-        let current = self.current_title.as_str();
+/// Una línea de `input` sin el salto final, o `None` si ya no hay más.
+fn read_answer(input: &mut impl io::BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+}
 
-        // Construir la cadena completa y ver si entra
-        let mut parts: Vec<&str> = self.history.iter().map(|e| e.title.as_str()).collect();
-        parts.push(current);
-        let full = parts.join(" › ");
+/// Pide en `prompt` la confirmación de `item`, como el modal pero en una
+/// línea: la frase de `[confirm_text=...]` tal cual, o `s` para seguir.
+fn confirm_line(
+    item: &MenuItem,
+    input: &mut impl io::BufRead,
+    prompt: &mut impl Write,
+) -> io::Result<bool> {
+    if let Some(message) = &item.confirm_message {
+        writeln!(prompt, "{}", message)?;
+    }
+    match &item.confirm_text {
+        Some(text) => write!(prompt, "Escribí \"{}\" para confirmar: ", text)?,
+        None => write!(prompt, "¿Ejecutar \"{}\"? [s/N]: ", item.label)?,
+    }
+    prompt.flush()?;
+    let Some(answer) = read_answer(input)? else {
+        return Ok(false);
+    };
+    Ok(match &item.confirm_text {
+        Some(text) => answer == *text,
+        None => matches!(
+            answer.trim().to_lowercase().as_str(),
+            "s" | "si" | "sí" | "y" | "yes"
+        ),
+    })
+}
 
-        if full.chars().count() <= MAX_WIDTH {
-            return full;
-        }
+/// Anota cómo terminó un comando en primer plano.
+fn log_exit(cmd: &str, status: &std::io::Result<Option<std::process::ExitStatus>>) {
+    match status {
+        Ok(Some(status)) => match status.code() {
+            Some(code) => debug!("terminó {:?}: código {}", cmd, code),
+            None => debug!("terminó {:?}: {}", cmd, status),
+        },
+        Ok(None) => debug!("terminó {:?}: se agotó el timeout", cmd),
+        Err(e) => warn!("no se pudo esperar a {:?}: {}", cmd, e),
+    }
+}
 
-        // Truncar: Raíz › .. › Actual
-        let candidate = format!("{} › .. › {}", root, current);
-        if candidate.chars().count() <= MAX_WIDTH {
-            return candidate;
-        }
+/// Aviso para la barra de estado con los errores de `keys:`, si hubo.
+fn key_errors_message(errors: &[String]) -> Option<String> {
+    (!errors.is_empty()).then(|| format!("keys: {}", errors.join("; ")))
+}
 
-        // Caso extremo: solo el nivel actual (root o current son muy largos)
-        if current.chars().count() <= MAX_WIDTH {
-            return current.to_string();
+/// Fecha de última modificación de un archivo, si se puede leer.
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Disposición del menú en el último dibujado, en coordenadas de la terminal.
+pub struct MenuLayout {
+    /// Filas de los ítems visibles de la lista
+    pub list: Rect,
+    /// Columnas en que se reparte la lista (`1` = lista simple)
+    pub columns: usize,
+    /// `[<-] Volver` en el pie, si se está en un submenú
+    pub back: Option<Rect>,
+}
+
+impl Default for MenuLayout {
+    fn default() -> Self {
+        MenuLayout {
+            list: Rect::default(),
+            columns: 1,
+            back: None,
         }
-        current.chars().take(MAX_WIDTH).collect()
     }
-    /// Finaliza el wizard: si requiere confirmación, muestra modal; sino, ejecuta directo.
-    pub fn finish_wizard(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    ) -> Result<bool, AppError> {
-        if let Some(ref wizard) = self.wizard {
-            let cmd = wizard.resolve();
-            let require_confirmation = wizard.require_confirmation;
-            self.wizard = None;
+}
 
-            if require_confirmation {
-                // Pedir confirmación antes de ejecutar el comando resuelto
-                return self.request_command_confirmation(terminal, &cmd);
-            } else {
-                // Ejecutar directo sin confirmación
-                self.execute_external_command(terminal, &cmd)?;
-            }
+/// Lo que hay bajo un clic: un ítem de la lista filtrada o el `[<-] Volver`.
+#[derive(Debug, PartialEq)]
+pub enum ClickTarget {
+    Item(usize),
+    Back,
+}
+
+/// Salida de un comando ejecutado en modo `capture`, mostrada en un panel
+/// desplazable debajo del menú (o a pantalla completa).
+pub struct CapturedOutput {
+    /// Etiqueta del ítem ejecutado
+    pub title: String,
+    pub lines: Vec<String>,
+    pub success: bool,
+    /// Primera línea visible
+    pub scroll: usize,
+    /// Ocupar toda la pantalla en lugar de la parte inferior (`f`)
+    pub fullscreen: bool,
+    /// Última búsqueda confirmada con `/`; sus coincidencias se resaltan
+    pub query: Option<String>,
+    /// Texto que se está escribiendo en el pie del panel
+    pub input: Option<OutputInput>,
+}
+
+/// Campo de texto del pie del panel de salida.
+pub enum OutputInput {
+    /// Texto a buscar (`/`)
+    Search(String),
+    /// Archivo donde guardar la salida (`w`)
+    Save(String),
+}
+
+impl CapturedOutput {
+    pub fn new(title: impl Into<String>, text: &str, success: bool) -> Self {
+        CapturedOutput {
+            title: title.into(),
+            lines: text.lines().map(str::to_string).collect(),
+            success,
+            scroll: 0,
+            fullscreen: false,
+            query: None,
+            input: None,
         }
-        Ok(false)
     }
 
-    /// Valida que el comando no contenga path traversal ni caracteres de shell peligrosos.
-    ///
-    /// Nota: no se usan pipes/shell, así que `|`, `&`, `;` no son vectores de inyección
-    /// en este contexto — pero `..` sí puede usarse para path traversal en argumentos.
-    fn is_safe_command(cmd: &str) -> bool {
-        // Rechazar path traversal explícito
-        if cmd.split_whitespace().any(|part| part.contains("..")) {
+    /// Desplaza el panel `delta` líneas, sin pasar de la última.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let max = self.lines.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Lleva la vista a la siguiente línea (o la anterior, sin `forward`) que
+    /// contiene `query`, sin distinguir mayúsculas y volviendo a empezar al
+    /// llegar al final. Retorna `false` si no hay ninguna.
+    pub fn find(&mut self, forward: bool) -> bool {
+        let Some(query) = self.query.as_ref().map(|q| q.to_lowercase()) else {
             return false;
+        };
+        let len = self.lines.len();
+        let found = (1..=len)
+            .map(|offset| {
+                if forward {
+                    (self.scroll + offset) % len
+                } else {
+                    (self.scroll + len - offset % len) % len
+                }
+            })
+            .find(|&i| self.lines[i].to_lowercase().contains(&query));
+        if let Some(i) = found {
+            self.scroll = i;
         }
-        // Allowlist de caracteres válidos (extendida respecto al original)
-        cmd.chars().all(|c| {
-            matches!(c,
-                'a'..='z' | 'A'..='Z' | '0'..='9'
-                | ' ' | '.' | '/' | '_' | '-' | '='
-                | ':' | '@' | '+' | '%' | '~' | ','
-                | '\'' | '"'
-            )
-        })
+        found.is_some()
+    }
+
+    /// Escribe la salida en `path`, relativo al directorio actual (acepta `~`).
+    pub fn save(&self, path: &str) -> io::Result<PathBuf> {
+        let cwd = std::env::current_dir()?;
+        let path = parser::resolve_path(&cwd, path);
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        std::fs::write(&path, text)?;
+        Ok(path)
     }
 }
 
+/// Qué se imprime del ítem elegido en modo selección (`--print`).
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum PrintTarget {
+    /// El comando, con los valores del wizard ya reemplazados
+    Command,
+    /// La etiqueta del ítem
+    Label,
+}
+
+/// Texto en un popup desplazable: la ayuda de un ítem (`[help=...]`) o una
+/// simulación (`--dry-run`).
+pub struct TextPopup {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// Primera línea visible
+    pub scroll: usize,
+}
+
+impl TextPopup {
+    /// Desplaza la ayuda `delta` líneas, sin pasar de la última.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let max = self.lines.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+}
+
+/// Origen de un ítem (`s`): archivo y línea donde se definió.
+pub struct SourceView {
+    /// Etiqueta del ítem
+    pub label: String,
+    pub location: SourceLocation,
+    /// Texto actual de esa línea, si se pudo leer el archivo
+    pub snippet: Option<String>,
+}
+
+/// Vista de árbol (`t`): todo el menú de una vez, con los submenús plegables.
+pub struct TreeView {
+    pub state: ListState,
+    /// Caminos (etiquetas desde la raíz) de los submenús desplegados.
+    pub expanded: HashSet<Vec<String>>,
+}
+
+/// Una fila de la vista de árbol.
+pub struct TreeRow {
+    /// Etiquetas de los submenús que contienen al ítem; su largo es la sangría
+    pub path: Vec<String>,
+    pub item: MenuItem,
+    /// Si es un submenú desplegado
+    pub expanded: bool,
+}
+
+impl TreeRow {
+    /// Camino hasta el ítem mismo, como lo recibe `jump_to`.
+    pub fn full_path(&self) -> Vec<String> {
+        let mut path = self.path.clone();
+        path.push(self.item.label.clone());
+        path
+    }
+}
+
+/// Vista de variables de sesión (`v`).
+pub struct VarsView {
+    pub state: ListState,
+    /// Texto del valor que se está editando, si hay una edición en curso.
+    pub editing: Option<String>,
+}
+
 /// Estado del wizard de interpolación de parámetros.
 pub struct WizardState {
     /// Parámetros a completar, en orden.
@@ -431,12 +2987,18 @@ pub struct WizardState {
     pub input: String,
     /// Comando original con placeholders sin reemplazar.
     pub original_cmd: String,
-    /// Si el ítem requiere confirmación después del wizard.
-    pub require_confirmation: bool,
+    /// Ítem que originó el wizard (define confirmación y atributos de ejecución).
+    pub item: MenuItem,
+    /// Líneas ofrecidas por el `{pick:...}` actual; `input` las filtra.
+    pub options: Vec<String>,
+    /// Índice, dentro de las opciones filtradas, de la opción resaltada.
+    pub selected: usize,
+    /// Parámetro para el que se cargaron `options`, para no volver a ejecutar su comando.
+    options_for: Option<usize>,
 }
 
 impl WizardState {
-    pub fn new(params: Vec<CommandParam>, cmd: String, require_confirmation: bool) -> Self {
+    pub fn new(params: Vec<CommandParam>, cmd: String, item: MenuItem) -> Self {
         let len = params.len();
         WizardState {
             params,
@@ -444,8 +3006,60 @@ impl WizardState {
             values: vec![String::new(); len],
             input: String::new(),
             original_cmd: cmd,
-            require_confirmation,
+            item,
+            options: Vec::new(),
+            selected: 0,
+            options_for: None,
+        }
+    }
+
+    /// Comando del `{pick:...}` actual si todavía no se cargaron sus opciones.
+    pub fn pending_pick(&self) -> Option<&str> {
+        (self.options_for != Some(self.current))
+            .then(|| self.current_param().pick.as_deref())
+            .flatten()
+    }
+
+    /// Carga las opciones del `{pick:...}` actual.
+    pub fn set_options(&mut self, options: Vec<String>) {
+        self.options = options;
+        self.selected = 0;
+        self.options_for = Some(self.current);
+    }
+
+    /// Si el parámetro actual se elige de una lista (`{pick:...}`).
+    pub fn is_picking(&self) -> bool {
+        self.current_param().pick.is_some()
+    }
+
+    /// Opciones que contienen el texto escrito, sin distinguir mayúsculas.
+    pub fn filtered_options(&self) -> Vec<&str> {
+        let filter = self.input.to_lowercase();
+        self.options
+            .iter()
+            .filter(|option| option.to_lowercase().contains(&filter))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Mueve la opción resaltada `delta` posiciones, sin dar la vuelta.
+    pub fn move_selection(&mut self, delta: isize) {
+        let max = self.filtered_options().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(max);
+    }
+
+    /// Propone las respuestas guardadas en las variables de sesión para los
+    /// parámetros con la misma etiqueta. Los `{pick:...}` arrancan sin filtro.
+    pub fn prefill(&mut self, vars: &SessionVars) {
+        for (param, value) in self.params.iter().zip(self.values.iter_mut()) {
+            if param.pick.is_some() {
+                continue;
+            }
+            if let Some(saved) = vars.get(&param.label) {
+                *value = saved.to_string();
+            }
         }
+        self.input = self.values[self.current].clone();
     }
 
     /// Parámetro que se está pidiendo ahora.
@@ -454,13 +3068,27 @@ impl WizardState {
     }
 
     /// Confirma el campo actual y avanza. Retorna `true` si era el último.
+    /// En un `{pick:...}` sin opciones que coincidan con el filtro no avanza.
     pub fn confirm_current(&mut self) -> bool {
-        self.values[self.current] = self.input.clone();
-        self.input.clear();
+        if self.is_picking() {
+            let Some(choice) = self
+                .filtered_options()
+                .get(self.selected)
+                .map(|s| s.to_string())
+            else {
+                return false;
+            };
+            self.values[self.current] = choice;
+            self.input.clear();
+        } else {
+            self.values[self.current] = std::mem::take(&mut self.input);
+        }
         if self.current + 1 >= self.params.len() {
             true // wizard completo
         } else {
             self.current += 1;
+            // El siguiente campo arranca con la respuesta propuesta, si hay
+            self.input = self.values[self.current].clone();
             false
         }
     }
@@ -475,3 +3103,1032 @@ impl WizardState {
         cmd
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_toon_str;
+    use std::time::Instant;
+
+    /// Menú con `depth` niveles de submenús anidados; el submenú siguiente es
+    /// siempre el primer ítem de cada nivel, seguido de `width` comandos.
+    fn large_menu(width: usize, depth: usize) -> String {
+        fn level(toon: &mut String, current: usize, width: usize, depth: usize) {
+            let indent = "    ".repeat(current + 1);
+            toon.push_str(&format!("{}\"Nivel {}\":\n", indent, current));
+            if current + 1 < depth {
+                level(toon, current + 1, width, depth);
+            }
+            for i in 0..width {
+                toon.push_str(&format!("{}    \"Item {}\": \"echo {}\"\n", indent, i, i));
+            }
+        }
+        let mut toon = String::from("\"Bench\":\n");
+        level(&mut toon, 0, width, depth);
+        toon
+    }
+
+    #[test]
+    fn test_back_restores_parent_level() {
+        let mut app = App::from_toon_str(&large_menu(3, 2), false);
+        let submenu = app.current_items[0].clone();
+        app.enter_submenu(
+            submenu.label.clone(),
+            app.submenu_items(&submenu).unwrap().unwrap(),
+        );
+        assert_eq!(app.current_title, "Nivel 0");
+        assert!(app.back());
+        assert_eq!(app.current_title, "Bench");
+        assert!(!app.back());
+    }
+
+    /// Entrar y volver comparten las listas del árbol en lugar de copiarlas
+    /// (la medición está en `benches/navigation.rs`).
+    #[test]
+    fn test_navigation_shares_item_lists() {
+        let mut app = App::from_toon_str(&large_menu(5_000, 4), false);
+        let mut levels = vec![Rc::clone(&app.current_items)];
+        for _ in 0..3 {
+            let MenuAction::OpenSubmenu(children) = &app.current_items[0].action else {
+                panic!("el primer ítem de cada nivel es un submenú");
+            };
+            let children = Rc::clone(children);
+            assert!(app.enter_selected().unwrap());
+            assert!(Rc::ptr_eq(&app.current_items, &children));
+            assert!(Rc::ptr_eq(&app.filtered_items(), &children));
+            levels.push(children);
+        }
+        levels.pop();
+        while let Some(parent) = levels.pop() {
+            assert!(app.back());
+            assert!(Rc::ptr_eq(&app.current_items, &parent));
+        }
+        assert!(!app.back());
+    }
+
+    #[test]
+    fn test_confirm_text_requires_exact_phrase() {
+        let (_, _, items) = parse_toon_str(
+            "\"Menu\":\n    Drop: \"./drop.sh\" [confirm_text=delete production]\n",
+            Path::new("."),
+        );
+        let item = &items[0];
+        assert!(item.needs_confirmation());
+
+        let mut conf =
+            ConfirmationState::new("./drop.sh".to_string(), None, item.confirm_text.clone());
+        conf.input = "delete prod".to_string();
+        assert!(!conf.is_confirmed());
+        conf.input.push_str("uction");
+        assert!(conf.is_confirmed());
+    }
+
+    #[test]
+    fn test_cooldown_expires() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Failover: \"./failover.sh\" [cooldown=60s]\n    Estado: \"git status\"\n",
+            false,
+        );
+        let failover = app.current_items[0].clone();
+        let status = app.current_items[1].clone();
+        assert_eq!(app.cooldown_remaining(&failover), None);
+
+        app.cooldowns.insert(
+            item_key(&failover).unwrap(),
+            Instant::now() + Duration::from_secs(60),
+        );
+        assert!(app.cooldown_remaining(&failover).unwrap() > Duration::from_secs(58));
+        assert_eq!(app.cooldown_remaining(&status), None);
+        // Mientras corre la cuenta regresiva cada tick pide redibujar
+        assert!(app.on_tick());
+
+        app.cooldowns
+            .insert(item_key(&failover).unwrap(), Instant::now());
+        app.on_tick();
+        assert_eq!(app.cooldown_remaining(&failover), None);
+        assert!(!app.on_tick());
+    }
+
+    #[test]
+    fn test_capture_mode_fills_output_pane() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Saludo: \"echo hola\" [execution_mode=capture]\n",
+            false,
+        );
+        let item = app.current_items[0].clone();
+        assert_eq!(item.execution_mode, Some(ExecutionMode::Capture));
+
+        let parts = app.shell.command_line("echo hola");
+        app.run_captured(&item, "echo hola", &[("echo hola", parts)]);
+        let output = app.output.as_mut().expect("panel de salida");
+        assert!(output.success);
+        assert_eq!(output.lines, vec!["hola"]);
+
+        output.scroll_by(5);
+        assert_eq!(output.scroll, 0);
+        assert_eq!(app.vars.get("Saludo"), Some("hola"));
+    }
+
+    #[cfg(feature = "scripting")]
+    #[test]
+    fn test_scripts_in_conditions_menus_and_output() {
+        let mut app = App::from_toon_str(
+            r#""Menu":
+    Si: "true" [enabled_if=script:1 < 2]
+    No: "true" [enabled_if=script:1 > 2]
+    Gen: "script:[`echo a`]" [cwd=/tmp]
+    Log: "echo ERROR x" [output_filter=if output.contains(`ERROR`) { `con errores` } else { output }]
+"#,
+            false,
+        );
+        let items = app.current_items.clone();
+        assert!(!app.is_disabled(&items[0]));
+        assert!(app.is_disabled(&items[1]));
+
+        let generated = submenu_items(&items[2], &app.shell).unwrap().unwrap();
+        assert_eq!(
+            generated[0].action,
+            MenuAction::Execute("echo a".to_string())
+        );
+        assert_eq!(generated[0].cwd, Some(PathBuf::from("/tmp")));
+
+        let cmd = "echo ERROR x";
+        app.run_captured(&items[3], cmd, &[(cmd, app.shell.command_line(cmd))]);
+        assert_eq!(app.output.take().unwrap().lines, vec!["con errores"]);
+    }
+
+    #[test]
+    fn test_run_path_without_interface() {
+        let mut app = App::from_toon_str(
+            "config:\n    history: false\n\n\"Menu\":\n    Ops:\n        Salir: \"sh -c 'exit {{text: Codigo}}'\" [confirm=true, hidden=true]\n",
+            false,
+        );
+        let path = vec!["Ops".to_string(), "Salir".to_string()];
+        let mut prompt = Vec::new();
+
+        let code = app.run_path(&path, false, &mut "3\ns\n".as_bytes(), &mut prompt);
+        assert_eq!(code.unwrap(), 3);
+        assert_eq!(
+            String::from_utf8(prompt).unwrap(),
+            "Codigo: ¿Ejecutar \"Salir\"? [s/N]: "
+        );
+        assert_eq!(app.vars.get("Codigo"), Some("3"));
+
+        // El valor anterior se propone; sin confirmar no se ejecuta
+        let cancelled = app.run_path(&path, false, &mut "\nn\n".as_bytes(), &mut Vec::new());
+        assert!(matches!(cancelled, Err(TmenuError::RunError(_))));
+        let code = app.run_path(&path, true, &mut "0\n".as_bytes(), &mut Vec::new());
+        assert_eq!(code.unwrap(), 0);
+
+        let missing = app.run_path(
+            &["Ops".to_string()],
+            true,
+            &mut "".as_bytes(),
+            &mut Vec::new(),
+        );
+        assert!(matches!(missing, Err(TmenuError::RunError(_))));
+    }
+
+    #[test]
+    fn test_run_path_reaches_labels_with_slashes() {
+        let mut app = App::from_toon_str(
+            "config:\n    history: false\n\n\"Menu\":\n    Red:\n        \"VPN up/down\": \"true\"\n        \"C:\\\\\": \"true\"\n",
+            false,
+        );
+        let paths = crate::commands::item_paths(&app.menu_items);
+        assert_eq!(paths, "Red/VPN up\\/down\nRed/C:\\\\\n");
+
+        for line in paths.lines() {
+            let path = crate::model::parse_item_path(line);
+            assert_eq!(item_path(&path), line);
+            let code = app.run_path(&path, true, &mut "".as_bytes(), &mut Vec::new());
+            assert_eq!(code.unwrap(), 0);
+        }
+        assert_eq!(
+            crate::model::parse_item_path("Red/VPN up\\/down"),
+            ["Red", "VPN up/down"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_commands_skip_the_allowlist() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::from_toon_str(
+            "config:\n    shell: sh\n    execution_mode: capture\n    history: false\n\n\"Menu\":\n    Pipe: \"echo a | tr a b\"\n    Directo: \"echo a | tr a b\" [shell=none]\n",
+            false,
+        );
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        let items = app.current_items.clone();
+
+        app.activate_item(&mut terminal, &items).unwrap();
+        let output = app.output.take().expect("panel de salida");
+        assert!(output.success);
+        assert_eq!(output.lines, vec!["b"]);
+
+        app.state.select(Some(1));
+        assert!(matches!(
+            app.activate_item(&mut terminal, &items),
+            Err(TmenuError::ForbiddenCommand(_))
+        ));
+    }
+
+    #[test]
+    fn test_spawn_failure_is_reported_not_fatal() {
+        let mut app = App::from_toon_str("\"Menu\":\n    Roto: \"tmenu-no-existe\"\n", false);
+        let item = app.current_items[0].clone();
+        let parts = vec!["tmenu-no-existe".to_string()];
+
+        assert!(!app.run_foreground(&item, "tmenu-no-existe", &parts));
+        let message = app.status_message.clone().unwrap();
+        assert!(message.starts_with("No se pudo ejecutar 'tmenu-no-existe': "));
+
+        app.run_captured(&item, "tmenu-no-existe", &[("tmenu-no-existe", parts)]);
+        let output = app.output.take().expect("panel de salida");
+        assert!(!output.success);
+        assert_eq!(output.lines, vec![message]);
+    }
+
+    #[test]
+    fn test_captured_sequence_stops_on_failure() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Release: \"echo uno\" [execution_mode=capture]\n        - \"false\"\n        - \"echo tres\"\n    Log: \"git log\"\n",
+            false,
+        );
+        assert_eq!(app.current_items.len(), 2);
+        let item = app.current_items[0].clone();
+        assert_eq!(item.steps, vec!["false", "echo tres"]);
+
+        let cmd = item.command_sequence("echo uno");
+        let steps: Vec<_> = cmd
+            .lines()
+            .map(|step| (step, app.shell.command_line(step)))
+            .collect();
+        app.run_captured(&item, &cmd, &steps);
+        let output = app.output.take().expect("panel de salida");
+        assert!(!output.success);
+        assert_eq!(output.lines[..3], ["[1/3] echo uno", "uno", "[2/3] false"]);
+        assert!(!output.lines.iter().any(|line| line.contains("tres")));
+    }
+
+    #[test]
+    fn test_follow_up_after_run() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Deploy: \"./deploy.sh\" [cwd=/tmp, on_success=notify-send listo, on_failure=goto:Logs/Deploy]\n    Logs:\n        App: \"less app.log\"\n        Deploy: \"less deploy.log\"\n",
+            false,
+        );
+        let item = app.current_items[0].clone();
+
+        app.after_run(&item, true);
+        let follow_up = app.follow_up.take().expect("comando de on_success");
+        assert_eq!(
+            follow_up.action,
+            MenuAction::Execute("notify-send listo".to_string())
+        );
+        assert_eq!(follow_up.cwd, item.cwd);
+        assert_eq!(follow_up.on_failure, None);
+
+        app.after_run(&item, false);
+        assert!(app.follow_up.is_none());
+        assert_eq!(app.current_title, "Logs");
+        assert_eq!(app.state.selected(), Some(1));
+
+        assert!(!app.jump_to(&["Nada".to_string()]));
+    }
+
+    #[test]
+    fn test_position_is_the_path_back() {
+        let menu = "\"Menu\":\n    Uno: \"echo 1\"\n    Git:\n        Estado: \"git status\"\n        Log: \"git log\"\n";
+        let mut app = App::from_toon_str(menu, false);
+        assert_eq!(app.position(), vec!["Uno"]);
+        app.next();
+        app.enter_selected().unwrap();
+        app.next();
+        assert_eq!(app.position(), vec!["Git", "Log"]);
+        // Sin archivo de menú no hay nada que guardar
+        assert!(app.save_position().is_ok());
+
+        let mut restored = App::from_toon_str(menu, false);
+        assert!(restored.jump_to(&app.position()));
+        assert_eq!(restored.current_title, "Git");
+        assert_eq!(restored.state.selected(), Some(1));
+        restored.back();
+        assert_eq!(restored.state.selected(), Some(1));
+
+        let mut forgetful =
+            App::from_toon_str(&format!("config:\n    restore: false\n{}", menu), false);
+        assert!(!forgetful.restore);
+        forgetful.menu_path = Some(PathBuf::from("/tmp/tmenu-restore-false.toon"));
+        forgetful.restore_position().unwrap();
+        assert!(forgetful.history.is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_shows_resolved_command() {
+        let mut app = App::from_toon_str(
+            "config:\n    shell: bash\nenv:\n    REGION: eu\n\"Menu\":\n    Pods: \"kubectl get pods\" [cwd=/tmp, env=REGION=us CTX=prod]\n",
+            false,
+        );
+        let item = app.current_items[0].clone();
+        let steps = vec![(
+            "kubectl get pods",
+            app.shell.command_line("kubectl get pods"),
+        )];
+        app.show_dry_run(&item, &steps);
+
+        let popup = app.popup.take().expect("simulación");
+        assert_eq!(popup.title, "Simulación: Pods");
+        assert_eq!(
+            popup.lines,
+            vec![
+                "$ bash -c 'kubectl get pods'",
+                "",
+                "Directorio: /tmp",
+                "Entorno: REGION=us CTX=prod",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_details_show_expanded_command_and_flags() {
+        let mut app = App::from_toon_str(
+            "config:\n    shell: bash\n\"Menu\":\n    Deploy: \"deploy {{text: Entorno}} {{text: Tag}}\" [confirm=true, cwd=/srv, env=CTX=prod, timeout=30s]\n    \"Sub\":\n        A: \"echo a\"\n",
+            false,
+        );
+        app.vars.set("Entorno", "staging", VarSource::Prompt);
+        let items = app.filtered_items();
+        app.state.select(Some(0));
+        app.open_command_details(&items);
+
+        let popup = app.popup.take().expect("detalles");
+        assert_eq!(popup.title, "Deploy");
+        assert_eq!(
+            popup.lines,
+            vec![
+                "$ bash -c 'deploy staging {{text: Tag}}'",
+                "",
+                "Directorio: /srv",
+                "Entorno: CTX=prod",
+                "Se pedirán al ejecutar: Tag",
+                "",
+                "Atributos:",
+                "  confirm=true",
+                "  timeout=30s",
+            ]
+        );
+
+        // Un submenú no tiene comando que mostrar
+        app.state.select(Some(1));
+        app.open_command_details(&items);
+        assert!(app.popup.is_none());
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("\"Sub\" no ejecuta un comando")
+        );
+    }
+
+    #[test]
+    fn test_menu_config_overrides_user_defaults() {
+        let defaults = GlobalConfig {
+            shell: Shell::parse("bash"),
+            wait: false,
+            history: false,
+            keys: vec![("quit".to_string(), "ctrl+x".to_string())],
+            ..GlobalConfig::default()
+        };
+        let app = App::from_toon_str_with(
+            "config:\n    wait: true\nkeys:\n    back: h\n\"Menu\":\n    Hola: \"echo hola\"\n",
+            false,
+            defaults,
+        );
+        assert_eq!(app.shell, Shell::parse("bash"));
+        assert!(app.wait);
+        assert!(!app.record_history);
+        assert_eq!(
+            app.keymap.key_label(crate::keymap::Action::Quit).as_deref(),
+            Some("Ctrl+X")
+        );
+        assert_eq!(
+            app.keymap.key_label(crate::keymap::Action::Back).as_deref(),
+            Some("h")
+        );
+    }
+
+    #[test]
+    fn test_generated_submenus_follow_sort() {
+        let mut app = App::from_toon_str(
+            "config:\n    sort: alpha\n\"Menu\":\n    Ramas: list:printf 'main\\nfeature\\nDev\\n'\n    Tal cual: list:printf 'main\\nfeature\\n' [sort=file]\n",
+            false,
+        );
+        let labels = |items: &[MenuItem]| -> Vec<String> {
+            items.iter().map(|item| item.label.clone()).collect()
+        };
+        assert_eq!(labels(&app.root_items), ["Ramas", "Tal cual"]);
+        let ramas = app.root_items[0].clone();
+        let items = app.submenu_items(&ramas).unwrap().unwrap();
+        assert_eq!(labels(&items), ["Dev", "feature", "main"]);
+        let tal_cual = app.root_items[1].clone();
+        let items = app.submenu_items(&tal_cual).unwrap().unwrap();
+        assert_eq!(labels(&items), ["main", "feature"]);
+
+        app.sort = SortOrder::File;
+        let items = app.submenu_items(&ramas).unwrap().unwrap();
+        assert_eq!(labels(&items), ["main", "feature", "Dev"]);
+    }
+
+    #[test]
+    fn test_hidden_items_show_on_toggle() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Estado: \"git status\"\n    Reset: \"git reset --hard\" [hidden=true]\n    Mantenimiento: [hidden=true]\n        Vacuum: \"vacuumdb\"\n    Git:\n        Log: \"git log\"\n        Gc: \"git gc\" [hidden=true]\n",
+            false,
+        );
+        let labels = |items: &[MenuItem]| -> Vec<String> {
+            items.iter().map(|item| item.label.clone()).collect()
+        };
+        assert_eq!(labels(&app.current_items), ["Estado", "Git"]);
+        assert!(app.jump_to(&["Git".to_string(), "Log".to_string()]));
+        assert_eq!(labels(&app.current_items), ["Log"]);
+
+        // Al mostrarlos se queda en el mismo submenú
+        app.toggle_hidden();
+        assert_eq!(app.current_title, "Git");
+        assert_eq!(labels(&app.current_items), ["Log", "Gc"]);
+        app.go_home();
+        assert_eq!(
+            labels(&app.current_items),
+            ["Estado", "Reset", "Mantenimiento", "Git"]
+        );
+
+        // Al esconderlos, desde un submenú oculto se vuelve al nivel anterior
+        assert!(app.jump_to(&["Mantenimiento".to_string(), "Vacuum".to_string()]));
+        app.toggle_hidden();
+        assert_eq!(app.current_title, "Menu");
+        assert_eq!(labels(&app.current_items), ["Estado", "Git"]);
+    }
+
+    #[test]
+    fn test_quick_select() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Uno: \"echo 1\"\n    Dos: \"echo 2\"\n    Tres: \"echo 3\"\n",
+            false,
+        );
+        assert!(app.quick_select(2));
+        assert_eq!(app.state.selected(), Some(1));
+        assert!(!app.quick_select(4));
+        assert_eq!(app.state.selected(), Some(1));
+
+        app.quick_select = QuickSelect::Select;
+        assert!(!app.quick_select(3));
+        assert_eq!(app.state.selected(), Some(2));
+
+        // Con teclas propias en el nivel, los números no se usan por posición
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Uno: \"echo 1\"\n    \"Dos\"[d]: \"echo 2\"\n",
+            false,
+        );
+        assert!(!app.quick_select(1));
+        assert!(app.select_hotkey('d'));
+        assert_eq!(app.state.selected(), Some(1));
+    }
+
+    #[test]
+    fn test_choice_prints_the_line_as_is() {
+        use ratatui::backend::TestBackend;
+
+        let menu = Menu::builder()
+            .choice("uno")
+            .choice("rm -rf ../{{x}}; exit")
+            .build();
+        let mut app = App::from_menu(menu, false);
+        app.print = Some(PrintTarget::Label);
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+
+        app.state.select(Some(1));
+        let items = app.current_items.clone();
+        assert!(app.activate_item(&mut terminal, &items).unwrap());
+        assert_eq!(app.selection.as_deref(), Some("rm -rf ../{{x}}; exit"));
+        assert!(app.wizard.is_none());
+    }
+
+    #[test]
+    fn test_grid_layout_on_short_terminals() {
+        use ratatui::backend::TestBackend;
+
+        let items: String = (1..=12)
+            .map(|i| format!("    Item{}: \"echo {}\"\n", i, i))
+            .collect();
+        let mut app = App::from_toon_str(
+            &format!("config:\n    columns: auto\n\n\"Menu\":\n{}", items),
+            false,
+        );
+        // Entran siete filas: doce ítems necesitan dos columnas de seis
+        let mut terminal = Terminal::new(TestBackend::new(80, 14)).unwrap();
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+        assert_eq!(app.layout.columns, 2);
+        assert_eq!(app.layout.list.height, 6);
+
+        assert!(app.move_column(1));
+        assert_eq!(app.state.selected(), Some(6));
+        assert!(app.move_column(1));
+        assert_eq!(app.state.selected(), Some(11));
+
+        let buffer = terminal.backend().buffer();
+        let (x, y) = (0..buffer.area.height)
+            .find_map(|y| {
+                let row: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                row.find("Item8")
+                    .map(|byte| (row[..byte].chars().count() as u16, y))
+            })
+            .unwrap();
+        assert_eq!(app.click_target(x, y), Some(ClickTarget::Item(7)));
+    }
+
+    #[test]
+    fn test_breadcrumb_keeps_the_deepest_levels() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Infraestructura:\n        Kubernetes:\n            Deploy:\n                Staging: \"./deploy staging\"\n",
+            false,
+        );
+        assert_eq!(app.breadcrumb(), "Menu");
+        assert!(app.enter_selected().unwrap());
+        assert!(app.enter_selected().unwrap());
+        assert_eq!(app.breadcrumb(), "Menu › Infraestructura › Kubernetes");
+        assert!(app.enter_selected().unwrap());
+        assert_eq!(app.breadcrumb(), "Menu › .. › Kubernetes › Deploy");
+    }
+
+    #[test]
+    fn test_favorites_and_recent_are_pinned_to_the_root_menu() {
+        let menu =
+            "\"Menu\":\n    Uno: \"echo 1\"\n    Git:\n        Status: \"git status\" [cwd=/tmp]\n";
+        let mut app = App::from_toon_str(menu, false);
+        app.favorites = vec![
+            "Status\0git status".to_string(),
+            "Otro\0echo otro".to_string(),
+        ];
+        app.pin_submenus();
+        assert_eq!(app.current_items[0].label, FAVORITES_LABEL);
+        // La selección sigue en el mismo ítem
+        assert_eq!(app.state.selected(), Some(1));
+        app.state.select(Some(0));
+        assert!(app.enter_selected().unwrap());
+        // Los ítems conservan sus atributos; los que no están en el menú se omiten
+        assert_eq!(app.current_items.len(), 1);
+        assert_eq!(app.current_items[0].cwd, Some(PathBuf::from("/tmp")));
+
+        // Los recientes van debajo de los favoritos
+        app.go_home();
+        app.recent = vec!["Uno\0echo 1".to_string(), "Status\0git status".to_string()];
+        app.pin_submenus();
+        assert_eq!(app.current_items[1].label, RECENT_LABEL);
+        let recent = app.submenu_items(&MenuItem::new("", MenuAction::Recent(1)));
+        assert_eq!(recent.unwrap().unwrap()[0].label, "Uno");
+
+        app.favorites.clear();
+        app.recent.clear();
+        app.pin_submenus();
+        assert_eq!(app.current_items[0].label, "Uno");
+
+        // Con `favorites:` en el menú no se agrega otro
+        let mut app =
+            App::from_toon_str(&format!("{}    Mis favoritos: favorites:\n", menu), false);
+        app.favorites = vec!["Uno\0echo 1".to_string()];
+        app.pin_submenus();
+        assert_eq!(app.current_items.len(), 3);
+        assert_eq!(app.current_items[2].label, "Mis favoritos");
+    }
+
+    #[test]
+    fn test_wrap_can_be_disabled() {
+        let menu = "\"Menu\":\n    Uno: \"echo 1\"\n    Dos: \"echo 2\"\n";
+        let mut app = App::from_toon_str(menu, false);
+        app.previous();
+        assert_eq!(app.state.selected(), Some(1));
+        app.next();
+        assert_eq!(app.state.selected(), Some(0));
+
+        let mut app = App::from_toon_str(&format!("config:\n    wrap: false\n\n{}", menu), false);
+        app.previous();
+        assert_eq!(app.state.selected(), Some(0));
+        app.next();
+        app.next();
+        assert_eq!(app.state.selected(), Some(1));
+    }
+
+    #[test]
+    fn test_selection_skips_disabled_items() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Uno: \"echo 1\" [disabled=true]\n    Dos: \"echo 2\"\n    Tres: \"echo 3\" [enabled_if=false]\n    Cuatro: \"echo 4\" [enabled_if=true]\n",
+            false,
+        );
+        let items = app.current_items.clone();
+        app.skip_disabled(&items);
+        assert_eq!(app.state.selected(), Some(1));
+        app.next();
+        assert_eq!(app.state.selected(), Some(3));
+        app.next();
+        assert_eq!(app.state.selected(), Some(1));
+        app.previous();
+        assert_eq!(app.state.selected(), Some(3));
+        app.select_edge(false);
+        assert_eq!(app.state.selected(), Some(1));
+        assert!(app.is_disabled(&items[2]));
+        assert!(!app.is_disabled(&items[3]));
+    }
+
+    #[test]
+    fn test_status_badges_show_probe_results() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Web: \"echo web\" [status=echo active]\n    Db: \"echo db\" [status=false]\n    Cola: \"echo cola\" [status=true]\n",
+            false,
+        );
+        let items = app.current_items.clone();
+        let texts: Vec<(String, bool)> = items
+            .iter()
+            .filter_map(|item| app.status_badge(item))
+            .map(|badge| (badge.text, badge.ok))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("active".to_string(), true),
+                ("✗".to_string(), false),
+                ("✓".to_string(), true)
+            ]
+        );
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..20)
+            .flat_map(|y| (0..60).map(move |x| (x, y)))
+            .map(|cell| buffer[cell].symbol())
+            .collect();
+        assert!(screen.contains("Web  active"), "{}", screen);
+        assert!(screen.contains("Db  ✗"), "{}", screen);
+    }
+
+    #[test]
+    fn test_tabs_switch_between_root_submenus() {
+        use ratatui::backend::TestBackend;
+
+        let menu = "\"Menu\":\n    Docker:\n        Ps: \"docker ps\"\n    Git:\n        Status: \"git status\"\n        Log: \"git log\"\n";
+        let mut app = App::from_toon_str(&format!("config:\n    tabs: true\n\n{}", menu), false);
+        app.open_first_tab();
+        assert_eq!(app.tab_labels(), vec!["Docker", "Git"]);
+        assert_eq!(app.current_title, "Docker");
+        assert!(app.at_root());
+        app.switch_tab(true);
+        assert_eq!((app.current_title.as_str(), app.active_tab()), ("Git", 1));
+        app.switch_tab(true);
+        assert_eq!(app.current_title, "Docker");
+        app.switch_tab(false);
+        assert_eq!(app.current_title, "Git");
+        // El primer nivel de una pestaña hace de raíz
+        assert!(!app.back());
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..20)
+            .flat_map(|y| (0..60).map(move |x| (x, y)))
+            .map(|cell| buffer[cell].symbol())
+            .collect();
+        assert!(screen.contains(" Docker │ Git "), "{}", screen);
+
+        // Con ítems sueltos en la raíz, la raíz es la primera pestaña
+        let mut app = App::from_toon_str(
+            &format!("config:\n    tabs: true\n\n{}    Salir: exit\n", menu),
+            false,
+        );
+        app.open_first_tab();
+        assert_eq!(app.tab_labels(), vec!["Menu", "Docker", "Git"]);
+        assert_eq!(app.active_tab(), 0);
+        app.switch_tab(false);
+        assert_eq!(app.current_title, "Git");
+        app.switch_tab(true);
+        assert!(app.history.is_empty());
+    }
+
+    #[test]
+    fn test_tree_view_expands_and_goes_to_items() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Docker:\n        Ps: \"docker ps\"\n        Red:\n            Listar: \"docker network ls\"\n    Git:\n        Status: \"git status\"\n",
+            false,
+        );
+        app.enter_selected().unwrap();
+        app.next();
+        app.open_tree_view();
+        let labels = |app: &App| -> Vec<String> {
+            let rows = app.tree_rows(app.tree_view.as_ref().unwrap());
+            rows.iter()
+                .map(|row| format!("{}{}", "-".repeat(row.path.len()), row.item.label))
+                .collect()
+        };
+        // Se abre con el camino actual desplegado y el ítem actual marcado
+        assert_eq!(labels(&app), vec!["Docker", "-Ps", "-Red", "Git"]);
+        assert_eq!(app.tree_selected().unwrap().item.label, "Red");
+
+        app.tree_expand(true);
+        assert_eq!(
+            labels(&app),
+            vec!["Docker", "-Ps", "-Red", "--Listar", "Git"]
+        );
+        app.tree_expand(true);
+        assert_eq!(app.tree_selected().unwrap().item.label, "Listar");
+        // Plegar desde un hijo sube al submenú; otra vez lo pliega
+        app.tree_expand(false);
+        app.tree_expand(false);
+        assert_eq!(labels(&app), vec!["Docker", "-Ps", "-Red", "Git"]);
+
+        app.tree_expand_all();
+        assert_eq!(labels(&app).len(), 6);
+        app.tree_view.as_mut().unwrap().state.select(Some(5));
+        assert!(app.leave_tree_view());
+        assert_eq!(app.breadcrumb(), "Menu › Git");
+        assert_eq!(
+            app.current_items[app.state.selected().unwrap()].label,
+            "Status"
+        );
+    }
+
+    #[test]
+    fn test_status_bar_shows_the_selected_command() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Deploy: \"kubectl rollout restart deployment/api --namespace produccion\"\n    Git:\n        Status: \"git status\"\n",
+            false,
+        );
+        let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+        let mut bottom = |app: &mut App, rows: u16| {
+            terminal.draw(|f| crate::ui::ui(f, app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            (20 - rows..20)
+                .map(|y| (0..40).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+                .collect::<Vec<_>>()
+                .join("")
+        };
+
+        assert_eq!(
+            bottom(&mut app, 1),
+            " $ kubectl rollout restar… [e] ver todo "
+        );
+        app.status_expanded = true;
+        let text = bottom(&mut app, 2);
+        assert!(text.contains("--namespace produccion"), "{}", text);
+
+        app.next();
+        assert!(bottom(&mut app, 1).starts_with(" \u{25b6} Submenú Git (1 ítem)"));
+        app.status_bar = false;
+        assert!(!bottom(&mut app, 1).contains("Submenú"));
+    }
+
+    #[test]
+    fn test_global_search_shows_and_locates_every_level() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Uno: \"echo 1\"\n    Docker:\n        Logs: \"docker logs\"\n        Red:\n            Listar: \"docker network ls\"\n",
+            false,
+        );
+        app.enter_selected().unwrap();
+        app.search_mode = true;
+        app.global_search = true;
+        app.search_text = "red".to_string();
+        // Desde un submenú igual se busca desde la raíz, submenús incluidos
+        let labels: Vec<String> = app.search_results().into_iter().map(|i| i.label).collect();
+        assert_eq!(labels, vec!["Red", "Listar"]);
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..20)
+            .flat_map(|y| (0..60).map(move |x| (x, y)))
+            .map(|cell| buffer[cell].symbol())
+            .collect();
+        assert!(screen.contains("Listar  Docker › Red"), "{}", screen);
+
+        app.state.select(Some(1));
+        assert!(app.locate_result());
+        assert!(!app.search_mode);
+        assert_eq!(app.breadcrumb(), "Menu › Docker › Red");
+        assert_eq!(
+            app.current_items[app.state.selected().unwrap()].label,
+            "Listar"
+        );
+    }
+
+    #[test]
+    fn test_click_targets_follow_the_drawn_menu() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Uno: \"echo 1\"\n    Git:\n        Status: \"git status\"\n",
+            false,
+        );
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        let mut draw = |app: &mut App| {
+            terminal.draw(|f| crate::ui::ui(f, app)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            // Celda donde empieza `text` en la pantalla dibujada
+            move |text: &str| {
+                (0..buffer.area.height)
+                    .find_map(|y| {
+                        let row: String = (0..buffer.area.width)
+                            .map(|x| buffer[(x, y)].symbol())
+                            .collect();
+                        row.find(text)
+                            .map(|byte| (row[..byte].chars().count() as u16, y))
+                    })
+                    .unwrap()
+            }
+        };
+
+        let find = draw(&mut app);
+        let (x, y) = find("Git");
+        assert_eq!(app.click_target(x, y), Some(ClickTarget::Item(1)));
+        assert_eq!(app.click_target(0, 0), None);
+        // Un clic selecciona; el segundo sobre el mismo ítem es doble clic
+        assert!(!app.click_item(1));
+        assert_eq!(app.state.selected(), Some(1));
+        assert!(app.click_item(1));
+
+        assert!(app.enter_selected().unwrap());
+        let find = draw(&mut app);
+        let (x, y) = find("Volver");
+        assert_eq!(app.click_target(x, y), Some(ClickTarget::Back));
+    }
+
+    #[test]
+    fn test_output_search_and_save() {
+        let mut output = CapturedOutput::new(
+            "Build",
+            "Compiling a\nwarning: unused\nCompiling b\nWARNING: deprecated\nFinished",
+            true,
+        );
+        output.query = Some("warning".to_string());
+        assert!(output.find(true));
+        assert_eq!(output.scroll, 1);
+        assert!(output.find(true));
+        assert_eq!(output.scroll, 3);
+        // Al final vuelve a empezar; hacia atrás también da la vuelta
+        assert!(output.find(true));
+        assert_eq!(output.scroll, 1);
+        assert!(output.find(false));
+        assert_eq!(output.scroll, 3);
+        output.query = Some("error".to_string());
+        assert!(!output.find(true));
+        assert_eq!(output.scroll, 3);
+
+        let path = std::env::temp_dir().join(format!("tmenu-output-{}.log", std::process::id()));
+        let saved = output.save(&path.display().to_string()).unwrap();
+        assert_eq!(saved, path);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(text.starts_with("Compiling a\n") && text.ends_with("Finished\n"));
+    }
+
+    #[test]
+    fn test_hooks_receive_item_env() {
+        let out = std::env::temp_dir().join(format!("tmenu-hook-{}.txt", std::process::id()));
+        let app = App::from_toon_str("\"Menu\":\n    Hola: \"echo hola\"\n", false);
+        let item = app.current_items[0].clone();
+        let hook = format!(
+            "echo \"$TMENU_LABEL|$TMENU_COMMAND|$TMENU_STATUS\" > {}",
+            out.display()
+        );
+        assert!(app.run_hook(&hook, &item, "echo hola\necho chau", Some(false)));
+        let written = std::fs::read_to_string(&out).unwrap();
+        std::fs::remove_file(&out).ok();
+        assert_eq!(written, "Hola|echo hola && echo chau|fail\n");
+        assert!(!app.run_hook("exit 3", &item, "echo hola", None));
+    }
+
+    #[test]
+    fn test_source_view_shows_defining_line() {
+        let path = std::env::temp_dir().join(format!("tmenu-source-{}.toon", std::process::id()));
+        std::fs::write(
+            &path,
+            "\"Menu\":\n    Git:\n        Status: \"git status\"\n    Log: \"git log\"\n",
+        )
+        .unwrap();
+        let mut app = App::from_toon(&path, false).unwrap();
+        assert!(app.enter_selected().unwrap());
+        let items = app.filtered_items();
+        app.open_source_view(&items);
+        std::fs::remove_file(&path).unwrap();
+
+        let view = app.source_view.take().expect("origen del ítem");
+        assert_eq!(view.label, "Status");
+        assert_eq!(view.location.file.as_deref(), Some(path.as_path()));
+        assert_eq!(view.location.line, 3);
+        assert_eq!(
+            view.snippet.as_deref(),
+            Some("        Status: \"git status\"")
+        );
+
+        let mut app = App::from_toon_str("\"Menu\":\n    Log: \"git log\"\n", false);
+        let items = app.filtered_items();
+        app.open_source_view(&items);
+        let view = app.source_view.take().expect("origen del ítem");
+        assert_eq!(view.location.file, None);
+        assert_eq!(view.location.line, 2);
+        assert_eq!(view.snippet, None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_elevated_command_line_passes_env() {
+        let elevator = vec!["doas".to_string()];
+        let parts = vec![
+            "systemctl".to_string(),
+            "restart".to_string(),
+            "nginx".to_string(),
+        ];
+        assert_eq!(
+            elevated_command_line(&elevator, parts.clone(), [].iter()),
+            vec!["doas", "systemctl", "restart", "nginx"]
+        );
+        let env = [("KUBECONFIG".to_string(), "/etc/kube/prod".to_string())];
+        assert_eq!(
+            elevated_command_line(&elevator, parts, env.iter()),
+            vec![
+                "doas",
+                "env",
+                "KUBECONFIG=/etc/kube/prod",
+                "systemctl",
+                "restart",
+                "nginx"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_item_help_opens_for_selected_item() {
+        let mut app = App::from_toon_str(
+            "\"Menu\":\n    Rebase: \"git rebase -i\" [help=Paso 1\\nPaso 2]\n    Log: \"git log\"\n",
+            false,
+        );
+        let items = app.filtered_items();
+        app.open_item_help(&items);
+        let help = app.popup.take().expect("ayuda del ítem");
+        assert_eq!(help.title, "Rebase");
+        assert_eq!(help.lines, vec!["Paso 1", "Paso 2"]);
+
+        app.next();
+        app.open_item_help(&items);
+        assert!(app.popup.is_none());
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_wizard_pick_offers_command_lines() {
+        let mut app = App::from_toon_str("\"Menu\":\n", false);
+        let cmd = "echo {pick:seq 3} {pick:true}";
+        let item = MenuItem::new("Elegir", MenuAction::Execute(cmd.to_string()));
+        app.wizard = Some(WizardState::new(
+            parser::extract_params(cmd),
+            cmd.to_string(),
+            item,
+        ));
+        app.prepare_wizard_step();
+
+        let wizard = app.wizard.as_mut().expect("wizard con opciones");
+        assert_eq!(wizard.options, vec!["1", "2", "3"]);
+        wizard.move_selection(5);
+        assert_eq!(wizard.selected, 2);
+        wizard.input.push('2');
+        wizard.selected = 0;
+        assert_eq!(wizard.filtered_options(), vec!["2"]);
+        assert!(!wizard.confirm_current());
+        assert_eq!(wizard.values[0], "2");
+
+        // Un pick sin salida cancela el wizard
+        app.prepare_wizard_step();
+        assert!(app.wizard.is_none());
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_wizard_prefills_saved_answers() {
+        let cmd = "git checkout {{text: Rama}} {{text: Remoto}}";
+        let item = MenuItem::new("Checkout", MenuAction::Execute(cmd.to_string()));
+        let mut vars = SessionVars::default();
+        vars.set("Remoto", "origin", VarSource::Prompt);
+
+        let mut wizard = WizardState::new(parser::extract_params(cmd), cmd.to_string(), item);
+        wizard.prefill(&vars);
+        assert_eq!(wizard.input, "");
+        wizard.input.push_str("main");
+        assert!(!wizard.confirm_current());
+        assert_eq!(wizard.input, "origin");
+        assert!(wizard.confirm_current());
+        assert_eq!(wizard.resolve(), "git checkout main origin");
+    }
+}
@@ -0,0 +1,152 @@
+//! Menús armados desde código, sin pasar por un archivo `.toon`:
+//!
+//! ```
+//! use tmenu::builder::Menu;
+//!
+//! let menu = Menu::builder()
+//!     .title("Proyecto")
+//!     .submenu("Git", |git| {
+//!         git.command("Estado", "git status")
+//!             .command("Log", "git log --oneline")
+//!     })
+//!     .command("Tests", "cargo test")
+//!     .quit("Salir")
+//!     .build();
+//! assert_eq!(menu.items.len(), 3);
+//! ```
+//!
+//! Para los atributos de un ítem (`confirm`, `cwd`, `env`…) se arma el
+//! [`MenuItem`] y se agrega con [`MenuBuilder::item`].
+use std::rc::Rc;
+
+use crate::model::{MenuAction, MenuItem, MenuItems};
+use crate::parser::{GlobalConfig, sort_tree};
+
+/// Un menú completo: lo mismo que se obtiene al parsear un `.toon`.
+#[derive(Clone, Debug)]
+pub struct Menu {
+    pub config: GlobalConfig,
+    pub title: String,
+    pub items: MenuItems,
+}
+
+impl Menu {
+    /// Empieza un menú vacío, con la configuración por defecto.
+    pub fn builder() -> MenuBuilder {
+        MenuBuilder {
+            config: GlobalConfig::default(),
+            title: String::from("Menu Principal"),
+            items: Vec::new(),
+        }
+    }
+}
+
+/// Arma un [`Menu`] ítem por ítem, en el orden en que se agregan.
+#[derive(Clone, Debug)]
+pub struct MenuBuilder {
+    config: GlobalConfig,
+    title: String,
+    items: Vec<MenuItem>,
+}
+
+impl MenuBuilder {
+    /// Título del menú raíz (en un submenú no se usa: manda su etiqueta).
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Configuración global, como la del bloque `config:`.
+    pub fn config(mut self, config: GlobalConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Un ítem ya armado, con todos sus atributos.
+    pub fn item(mut self, item: MenuItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Un ítem que ejecuta `cmd`.
+    pub fn command(self, label: impl Into<String>, cmd: impl Into<String>) -> Self {
+        self.item(MenuItem::new(label, MenuAction::Execute(cmd.into())))
+    }
+
+    /// Un ítem sin comando, para elegirlo en el modo selección
+    /// (`--print=label`): activarlo no ejecuta nada.
+    pub fn choice(self, label: impl Into<String>) -> Self {
+        self.item(MenuItem::new(label, MenuAction::Execute(String::new())))
+    }
+
+    /// Un ítem que cierra tmenu, como `exit` en el `.toon`.
+    pub fn quit(self, label: impl Into<String>) -> Self {
+        self.item(MenuItem::new(label, MenuAction::Quit))
+    }
+
+    /// Un submenú con los ítems que agrega `build` sobre un constructor vacío.
+    pub fn submenu(
+        self,
+        label: impl Into<String>,
+        build: impl FnOnce(MenuBuilder) -> MenuBuilder,
+    ) -> Self {
+        let children = build(Menu::builder()).items;
+        self.item(MenuItem::new(
+            label,
+            MenuAction::OpenSubmenu(Rc::new(children)),
+        ))
+    }
+
+    /// El menú terminado, ordenado según `sort` y los `[sort=...]` de cada
+    /// submenú como al parsear un archivo.
+    pub fn build(mut self) -> Menu {
+        let order = self.config.sort;
+        sort_tree(&mut self.items, order, order);
+        Menu {
+            config: self.config,
+            title: self.title,
+            items: Rc::new(self.items),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::SortOrder;
+    use crate::writer::write_toon;
+
+    #[test]
+    fn test_builder_matches_the_toon_format() {
+        let mut deploy = MenuItem::new("Deploy", MenuAction::Execute("./deploy.sh".to_string()));
+        deploy.require_confirmation = true;
+        let menu = Menu::builder()
+            .title("Proyecto")
+            .config(GlobalConfig {
+                wait: false,
+                ..GlobalConfig::default()
+            })
+            .submenu("Git", |git| {
+                git.command("Log", "git log")
+                    .command("Estado", "git status")
+            })
+            .item(deploy)
+            .quit("Salir")
+            .build();
+
+        assert_eq!(
+            write_toon(&menu.config, &menu.title, &menu.items),
+            "config:\n    wait: false\n\n\"Proyecto\":\n    \"Git\":\n        \"Log\": \"git log\"\n        \"Estado\": \"git status\"\n    \"Deploy\": \"./deploy.sh\" [confirm=true]\n    \"Salir\": exit\n"
+        );
+
+        let sorted = Menu::builder()
+            .config(GlobalConfig {
+                sort: SortOrder::Alpha,
+                ..GlobalConfig::default()
+            })
+            .command("b", "true")
+            .command("A", "true")
+            .build();
+        assert_eq!(sorted.items[0].label, "A");
+    }
+}
@@ -0,0 +1,86 @@
+//! Copiar texto al portapapeles del sistema (`y` en el menú).
+//!
+//! Se prueba primero con las herramientas del sistema (`wl-copy`, `xclip`,
+//! `xsel`, `pbcopy`, `clip`); si no hay ninguna o fallan (por ejemplo por SSH,
+//! sin servidor gráfico) se usa la secuencia OSC 52, que la terminal
+//! interpreta y copia en el portapapeles de la máquina local.
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::app::in_path;
+
+/// Programas de portapapeles, en orden, con sus argumentos.
+const TOOLS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["pbcopy"],
+    &["clip"],
+];
+
+/// Copia `text` al portapapeles.
+pub fn copy(text: &str) -> io::Result<()> {
+    if TOOLS
+        .iter()
+        .filter(|tool| in_path(tool[0]))
+        .any(|tool| copy_with(tool, text))
+    {
+        return Ok(());
+    }
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+/// Pasa `text` por la entrada de `tool`. `true` si terminó bien.
+fn copy_with(tool: &[&str], text: &str) -> bool {
+    let Ok(mut child) = Command::new(tool[0])
+        .args(&tool[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    // Al soltar stdin se cierra el pipe y la herramienta termina de leer
+    let written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+    child.wait().is_ok_and(|status| status.success()) && written
+}
+
+/// Codificación base64 estándar, con relleno, como la espera OSC 52.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("git log ñ".as_bytes()), "Z2l0IGxvZyDDsQ==");
+    }
+}
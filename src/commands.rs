@@ -0,0 +1,284 @@
+//! Subcomandos no interactivos: trabajan sobre el archivo de menú sin abrir la TUI.
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::TmenuError;
+use crate::model::{MenuAction, MenuItem, MenuItems, item_path};
+use crate::parser::{GlobalConfig, parse_toon_file, parse_toon_str};
+use crate::paths::DEFAULT_MENU_FILE;
+use crate::settings::SETTINGS_FILE;
+use crate::writer::{action_value, item_flags, write_toon};
+
+/// Formatos de archivo de menú que se pueden escribir.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum MenuFormat {
+    /// Formato nativo `.toon`
+    Toon,
+}
+
+/// Carga un menú desde `path`, o desde la entrada estándar si `path` es `-`.
+pub fn load_menu(path: &Path) -> Result<(GlobalConfig, String, MenuItems), TmenuError> {
+    if path.as_os_str() == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        return Ok(parse_toon_str(&content, Path::new(".")));
+    }
+    parse_toon_file(path).map_err(|e| match e {
+        TmenuError::IoError(ref io) if io.kind() == io::ErrorKind::NotFound => {
+            TmenuError::MenuFileNotFound(path.to_path_buf())
+        }
+        other => other,
+    })
+}
+
+/// `tmenu convert`: carga un menú y lo escribe en el formato `to`,
+/// en `output` o en la salida estándar.
+pub fn convert(input: &Path, to: MenuFormat, output: Option<&PathBuf>) -> Result<(), TmenuError> {
+    let (config, title, items) = load_menu(input)?;
+    let content = match to {
+        MenuFormat::Toon => write_toon(&config, &title, &items),
+    };
+
+    match output {
+        Some(path) => fs::write(path, content)?,
+        None => io::stdout().write_all(content.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Cómo imprime el menú `tmenu list`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListFormat {
+    /// Árbol de etiquetas y comandos
+    Tree,
+    /// JSON, para procesarlo con otras herramientas (`--json`)
+    Json,
+    /// Un camino de `tmenu run` por línea (`--paths`)
+    Paths,
+}
+
+/// `tmenu list`: imprime el menú como árbol, como JSON o como caminos de
+/// ítems, sin abrir la interfaz. Los submenús generados (`dir:`, `list:`…) se
+/// muestran con su origen, sin ejecutar nada.
+pub fn list(path: &Path, format: ListFormat) -> Result<(), TmenuError> {
+    let (_, title, items) = load_menu(path)?;
+    let content = match format {
+        ListFormat::Tree => tree_text(&title, &items),
+        ListFormat::Json => tree_json(&title, &items),
+        ListFormat::Paths => item_paths(&items),
+    };
+    io::stdout().write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// El menú como árbol: `Etiqueta: comando [atributos]`, con los pasos de una
+/// secuencia debajo de su ítem.
+pub fn tree_text(title: &str, items: &[MenuItem]) -> String {
+    fn walk(out: &mut String, items: &[MenuItem], prefix: &str) {
+        for (i, item) in items.iter().enumerate() {
+            let last = i + 1 == items.len();
+            let (branch, inner) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            out.push_str(prefix);
+            out.push_str(branch);
+            out.push_str(&item.label);
+            if let Some(value) = action_value(&item.action) {
+                out.push_str(": ");
+                out.push_str(&value);
+            }
+            let flags = item_flags(item);
+            if !flags.is_empty() {
+                out.push_str(&format!(" [{}]", flags.join(", ")));
+            }
+            out.push('\n');
+            let inner = format!("{}{}", prefix, inner);
+            for step in &item.steps {
+                out.push_str(&format!("{}  - {}\n", inner, step));
+            }
+            if let MenuAction::OpenSubmenu(children) = &item.action {
+                walk(out, children, &inner);
+            }
+        }
+    }
+
+    let mut out = format!("{}\n", title);
+    walk(&mut out, items, "");
+    out
+}
+
+/// Los caminos que acepta `tmenu run` (`Git/Estado`), uno por línea, de los
+/// ítems que ejecutan un comando, con las `/` de las etiquetas escapadas como
+/// las lee [`crate::model::parse_item_path`]. Los submenús generados no se recorren.
+pub fn item_paths(items: &[MenuItem]) -> String {
+    fn walk(out: &mut String, items: &[MenuItem], prefix: &str) {
+        for item in items {
+            let path = format!("{}{}", prefix, item_path(std::slice::from_ref(&item.label)));
+            match &item.action {
+                MenuAction::Execute(_) => {
+                    out.push_str(&path);
+                    out.push('\n');
+                }
+                MenuAction::OpenSubmenu(children) => walk(out, children, &format!("{}/", path)),
+                _ => {}
+            }
+        }
+    }
+
+    let mut out = String::new();
+    walk(&mut out, items, "");
+    out
+}
+
+/// El menú como JSON, en una línea: `{"title": ..., "items": [...]}`. Cada
+/// ítem tiene `label` y, según lo que haga, `command` (y `steps`), `items`
+/// (un submenú), `source` (un submenú generado, como en el `.toon`) o
+/// `exit: true`; además `confirm`, `cwd`, `hidden` y `disabled` cuando los lleva.
+pub fn tree_json(title: &str, items: &[MenuItem]) -> String {
+    fn items_json(items: &[MenuItem]) -> Value {
+        items.iter().map(item_json).collect()
+    }
+
+    fn item_json(item: &MenuItem) -> Value {
+        let mut fields = Map::new();
+        fields.insert("label".to_string(), json!(item.label));
+        match &item.action {
+            MenuAction::Execute(cmd) => {
+                fields.insert("command".to_string(), json!(cmd));
+                if !item.steps.is_empty() {
+                    fields.insert("steps".to_string(), json!(item.steps));
+                }
+            }
+            MenuAction::Quit => {
+                fields.insert("exit".to_string(), json!(true));
+            }
+            MenuAction::OpenSubmenu(children) => {
+                fields.insert("items".to_string(), items_json(children));
+            }
+            action => {
+                let source = action_value(action).unwrap_or_default();
+                fields.insert("source".to_string(), json!(source));
+            }
+        }
+        if item.require_confirmation || item.confirm_text.is_some() {
+            fields.insert("confirm".to_string(), json!(true));
+        }
+        if let Some(cwd) = &item.cwd {
+            fields.insert("cwd".to_string(), json!(cwd.display().to_string()));
+        }
+        for (name, set) in [("hidden", item.hidden), ("disabled", item.disabled)] {
+            if set {
+                fields.insert(name.to_string(), json!(true));
+            }
+        }
+        Value::Object(fields)
+    }
+
+    format!("{}\n", json!({"title": title, "items": items_json(items)}))
+}
+
+/// Menú de ejemplo, comentado, que escribe `tmenu init`.
+pub const EXAMPLE_MENU: &str = include_str!("../samples/init.toon");
+
+/// `config.toml` de ejemplo de `tmenu init --with-config`.
+pub const EXAMPLE_SETTINGS: &str = include_str!("../samples/config.toml");
+
+/// `tmenu init`: escribe el menú de ejemplo como `tmenu.toon` en `dir` y, con
+/// `settings_dir`, un `config.toml` de ejemplo ahí. Sin `force` no reemplaza
+/// archivos que ya existen (y entonces no escribe ninguno). Retorna los
+/// archivos creados.
+pub fn init(
+    dir: &Path,
+    settings_dir: Option<&Path>,
+    force: bool,
+) -> Result<Vec<PathBuf>, TmenuError> {
+    let mut files = vec![(dir.join(DEFAULT_MENU_FILE), EXAMPLE_MENU)];
+    if let Some(settings_dir) = settings_dir {
+        files.push((settings_dir.join(SETTINGS_FILE), EXAMPLE_SETTINGS));
+    }
+    if !force && let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+        return Err(TmenuError::FileExists(path.clone()));
+    }
+    for (path, content) in &files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+
+    #[test]
+    fn test_list_tree_json_and_paths() {
+        let (_, title, items) = parse_toon_str(
+            "\"Menu\":\n    Git:\n        Estado: \"git status\"\n        Push: \"git push\" [confirm=true]\n    Scripts: dir:/opt/scripts\n    Release: \"cargo build\"\n        - \"cargo publish\"\n    Salir: exit\n",
+            Path::new("/"),
+        );
+        assert_eq!(
+            tree_text(&title, &items),
+            "Menu\n\
+             ├── Git\n\
+             │   ├── Estado: git status\n\
+             │   └── Push: git push [confirm=true]\n\
+             ├── Scripts: dir:/opt/scripts\n\
+             ├── Release: cargo build\n\
+             │     - cargo publish\n\
+             └── Salir: exit\n"
+        );
+        assert_eq!(
+            tree_json(&title, &items),
+            "{\"title\":\"Menu\",\"items\":[\
+             {\"label\":\"Git\",\"items\":[{\"label\":\"Estado\",\"command\":\"git status\"},{\"label\":\"Push\",\"command\":\"git push\",\"confirm\":true}]},\
+             {\"label\":\"Scripts\",\"source\":\"dir:/opt/scripts\"},\
+             {\"label\":\"Release\",\"command\":\"cargo build\",\"steps\":[\"cargo publish\"]},\
+             {\"label\":\"Salir\",\"exit\":true}]}\n"
+        );
+        let label = "a \"b\"\\\n\u{1}";
+        let odd = [MenuItem::new(label, MenuAction::Quit)];
+        let parsed: Value = serde_json::from_str(&tree_json(label, &odd)).unwrap();
+        assert_eq!(parsed["title"], label);
+        assert_eq!(parsed["items"][0]["label"], label);
+        assert_eq!(item_paths(&items), "Git/Estado\nGit/Push\nRelease\n");
+    }
+
+    #[test]
+    fn test_init_writes_examples_that_load_cleanly() {
+        let (config, title, items) = parse_toon_str(EXAMPLE_MENU, Path::new("."));
+        assert_eq!(title, "Mi menú");
+        assert!(config.wait);
+        assert!(matches!(items[0].action, MenuAction::OpenSubmenu(_)));
+        assert!(matches!(items.last().unwrap().action, MenuAction::Quit));
+        let (_, errors) = Settings::parse(EXAMPLE_SETTINGS);
+        assert!(errors.is_empty());
+
+        let dir = std::env::temp_dir().join(format!("tmenu-init-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let written = init(&dir, Some(&dir), false).unwrap();
+        assert_eq!(
+            written,
+            vec![dir.join("tmenu.toon"), dir.join("config.toml")]
+        );
+        // Sin --force no se pisa nada
+        fs::write(dir.join("tmenu.toon"), "mío").unwrap();
+        assert!(matches!(
+            init(&dir, None, false),
+            Err(TmenuError::FileExists(path)) if path == dir.join("tmenu.toon")
+        ));
+        assert_eq!(fs::read_to_string(dir.join("tmenu.toon")).unwrap(), "mío");
+        init(&dir, None, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("tmenu.toon")).unwrap(),
+            EXAMPLE_MENU
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,189 @@
+//! `tmenu completions`: scripts de autocompletado para bash, zsh y fish.
+//!
+//! La parte fija (subcomandos, opciones, los valores de cada opción y las
+//! rutas de archivo) la genera `clap_complete` a partir de la definición de
+//! la línea de comandos. Encima se agrega, para cada shell, el camino de ítem
+//! de `tmenu run`, que depende del menú: se pide en el momento a
+//! `tmenu list --paths`.
+use clap::Command;
+use clap_complete::Shell;
+
+/// Shells para los que se generan scripts.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Script de autocompletado de `command` para `shell`.
+pub fn generate(command: &Command, shell: CompletionShell) -> String {
+    let mut command = command.clone();
+    let name = command.get_name().to_string();
+    let target = match shell {
+        CompletionShell::Bash => Shell::Bash,
+        CompletionShell::Zsh => Shell::Zsh,
+        CompletionShell::Fish => Shell::Fish,
+    };
+    let mut script = Vec::new();
+    clap_complete::generate(target, &mut command, &name, &mut script);
+    let script = String::from_utf8_lossy(&script).into_owned();
+    match shell {
+        CompletionShell::Bash => bash(&name, script),
+        CompletionShell::Zsh => zsh(&name, script),
+        CompletionShell::Fish => fish(&name, script),
+    }
+}
+
+/// Nombre de la función de completado de clap para `name` (`_tmenu`).
+fn function(name: &str) -> String {
+    format!("_{}", name.replace('-', "_"))
+}
+
+/// Con bash, una función propia completa el primer argumento después de
+/// `run` y deja el resto a la de clap, que se registra de nuevo apuntando a ella.
+fn bash(name: &str, mut script: String) -> String {
+    let function = function(name);
+    script.push_str(&format!(
+        r#"
+# Caminos de ítems para `{name} run` (`{name} list --paths`)
+{function}_run_paths() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" i run=0 args=0
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        if ((run)); then
+            [[ ${{COMP_WORDS[i]}} == -* ]] || ((args++))
+        elif [[ ${{COMP_WORDS[i]}} == run ]]; then
+            run=1
+        fi
+    done
+    if ((run && args == 0)) && [[ $cur != -* ]]; then
+        local IFS=$'\n' path
+        COMPREPLY=()
+        for path in $({name} list --paths 2>/dev/null); do
+            [[ $path == "$cur"* ]] && COMPREPLY+=("$(printf '%q' "$path")")
+        done
+        return
+    fi
+    {function} "$@"
+}}
+complete -F {function}_run_paths -o bashdefault -o default {name}
+"#
+    ));
+    script
+}
+
+/// Con zsh, el bloque final de clap (que llama a `_tmenu` o la registra con
+/// `compdef`) se reemplaza por uno que pasa antes por los caminos de `run`.
+fn zsh(name: &str, mut script: String) -> String {
+    let function = function(name);
+    if let Some(start) = script.rfind(&format!("if [ \"$funcstack[1]\" = \"{}\" ]", function)) {
+        script.truncate(start);
+    }
+    script.push_str(&format!(
+        r#"# Caminos de ítems para `{name} run` (`{name} list --paths`)
+{function}_run_paths() {{
+    local i run=0 args=0
+    for ((i = 2; i < CURRENT; i++)); do
+        if ((run)); then
+            [[ ${{words[i]}} == -* ]] || ((args++))
+        elif [[ ${{words[i]}} == run ]]; then
+            run=1
+        fi
+    done
+    if ((run && args == 0)) && [[ $PREFIX != -* ]]; then
+        compadd -- ${{(f)"$({name} list --paths 2>/dev/null)"}}
+        return
+    fi
+    {function} "$@"
+}}
+
+if [ "$funcstack[1]" = "{function}" ]; then
+    {function}_run_paths "$@"
+else
+    compdef {function}_run_paths {name}
+fi
+"#
+    ));
+    script
+}
+
+/// Con fish alcanza con una regla más para el primer argumento de `run`,
+/// con la condición que define el script de clap para ese subcomando.
+fn fish(name: &str, mut script: String) -> String {
+    script.push_str(&format!(
+        "\n# Caminos de ítems para `{name} run` (`{name} list --paths`)\n\
+         complete -c {name} -n '__fish_{name}_using_subcommand run; and test (count (commandline -opc | string match -v -- \"-*\")) -eq 2' -f -a '({name} list --paths 2>/dev/null)'\n"
+    ));
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+    use clap::builder::ValueHint;
+
+    fn command() -> Command {
+        Command::new("tmenu")
+            .arg(Arg::new("menu").value_hint(ValueHint::FilePath))
+            .subcommand(
+                Command::new("run")
+                    .about("Ejecuta un item")
+                    .arg(Arg::new("path").required(true))
+                    .arg(Arg::new("menu").value_hint(ValueHint::FilePath))
+                    .arg(
+                        Arg::new("yes")
+                            .long("yes")
+                            .short('y')
+                            .action(clap::ArgAction::SetTrue),
+                    ),
+            )
+            .subcommand(Command::new("list").about("Imprime el menu"))
+    }
+
+    #[test]
+    fn test_scripts_complete_run_paths() {
+        let bash = generate(&command(), CompletionShell::Bash);
+        assert!(bash.contains("_tmenu() {"));
+        assert!(bash.contains("for path in $(tmenu list --paths 2>/dev/null); do"));
+        assert!(bash.ends_with("complete -F _tmenu_run_paths -o bashdefault -o default tmenu\n"));
+
+        let zsh = generate(&command(), CompletionShell::Zsh);
+        assert!(zsh.starts_with("#compdef tmenu"));
+        assert!(zsh.contains("compadd -- ${(f)\"$(tmenu list --paths 2>/dev/null)\"}"));
+        assert!(zsh.contains("    compdef _tmenu_run_paths tmenu\n"));
+        assert!(!zsh.contains("    compdef _tmenu tmenu\n"));
+
+        let fish = generate(&command(), CompletionShell::Fish);
+        assert!(fish.contains("complete -c tmenu -n \"__fish_tmenu_using_subcommand run\""));
+        assert!(fish.contains("-f -a '(tmenu list --paths 2>/dev/null)'\n"));
+    }
+
+    /// El script de bash, cargado en un bash real con un `tmenu` de mentira.
+    #[cfg(unix)]
+    #[test]
+    fn test_bash_offers_item_paths_after_run() {
+        let script = generate(&command(), CompletionShell::Bash);
+        let complete = |words: &str| {
+            let output = std::process::Command::new("bash")
+                .arg("-c")
+                .arg(format!(
+                    "{}\ntmenu() {{ printf 'Git/Estado\\nDeploy/Restart api\\n'; }}\n\
+                     COMP_WORDS=({}); COMP_CWORD=$((${{#COMP_WORDS[@]}} - 1))\n\
+                     _tmenu_run_paths tmenu \"${{COMP_WORDS[COMP_CWORD]}}\" \"${{COMP_WORDS[COMP_CWORD-1]}}\"\n\
+                     printf '%s\\n' \"${{COMPREPLY[@]}}\"",
+                    script, words
+                ))
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+        assert_eq!(
+            complete("tmenu run ''"),
+            "Git/Estado\nDeploy/Restart\\ api\n"
+        );
+        assert_eq!(complete("tmenu run -y De"), "Deploy/Restart\\ api\n");
+        assert!(!complete("tmenu run Git/Estado ''").contains("Deploy"));
+        assert!(complete("tmenu ''").contains("run\n"));
+    }
+}
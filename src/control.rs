@@ -0,0 +1,240 @@
+//! Socket de control (`--listen ruta`): otros programas manejan un tmenu
+//! abierto mandando una línea por conexión, por ejemplo desde un atajo del
+//! gestor de ventanas con `tmenu send ruta "select Git/Log"` o con
+//! `echo reload | socat - UNIX-CONNECT:ruta`.
+//!
+//! Pedidos:
+//! - `select Camino/Al/Ítem`: navega hasta el ítem y lo deja seleccionado.
+//! - `run Camino/Al/Ítem`: lo mismo, y lo activa como con Enter.
+//! - `reload`: vuelve a leer el archivo de menú.
+//! - `quit`: cierra tmenu.
+//!
+//! Cada pedido recibe una línea de respuesta: `ok` o `error: motivo`. Los
+//! pedidos se atienden entre eventos, como mucho un `tick_rate` después de
+//! llegar. Solo está disponible en sistemas Unix.
+use std::path::{Path, PathBuf};
+
+use crate::error::TmenuError;
+use crate::model::parse_item_path;
+
+/// Un pedido recibido por el socket.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    Select(Vec<String>),
+    Run(Vec<String>),
+    Reload,
+    Quit,
+}
+
+impl Request {
+    /// Interpreta una línea (`select Git/Log`).
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let (verb, rest) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((line.trim(), ""));
+        let path = || -> Result<Vec<String>, String> {
+            let path = parse_item_path(rest);
+            if path.is_empty() {
+                return Err(format!("falta el camino del ítem: {} Camino/Al/Ítem", verb));
+            }
+            Ok(path)
+        };
+        match verb {
+            "select" => Ok(Request::Select(path()?)),
+            "run" => Ok(Request::Run(path()?)),
+            "reload" => Ok(Request::Reload),
+            "quit" => Ok(Request::Quit),
+            "" => Err("pedido vacío".to_string()),
+            _ => Err(format!("pedido desconocido '{}'", verb)),
+        }
+    }
+}
+
+/// Socket Unix donde escucha tmenu. El archivo se borra al soltarlo.
+pub struct ControlSocket {
+    path: PathBuf,
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+}
+
+/// Un pedido con la conexión por donde se responde.
+pub struct Connection {
+    pub request: Result<Request, String>,
+    #[cfg(unix)]
+    stream: std::os::unix::net::UnixStream,
+}
+
+#[cfg(unix)]
+impl ControlSocket {
+    /// Empieza a escuchar en `path`. Un socket viejo que ya nadie atiende se
+    /// reemplaza; si otro tmenu lo está usando, es un error.
+    pub fn bind(path: &Path) -> Result<Self, TmenuError> {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let error =
+            |e: std::io::Error| TmenuError::ControlError(format!("{}: {}", path.display(), e));
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(TmenuError::ControlError(format!(
+                    "{}: ya hay un tmenu escuchando",
+                    path.display()
+                )));
+            }
+            std::fs::remove_file(path).map_err(error)?;
+        }
+        let listener = UnixListener::bind(path).map_err(error)?;
+        listener.set_nonblocking(true).map_err(error)?;
+        Ok(ControlSocket {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    /// Los pedidos que llegaron desde la última vez, sin esperar.
+    pub fn accept(&self) -> Vec<Connection> {
+        use std::io::{BufRead, BufReader};
+        use std::time::Duration;
+
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = self.listener.accept() {
+            // El pedido llega enseguida; un cliente que no escribe no traba la interfaz
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(Duration::from_millis(200)));
+            let mut line = String::new();
+            let request = match stream.try_clone() {
+                Ok(reader) => match BufReader::new(reader).read_line(&mut line) {
+                    Ok(_) => Request::parse(&line),
+                    Err(e) => Err(e.to_string()),
+                },
+                Err(e) => Err(e.to_string()),
+            };
+            connections.push(Connection { request, stream });
+        }
+        connections
+    }
+}
+
+#[cfg(not(unix))]
+impl ControlSocket {
+    pub fn bind(_path: &Path) -> Result<Self, TmenuError> {
+        Err(TmenuError::ControlError(
+            "el socket de control solo está disponible en sistemas Unix".to_string(),
+        ))
+    }
+
+    pub fn accept(&self) -> Vec<Connection> {
+        Vec::new()
+    }
+}
+
+impl ControlSocket {
+    /// Ruta del socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Connection {
+    /// Responde `ok` o `error: motivo` y cierra la conexión.
+    pub fn reply(self, result: Result<(), String>) {
+        #[cfg(unix)]
+        {
+            use std::io::Write;
+            let mut stream = self.stream;
+            let _ = match result {
+                Ok(()) => writeln!(stream, "ok"),
+                Err(e) => writeln!(stream, "error: {}", e),
+            };
+        }
+        #[cfg(not(unix))]
+        let _ = result;
+    }
+}
+
+/// Manda `request` al tmenu que escucha en `path` y retorna su respuesta
+/// (`tmenu send`).
+#[cfg(unix)]
+pub fn send(path: &Path, request: &str) -> Result<String, TmenuError> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let error = |e: std::io::Error| TmenuError::ControlError(format!("{}: {}", path.display(), e));
+    let mut stream = UnixStream::connect(path).map_err(error)?;
+    writeln!(stream, "{}", request.trim()).map_err(error)?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).map_err(error)?;
+    Ok(reply.trim_end().to_string())
+}
+
+#[cfg(not(unix))]
+pub fn send(_path: &Path, _request: &str) -> Result<String, TmenuError> {
+    Err(TmenuError::ControlError(
+        "el socket de control solo está disponible en sistemas Unix".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        assert_eq!(
+            Request::parse("select Git / Log\n"),
+            Ok(Request::Select(vec!["Git".to_string(), "Log".to_string()]))
+        );
+        assert_eq!(
+            Request::parse("run Deploy"),
+            Ok(Request::Run(vec!["Deploy".to_string()]))
+        );
+        assert_eq!(
+            Request::parse(r"select Red/VPN up\/down"),
+            Ok(Request::Select(vec![
+                "Red".to_string(),
+                "VPN up/down".to_string()
+            ]))
+        );
+        assert_eq!(Request::parse("reload"), Ok(Request::Reload));
+        assert!(Request::parse("select").is_err());
+        assert_eq!(
+            Request::parse("borrar todo"),
+            Err("pedido desconocido 'borrar'".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_round_trip() {
+        let path = std::env::temp_dir().join(format!("tmenu-control-{}.sock", std::process::id()));
+        let socket = ControlSocket::bind(&path).unwrap();
+        assert!(ControlSocket::bind(&path).is_err());
+
+        let client = std::thread::spawn({
+            let path = path.clone();
+            move || send(&path, "select Git/Log").unwrap()
+        });
+        // La conexión del segundo `bind` llega vacía y se descarta
+        let connection = loop {
+            if let Some(connection) = socket.accept().into_iter().find(|c| c.request.is_ok()) {
+                break connection;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(
+            connection.request,
+            Ok(Request::Select(vec!["Git".to_string(), "Log".to_string()]))
+        );
+        connection.reply(Err("no existe".to_string()));
+        assert_eq!(client.join().unwrap(), "error: no existe");
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,119 @@
+//! Registro de depuración de `--debug`: qué menú se leyó y con qué
+//! configuración, las teclas recibidas, los comandos ejecutados y sus códigos
+//! de salida, en `~/.local/share/tmenu/debug.log`. Nunca escribe en la
+//! terminal, que es de la interfaz.
+//!
+//! La biblioteca anota con las macros de [`log`] (target `tmenu::...`); el
+//! binario instala [`FileLogger`] con [`init`]. Quien embeba el motor o use
+//! la API `ffi` puede instalar su propio logger, y sin ninguno los mensajes
+//! se descartan.
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::TmenuError;
+
+/// Retorna la ruta del registro: `~/.local/share/tmenu/debug.log`
+pub fn log_file_path() -> Result<PathBuf, TmenuError> {
+    let home = dirs::home_dir().ok_or_else(|| {
+        TmenuError::DebugLogError("No se pudo determinar el directorio home".to_string())
+    })?;
+    Ok(home.join(".local/share/tmenu/debug.log"))
+}
+
+/// Logger que agrega líneas `[HH:MM:SS.mmm] mensaje` a un archivo. Solo
+/// anota lo de tmenu, no lo que registren las dependencias.
+pub struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl FileLogger {
+    /// Abre `path` para agregar al final, creando la carpeta si hace falta.
+    pub fn open(path: &Path) -> Result<Self, TmenuError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                TmenuError::DebugLogError(format!("No se pudo crear directorio: {}", e))
+            })?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                TmenuError::DebugLogError(format!("No se pudo abrir {}: {}", path.display(), e))
+            })?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("tmenu")
+    }
+
+    /// Un error al escribir se ignora: depurar no puede romper el menú.
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(
+            file,
+            "[{}] {}",
+            Local::now().format("%H:%M:%S%.3f"),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap_or_else(|e| e.into_inner()).flush();
+    }
+}
+
+/// Instala un [`FileLogger`] sobre `path` como logger del proceso. Cada
+/// sesión arranca con una línea que la separa de la anterior.
+pub fn init(path: &Path) -> Result<(), TmenuError> {
+    let logger = FileLogger::open(path)?;
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|e| TmenuError::DebugLogError(e.to_string()))?;
+    log::set_max_level(LevelFilter::Debug);
+    log::info!(
+        "--- tmenu {} (pid {})",
+        env!("CARGO_PKG_VERSION"),
+        std::process::id()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_logger_writes_only_tmenu_records() {
+        let path = std::env::temp_dir().join(format!("tmenu-debug-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let logger = FileLogger::open(&path).unwrap();
+        for (target, message) in [("tmenu::tui", "tecla: j"), ("mio::poll", "descartado")] {
+            logger.log(
+                &Record::builder()
+                    .target(target)
+                    .level(log::Level::Debug)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        let content = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(content.lines().count(), 1);
+        assert!(content.starts_with('['));
+        assert!(content.trim_end().ends_with("] tecla: j"));
+    }
+}
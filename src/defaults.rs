@@ -0,0 +1,141 @@
+//! Valores por defecto del usuario para todos los menús, en `config.toml`:
+//!
+//! ```toml
+//! menu = "~/menus/ops.toon"
+//!
+//! [config]
+//! shell = "bash"
+//! wait = false
+//! history = false
+//!
+//! [keys]
+//! quit = "ctrl+x, q"
+//! ```
+//!
+//! `menu` es el archivo que se abre cuando no se indica uno (relativo a la
+//! carpeta de `config.toml`). `[config]` acepta
+//! las mismas claves que el `config:` de un menú y `[keys]` las mismas que su
+//! `keys:`; son el punto de partida de cada menú, que puede cambiarlas, y las
+//! opciones de la línea de comandos (`--shell`, `--no-wait`…) se aplican encima.
+use std::path::PathBuf;
+
+use crate::parser::{GlobalConfig, apply_config, resolve_path};
+use crate::settings::{Settings, Value};
+
+/// Preferencias de `config.toml` que no son de la interfaz.
+#[derive(Clone, Debug, Default)]
+pub struct Defaults {
+    /// Archivo de menú por defecto (`menu = "..."`)
+    pub menu_file: Option<PathBuf>,
+    /// Configuración de partida de los menús (`[config]` y `[keys]`)
+    pub config: GlobalConfig,
+}
+
+impl Defaults {
+    /// Lee `menu`, `[config]` y `[keys]`. El segundo valor describe las claves
+    /// desconocidas o con valores inválidos.
+    pub fn from_settings(settings: &Settings) -> (Self, Vec<String>) {
+        let mut defaults = Defaults::default();
+        let mut errors = Vec::new();
+        for (key, value) in settings.section("") {
+            match (key.as_str(), value) {
+                ("menu", Value::Str(path)) => {
+                    defaults.menu_file = Some(resolve_path(settings.dir(), path))
+                }
+                ("menu", _) => errors.push("menu: se esperaba una ruta entre comillas".to_string()),
+                _ => errors.push(format!("{}: clave desconocida", key)),
+            }
+        }
+        for (key, value) in settings.section("config") {
+            let known = match value {
+                Value::Str(text) => apply_config(&mut defaults.config, key, text),
+                Value::Int(n) => apply_config(&mut defaults.config, key, &n.to_string()),
+                Value::Float(x) => apply_config(&mut defaults.config, key, &x.to_string()),
+                Value::Bool(flag) => apply_config(&mut defaults.config, key, &flag.to_string()),
+                Value::List(_) => {
+                    errors.push(format!("[config] {}: se esperaba un solo valor", key));
+                    continue;
+                }
+            };
+            if !known {
+                errors.push(format!("[config] {}: clave desconocida", key));
+            }
+        }
+        for (action, value) in settings.section("keys") {
+            let keys = match value {
+                Value::Str(keys) => keys.clone(),
+                Value::List(keys) => keys.join(", "),
+                _ => {
+                    errors.push(format!(
+                        "[keys] {}: se esperaba un texto o una lista de teclas",
+                        action
+                    ));
+                    continue;
+                }
+            };
+            defaults.config.keys.push((action.clone(), keys));
+        }
+        (defaults, errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Shell;
+
+    #[test]
+    fn test_defaults_from_settings() {
+        let (settings, _) = Settings::parse(
+            "menu = \"/srv/menus/ops.toon\"\n\
+             color = true\n\
+             [config]\n\
+             shell = \"bash -lc\"\n\
+             wait = false\n\
+             tick_rate = 250\n\
+             history = false\n\
+             colour = \"red\"\n\
+             [keys]\n\
+             quit = \"ctrl+x\"\n\
+             back = [\"esc\", \"h\"]\n",
+        );
+        let (defaults, errors) = Defaults::from_settings(&settings);
+        assert_eq!(
+            errors,
+            vec![
+                "color: clave desconocida",
+                "[config] colour: clave desconocida"
+            ]
+        );
+        assert_eq!(
+            defaults.menu_file,
+            Some(PathBuf::from("/srv/menus/ops.toon"))
+        );
+        assert_eq!(defaults.config.shell, Shell::parse("bash -lc"));
+        assert!(!defaults.config.wait);
+        assert!(!defaults.config.history);
+        assert_eq!(defaults.config.tick_rate.as_millis(), 250);
+        assert_eq!(
+            defaults.config.keys,
+            vec![
+                ("quit".to_string(), "ctrl+x".to_string()),
+                ("back".to_string(), "esc, h".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_menu_is_relative_to_the_config_file() {
+        let dir = std::env::temp_dir().join(format!("tmenu-kiosk-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("kiosk.toml");
+        std::fs::write(&path, "menu = \"menus/kiosk.toon\"\n").unwrap();
+
+        let (settings, errors) = Settings::load_from(&path);
+        let (defaults, _) = Defaults::from_settings(&settings);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(errors.is_empty());
+        assert_eq!(defaults.menu_file, Some(dir.join("menus/kiosk.toon")));
+    }
+}
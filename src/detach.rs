@@ -0,0 +1,127 @@
+//! Comandos que se lanzan aparte de tmenu (`[detach=true]` y `[detach=terminal]`).
+//!
+//! Un comando separado corre en su propia sesión, sin la terminal de tmenu:
+//! una aplicación gráfica o una TUI larga no la bloquea, y sigue viva al
+//! cerrar el menú. Con `terminal` se abre además en un emulador de terminal.
+use std::process::{Command, Stdio};
+
+/// Emuladores que se prueban en orden cuando no hay `terminal:` ni
+/// `$TERMINAL`, con las opciones que preceden al comando a ejecutar.
+#[cfg(all(unix, not(target_os = "macos")))]
+const TERMINALS: &[&[&str]] = &[
+    &["x-terminal-emulator", "-e"],
+    &["gnome-terminal", "--"],
+    &["konsole", "-e"],
+    &["alacritty", "-e"],
+    &["kitty"],
+    &["wezterm", "start", "--"],
+    &["foot"],
+    &["xterm", "-e"],
+];
+
+/// Lanza `command` en una sesión propia, sin entrada ni salida, y no espera a
+/// que termine.
+///
+/// En Unix se hace un doble fork: el proceso intermedio crea la sesión, se
+/// bifurca y sale enseguida, y es lo único que se espera. El comando queda
+/// a cargo de init, que lo recoge al terminar; si no, quedaría como zombi de
+/// tmenu hasta cerrarlo.
+pub fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid, fork y _exit son async-signal-safe
+        unsafe {
+            command.pre_exec(|| {
+                libc::setsid();
+                match libc::fork() {
+                    -1 => Err(std::io::Error::last_os_error()),
+                    // El nieto sigue hasta el exec del comando
+                    0 => Ok(()),
+                    _ => libc::_exit(0),
+                }
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?
+        .wait()
+        .map(|_| ())
+}
+
+/// Línea de comando que abre `parts` en un emulador de terminal: el de
+/// `terminal:` si está configurado, si no `$TERMINAL` o el primero que se
+/// encuentre. `None` si no hay ninguno disponible.
+pub fn in_terminal(configured: Option<&[String]>, parts: &[String]) -> Option<Vec<String>> {
+    let mut line = match configured {
+        Some(words) => words.to_vec(),
+        None => default_terminal()?,
+    };
+    line.extend(parts.iter().cloned());
+    Some(line)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn default_terminal() -> Option<Vec<String>> {
+    if let Ok(terminal) = std::env::var("TERMINAL")
+        && !terminal.trim().is_empty()
+    {
+        let mut words = shlex::split(&terminal).unwrap_or_else(|| vec![terminal.clone()]);
+        words.push("-e".to_string());
+        return Some(words);
+    }
+    TERMINALS
+        .iter()
+        .find(|words| crate::app::in_path(words[0]))
+        .map(|words| words.iter().map(|w| w.to_string()).collect())
+}
+
+/// Fuera de Linux y BSD no hay una forma común de pasarle un comando al
+/// emulador: hace falta `terminal:`.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn default_terminal() -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spawn_detached_runs_the_command() {
+        let path = std::env::temp_dir().join(format!("tmenu-detach-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut command = Command::new("touch");
+        command.arg(&path);
+
+        spawn_detached(&mut command).unwrap();
+        let started = std::time::Instant::now();
+        while !path.exists() {
+            assert!(started.elapsed() < std::time::Duration::from_secs(5));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let _ = std::fs::remove_file(&path);
+        assert!(spawn_detached(&mut Command::new("tmenu-no-existe")).is_err());
+    }
+
+    #[test]
+    fn test_in_terminal_uses_configured_prefix() {
+        let configured = vec!["wezterm".to_string(), "start".to_string(), "--".to_string()];
+        let parts = vec!["htop".to_string(), "-d".to_string(), "10".to_string()];
+        assert_eq!(
+            in_terminal(Some(&configured), &parts).unwrap(),
+            vec!["wezterm", "start", "--", "htop", "-d", "10"]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use ratatui::{Terminal, backend::Backend};
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::app::{resume_tui, suspend_tui};
+use crate::error::TmenuError;
+
+/// Editor usado cuando ni `$VISUAL` ni `$EDITOR` están definidos.
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Abre `file` en el editor del usuario (`$VISUAL`, `$EDITOR` o `vi`) con el
+/// cursor en `line`. La TUI se suspende mientras el editor está abierto; si el
+/// archivo es el menú principal, la recarga automática toma los cambios.
+pub fn open_in_editor<B: Backend>(
+    terminal: &mut Terminal<B>,
+    file: &Path,
+    line: usize,
+) -> Result<(), TmenuError> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let parts = editor_command(&editor, file, line);
+    let Some((bin, args)) = parts.split_first() else {
+        return Ok(());
+    };
+
+    suspend_tui();
+    let result = Command::new(bin).args(args).status();
+    resume_tui(terminal)?;
+
+    result
+        .map(|_| ())
+        .map_err(|e| TmenuError::TerminalError(format!("no se pudo abrir '{}': {}", bin, e)))
+}
+
+/// Línea de comando para abrir `file` en `line` según el editor: la mayoría
+/// acepta `+línea archivo` (vi, vim, nano, emacs, micro); VS Code y Helix usan
+/// `archivo:línea`.
+fn editor_command(editor: &str, file: &Path, line: usize) -> Vec<String> {
+    // $EDITOR puede traer argumentos ("code --wait"), respetar el quoting
+    let mut parts = shlex::split(editor).unwrap_or_else(|| vec![editor.to_string()]);
+    let name = parts
+        .first()
+        .and_then(|bin| Path::new(bin).file_stem())
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let file = file.display().to_string();
+    match name {
+        "code" | "codium" => parts.extend(["-g".to_string(), format!("{}:{}", file, line)]),
+        "hx" | "helix" => parts.push(format!("{}:{}", file, line)),
+        _ => parts.extend([format!("+{}", line), file]),
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_command() {
+        let file = Path::new("/menus/ops.toon");
+        assert_eq!(
+            editor_command("vim", file, 12),
+            vec!["vim", "+12", "/menus/ops.toon"]
+        );
+        assert_eq!(
+            editor_command("/usr/bin/code --wait", file, 3),
+            vec!["/usr/bin/code", "--wait", "-g", "/menus/ops.toon:3"]
+        );
+        assert_eq!(
+            editor_command("hx", file, 7),
+            vec!["hx", "/menus/ops.toon:7"]
+        );
+    }
+}
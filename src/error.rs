@@ -1,49 +1,63 @@
-use std::fmt;
 use std::path::PathBuf;
+use thiserror::Error;
 
 /// Errores tipados de la aplicación.
-#[derive(Debug)]
-pub enum AppError {
+#[derive(Debug, Error)]
+pub enum TmenuError {
+    #[error("El archivo de menú no fue encontrado: {}\n", .0.display())]
     MenuFileNotFound(PathBuf),
-    IoError(std::io::Error),
+    #[error("El archivo de configuración no fue encontrado: {}\n", .0.display())]
+    ConfigFileNotFound(PathBuf),
+    #[error("El archivo ya existe: {} (--force lo reemplaza)\n", .0.display())]
+    FileExists(PathBuf),
+    #[error("Error de I/O: {0}\n")]
+    IoError(#[from] std::io::Error),
+    #[error("Error de terminal: {0}\n")]
     TerminalError(String),
+    #[error("El comando contiene caracteres no permitidos: '{0}'\n")]
     ForbiddenCommand(String),
+    #[error("Error de evento de terminal: {0}\n")]
     EventError(String),
+    #[error("Error al guardar historial: {0}\n")]
     HistoryError(String),
+    #[error("No se pudo generar el submenú: {0}\n")]
+    SubmenuError(String),
+    #[error("Error en estadísticas de uso: {0}\n")]
+    StatsError(String),
+    #[error("Error en favoritos o recientes: {0}\n")]
+    SavedItemsError(String),
+    #[error("Error en la última posición del menú: {0}\n")]
+    PositionError(String),
+    #[error("Error en el socket de control: {0}\n")]
+    ControlError(String),
+    #[error("Error en el registro de depuración: {0}\n")]
+    DebugLogError(String),
+    /// `tmenu run`: el ítem no existe, no ejecuta nada o se canceló
+    #[error("No se pudo ejecutar el ítem: {0}\n")]
+    RunError(String),
+    /// Un script Rhai que falló o no retornó lo esperado (`script:...`)
+    #[error("Error en el script: {0}\n")]
+    ScriptError(String),
+    /// Un comando que no se pudo lanzar: programa y motivo
+    #[error("No se pudo ejecutar '{0}': {1}\n")]
+    SpawnError(String, #[source] std::io::Error),
 }
 
-impl fmt::Display for AppError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            AppError::MenuFileNotFound(path) => {
-                write!(
-                    f,
-                    "El archivo de menú no fue encontrado: {}\n",
-                    path.display()
-                )
-            }
-            AppError::IoError(e) => write!(f, "Error de I/O: {}\n", e),
-            AppError::TerminalError(msg) => write!(f, "Error de terminal: {}\n", msg),
-            AppError::ForbiddenCommand(c) => {
-                write!(f, "El comando contiene caracteres no permitidos: '{}'\n", c)
-            }
-            AppError::EventError(msg) => write!(f, "Error de evento de terminal: {}\n", msg),
-            AppError::HistoryError(msg) => write!(f, "Error al guardar historial: {}\n", msg),
-        }
-    }
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+    use std::io;
 
-impl std::error::Error for AppError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            AppError::IoError(e) => Some(e),
-            _ => None,
-        }
-    }
-}
+    #[test]
+    fn test_io_errors_are_the_source() {
+        let err = TmenuError::from(io::Error::new(io::ErrorKind::NotFound, "falta"));
+        assert!(matches!(err, TmenuError::IoError(_)));
+        assert_eq!(err.source().unwrap().to_string(), "falta");
 
-impl From<std::io::Error> for AppError {
-    fn from(e: std::io::Error) -> Self {
-        AppError::IoError(e)
+        let err = TmenuError::SpawnError("htop".to_string(), io::Error::other("sin permiso"));
+        assert_eq!(err.to_string(), "No se pudo ejecutar 'htop': sin permiso\n");
+        assert_eq!(err.source().unwrap().to_string(), "sin permiso");
+        assert!(TmenuError::RunError("x".to_string()).source().is_none());
     }
 }
@@ -0,0 +1,214 @@
+//! API `extern "C"` del motor de menús, para reutilizar el parser y la
+//! navegación desde otros lenguajes. La biblioteca para C se compila aparte,
+//! con `cargo rustc --lib --release --features ffi --crate-type cdylib` (o
+//! `staticlib`); las declaraciones para C están en `include/tmenu.h`.
+//!
+//! Convenciones:
+//! - `tmenu_load` retorna un handle opaco que se libera con `tmenu_free`.
+//! - Los strings retornados son UTF-8 terminados en NUL, propiedad del
+//!   llamador, y se liberan con `tmenu_string_free`.
+//! - Las funciones que retornan `c_int` usan `0` = ok / no, `1` = sí, `-1` = error.
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::path::Path;
+use std::process::Command;
+use std::ptr;
+
+use crate::app::App;
+use crate::model::MenuAction;
+
+/// Carga un archivo de menú `.toon`. Retorna NULL si no se pudo cargar.
+///
+/// # Safety
+/// `path` debe ser un string C válido terminado en NUL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_load(path: *const c_char) -> *mut App {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+    let Ok(path) = unsafe { CStr::from_ptr(path) }.to_str() else {
+        return ptr::null_mut();
+    };
+    match App::from_toon(Path::new(path), false) {
+        Ok(app) => Box::into_raw(Box::new(app)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Libera un handle obtenido con `tmenu_load`. Acepta NULL.
+///
+/// # Safety
+/// `app` debe venir de `tmenu_load` y no usarse después de liberarlo.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_free(app: *mut App) {
+    if !app.is_null() {
+        drop(unsafe { Box::from_raw(app) });
+    }
+}
+
+/// Libera un string retornado por esta API. Acepta NULL.
+///
+/// # Safety
+/// `s` debe venir de una función de esta API y no usarse después de liberarlo.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Mueve la selección al ítem siguiente (con wrap-around).
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_next(app: *mut App) {
+    if let Some(app) = unsafe { app.as_mut() } {
+        app.next();
+    }
+}
+
+/// Mueve la selección al ítem anterior (con wrap-around).
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_prev(app: *mut App) {
+    if let Some(app) = unsafe { app.as_mut() } {
+        app.previous();
+    }
+}
+
+/// Entra al submenú seleccionado. Retorna 1 si entró, 0 si el ítem no es
+/// un submenú y -1 si un submenú generado no se pudo construir.
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_enter(app: *mut App) -> c_int {
+    let Some(app) = (unsafe { app.as_mut() }) else {
+        return -1;
+    };
+    match app.enter_selected() {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Vuelve al menú anterior. Retorna 1 si volvió, 0 si ya estaba en la raíz.
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_back(app: *mut App) -> c_int {
+    match unsafe { app.as_mut() } {
+        Some(app) => c_int::from(app.back()),
+        None => -1,
+    }
+}
+
+/// Cantidad de ítems del nivel actual.
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_item_count(app: *const App) -> usize {
+    unsafe { app.as_ref() }.map_or(0, |app| app.current_items.len())
+}
+
+/// Índice del ítem seleccionado, o -1 si no hay selección.
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_selected(app: *const App) -> isize {
+    unsafe { app.as_ref() }
+        .and_then(|app| app.state.selected())
+        .map_or(-1, |i| i as isize)
+}
+
+/// Etiqueta del ítem `index` del nivel actual, o NULL si no existe.
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_item_label(app: *const App, index: usize) -> *mut c_char {
+    unsafe { app.as_ref() }
+        .and_then(|app| app.current_items.get(index))
+        .and_then(|item| CString::new(item.label.as_str()).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Ejecuta el comando del ítem seleccionado heredando la terminal del
+/// llamador y espera a que termine. Retorna el código de salida, o -1 si el
+/// ítem no es un comando, el comando no es seguro o no se pudo lanzar.
+/// Los parámetros interpolados (`{{text: ...}}`) no se piden: se ejecuta tal cual.
+///
+/// # Safety
+/// `app` debe ser un handle válido de `tmenu_load`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tmenu_run_selected(app: *const App) -> c_int {
+    let Some(app) = (unsafe { app.as_ref() }) else {
+        return -1;
+    };
+    let Some(item) = app.state.selected().and_then(|i| app.current_items.get(i)) else {
+        return -1;
+    };
+    let MenuAction::Execute(cmd) = &item.action else {
+        return -1;
+    };
+    if !App::is_safe_command(cmd) {
+        return -1;
+    }
+    let Some(parts) = shlex::split(cmd) else {
+        return -1;
+    };
+    let Some((bin, args)) = parts.split_first() else {
+        return -1;
+    };
+
+    let mut command = Command::new(bin);
+    command.args(args);
+    command.envs(app.env.iter().chain(&item.env).map(|(k, v)| (k, v)));
+    if let Some(cwd) = &item.cwd {
+        command.current_dir(cwd);
+    }
+    match command.status() {
+        Ok(status) => status.code().unwrap_or(-1),
+        Err(_) => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_load_and_navigate() {
+        let path = std::env::temp_dir().join(format!("tmenu-ffi-{}.toon", std::process::id()));
+        std::fs::write(
+            &path,
+            "\"M\":\n    Sub:\n        A: \"echo a\"\n    Salir: exit\n",
+        )
+        .unwrap();
+        let c_path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+
+        unsafe {
+            let app = tmenu_load(c_path.as_ptr());
+            let _ = std::fs::remove_file(&path);
+            assert!(!app.is_null());
+            assert_eq!(tmenu_item_count(app), 2);
+
+            let label = tmenu_item_label(app, 0);
+            assert_eq!(CStr::from_ptr(label).to_str().unwrap(), "Sub");
+            tmenu_string_free(label);
+
+            assert_eq!(tmenu_enter(app), 1);
+            assert_eq!(tmenu_item_count(app), 1);
+            assert_eq!(tmenu_enter(app), 0);
+            assert_eq!(tmenu_back(app), 1);
+            assert_eq!(tmenu_back(app), 0);
+            tmenu_free(app);
+        }
+    }
+}
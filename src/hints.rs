@@ -0,0 +1,143 @@
+//! Textos del pie del menú, configurables (y traducibles) en la sección
+//! `[hints]` de `config.toml`:
+//!
+//! ```toml
+//! [hints]
+//! back = "Back"
+//! quit = "Quit"
+//! item_help = "Help"
+//! dry_run = "DRY RUN"
+//! ```
+//!
+//! Las teclas entre corchetes salen del keymap, así que siguen a `keys:`.
+//! Un texto vacío oculta esa indicación.
+use crate::keymap::{Action, Keymap};
+use crate::settings::{Settings, Value};
+use unicode_width::UnicodeWidthStr;
+
+/// Textos de cada indicación del pie.
+#[derive(Clone, Debug)]
+pub struct Hints {
+    /// Volver al menú anterior, solo en los submenús
+    pub back: String,
+    /// Salir de la aplicación
+    pub quit: String,
+    /// Ayuda del ítem seleccionado, solo si tiene una
+    pub item_help: String,
+    /// Marca del modo simulación (`--dry-run`, F3)
+    pub dry_run: String,
+}
+
+impl Default for Hints {
+    fn default() -> Self {
+        Hints {
+            back: "Volver".to_string(),
+            quit: "Salir".to_string(),
+            item_help: "Ayuda".to_string(),
+            dry_run: "SIMULACIÓN".to_string(),
+        }
+    }
+}
+
+/// Pie armado para un nivel del menú.
+#[derive(Debug, PartialEq)]
+pub struct Footer {
+    pub text: String,
+    /// Columna (en celdas de la terminal, desde el inicio del texto) y ancho del
+    /// `[Esc] Volver`, que se puede clicar
+    pub back: Option<(usize, usize)>,
+}
+
+impl Hints {
+    /// Textos por defecto con los de `[hints]` aplicados encima. El segundo
+    /// valor describe las claves desconocidas o con valores inválidos.
+    pub fn from_settings(settings: &Settings) -> (Self, Vec<String>) {
+        let mut hints = Hints::default();
+        let mut errors = Vec::new();
+        for (key, value) in settings.section("hints") {
+            let field = match key.as_str() {
+                "back" => &mut hints.back,
+                "quit" => &mut hints.quit,
+                "item_help" => &mut hints.item_help,
+                "dry_run" => &mut hints.dry_run,
+                _ => {
+                    errors.push(format!("[hints] {}: clave desconocida", key));
+                    continue;
+                }
+            };
+            match value {
+                Value::Str(text) => *field = text.clone(),
+                _ => errors.push(format!(
+                    "[hints] {}: se esperaba un texto entre comillas",
+                    key
+                )),
+            }
+        }
+        (hints, errors)
+    }
+
+    /// Pie de un nivel: ` [i] Ayuda [Esc] Volver [Ctrl+Q] Salir `, con la
+    /// primera tecla de cada acción. `nested` = hay un menú al que volver.
+    pub fn footer(&self, keymap: &Keymap, nested: bool, has_help: bool, dry_run: bool) -> Footer {
+        let mut text = String::from(" ");
+        let mut back = None;
+        if dry_run && !self.dry_run.is_empty() {
+            text.push_str(&format!("{} · ", self.dry_run));
+        }
+        let parts = [
+            (Action::ItemHelp, &self.item_help, has_help),
+            (Action::Back, &self.back, nested),
+            (Action::Quit, &self.quit, true),
+        ];
+        for (action, label, shown) in parts {
+            let Some(key) = keymap
+                .key_label(action)
+                .filter(|_| shown && !label.is_empty())
+            else {
+                continue;
+            };
+            let part = format!("[{}] {}", key, label);
+            if action == Action::Back {
+                back = Some((text.width(), part.width()));
+            }
+            text.push_str(&part);
+            text.push(' ');
+        }
+        Footer { text, back }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footer_follows_keys_and_texts() {
+        let hints = Hints::default();
+        let keymap = Keymap::default();
+        assert_eq!(
+            hints.footer(&keymap, false, false, false).text,
+            " [Ctrl+Q] Salir "
+        );
+        let footer = hints.footer(&keymap, true, true, true);
+        assert_eq!(
+            footer.text,
+            " SIMULACIÓN · [i] Ayuda [Esc] Volver [Ctrl+Q] Salir "
+        );
+        let (column, width) = footer.back.unwrap();
+        let back: String = footer.text.chars().skip(column).take(width).collect();
+        assert_eq!(back, "[Esc] Volver");
+
+        // Traducido y con las teclas de `keys:`
+        let (settings, _) = Settings::parse(
+            "[hints]\nback = \"Back\"\nquit = \"Quit\"\nitem_help = \"\"\ncolor = \"red\"\n",
+        );
+        let (hints, errors) = Hints::from_settings(&settings);
+        assert_eq!(errors, vec!["[hints] color: clave desconocida"]);
+        let (keymap, _) = Keymap::from_config(&[("back".to_string(), "backspace".to_string())]);
+        assert_eq!(
+            hints.footer(&keymap, true, true, false).text,
+            " [Backspace] Back [Ctrl+Q] Quit "
+        );
+    }
+}
@@ -3,56 +3,100 @@ use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
 
-use crate::error::AppError;
+use crate::error::TmenuError;
 
 /// Retorna la ruta al archivo de historial: `~/.local/share/tmenu/history.log`
-fn history_file_path() -> Result<PathBuf, AppError> {
+fn history_file_path() -> Result<PathBuf, TmenuError> {
     let home = dirs::home_dir().ok_or_else(|| {
-        AppError::HistoryError("No se pudo determinar el directorio home".to_string())
+        TmenuError::HistoryError("No se pudo determinar el directorio home".to_string())
     })?;
     Ok(home.join(".local/share/tmenu/history.log"))
 }
 
 /// Asegura que el directorio `~/.local/share/tmenu/` existe.
 /// Si no existe, lo crea con permisos estándar (0o755).
-fn ensure_history_dir() -> Result<(), AppError> {
+fn ensure_history_dir() -> Result<(), TmenuError> {
     let history_path = history_file_path()?;
     let dir = history_path
         .parent()
-        .ok_or_else(|| AppError::HistoryError("Ruta de historial inválida".to_string()))?;
+        .ok_or_else(|| TmenuError::HistoryError("Ruta de historial inválida".to_string()))?;
 
     if !dir.exists() {
         fs::create_dir_all(dir)
-            .map_err(|e| AppError::HistoryError(format!("No se pudo crear directorio: {}", e)))?;
+            .map_err(|e| TmenuError::HistoryError(format!("No se pudo crear directorio: {}", e)))?;
     }
     Ok(())
 }
 
-/// Registra un comando ejecutado en el historial.
-/// Formato: `[YYYY-MM-DD HH:MM:SS] comando completo`
+/// Una línea del historial.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// Fecha y hora, `YYYY-MM-DD HH:MM:SS`
+    pub timestamp: String,
+    /// Código de salida; `None` si no hubo (terminado por señal o por
+    /// `timeout`) o si la línea es de una versión que no lo registraba
+    pub code: Option<i32>,
+    pub cmd: String,
+}
+
+/// Registra un comando ejecutado en el historial, con su código de salida.
+/// Formato: `[YYYY-MM-DD HH:MM:SS] [código] comando completo` (`[?]` sin código).
 ///
 /// # Errores
-/// Retorna `AppError::HistoryError` si no se puede escribir el archivo.
+/// Retorna `TmenuError::HistoryError` si no se puede escribir el archivo.
 /// No es un error fatal — si falla, la app continúa (solo se pierden los logs).
-pub fn log_command(cmd: &str) -> Result<(), AppError> {
+pub fn log_command(cmd: &str, code: Option<i32>) -> Result<(), TmenuError> {
     ensure_history_dir()?;
     let history_path = history_file_path()?;
 
     let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    let entry = format!("[{}] {}\n", timestamp, cmd);
+    let code = code.map_or_else(|| "?".to_string(), |code| code.to_string());
+    let entry = format!("[{}] [{}] {}\n", timestamp, code, cmd);
 
     let mut file = fs::OpenOptions::new()
         .create(true)
         .append(true)
         .open(&history_path)
-        .map_err(|e| AppError::HistoryError(format!("No se pudo abrir historial: {}", e)))?;
+        .map_err(|e| TmenuError::HistoryError(format!("No se pudo abrir historial: {}", e)))?;
 
     file.write_all(entry.as_bytes())
-        .map_err(|e| AppError::HistoryError(format!("No se pudo escribir historial: {}", e)))?;
+        .map_err(|e| TmenuError::HistoryError(format!("No se pudo escribir historial: {}", e)))?;
 
     Ok(())
 }
 
+/// Lee el historial, del más reciente al más antiguo. Sin archivo, vacío.
+pub fn read_entries() -> Result<Vec<Entry>, TmenuError> {
+    let history_path = history_file_path()?;
+    let content = match fs::read_to_string(&history_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(TmenuError::HistoryError(format!(
+                "No se pudo leer historial: {}",
+                e
+            )));
+        }
+    };
+    Ok(content.lines().rev().filter_map(parse_line).collect())
+}
+
+/// Parsea una línea del historial; acepta también las del formato anterior,
+/// sin código de salida.
+fn parse_line(line: &str) -> Option<Entry> {
+    let (timestamp, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    let (code, cmd) = match rest.strip_prefix('[').and_then(|r| r.split_once("] ")) {
+        Some(("?", cmd)) => (None, cmd),
+        Some((code, cmd)) if code.parse::<i32>().is_ok() => (code.parse().ok(), cmd),
+        _ => (None, rest),
+    };
+    Some(Entry {
+        timestamp: timestamp.to_string(),
+        code,
+        cmd: cmd.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,7 +116,29 @@ mod tests {
     fn test_log_command_creates_entry() {
         // Este test requiere que exista ~/.local/share/tmenu/
         // En un proyecto real usarías tempfiles o mocks
-        let _ = log_command("echo test");
+        let _ = log_command("echo test", Some(0));
         // Verificar que no retorne error
     }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("[2026-10-14 21:05:00] [2] cargo test"),
+            Some(Entry {
+                timestamp: "2026-10-14 21:05:00".to_string(),
+                code: Some(2),
+                cmd: "cargo test".to_string(),
+            })
+        );
+        let old = parse_line("[2026-01-02 10:00:00] [ -f x ] && echo si").unwrap();
+        assert_eq!(old.code, None);
+        assert_eq!(old.cmd, "[ -f x ] && echo si");
+        assert_eq!(
+            parse_line("[2026-10-14 21:05:00] [?] sleep 99")
+                .unwrap()
+                .cmd,
+            "sleep 99"
+        );
+        assert_eq!(parse_line("basura"), None);
+    }
 }
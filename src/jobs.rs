@@ -0,0 +1,292 @@
+//! Comandos lanzados en segundo plano (`[background=true]`).
+//!
+//! El proceso se lanza sin suspender la interfaz y se revisa en cada tick
+//! con `try_wait`, así el menú sigue usable mientras corre. stdout y stderr
+//! van a un archivo de log por trabajo en la carpeta temporal, que la vista
+//! de trabajos muestra a pedido. El log se borra al descartar el trabajo o
+//! al cerrar tmenu.
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+
+use crate::model::MenuItem;
+use crate::process;
+
+/// Estado de un trabajo en segundo plano.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobStatus {
+    Running,
+    /// Terminó; `true` si el código de salida fue 0
+    Finished(bool),
+    /// Terminado desde la vista de trabajos
+    Killed,
+    /// Terminado por superar su `[timeout=...]`
+    TimedOut,
+}
+
+/// Un comando lanzado en segundo plano.
+pub struct Job {
+    /// Identifica al ítem que lo lanzó (ver `app::item_key`)
+    pub key: String,
+    /// Ítem que lo lanzó, con sus atributos (cooldown, etc.)
+    pub item: MenuItem,
+    /// Comando ya resuelto (con los parámetros del wizard sustituidos)
+    pub cmd: String,
+    pub started: Instant,
+    /// Hora de inicio, para mostrarla en la vista de trabajos
+    pub started_at: NaiveDateTime,
+    pub status: JobStatus,
+    /// Duración total, una vez terminado
+    pub duration: Option<Duration>,
+    /// Código de salida, si terminó por su cuenta
+    pub code: Option<i32>,
+    /// Archivo con la salida (stdout y stderr intercalados)
+    pub log_path: PathBuf,
+    child: Child,
+}
+
+impl Job {
+    /// Tiempo que lleva corriendo, o que tardó si ya terminó.
+    pub fn elapsed(&self) -> Duration {
+        self.duration.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    /// Contenido actual del log (puede seguir creciendo si el trabajo corre).
+    pub fn output(&self) -> String {
+        fs::read(&self.log_path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Trabajos de la sesión, en el orden en que se lanzaron.
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    /// Trabajos lanzados en la sesión, descartados incluidos: numera los logs
+    launched: usize,
+}
+
+impl Jobs {
+    /// Lanza `command` sin esperar a que termine. La salida va al log del
+    /// trabajo y la entrada estándar queda cerrada para que no compita con la interfaz.
+    pub fn spawn(
+        &mut self,
+        key: String,
+        item: &MenuItem,
+        cmd: &str,
+        mut command: Command,
+    ) -> std::io::Result<()> {
+        self.launched += 1;
+        let log_path = std::env::temp_dir().join(format!(
+            "tmenu-{}-job{}.log",
+            std::process::id(),
+            self.launched
+        ));
+        let log = File::create(&log_path)?;
+        if item.timeout.is_some() {
+            process::isolate(&mut command, false);
+        }
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .spawn()?;
+        self.jobs.push(Job {
+            key,
+            item: item.clone(),
+            cmd: cmd.to_string(),
+            started: Instant::now(),
+            started_at: chrono::Local::now().naive_local(),
+            status: JobStatus::Running,
+            duration: None,
+            code: None,
+            log_path,
+            child,
+        });
+        Ok(())
+    }
+
+    /// Revisa los trabajos en curso y retorna los índices de los que terminaron
+    /// desde la última revisión. Los que superaron su `[timeout=...]` se terminan.
+    pub fn poll(&mut self) -> Vec<usize> {
+        let mut finished = Vec::new();
+        for (i, job) in self.jobs.iter_mut().enumerate() {
+            if job.status != JobStatus::Running {
+                continue;
+            }
+            match job.child.try_wait() {
+                Ok(Some(status)) => {
+                    job.status = JobStatus::Finished(status.success());
+                    job.code = status.code();
+                    job.duration = Some(job.started.elapsed());
+                    finished.push(i);
+                }
+                Ok(None) => {
+                    if job.item.timeout.is_some_and(|t| job.started.elapsed() >= t) {
+                        process::kill_tree(&mut job.child);
+                        job.status = JobStatus::TimedOut;
+                        job.duration = Some(job.started.elapsed());
+                        finished.push(i);
+                    }
+                }
+                // Si no se puede consultar el proceso se lo da por terminado con error
+                Err(_) => {
+                    job.status = JobStatus::Finished(false);
+                    job.duration = Some(job.started.elapsed());
+                    finished.push(i);
+                }
+            }
+        }
+        finished
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Job> {
+        self.jobs.get(index)
+    }
+
+    pub fn list(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Termina el trabajo `index` si sigue en curso. Retorna `true` si se terminó.
+    pub fn kill(&mut self, index: usize) -> bool {
+        let Some(job) = self.jobs.get_mut(index) else {
+            return false;
+        };
+        if job.status != JobStatus::Running {
+            return false;
+        }
+        process::kill_tree(&mut job.child);
+        job.status = JobStatus::Killed;
+        job.duration = Some(job.started.elapsed());
+        true
+    }
+
+    /// Quita de la lista el trabajo `index` y borra su log, si ya no está en
+    /// curso. Retorna `true` si se quitó.
+    pub fn dismiss(&mut self, index: usize) -> bool {
+        if self
+            .jobs
+            .get(index)
+            .is_none_or(|job| job.status == JobStatus::Running)
+        {
+            return false;
+        }
+        let job = self.jobs.remove(index);
+        let _ = fs::remove_file(&job.log_path);
+        true
+    }
+
+    /// Si hay un trabajo en curso lanzado por el ítem `key`.
+    pub fn is_running(&self, key: &str) -> bool {
+        self.jobs
+            .iter()
+            .any(|job| job.key == key && job.status == JobStatus::Running)
+    }
+
+    pub fn any_running(&self) -> bool {
+        self.jobs.iter().any(|job| job.status == JobStatus::Running)
+    }
+}
+
+/// Los logs no sobreviven a la sesión. Un trabajo que sigue corriendo
+/// escribe en el archivo ya borrado hasta terminar.
+impl Drop for Jobs {
+    fn drop(&mut self) {
+        for job in &self.jobs {
+            let _ = fs::remove_file(&job.log_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_runs_and_finishes() {
+        let mut jobs = Jobs::default();
+        let item = MenuItem::new(
+            "Falla",
+            crate::model::MenuAction::Execute("sh -c 'exit 3'".to_string()),
+        );
+        let mut command = Command::new("sh");
+        command.args(["-c", "exit 3"]);
+        jobs.spawn("k".to_string(), &item, "sh -c 'exit 3'", command)
+            .unwrap();
+        assert!(jobs.is_running("k"));
+
+        let started = Instant::now();
+        while jobs.poll().is_empty() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!jobs.is_running("k"));
+        assert!(!jobs.any_running());
+        assert_eq!(jobs.get(0).unwrap().status, JobStatus::Finished(false));
+    }
+
+    #[test]
+    fn test_job_output_and_kill() {
+        let mut jobs = Jobs::default();
+        let item = MenuItem::new("Lento", crate::model::MenuAction::Execute("sh".to_string()));
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo salida; echo error >&2; sleep 30"]);
+        jobs.spawn("k".to_string(), &item, "sh", command).unwrap();
+
+        let started = Instant::now();
+        while !jobs.get(0).unwrap().output().contains("error") {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(jobs.get(0).unwrap().output(), "salida\nerror\n");
+
+        assert!(jobs.kill(0));
+        assert_eq!(jobs.get(0).unwrap().status, JobStatus::Killed);
+        assert!(!jobs.kill(0));
+        assert!(jobs.poll().is_empty());
+    }
+
+    #[test]
+    fn test_job_logs_are_removed() {
+        let item = MenuItem::new("Eco", crate::model::MenuAction::Execute("true".to_string()));
+        let mut jobs = Jobs::default();
+        for _ in 0..2 {
+            jobs.spawn("k".to_string(), &item, "true", Command::new("true"))
+                .unwrap();
+        }
+        let started = Instant::now();
+        while jobs.any_running() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            jobs.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let first = jobs.get(0).unwrap().log_path.clone();
+        let second = jobs.get(1).unwrap().log_path.clone();
+
+        assert!(jobs.dismiss(0));
+        assert!(!first.exists());
+        assert_eq!(jobs.len(), 1);
+        // El siguiente no reusa el nombre del que quedó
+        jobs.spawn("k".to_string(), &item, "true", Command::new("true"))
+            .unwrap();
+        let third = jobs.get(1).unwrap().log_path.clone();
+        assert_ne!(third, second);
+
+        drop(jobs);
+        assert!(!second.exists());
+        assert!(!third.exists());
+    }
+}
@@ -0,0 +1,452 @@
+//! Teclas de las acciones de la interfaz, configurables con `keys:`.
+//!
+//! ```toon
+//! keys:
+//!     quit: ctrl+x
+//!     back: esc, backspace
+//! ```
+//!
+//! Las teclas de una acción en `keys:` reemplazan a las de fábrica de esa
+//! acción; las demás acciones conservan las suyas.
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Acción de la interfaz en el modo navegación.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Down,
+    Up,
+    First,
+    Last,
+    PageDown,
+    PageUp,
+    Enter,
+    Back,
+    Home,
+    Search,
+    Filter,
+    GlobalSearch,
+    NextTab,
+    PrevTab,
+    Locate,
+    Quit,
+    Help,
+    Preview,
+    DryRun,
+    Jobs,
+    Vars,
+    ItemHelp,
+    Source,
+    Copy,
+    Rerun,
+    Favorite,
+    Refresh,
+    Tree,
+    ShowHidden,
+    Details,
+    ExpandCommand,
+}
+
+/// Cada acción con su nombre en `keys:`, sus teclas de fábrica y su
+/// descripción en la ayuda (F1), en el orden en que se muestran.
+const ACTIONS: &[(Action, &str, &str, &str)] = &[
+    (Action::Down, "down", "down, j", "Bajar"),
+    (Action::Up, "up", "up, k", "Subir"),
+    (Action::First, "first", "home", "Primer ítem"),
+    (Action::Last, "last", "G, end", "Último ítem"),
+    (
+        Action::PageDown,
+        "page_down",
+        "pagedown",
+        "Bajar una página",
+    ),
+    (Action::PageUp, "page_up", "pageup", "Subir una página"),
+    (
+        Action::Enter,
+        "enter",
+        "enter, right, l",
+        "Seleccionar / entrar al submenú",
+    ),
+    (
+        Action::Back,
+        "back",
+        "esc, left, h",
+        "Volver al menú anterior / limpiar búsqueda",
+    ),
+    (Action::Home, "home", "H, ctrl+home", "Ir al menú raíz"),
+    (Action::Search, "search", "tab", "Activar / cerrar búsqueda"),
+    (
+        Action::Filter,
+        "filter",
+        "/",
+        "Filtrar el menú actual (Enter ejecuta el primero)",
+    ),
+    (
+        Action::GlobalSearch,
+        "global_search",
+        "ctrl+f",
+        "Buscar en todos los submenús, desde la raíz",
+    ),
+    (
+        Action::NextTab,
+        "next_tab",
+        "tab",
+        "Pestaña siguiente (con `tabs:`)",
+    ),
+    (
+        Action::PrevTab,
+        "prev_tab",
+        "shift+tab",
+        "Pestaña anterior (con `tabs:`)",
+    ),
+    (
+        Action::Locate,
+        "locate",
+        "ctrl+g",
+        "Ir al lugar del resultado de la búsqueda en todo el menú",
+    ),
+    (Action::Quit, "quit", "ctrl+q", "Salir de la aplicación"),
+    (Action::Help, "help", "f1, ?", "Mostrar / cerrar esta ayuda"),
+    (
+        Action::Preview,
+        "preview",
+        "f2",
+        "Mostrar / ocultar vista previa",
+    ),
+    (
+        Action::DryRun,
+        "dry_run",
+        "f3",
+        "Simulación: mostrar comandos sin ejecutarlos",
+    ),
+    (Action::Jobs, "jobs", "J", "Trabajos en segundo plano"),
+    (Action::Vars, "vars", "v", "Variables de sesión"),
+    (
+        Action::ItemHelp,
+        "item_help",
+        "i",
+        "Ayuda del ítem seleccionado",
+    ),
+    (
+        Action::Source,
+        "source",
+        "s",
+        "Archivo y línea que definen el ítem",
+    ),
+    (
+        Action::Copy,
+        "copy",
+        "y",
+        "Copiar el comando al portapapeles",
+    ),
+    (Action::Rerun, "rerun", ".", "Repetir el último comando"),
+    (
+        Action::Refresh,
+        "refresh",
+        "ctrl+r",
+        "Volver a evaluar estados y condiciones del menú",
+    ),
+    (Action::Tree, "tree", "t", "Vista de árbol de todo el menú"),
+    (
+        Action::ShowHidden,
+        "show_hidden",
+        "o",
+        "Mostrar / esconder los ítems ocultos",
+    ),
+    (
+        Action::Details,
+        "details",
+        "p",
+        "Comando completo, directorio, entorno y atributos",
+    ),
+    (
+        Action::Favorite,
+        "favorite",
+        "f",
+        "Marcar / desmarcar como favorito",
+    ),
+    (
+        Action::ExpandCommand,
+        "expand_command",
+        "e",
+        "Comando completo en la barra inferior",
+    ),
+];
+
+/// Una tecla con sus modificadores.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parsea `ctrl+q`, `alt+enter`, `f5`, `pagedown`, `space` o un carácter
+    /// (`J`, `/`). Los nombres no distinguen mayúsculas; un carácter sí.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec.trim();
+        // `+` solo es la tecla `+`, no un separador
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+            rest = key;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                // La terminal manda Shift+Tab como una tecla propia
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => {
+                    modifiers |= KeyModifiers::SHIFT;
+                    KeyCode::BackTab
+                }
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(KeyBinding { code, modifiers })
+    }
+
+    /// Si `key` es esta tecla. En los caracteres se ignora Shift: la
+    /// terminal ya lo refleja en el carácter (`J`, `?`), como en Shift+Tab.
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let ignored = match key.code {
+            KeyCode::Char(_) | KeyCode::BackTab => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        self.code == key.code && self.modifiers - ignored == key.modifiers - ignored
+    }
+
+    /// Un carácter sin Ctrl ni Alt: en los campos de texto se escribe.
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    /// Nombre para la ayuda: `Ctrl+Q`, `Enter`, `→`, `J`.
+    fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Espacio".to_string(),
+            KeyCode::Char(c) if self.modifiers.is_empty() => c.to_string(),
+            KeyCode::Char(c) => c.to_uppercase().to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Home => "Inicio".to_string(),
+            KeyCode::PageUp => "RePág".to_string(),
+            KeyCode::PageDown => "AvPág".to_string(),
+            KeyCode::BackTab => "Tab".to_string(),
+            code => code.to_string(),
+        };
+        let mut label = String::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+        label + key.as_str()
+    }
+}
+
+/// Teclas de cada acción.
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|(action, _, keys, _)| {
+                parse_keys(keys)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|binding| (binding, *action))
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// Keymap de fábrica con las teclas de `keys:` aplicadas encima. Las
+    /// acciones o teclas desconocidas se ignoran y se describen en el segundo
+    /// valor, para avisar al usuario.
+    pub fn from_config(keys: &[(String, String)]) -> (Self, Vec<String>) {
+        let mut keymap = Keymap::default();
+        let mut errors = Vec::new();
+        for (name, spec) in keys {
+            let Some((action, ..)) = ACTIONS.iter().find(|(_, n, ..)| n == name) else {
+                errors.push(format!("acción desconocida '{}'", name));
+                continue;
+            };
+            let Some(bindings) = parse_keys(spec) else {
+                errors.push(format!("tecla inválida para '{}': {}", name, spec));
+                continue;
+            };
+            keymap.bindings.retain(|(_, a)| a != action);
+            keymap
+                .bindings
+                .extend(bindings.into_iter().map(|binding| (binding, *action)));
+        }
+        (keymap, errors)
+    }
+
+    /// Si `key` es una de las teclas de `action`, aunque otra acción anterior
+    /// la comparta (Tab busca y, con pestañas, cambia de pestaña).
+    pub fn is_bound(&self, action: Action, key: &KeyEvent) -> bool {
+        self.bindings
+            .iter()
+            .any(|(binding, a)| *a == action && binding.matches(key))
+    }
+
+    /// Acción de la tecla `key`, si tiene una.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.binding(key).map(|(_, action)| action)
+    }
+
+    /// Acción de `key` solo si no es un carácter que se escribe: para
+    /// aplicarla también mientras hay un campo de texto activo (salir con
+    /// `ctrl+q` sí, con `q` no).
+    pub fn command(&self, key: &KeyEvent) -> Option<Action> {
+        self.binding(key)
+            .filter(|(binding, _)| !binding.is_text())
+            .map(|(_, action)| action)
+    }
+
+    fn binding(&self, key: &KeyEvent) -> Option<(KeyBinding, Action)> {
+        self.bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(key))
+            .copied()
+    }
+
+    /// Primera tecla de `action` (`e`), para las indicaciones en pantalla.
+    pub fn key_label(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(binding, _)| binding.label())
+    }
+
+    /// Filas de la ayuda: teclas de cada acción (`Enter / → / l`) y descripción.
+    pub fn help_rows(&self) -> Vec<(String, &'static str)> {
+        ACTIONS
+            .iter()
+            .map(|(action, _, _, description)| {
+                let keys: Vec<String> = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, a)| a == action)
+                    .map(|(binding, _)| binding.label())
+                    .collect();
+                (keys.join(" / "), *description)
+            })
+            .filter(|(keys, _)| !keys.is_empty())
+            .collect()
+    }
+}
+
+/// Lista de teclas separadas por coma (`esc, left, h`). `None` si alguna
+/// no se reconoce.
+fn parse_keys(spec: &str) -> Option<Vec<KeyBinding>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(KeyBinding::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_key_binding_parse() {
+        let ctrl_x = KeyBinding::parse("Ctrl+x").unwrap();
+        assert!(ctrl_x.matches(&key(KeyCode::Char('x'), KeyModifiers::CONTROL)));
+        assert!(!ctrl_x.matches(&key(KeyCode::Char('x'), KeyModifiers::NONE)));
+        assert_eq!(KeyBinding::parse("F5").unwrap().code, KeyCode::F(5));
+        assert_eq!(KeyBinding::parse("+").unwrap().code, KeyCode::Char('+'));
+        // Shift ya viene en el carácter
+        assert!(
+            KeyBinding::parse("J")
+                .unwrap()
+                .matches(&key(KeyCode::Char('J'), KeyModifiers::SHIFT))
+        );
+        let back_tab = key(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert!(KeyBinding::parse("shift+tab").unwrap().matches(&back_tab));
+        assert_eq!(KeyBinding::parse("backtab").unwrap().label(), "Shift+Tab");
+        assert_eq!(KeyBinding::parse("hyper+x"), None);
+        assert_eq!(KeyBinding::parse("f99x"), None);
+    }
+
+    #[test]
+    fn test_keymap_overrides_one_action() {
+        let (keymap, errors) = Keymap::from_config(&[
+            ("quit".to_string(), "q, ctrl+x".to_string()),
+            ("jump".to_string(), "x".to_string()),
+        ]);
+        assert_eq!(errors, vec!["acción desconocida 'jump'"]);
+        let q = key(KeyCode::Char('q'), KeyModifiers::NONE);
+        let ctrl_q = key(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        let ctrl_x = key(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&q), Some(Action::Quit));
+        assert_eq!(keymap.action(&ctrl_q), None);
+        // `q` se escribe en los campos de texto; `ctrl+x` sale desde cualquier lado
+        assert_eq!(keymap.command(&q), None);
+        assert_eq!(keymap.command(&ctrl_x), Some(Action::Quit));
+        // Las demás acciones conservan sus teclas
+        assert_eq!(
+            keymap.action(&key(KeyCode::Tab, KeyModifiers::NONE)),
+            Some(Action::Search)
+        );
+        let rows = keymap.help_rows();
+        assert!(rows.contains(&("q / Ctrl+X".to_string(), "Salir de la aplicación")));
+        assert_eq!(keymap.key_label(Action::Quit), Some("q".to_string()));
+        // `?` abre la ayuda general; la del ítem va en `i`
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('?'), KeyModifiers::SHIFT)),
+            Some(Action::Help)
+        );
+        assert_eq!(keymap.key_label(Action::ItemHelp), Some("i".to_string()));
+        // Inicio va al primer ítem; el menú raíz, con Ctrl+Inicio o `H`
+        assert_eq!(
+            keymap.action(&key(KeyCode::Home, KeyModifiers::NONE)),
+            Some(Action::First)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Home, KeyModifiers::CONTROL)),
+            Some(Action::Home)
+        );
+    }
+}
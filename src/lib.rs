@@ -0,0 +1,91 @@
+//! Motor de menús de tmenu, con el binario `tmenu` como una interfaz delgada
+//! encima.
+//!
+//! El núcleo (modelo, parser, búsqueda y escritura de menús `.toon`) no
+//! depende de la terminal, así que compila también para
+//! `wasm32-unknown-unknown` (`cargo build --lib --target wasm32-unknown-unknown`),
+//! por ejemplo para un playground web que valide y previsualice menús con
+//! [`parser::parse_toon_str`] y [`writer::write_toon`]. Los menús también se
+//! pueden armar desde código con [`builder::Menu::builder`].
+//!
+//! Fuera de wasm están además la app y la interfaz, para embeber el menú en
+//! otra aplicación ratatui: [`app::App`] carga un menú (`App::from_toon` o
+//! `App::from_menu`), navega (`enter_selected`, `back`, `jump_to`…) y ejecuta
+//! ítems; [`widget::TmenuWidget`] dibuja un nivel en cualquier área,
+//! [`ui::ui`] la pantalla completa de tmenu y [`tui::run_app`] su bucle de
+//! eventos.
+pub mod builder;
+pub mod error;
+pub mod model;
+pub mod parser;
+pub mod search;
+pub mod writer;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod clipboard;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod commands;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod completions;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod control;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod debuglog;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod defaults;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod detach;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod editor;
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hints;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod history;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod jobs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keymap;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod notify;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pager;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod paths;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod plugin;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod position;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod process;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod provider;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod saved;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schedule;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod script;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod settings;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stats;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod theme;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod timefmt;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tmux;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ui;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod vars;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod widget;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod window;
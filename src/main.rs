@@ -1,40 +1,215 @@
-/// Lector de menus interactivos TUI en Rust utilizando Ratatui y Clap.
-mod app;
-mod error;
-mod history;
-mod model;
-mod parser;
-mod search;
-mod ui;
+//! Lector de menus interactivos TUI en Rust utilizando Ratatui y Clap: la
+//! línea de comandos y la terminal; el motor está en la biblioteca `tmenu`.
+use tmenu::{
+    app, builder, commands, completions, control, debuglog, defaults, error, hints, model, parser,
+    paths, saved, schedule, settings, stats, theme, tui, window,
+};
 
 use app::App;
-use error::AppError;
+use error::TmenuError;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
+use log::{debug, warn};
 use ratatui::{Terminal, backend::CrosstermBackend};
-use std::io;
-use std::path::PathBuf;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(
     author,
     version,
     about,
-    long_about = None
+    long_about = None,
+    args_conflicts_with_subcommands = true
 )]
 struct Args {
-    /// Ruta al archivo de menu (.toon)
-    #[arg(value_name = "ARCHIVO", default_value = "tmenu.toon")]
-    menu_file: PathBuf,
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// Ruta al archivo de menu (.toon); `-` lee el menu de la entrada estandar.
+    /// Por defecto se usa `menu` de config.toml o se busca tmenu.toon en la carpeta
+    /// de configuracion ($XDG_CONFIG_HOME/tmenu o equivalente) y luego en el
+    /// directorio actual
+    #[arg(value_name = "ARCHIVO")]
+    menu_file: Option<PathBuf>,
+
+    /// Archivo de configuracion a usar en lugar de config.toml de la carpeta de
+    /// configuracion del usuario, que entonces no se lee
+    #[arg(long, value_name = "ARCHIVO")]
+    config: Option<PathBuf>,
+
+    /// Lee el menu de la entrada estandar (equivale a usar `-` como archivo)
+    #[arg(long)]
+    stdin: bool,
 
-    /// Activa el modo depuracion
+    /// Anota el menu leido, las teclas, los comandos ejecutados y sus codigos de
+    /// salida en ~/.local/share/tmenu/debug.log
     #[arg(short, long)]
     debug: bool,
+
+    /// Intervalo en milisegundos de las tareas periodicas (recarga del menu, animaciones)
+    #[arg(long, value_name = "MS")]
+    tick_rate: Option<u64>,
+
+    /// Shell para ejecutar los comandos (`bash`, `fish`, `pwsh`, `"bash -lc"`, `none` para
+    /// ejecucion directa); reemplaza a `shell:` del archivo de menu
+    #[arg(long, value_name = "SHELL")]
+    shell: Option<String>,
+
+    /// Tema predefinido (`dark`, `light`, `solarized`, `monochrome`); reemplaza a
+    /// `preset` de `[theme]` en config.toml, cuyas demás claves se siguen aplicando
+    #[arg(long, value_name = "NOMBRE")]
+    theme: Option<String>,
+
+    /// Vuelve al menu apenas termina cada comando, sin la pausa "Presiona Enter"
+    /// (los items con `[wait=true]` la conservan)
+    #[arg(long)]
+    no_wait: bool,
+
+    /// Muestra cada comando, ya resuelto, en lugar de ejecutarlo (F3 lo alterna)
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Modo seleccion: no ejecuta nada, imprime el comando elegido (o su etiqueta,
+    /// con `--print=label`) en la salida estandar y sale; sale con codigo 1 si no
+    /// se eligio nada. Para usar en scripts: `eval "$(tmenu --print ops.toon)"`
+    #[arg(
+        long,
+        visible_alias = "select-only",
+        value_enum,
+        value_name = "QUE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "command"
+    )]
+    print: Option<app::PrintTarget>,
+
+    /// Elige una linea: lee lineas de la entrada estandar, las muestra como menu e
+    /// imprime la elegida en la salida estandar (`ls | tmenu --pick`); sale con
+    /// codigo 1 si no se eligio nada
+    #[arg(long, conflicts_with_all = ["menu_file", "stdin", "print"])]
+    pick: bool,
+
+    /// Escucha pedidos (`select Camino/Al/Item`, `run ...`, `reload`, `quit`) en un
+    /// socket Unix en esta ruta, para manejar tmenu desde otros programas
+    #[arg(long, value_name = "SOCKET")]
+    listen: Option<PathBuf>,
+}
+
+/// Subcomandos que trabajan sobre el menú sin abrir la interfaz.
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Convierte un archivo de menu a otro formato
+    Convert {
+        /// Archivo de menu de entrada (`-` para la entrada estandar)
+        #[arg(value_name = "ARCHIVO")]
+        input: PathBuf,
+
+        /// Formato de salida
+        #[arg(long, value_enum, default_value = "toon")]
+        to: commands::MenuFormat,
+
+        /// Archivo de salida (por defecto la salida estandar)
+        #[arg(short, long, value_name = "SALIDA")]
+        output: Option<PathBuf>,
+    },
+    /// Imprime el menu como arbol (etiquetas y comandos) sin abrir la interfaz
+    List {
+        /// Archivo de menu (por defecto el mismo que abre `tmenu`; `-` para la
+        /// entrada estandar)
+        #[arg(value_name = "ARCHIVO")]
+        menu_file: Option<PathBuf>,
+
+        /// Imprime el menu como JSON, para procesarlo con otras herramientas
+        #[arg(long)]
+        json: bool,
+
+        /// Imprime un camino de `tmenu run` por linea (`Git/Estado`), como los
+        /// completa el autocompletado
+        #[arg(long, conflicts_with = "json")]
+        paths: bool,
+    },
+    /// Ejecuta un item por su camino de etiquetas sin abrir la interfaz y sale con
+    /// su codigo (`tmenu run "Deploy/Staging/Restart api"`)
+    Run {
+        /// Camino del item: las etiquetas de los submenus y la del item, separadas por `/`
+        /// (una `/` dentro de una etiqueta se escribe `\/`)
+        #[arg(value_name = "CAMINO")]
+        path: String,
+
+        /// Archivo de menu (por defecto el mismo que abre `tmenu`)
+        #[arg(value_name = "ARCHIVO")]
+        menu_file: Option<PathBuf>,
+
+        /// Da por aceptada la confirmacion de los items con `[confirm=true]` o
+        /// `[confirm_text=...]`
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Resume las estadisticas de uso locales (requiere `stats: true` en config)
+    Stats {
+        /// Cantidad de items por seccion
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Manda un pedido a un tmenu abierto con `--listen` e imprime la respuesta
+    Send {
+        /// Socket donde escucha tmenu
+        #[arg(value_name = "SOCKET")]
+        socket: PathBuf,
+
+        /// Pedido: `select Camino/Al/Item`, `run Camino/Al/Item`, `reload` o `quit`
+        #[arg(value_name = "PEDIDO", required = true, num_args = 1..)]
+        request: Vec<String>,
+    },
+    /// Tareas programadas declaradas con `[schedule=...]` en el menu
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleCommand,
+    },
+    /// Crea un menu de ejemplo comentado (tmenu.toon) para empezar
+    Init {
+        /// Lo crea en la carpeta de configuracion ($XDG_CONFIG_HOME/tmenu o
+        /// equivalente), donde `tmenu` lo abre sin indicar archivo, en lugar del
+        /// directorio actual
+        #[arg(long)]
+        global: bool,
+
+        /// Crea tambien un config.toml de ejemplo en la carpeta de configuracion
+        #[arg(long)]
+        with_config: bool,
+
+        /// Reemplaza los archivos que ya existen
+        #[arg(long)]
+        force: bool,
+    },
+    /// Emite el script de autocompletado para la shell (`source <(tmenu completions bash)`)
+    Completions {
+        /// Shell del script
+        #[arg(value_enum)]
+        shell: completions::CompletionShell,
+    },
+}
+
+/// Acciones de `tmenu schedule`.
+#[derive(Subcommand, Debug)]
+enum ScheduleCommand {
+    /// Emite las entradas de crontab o de timers de systemd de los items programados
+    Export {
+        /// Archivo de menu (por defecto el mismo que abre `tmenu`)
+        #[arg(value_name = "ARCHIVO")]
+        menu_file: Option<PathBuf>,
+
+        /// Formato de salida
+        #[arg(long, value_enum, default_value = "crontab")]
+        format: schedule::ExportFormat,
+    },
 }
 
 fn main() {
@@ -53,28 +228,197 @@ fn main() {
     }
 }
 
-fn run() -> Result<(), AppError> {
+fn run() -> Result<(), TmenuError> {
     let args = Args::parse();
+    let (settings, mut settings_errors) = match &args.config {
+        Some(path) if !path.is_file() => return Err(TmenuError::ConfigFileNotFound(path.clone())),
+        Some(path) => settings::Settings::load_from(path),
+        None => settings::Settings::load(),
+    };
+    let (defaults, default_errors) = defaults::Defaults::from_settings(&settings);
+    let default_menu_file = || {
+        defaults
+            .menu_file
+            .clone()
+            .unwrap_or_else(paths::default_menu_file)
+    };
+
+    if let Some(command) = &args.command {
+        return match command {
+            Commands::Convert { input, to, output } => {
+                commands::convert(input, *to, output.as_ref())
+            }
+            Commands::List {
+                menu_file,
+                json,
+                paths,
+            } => {
+                let menu_file = menu_file.clone().unwrap_or_else(default_menu_file);
+                let format = if *json {
+                    commands::ListFormat::Json
+                } else if *paths {
+                    commands::ListFormat::Paths
+                } else {
+                    commands::ListFormat::Tree
+                };
+                commands::list(&menu_file, format)
+            }
+            Commands::Run {
+                path,
+                menu_file,
+                yes,
+            } => {
+                let menu_file = menu_file.clone().unwrap_or_else(default_menu_file);
+                let mut app = open_menu(&menu_file, false, defaults.config.clone())?;
+                let path = model::parse_item_path(path);
+                let code = app.run_path(&path, *yes, &mut io::stdin().lock(), &mut io::stderr())?;
+                std::process::exit(code);
+            }
+            Commands::Stats { limit } => stats::print_report(*limit),
+            Commands::Send { socket, request } => {
+                let reply = control::send(socket, &request.join(" "))?;
+                println!("{}", reply);
+                if !reply.starts_with("ok") {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            Commands::Schedule {
+                action: ScheduleCommand::Export { menu_file, format },
+            } => {
+                let menu_file = menu_file.clone().unwrap_or_else(default_menu_file);
+                schedule::print_export(&menu_file, *format)
+            }
+            Commands::Init {
+                global,
+                with_config,
+                force,
+            } => {
+                let config_dir = || {
+                    paths::config_dirs().into_iter().next().ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            "no se pudo determinar la carpeta de configuración",
+                        )
+                    })
+                };
+                let dir = if *global {
+                    config_dir()?
+                } else {
+                    PathBuf::from(".")
+                };
+                let settings_dir = if *with_config {
+                    Some(config_dir()?)
+                } else {
+                    None
+                };
+                for path in commands::init(&dir, settings_dir.as_deref(), *force)? {
+                    println!("Creado: {}", path.display());
+                }
+                Ok(())
+            }
+            Commands::Completions { shell } => {
+                print!("{}", completions::generate(&Args::command(), *shell));
+                Ok(())
+            }
+        };
+    }
 
-    let mut app = App::from_toon(&args.menu_file, args.debug).map_err(|e| match e {
-        AppError::IoError(ref io) if io.kind() == io::ErrorKind::NotFound => {
-            AppError::MenuFileNotFound(args.menu_file.clone())
+    if args.debug {
+        debuglog::init(&debuglog::log_file_path()?)?;
+    }
+    let menu_file = match &args.menu_file {
+        Some(path) => path.clone(),
+        None => {
+            let path = default_menu_file();
+            debug!(
+                "sin archivo de menú en la línea de comandos: se usa {}",
+                path.display()
+            );
+            path
         }
-        other => other,
-    })?;
+    };
+
+    let mut app = if args.pick {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        reattach_stdin_to_tty()?;
+        App::from_menu(pick_menu(&content, defaults.config), args.debug)
+    } else if args.stdin || menu_file.as_os_str() == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        reattach_stdin_to_tty()?;
+        App::from_toon_str_with(&content, args.debug, defaults.config)
+    } else {
+        open_menu(&menu_file, args.debug, defaults.config)?
+    };
+
+    if let Some(ms) = args.tick_rate {
+        app.tick_rate = Duration::from_millis(ms.max(1));
+    }
+    if args.no_wait {
+        app.wait = false;
+    }
+    app.dry_run = args.dry_run;
+    app.print = if args.pick {
+        Some(app::PrintTarget::Label)
+    } else {
+        args.print
+    };
+    // En modo selección stdout es para la elección: la interfaz va a la terminal
+    let mut selection_output = match app.print {
+        Some(_) => Some(redirect_stdout_to_tty()?),
+        None => None,
+    };
+    if let Some(shell) = &args.shell {
+        app.shell = model::Shell::parse(shell);
+    }
+    settings_errors.extend(default_errors);
+    let (theme, theme_errors) = theme::Theme::load(&settings, args.theme.as_deref());
+    let (hints, hint_errors) = hints::Hints::from_settings(&settings);
+    let (window, window_errors) = window::Window::from_settings(&settings);
+    app.theme = theme;
+    app.hints = hints;
+    app.window = window;
+    for (list, keys) in [
+        (saved::List::Favorites, &mut app.favorites),
+        (saved::List::Recent, &mut app.recent),
+    ] {
+        match saved::load(list) {
+            Ok(loaded) => *keys = loaded,
+            Err(e) => settings_errors.push(e.to_string().trim().to_string()),
+        }
+    }
+    app.pin_submenus();
+    app.open_first_tab();
+    if let Err(e) = app.restore_position() {
+        settings_errors.push(e.to_string().trim().to_string());
+    }
+    if let Some(path) = &args.listen {
+        app.control = Some(control::ControlSocket::bind(path)?);
+    }
+    settings_errors.extend(theme_errors);
+    settings_errors.extend(hint_errors);
+    settings_errors.extend(window_errors);
+    for error in &settings_errors {
+        warn!("config.toml: {}", error);
+    }
+    if !settings_errors.is_empty() {
+        app.status_message = Some(settings_errors.join("; "));
+    }
 
-    enable_raw_mode().map_err(|e| AppError::TerminalError(e.to_string()))?;
+    enable_raw_mode().map_err(|e| TmenuError::TerminalError(e.to_string()))?;
     let mut stdout = io::stdout();
     execute!(stdout, crossterm::cursor::SetCursorStyle::SteadyUnderScore)
-        .map_err(|e| AppError::TerminalError(e.to_string()))?;
+        .map_err(|e| TmenuError::TerminalError(e.to_string()))?;
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
-        .map_err(|e| AppError::TerminalError(e.to_string()))?;
+        .map_err(|e| TmenuError::TerminalError(e.to_string()))?;
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal =
-        Terminal::new(backend).map_err(|e| AppError::TerminalError(e.to_string()))?;
+        Terminal::new(backend).map_err(|e| TmenuError::TerminalError(e.to_string()))?;
 
-    let result = run_app(&mut terminal, &mut app);
+    let result = tui::run_app(&mut terminal, &mut app);
 
     let _ = disable_raw_mode();
     let _ = execute!(
@@ -84,300 +428,92 @@ fn run() -> Result<(), AppError> {
     );
     let _ = terminal.show_cursor();
 
-    result
-}
-
-/// Ciclo principal de eventos: dibuja la UI y procesa teclado.
-fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-) -> Result<(), AppError> {
-    loop {
-        terminal
-            .draw(|f| ui::ui(f, app))
-            .map_err(|e| AppError::TerminalError(e.to_string()))?;
-
-        // Un solo event::read() por iteracion — el KeyCode se pasa a los handlers
-        let event = event::read().map_err(|e| AppError::EventError(e.to_string()))?;
-
-        if let Event::Key(key) = event {
-            if key.kind != event::KeyEventKind::Press {
-                continue;
-            }
-            if key.code == KeyCode::F(1) {
-                app.show_help = true;
-                let quit = run_help_modal(terminal, app)?; // ← ahora retorna bool
-                if quit {
-                    return Ok(());
-                }
-                continue;
-            }
-            // Ctrl+q sale desde cualquier modo
-            if key.modifiers.contains(event::KeyModifiers::CONTROL)
-                && key.code == KeyCode::Char('q')
-            {
-                return Ok(());
-            }
-
-            let should_quit = if app.search_mode {
-                handle_search_mode(terminal, app, key.code)?
-            } else {
-                handle_navigation_mode(terminal, app, key.code)?
-            };
-            if app.wizard.is_some() {
-                let quit = run_wizard(terminal, app)?;
-                if quit {
-                    return Ok(());
-                }
-            }
-
-            if should_quit {
-                return Ok(());
-            }
-        }
+    result?;
+    if let Err(e) = app.save_position() {
+        eprintln!("[warn] no se pudo guardar la última posición: {}", e);
     }
-}
-
-/// Loop bloqueante del modal de ayuda.
-/// Retorna Ok(true) si el usuario eligió salir de la app, Ok(false) si cerró la ayuda para volver al menú.
-fn run_help_modal(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-) -> Result<bool, AppError> {
-    loop {
-        terminal
-            .draw(|f| ui::ui(f, app))
-            .map_err(|e| AppError::TerminalError(e.to_string()))?;
-
-        if let Event::Key(key) = event::read().map_err(|e| AppError::EventError(e.to_string()))? {
-            if key.kind != event::KeyEventKind::Press {
-                continue;
-            }
-            match key.code {
-                KeyCode::Esc | KeyCode::F(1) | KeyCode::F(2) => {
-                    app.show_help = false;
-                    return Ok(false); // cerrar ayuda, continuar app
-                }
-                KeyCode::Char('q') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                    return Ok(true); // salir de la app
-                }
-                _ => {}
-            }
+    if let Some(output) = selection_output.as_mut() {
+        match &app.selection {
+            Some(selection) => writeln!(output, "{}", selection)?,
+            None => std::process::exit(1),
         }
     }
+    Ok(())
 }
 
-/// Loop bloqueante del wizard de interpolación.
-/// Retorna Ok(true) si el usuario canceló, Ok(false) si completó.
-fn run_wizard(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-) -> Result<bool, AppError> {
-    loop {
-        terminal
-            .draw(|f| ui::ui(f, app))
-            .map_err(|e| AppError::TerminalError(e.to_string()))?;
-
-        if let Event::Key(key) = event::read().map_err(|e| AppError::EventError(e.to_string()))? {
-            if key.kind != event::KeyEventKind::Press {
-                continue;
-            }
-
-            // Ctrl+Q cancela y sale de la app
-            if key.modifiers.contains(event::KeyModifiers::CONTROL)
-                && key.code == KeyCode::Char('q')
-            {
-                app.wizard = None;
-                return Ok(true); // señal de quit
-            }
+/// Menú de `--pick`: un ítem por línea no vacía de `text`, en el orden en que
+/// llegan.
+fn pick_menu(text: &str, defaults: parser::GlobalConfig) -> builder::Menu {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .fold(builder::Menu::builder(), |menu, line| menu.choice(line))
+        .title("Elegí una opción")
+        .config(parser::GlobalConfig {
+            sort: model::SortOrder::File,
+            ..defaults
+        })
+        .build()
+}
 
-            match key.code {
-                KeyCode::Esc => {
-                    // Cancelar: descartar wizard, volver al menú
-                    app.wizard = None;
-                    return Ok(false);
-                }
-                KeyCode::Backspace => {
-                    if let Some(ref mut w) = app.wizard {
-                        w.input.pop();
-                    }
-                }
-                KeyCode::Char(c) => {
-                    if let Some(ref mut w) = app.wizard {
-                        w.input.push(c);
-                    }
-                }
-                KeyCode::Enter => {
-                    let done = app
-                        .wizard
-                        .as_mut()
-                        .map(|w| w.confirm_current())
-                        .unwrap_or(true);
-
-                    if done {
-                        // Último campo confirmado: ejecutar
-                        app.finish_wizard(terminal)?;
-                        return Ok(false);
-                    }
-                    // Si no es el último, el loop redibuja con el siguiente campo
-                }
-                _ => {}
-            }
+/// Lee el menú de `path`; si no existe, el error lo nombra.
+fn open_menu(path: &Path, debug: bool, defaults: parser::GlobalConfig) -> Result<App, TmenuError> {
+    App::from_toon_with(path, debug, defaults).map_err(|e| match e {
+        TmenuError::IoError(ref io) if io.kind() == io::ErrorKind::NotFound => {
+            TmenuError::MenuFileNotFound(path.to_path_buf())
         }
-    }
+        other => other,
+    })
 }
 
-/// Loop bloqueante del modal de confirmación.
-/// Retorna true si el usuario confirmó (Sí), false si canceló (No).
-pub fn run_confirmation_modal(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-) -> Result<bool, AppError> {
-    loop {
-        terminal
-            .draw(|f| ui::ui(f, app))
-            .map_err(|e| AppError::TerminalError(e.to_string()))?;
-
-        if let Event::Key(key) = event::read().map_err(|e| AppError::EventError(e.to_string()))? {
-            if key.kind != event::KeyEventKind::Press {
-                continue;
-            }
+/// Conecta la salida estándar a la terminal y retorna la original, donde
+/// el modo selección escribe la elección (normalmente un pipe: `$(tmenu --print)`).
+#[cfg(unix)]
+fn redirect_stdout_to_tty() -> Result<Box<dyn Write>, TmenuError> {
+    use std::os::fd::{AsRawFd, FromRawFd};
 
-            match key.code {
-                // Up/Down navega entre "Sí" y "No"
-                KeyCode::Up | KeyCode::Left => {
-                    if let Some(ref mut conf) = app.confirmation {
-                        conf.toggle();
-                    }
-                }
-                KeyCode::Down | KeyCode::Right => {
-                    if let Some(ref mut conf) = app.confirmation {
-                        conf.toggle();
-                    }
-                }
-                // Enter confirma la selección actual
-                KeyCode::Enter => {
-                    let confirmed = app
-                        .confirmation
-                        .as_ref()
-                        .map(|c| c.is_confirmed())
-                        .unwrap_or(false);
-                    app.confirmation = None;
-                    return Ok(confirmed);
-                }
-                // Esc o N = cancelar (ir a "No" implícitamente)
-                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
-                    app.confirmation = None;
-                    return Ok(false);
-                }
-                // Y = confirmar (ir a "Sí" implícitamente)
-                KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    app.confirmation = None;
-                    return Ok(true);
-                }
-                // Ctrl+Q cancela y sale de la app
-                _ if key.modifiers.contains(event::KeyModifiers::CONTROL)
-                    && key.code == KeyCode::Char('q') =>
-                {
-                    app.confirmation = None;
-                    return Err(AppError::EventError("Cancelado por Ctrl+Q".to_string()));
-                }
-                _ => {}
-            }
+    let tty = std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|e| TmenuError::TerminalError(format!("no se pudo abrir /dev/tty: {}", e)))?;
+    // SAFETY: dup y dup2 sobre descriptores válidos; el duplicado pasa a ser
+    // del File retornado, que lo cierra al soltarse.
+    unsafe {
+        let original = libc::dup(libc::STDOUT_FILENO);
+        if original < 0 || libc::dup2(tty.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+            return Err(TmenuError::TerminalError(
+                io::Error::last_os_error().to_string(),
+            ));
         }
+        Ok(Box::new(std::fs::File::from_raw_fd(original)))
     }
 }
 
-/// Maneja teclas en modo búsqueda.
-/// Ahora las teclas de navegación (↑↓) funcionan sobre el menú filtrado en vivo.
-/// Recibe el KeyCode ya leido por el loop — sin segundo event::read().
-fn handle_search_mode(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-    key: KeyCode,
-) -> Result<bool, AppError> {
-    match key {
-        // Tab cierra la búsqueda y mantiene el menú actual
-        KeyCode::Tab => {
-            app.search_mode = false;
-        }
-        // Esc cancela la búsqueda: limpia el texto y vuelve al menú completo
-        KeyCode::Esc => {
-            app.search_mode = false;
-            app.search_text.clear();
-            app.state.select(Some(0));
-        }
-        // Backspace: elimina carácter del filtro y reselecciona primer item
-        KeyCode::Backspace => {
-            app.search_text.pop();
-            app.state.select(Some(0));
-        }
-        // ↑ ↓: navegación en el menú filtrado
-        KeyCode::Up => {
-            let filtered = app.filtered_items();
-            if !filtered.is_empty() {
-                let current = app.state.selected().unwrap_or(0);
-                let next = if current == 0 {
-                    filtered.len() - 1
-                } else {
-                    current - 1
-                };
-                app.state.select(Some(next));
-            }
-        }
-        KeyCode::Down => {
-            let filtered = app.filtered_items();
-            if !filtered.is_empty() {
-                let current = app.state.selected().unwrap_or(0);
-                let next = (current + 1) % filtered.len();
-                app.state.select(Some(next));
-            }
-        }
-        // F2: toggle preview (funciona durante búsqueda)
-        KeyCode::F(2) => app.show_preview = !app.show_preview,
-        // Enter: ejecuta el item filtrado seleccionado
-        KeyCode::Enter => {
-            let filtered = app.filtered_items();
-            if !filtered.is_empty() {
-                if app.activate_item(terminal, &filtered)? {
-                    return Ok(true);
-                }
-            }
-        }
-        // Cualquier otro carácter: agregar al filtro y resetear a primer item
-        KeyCode::Char(c) => {
-            app.search_text.push(c);
-            app.state.select(Some(0));
-        }
-        _ => {}
-    }
-    Ok(false)
+/// En Windows no hay `/dev/tty`: se usa la salida estándar tal cual.
+#[cfg(not(unix))]
+fn redirect_stdout_to_tty() -> Result<Box<dyn Write>, TmenuError> {
+    Ok(Box::new(io::stdout()))
 }
 
-/// Maneja teclas en modo navegacion normal.
-fn handle_navigation_mode(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut App,
-    key: KeyCode,
-) -> Result<bool, AppError> {
-    match key {
-        KeyCode::Tab => app.search_mode = true,
-        KeyCode::Down => app.next(),
-        KeyCode::Up => app.previous(),
-        KeyCode::Home => app.go_home(),
-        KeyCode::F(2) => app.show_preview = !app.show_preview,
-        KeyCode::Enter | KeyCode::Right => {
-            let items = app.filtered_items();
-            if app.activate_item(terminal, &items)? {
-                return Ok(true);
-            }
-        }
-        KeyCode::Left | KeyCode::Esc => {
-            if !app.back() {
-                return Ok(true); // estamos en root, salir
-            }
-        }
-        _ => {}
+/// Después de consumir el menú desde un pipe, vuelve a conectar la entrada
+/// estándar a la terminal para que los comandos ejecutados (y la pausa
+/// "Presioná Enter") puedan leer del teclado.
+#[cfg(unix)]
+fn reattach_stdin_to_tty() -> Result<(), TmenuError> {
+    use std::os::fd::AsRawFd;
+
+    let tty = std::fs::File::open("/dev/tty")
+        .map_err(|e| TmenuError::TerminalError(format!("no se pudo abrir /dev/tty: {}", e)))?;
+    // SAFETY: ambos descriptores son válidos; dup2 reemplaza el fd 0 atómicamente.
+    if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO) } < 0 {
+        return Err(TmenuError::TerminalError(
+            io::Error::last_os_error().to_string(),
+        ));
     }
-    Ok(false)
+    Ok(())
+}
+
+/// En Windows la consola se lee con la API propia, independiente de stdin.
+#[cfg(not(unix))]
+fn reattach_stdin_to_tty() -> Result<(), TmenuError> {
+    Ok(())
 }
@@ -1,4 +1,9 @@
-use ratatui::widgets::ListState;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Lista de ítems compartida: navegar entre niveles clona el `Rc`, no los ítems.
+pub type MenuItems = Rc<Vec<MenuItem>>;
 
 /// Modo de ejecución de comandos.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -7,51 +12,551 @@ pub enum ExecutionMode {
     Inherit,
     /// Limpiar pantalla antes de ejecutar (mejor para visualizar salida)
     Clean,
+    /// Capturar la salida y mostrarla en un panel sin salir de la interfaz
+    Capture,
 }
 
 impl ExecutionMode {
     /// Parsea un string al modo correspondiente
+    /// (nunca falla: un valor desconocido equivale a `Inherit`, por eso no es `FromStr`)
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().trim() {
             "clean" => ExecutionMode::Clean,
+            "capture" => ExecutionMode::Capture,
             _ => ExecutionMode::Inherit, // default
         }
     }
+
+    /// Nombre del modo tal como se escribe en el archivo `.toon`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionMode::Inherit => "inherit",
+            ExecutionMode::Clean => "clean",
+            ExecutionMode::Capture => "capture",
+        }
+    }
+}
+
+/// Cómo se lanza un comando separado de tmenu (`[detach=...]`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Detach {
+    /// En una sesión propia, sin terminal (`[detach=true]`): aplicaciones gráficas.
+    Session,
+    /// En un emulador de terminal nuevo (`[detach=terminal]`): TUIs largas.
+    Terminal,
+}
+
+impl Detach {
+    /// Parsea el valor del flag: `terminal`, o un booleano (`false`/`no` = `None`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "terminal" => Some(Detach::Terminal),
+            "false" | "no" => None,
+            _ => Some(Detach::Session),
+        }
+    }
+
+    /// Valor del flag tal como se escribe en el archivo `.toon`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Detach::Session => "true",
+            Detach::Terminal => "terminal",
+        }
+    }
+}
+
+/// Dónde abre tmux un comando con `[tmux=...]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TmuxTarget {
+    /// Ventana nueva, con la etiqueta del ítem como nombre (`[tmux=window]`).
+    Window,
+    /// División de la ventana actual (`[tmux=pane]`).
+    Pane,
+}
+
+impl TmuxTarget {
+    /// Parsea `window` o `pane`; cualquier otro valor equivale a no usar tmux.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "window" => Some(TmuxTarget::Window),
+            "pane" => Some(TmuxTarget::Pane),
+            _ => None,
+        }
+    }
+
+    /// Valor del flag tal como se escribe en el archivo `.toon`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TmuxTarget::Window => "window",
+            TmuxTarget::Pane => "pane",
+        }
+    }
+}
+
+/// Qué hacen las teclas `1`–`9` en un menú sin teclas de acceso rápido propias.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuickSelect {
+    /// Ejecutar el N-ésimo ítem visible (default)
+    Run,
+    /// Solo seleccionarlo
+    Select,
+    /// Nada: los números no tienen efecto
+    Off,
+}
+
+impl QuickSelect {
+    /// Parsea `run`, `select` u `off` (o `false`); un valor desconocido equivale a `Run`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().trim() {
+            "select" => QuickSelect::Select,
+            "off" | "false" | "no" => QuickSelect::Off,
+            _ => QuickSelect::Run,
+        }
+    }
+
+    /// Nombre del modo tal como se escribe en el archivo `.toon`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuickSelect::Run => "run",
+            QuickSelect::Select => "select",
+            QuickSelect::Off => "off",
+        }
+    }
+}
+
+/// Cuántas columnas ocupa la lista de ítems.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Columns {
+    /// Una columna, o varias cuando el menú no entra en el alto de la terminal
+    Auto,
+    /// Siempre esta cantidad (mientras entren a lo ancho); `1` es la lista simple (default)
+    Fixed(usize),
+}
+
+impl Columns {
+    /// Parsea `auto` o un número; un valor desconocido equivale a una columna
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("auto") {
+            return Columns::Auto;
+        }
+        Columns::Fixed(s.parse().unwrap_or(1).max(1))
+    }
+
+    /// Valor tal como se escribe en el archivo `.toon`
+    pub fn to_toon(&self) -> String {
+        match self {
+            Columns::Auto => "auto".to_string(),
+            Columns::Fixed(n) => n.to_string(),
+        }
+    }
+}
+
+/// Orden de los ítems de un nivel (`sort: alpha` o `[sort=alpha]`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    /// El orden del archivo, o el que da el generador del submenú (default)
+    File,
+    /// Alfabético por etiqueta, sin distinguir mayúsculas
+    Alpha,
+}
+
+impl SortOrder {
+    /// Parsea `alpha` o `file`; un valor desconocido equivale a `File`
+    pub fn parse(s: &str) -> Self {
+        match s.to_lowercase().trim() {
+            "alpha" => SortOrder::Alpha,
+            _ => SortOrder::File,
+        }
+    }
+
+    /// Valor tal como se escribe en el archivo `.toon`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::File => "file",
+            SortOrder::Alpha => "alpha",
+        }
+    }
+
+    /// Ordena `items` según este orden. Es estable: las etiquetas iguales
+    /// conservan el orden que tenían.
+    pub fn apply(self, items: &mut [MenuItem]) {
+        if self == SortOrder::Alpha {
+            items.sort_by_cached_key(|item| item.label.to_lowercase());
+        }
+    }
+}
+
+/// Shell con el que se ejecutan los comandos.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Shell {
+    /// Ejecución directa, sin shell: el comando se divide respetando comillas (default).
+    #[default]
+    None,
+    /// Programa y argumentos que preceden al comando (`bash -c`, `cmd /C`).
+    Program(Vec<String>),
+}
+
+impl Shell {
+    /// Parsea `none`, un nombre de shell (`bash`, `fish`, `pwsh`, `cmd`) o una
+    /// línea completa (`bash -lc`). Con solo el nombre se agrega el flag que
+    /// corresponde a ese shell para recibir el comando.
+    pub fn parse(s: &str) -> Self {
+        let words: Vec<String> = shlex::split(s.trim()).unwrap_or_default();
+        match words.as_slice() {
+            [] => Shell::None,
+            [name] if name.eq_ignore_ascii_case("none") => Shell::None,
+            [name] => {
+                let stem = std::path::Path::new(name)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                let flag = match stem.as_str() {
+                    "cmd" => "/C",
+                    "pwsh" | "powershell" => "-Command",
+                    _ => "-c",
+                };
+                Shell::Program(vec![name.clone(), flag.to_string()])
+            }
+            _ => Shell::Program(words),
+        }
+    }
+
+    /// Shell del sistema: `cmd /C` en Windows y `sh -c` en el resto.
+    pub fn system() -> Self {
+        Shell::parse(if cfg!(windows) { "cmd" } else { "sh" })
+    }
+
+    /// Representación para el archivo `.toon` (vuelve a parsearse igual).
+    pub fn to_toon(&self) -> String {
+        match self {
+            Shell::None => "none".to_string(),
+            Shell::Program(words) => shlex::try_join(words.iter().map(String::as_str))
+                .unwrap_or_else(|_| words.join(" ")),
+        }
+    }
+
+    /// Programa y argumentos a ejecutar para correr `cmd` con este shell.
+    pub fn command_line(&self, cmd: &str) -> Vec<String> {
+        match self {
+            // Fallback a split_whitespace si shlex falla (comillas desbalanceadas, etc).
+            Shell::None => shlex::split(cmd)
+                .unwrap_or_else(|| cmd.split_whitespace().map(str::to_string).collect()),
+            Shell::Program(words) => {
+                let mut parts = words.clone();
+                parts.push(cmd.to_string());
+                parts
+            }
+        }
+    }
 }
 
 /// Acción asociada a cada ítem del menú.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum MenuAction {
     Execute(String),
     Quit,
-    OpenSubmenu(Vec<MenuItem>),
+    OpenSubmenu(MenuItems),
+    /// Submenú generado al abrirlo con los ejecutables de un directorio (`dir:ruta`).
+    Directory(PathBuf),
+    /// Submenú cargado al abrirlo desde otro archivo de menú (`menu:ruta.toon`).
+    MenuFile(PathBuf),
+    /// Submenú generado al abrirlo con las líneas que imprime `source` (`list:comando`).
+    /// Si hay `template`, cada entrada ejecuta el template con `{line}` reemplazado
+    /// por la línea elegida; si no, la línea misma es el comando.
+    Dynamic {
+        source: String,
+        template: Option<String>,
+    },
+    /// Submenú con los últimos `limit` comandos del historial, para volver a
+    /// ejecutarlos (`history:` o `history:50`).
+    History(usize),
+    /// Submenú con los ítems marcados como favoritos (`favorites:`).
+    Favorites,
+    /// Submenú con los últimos `limit` ítems ejecutados, entre sesiones
+    /// (`recent:` o `recent:5`).
+    Recent(usize),
+    /// Submenú con los ítems que devuelve un programa externo al abrirlo
+    /// (`plugin:comando`), según el protocolo del módulo `plugin`.
+    Plugin(String),
+    /// Submenú con los hosts de `~/.ssh/config` y `~/.ssh/known_hosts`: cada
+    /// uno ejecuta `ssh host` (`ssh:`).
+    SshHosts,
+    /// Submenú con los ítems que arma un script Rhai al abrirlo (`script:...`,
+    /// con la feature `scripting`).
+    Script(Script),
+}
+
+/// Código Rhai de un ítem: en línea, o en un archivo si termina en `.rhai`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Script {
+    Inline(String),
+    File(PathBuf),
+}
+
+impl Script {
+    /// Lo que sigue a `script:` en el `.toon`.
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        if s.ends_with(".rhai") {
+            Script::File(PathBuf::from(s))
+        } else {
+            Script::Inline(s.to_string())
+        }
+    }
+
+    /// Como se escribe en el `.toon`, sin el `script:`.
+    pub fn source(&self) -> String {
+        match self {
+            Script::Inline(code) => code.clone(),
+            Script::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+impl MenuAction {
+    /// Retorna true si la acción abre un submenú, estático o generado al abrirlo.
+    pub fn is_submenu(&self) -> bool {
+        !matches!(self, MenuAction::Execute(_) | MenuAction::Quit)
+    }
 }
 
 /// Un ítem del menú con su etiqueta y acción asociada.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct MenuItem {
     pub label: String,
     pub action: MenuAction,
     /// Si true, pedir confirmación antes de ejecutar este comando.
     /// Default: true (seguro por defecto).
     pub require_confirmation: bool,
+    /// Texto propio del diálogo de confirmación (`[confirm="¿Borrar la base?"]`).
+    pub confirm_message: Option<String>,
+    /// Frase que hay que escribir tal cual para confirmar (`[confirm_text=delete production]`).
+    /// Implica confirmación aunque no haya `confirm`.
+    pub confirm_text: Option<String>,
+    /// Directorio de trabajo del comando (`[cwd=ruta]`). `None` = el actual.
+    pub cwd: Option<PathBuf>,
+    /// Shell propio del ítem (`[shell=bash]`). `None` = el global.
+    pub shell: Option<Shell>,
+    /// Horario informativo (`[schedule=nightly 02:00]`): tmenu no lo ejecuta,
+    /// solo muestra la próxima ejecución y lo exporta a crontab/systemd.
+    pub schedule: Option<String>,
+    /// Pausa "Presioná Enter" al terminar (`[wait=false]`). `None` = la global.
+    pub wait: Option<bool>,
+    /// Tiempo que el ítem queda deshabilitado después de ejecutarse (`[cooldown=60s]`).
+    pub cooldown: Option<Duration>,
+    /// Tiempo máximo de ejecución (`[timeout=30s]`): al vencer se termina el
+    /// proceso con todos sus hijos.
+    pub timeout: Option<Duration>,
+    /// Comandos que siguen al principal, en orden (líneas `- comando` debajo
+    /// del ítem). La secuencia se corta en el primer paso que falla.
+    pub steps: Vec<String>,
+    /// Qué hacer cuando el comando termina bien (`[on_success=...]`).
+    pub on_success: Option<FollowUp>,
+    /// Qué hacer cuando el comando falla (`[on_failure=...]`).
+    pub on_failure: Option<FollowUp>,
+    /// Cerrar tmenu cuando termina el comando (`[exit=true]`), para usarlo como lanzador.
+    pub exit_after: bool,
+    /// Modo de ejecución propio (`[execution_mode=capture]`). `None` = el global.
+    pub execution_mode: Option<ExecutionMode>,
+    /// Lanzar el comando en segundo plano sin bloquear el menú (`[background=true]`).
+    pub background: bool,
+    /// Lanzar el comando aparte, sin esperarlo ni compartir la terminal (`[detach=true]`
+    /// o `[detach=terminal]`).
+    pub detach: Option<Detach>,
+    /// Script Rhai que reescribe la salida capturada antes de mostrarla
+    /// (`[output_filter=script:...]`).
+    pub output_filter: Option<Script>,
+    /// Emulador propio para `[detach=terminal]` (`[terminal=kitty -e]`). `None` = el de
+    /// `terminal:` en `config:`.
+    pub terminal: Option<Vec<String>>,
+    /// Dentro de tmux, ejecutar en una ventana o panel nuevo (`[tmux=window]`, `[tmux=pane]`).
+    pub tmux: Option<TmuxTarget>,
+    /// Ejecutar con privilegios elevados (`[elevate=true]`): `sudo`/`doas`, o
+    /// el programa de `elevate_with:`.
+    pub elevate: bool,
+    /// Ítem visible pero que no se puede elegir (`[disabled=true]`): se dibuja
+    /// atenuado y la selección lo saltea.
+    pub disabled: bool,
+    /// Ítem que no se muestra salvo al pedir los ocultos con `o`
+    /// (`[hidden=true]`), para tareas peligrosas o de mantenimiento.
+    pub hidden: bool,
+    /// Comando que decide si el ítem se puede elegir (`[enabled_if=test -f .env]`):
+    /// si no termina bien, el ítem queda deshabilitado. Se evalúa al entrar al nivel.
+    pub enabled_if: Option<String>,
+    /// Comando que informa el estado del ítem (`[status=systemctl is-active nginx]`):
+    /// su salida, o ✓/✗ según termine bien o no, se muestra junto a la etiqueta.
+    pub status: Option<String>,
+    /// Orden de los ítems del submenú que abre (`Logs: [sort=alpha]`, o en
+    /// un `dir:`/`list:`). `None` = el global `sort:`.
+    pub sort: Option<SortOrder>,
+    /// Ayuda extendida del ítem, mostrada con `?` (`[help=...]` o `[help_file=ruta]`).
+    pub help: Option<ItemHelp>,
+    /// Variables de entorno propias (`[env="KUBECONFIG=~/.kube/prod AWS_PROFILE=prod"]`),
+    /// aplicadas sobre las del bloque global `env:`.
+    pub env: Vec<(String, String)>,
+    /// Dónde se definió el ítem. `None` para los creados por código.
+    pub source: Option<SourceLocation>,
+    /// Tecla de acceso rápido que lo ejecuta directamente (`"Nombre"[2]:`).
+    pub hotkey: Option<char>,
+}
+
+impl MenuItem {
+    /// Crea un ítem con los atributos por defecto.
+    pub fn new(label: impl Into<String>, action: MenuAction) -> Self {
+        MenuItem {
+            label: label.into(),
+            action,
+            require_confirmation: false,
+            confirm_message: None,
+            confirm_text: None,
+            cwd: None,
+            shell: None,
+            schedule: None,
+            wait: None,
+            cooldown: None,
+            timeout: None,
+            steps: Vec::new(),
+            on_success: None,
+            on_failure: None,
+            exit_after: false,
+            execution_mode: None,
+            background: false,
+            detach: None,
+            output_filter: None,
+            terminal: None,
+            tmux: None,
+            elevate: false,
+            disabled: false,
+            hidden: false,
+            enabled_if: None,
+            status: None,
+            sort: None,
+            help: None,
+            env: Vec::new(),
+            source: None,
+            hotkey: None,
+        }
+    }
+
+    /// Comando completo a partir del principal `cmd`: con pasos, uno por línea.
+    pub fn command_sequence(&self, cmd: &str) -> String {
+        std::iter::once(cmd)
+            .chain(self.steps.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Si hay que pedir confirmación antes de ejecutar el comando.
+    pub fn needs_confirmation(&self) -> bool {
+        self.require_confirmation || self.confirm_text.is_some()
+    }
 }
 
-/// Entrada del historial de navegación para poder volver atrás.
-pub struct HistoryEntry {
-    pub title: String,
-    pub items: Vec<MenuItem>,
-    pub state: ListState,
+/// Archivo y línea que definen un ítem.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceLocation {
+    /// `None` si el menú no se leyó de un archivo (por ejemplo, de la entrada estándar)
+    pub file: Option<PathBuf>,
+    /// Número de línea, desde 1
+    pub line: usize,
+}
+
+/// Acción que sigue a un comando según cómo terminó (`[on_success=...]`,
+/// `[on_failure=...]`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum FollowUp {
+    /// Otro comando, ejecutado con los atributos del ítem
+    Command(String),
+    /// Saltar a un ítem del menú por su camino de etiquetas (`goto:Logs/Ver log`)
+    Goto(Vec<String>),
+}
+
+impl FollowUp {
+    pub fn parse(value: &str) -> Self {
+        match value.strip_prefix("goto:") {
+            Some(path) => FollowUp::Goto(parse_item_path(path)),
+            None => FollowUp::Command(value.to_string()),
+        }
+    }
+
+    /// Representación para el archivo `.toon` (vuelve a parsearse igual).
+    pub fn to_toon(&self) -> String {
+        match self {
+            FollowUp::Command(cmd) => cmd.clone(),
+            FollowUp::Goto(path) => format!("goto:{}", item_path(path)),
+        }
+    }
+}
+
+/// Lee un camino de etiquetas (`Git/Estado`), como los de `tmenu run` o
+/// `goto:`. Una `/` que es parte de la etiqueta se escribe `\/`, y una `\`
+/// al final de la etiqueta, `\\`.
+pub fn parse_item_path(path: &str) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut label = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.clone().next() {
+                Some(next @ ('/' | '\\')) => {
+                    label.push(next);
+                    chars.next();
+                }
+                _ => label.push(c),
+            },
+            '/' => labels.push(std::mem::take(&mut label)),
+            _ => label.push(c),
+        }
+    }
+    labels.push(label);
+    labels
+        .into_iter()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect()
+}
+
+/// Escribe `labels` como un camino que [`parse_item_path`] vuelve a leer igual.
+pub fn item_path(labels: &[String]) -> String {
+    labels
+        .iter()
+        .map(|label| label.replace('\\', "\\\\").replace('/', "\\/"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Ayuda extendida de un ítem: texto en el propio menú o un archivo
+/// (texto plano o Markdown) que se lee al abrirla.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemHelp {
+    Text(String),
+    File(PathBuf),
+}
+
+impl ItemHelp {
+    /// Texto de la ayuda; los archivos se leen en cada apertura para ver los cambios.
+    pub fn load(&self) -> std::io::Result<String> {
+        match self {
+            ItemHelp::Text(text) => Ok(text.clone()),
+            ItemHelp::File(path) => std::fs::read_to_string(path),
+        }
+    }
 }
 
 /// Un parámetro interpolable extraído de un comando.
-/// Corresponde a una ocurrencia de `{{text: Etiqueta}}` en el string del comando.
+/// Corresponde a una ocurrencia de `{{text: Etiqueta}}`, `{input:Etiqueta}` o
+/// `{pick:comando}` en el string del comando.
 #[derive(Clone, Debug)]
 pub struct CommandParam {
     /// Texto que se muestra al usuario como prompt ("Branch name").
     pub label: String,
     /// Placeholder original completo para hacer el reemplazo ("{{text: Branch name}}").
     pub placeholder: String,
+    /// Para `{pick:comando}`: comando cuyas líneas se ofrecen para elegir.
+    pub pick: Option<String>,
 }
 
 /// Estado de una confirmación de ejecución de comando.
@@ -61,22 +566,35 @@ pub struct CommandParam {
 pub struct ConfirmationState {
     /// El comando que se quiere ejecutar
     pub cmd: String,
+    /// Mensaje propio del ítem, mostrado sobre el comando
+    pub message: Option<String>,
+    /// Frase a escribir para confirmar; con ella no hay opciones Sí / No
+    pub required_text: Option<String>,
+    /// Lo que lleva escrito el usuario de `required_text`
+    pub input: String,
     /// Índice de selección: 0 = "Sí", 1 = "No" (se navega con Up/Down)
     pub selected: usize,
 }
 
 #[allow(dead_code)]
 impl ConfirmationState {
-    pub fn new(cmd: String) -> Self {
+    pub fn new(cmd: String, message: Option<String>, required_text: Option<String>) -> Self {
         ConfirmationState {
             cmd,
+            message,
+            required_text,
+            input: String::new(),
             selected: 0, // por defecto "Sí" está seleccionado (es más seguro que "No")
         }
     }
 
-    /// Retorna true si el usuario selecciona "Sí"
+    /// Retorna true si el usuario selecciona "Sí", o si escribió exactamente
+    /// la frase requerida
     pub fn is_confirmed(&self) -> bool {
-        self.selected == 0
+        match &self.required_text {
+            Some(text) => self.input == *text,
+            None => self.selected == 0,
+        }
     }
 
     /// Navega entre las opciones (Up/Down)
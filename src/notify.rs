@@ -0,0 +1,69 @@
+//! Notificaciones de escritorio al terminar un comando en segundo plano.
+//!
+//! Se usan las herramientas del sistema (`notify-send` en Linux y BSD,
+//! `osascript` en macOS) en lugar de hablar con el servidor de
+//! notificaciones: si no están instaladas, no se notifica y listo.
+use std::process::{Command, Stdio};
+
+/// Muestra una notificación con `title` y `body`. No espera a que el
+/// notificador termine ni informa errores: una notificación perdida no
+/// debe interrumpir el menú. Un hilo aparte lo espera para que no quede
+/// como zombi.
+pub fn send(title: &str, body: &str) {
+    let Some(mut command) = notifier(title, body) else {
+        return;
+    };
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = child {
+        std::thread::spawn(move || child.wait());
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn notifier(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("osascript");
+    command.args([
+        "-e",
+        &format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        ),
+    ]);
+    Some(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notifier(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=tmenu", title, body]);
+    Some(command)
+}
+
+#[cfg(not(unix))]
+fn notifier(_title: &str, _body: &str) -> Option<Command> {
+    None
+}
+
+/// Literal de AppleScript: entre comillas, escapando `\` y `"`.
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applescript_string_escapes_quotes() {
+        assert_eq!(
+            applescript_string(r#"Build "prod" C:\tmp"#),
+            r#""Build \"prod\" C:\\tmp""#
+        );
+    }
+}
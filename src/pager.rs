@@ -0,0 +1,64 @@
+use ratatui::{Terminal, backend::Backend};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::app::{resume_tui, suspend_tui};
+use crate::error::TmenuError;
+
+/// Pager usado cuando `$PAGER` no está definido.
+const DEFAULT_PAGER: &str = "less";
+
+/// Muestra `text` en el pager externo (`$PAGER`, o `less` por defecto).
+///
+/// La TUI se suspende mientras el pager está abierto y se restaura al salir,
+/// de modo que el usuario puede usar la búsqueda y navegación propias del pager.
+pub fn open_in_pager<B: Backend>(terminal: &mut Terminal<B>, text: &str) -> Result<(), TmenuError> {
+    let parts = pager_command(std::env::var("PAGER").ok());
+    let Some((bin, args)) = parts.split_first() else {
+        return Ok(());
+    };
+
+    suspend_tui();
+
+    let result = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                // El pager puede cerrarse antes de leer todo (BrokenPipe): no es un error
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()
+        });
+
+    resume_tui(terminal)?;
+
+    result
+        .map(|_| ())
+        .map_err(|e| TmenuError::TerminalError(format!("no se pudo abrir '{}': {}", bin, e)))
+}
+
+/// Programa y argumentos del pager a partir del valor de `$PAGER`.
+fn pager_command(pager: Option<String>) -> Vec<String> {
+    let pager = pager
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    // $PAGER puede traer argumentos ("less -R"), respetar el quoting
+    shlex::split(&pager).unwrap_or_else(|| vec![pager.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None), vec!["less"]);
+        assert_eq!(pager_command(Some("  ".to_string())), vec!["less"]);
+        assert_eq!(
+            pager_command(Some("less -R '+/error'".to_string())),
+            vec!["less", "-R", "+/error"]
+        );
+    }
+}
@@ -1,41 +1,226 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
 
-use crate::error::AppError;
+use crate::error::TmenuError;
 use crate::model::CommandParam;
-use crate::model::{ExecutionMode, MenuAction, MenuItem};
+use crate::model::{
+    Columns, Detach, ExecutionMode, FollowUp, ItemHelp, MenuAction, MenuItem, MenuItems,
+    QuickSelect, Script, Shell, SortOrder, SourceLocation, TmuxTarget,
+};
 
 /// Configuración global del menú extraída del archivo `.toon`.
 #[derive(Clone, Debug)]
 pub struct GlobalConfig {
     /// Modo de ejecución: limpiar pantalla o usar la actual
     pub execution_mode: ExecutionMode,
+    /// Intervalo de las tareas periódicas (`tick_rate: 500`, en milisegundos)
+    pub tick_rate: Duration,
+    /// Registrar estadísticas de uso locales (`stats: true`, desactivado por defecto)
+    pub stats: bool,
+    /// Shell para ejecutar los comandos (`shell: bash`; por defecto `none`, ejecución directa)
+    pub shell: Shell,
+    /// Esperar Enter tras cada comando antes de volver al menú (`wait: false` lo desactiva)
+    pub wait: bool,
+    /// Teclas `1`–`9` sobre los ítems visibles (`quick_select: select` solo
+    /// selecciona, `off` las desactiva)
+    pub quick_select: QuickSelect,
+    /// Columnas de la lista (`columns: auto` o `columns: 3`; por defecto una)
+    pub columns: Columns,
+    /// Al bajar desde el último ítem se pasa al primero, y al revés
+    /// (`wrap: false` frena en los extremos)
+    pub wrap: bool,
+    /// Barra inferior con el comando del ítem seleccionado (`status_bar: false`
+    /// la oculta)
+    pub status_bar: bool,
+    /// Los submenús del menú raíz se muestran como pestañas (`tabs: true`)
+    pub tabs: bool,
+    /// Orden de los ítems en todos los niveles (`sort: alpha`); cada submenú
+    /// puede cambiarlo con `[sort=...]`
+    pub sort: SortOrder,
+    /// Notificación de escritorio al terminar un comando en segundo plano
+    /// (`notify: false` la desactiva)
+    pub notify: bool,
+    /// Guardar cada comando ejecutado en el historial (`history: false` lo desactiva)
+    pub history: bool,
+    /// Volver al abrir el menú al submenú y el ítem donde se salió, con el
+    /// camino para volver (`restore: false` lo desactiva)
+    pub restore: bool,
+    /// Variables de entorno del bloque `env:`, para todos los comandos
+    pub env: Vec<(String, String)>,
+    /// Teclas del bloque `keys:`: acción y teclas tal como se escribieron
+    /// (`quit: ctrl+x, q`); las interpreta la interfaz
+    pub keys: Vec<(String, String)>,
+    /// Programa para los ítems con `[elevate=true]` (`elevate_with: doas`);
+    /// `None` = el primero que se encuentre en el PATH
+    pub elevate_with: Option<Vec<String>>,
+    /// Emulador para los ítems con `[detach=terminal]` (`terminal: kitty`),
+    /// seguido del comando; `None` = `$TERMINAL -e` o el primero que se encuentre
+    pub terminal: Option<Vec<String>>,
+    /// Comando que corre antes de cada comando del menú (`before_exec:`); si
+    /// falla, el comando no se ejecuta
+    pub before_exec: Option<String>,
+    /// Comando que corre después de cada comando del menú (`after_exec:`)
+    pub after_exec: Option<String>,
+    /// Formato strftime de las fechas mostradas (`date_format: %d/%m/%Y`)
+    pub date_format: Option<String>,
+    /// Formato strftime de las horas mostradas (`time_format: %I:%M %p`);
+    /// sin configurar depende del locale
+    pub time_format: Option<String>,
 }
 
 impl Default for GlobalConfig {
     fn default() -> Self {
         GlobalConfig {
             execution_mode: ExecutionMode::Inherit,
+            tick_rate: Duration::from_millis(500),
+            stats: false,
+            shell: Shell::None,
+            wait: true,
+            quick_select: QuickSelect::Run,
+            columns: Columns::Fixed(1),
+            wrap: true,
+            status_bar: true,
+            tabs: false,
+            sort: SortOrder::File,
+            notify: true,
+            history: true,
+            restore: true,
+            env: Vec::new(),
+            keys: Vec::new(),
+            elevate_with: None,
+            terminal: None,
+            before_exec: None,
+            after_exec: None,
+            date_format: None,
+            time_format: None,
         }
     }
 }
 
+/// Comandos que muestra un submenú `history:` sin límite propio.
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// Ítems que muestra un submenú `recent:` sin límite propio.
+pub const DEFAULT_RECENT_LIMIT: usize = 10;
+
+/// Secciones especiales del archivo `.toon` que no forman parte del menú.
+#[derive(Clone, Copy)]
+enum Section {
+    /// `config:` — opciones globales de la aplicación.
+    Config,
+    /// `defaults:` — atributos por defecto para todos los ítems.
+    Defaults,
+    /// `env:` — variables de entorno para todos los comandos.
+    Env,
+    /// `keys:` — teclas propias para las acciones de la interfaz.
+    Keys,
+}
+
 /// Carga y parsea un archivo `.toon`, retornando la configuración global,
 /// el titulo principal y la lista de items del menu raiz.
-pub fn parse_toon_file(path: &Path) -> Result<(GlobalConfig, String, Vec<MenuItem>), AppError> {
+pub fn parse_toon_file(path: &Path) -> Result<(GlobalConfig, String, MenuItems), TmenuError> {
+    parse_toon_file_with(path, GlobalConfig::default())
+}
+
+/// Como [`parse_toon_file`], partiendo de `defaults` en lugar de la
+/// configuración por defecto: el `config:` y el `keys:` del menú se aplican
+/// encima (las teclas del menú se suman a las de `defaults`).
+pub fn parse_toon_file_with(
+    path: &Path,
+    defaults: GlobalConfig,
+) -> Result<(GlobalConfig, String, MenuItems), TmenuError> {
     let content = fs::read_to_string(path)?;
+    // Las rutas relativas del menú (`dir:`, `menu:`) se resuelven desde la carpeta del archivo
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    Ok(parse_toon_source(&content, base_dir, Some(path), defaults))
+}
 
-    let mut config = GlobalConfig::default();
+/// Parsea el contenido de un menú `.toon`. Las rutas relativas se resuelven
+/// desde `base_dir`.
+pub fn parse_toon_str(content: &str, base_dir: &Path) -> (GlobalConfig, String, MenuItems) {
+    parse_toon_str_with(content, base_dir, GlobalConfig::default())
+}
+
+/// Como [`parse_toon_str`], partiendo de `defaults` (ver [`parse_toon_file_with`]).
+pub fn parse_toon_str_with(
+    content: &str,
+    base_dir: &Path,
+    defaults: GlobalConfig,
+) -> (GlobalConfig, String, MenuItems) {
+    parse_toon_source(content, base_dir, None, defaults)
+}
+
+/// Aplica la opción `key: value` de `config:` a `config`. Retorna false si
+/// la clave no existe; un valor inválido deja el anterior o el por defecto.
+pub fn apply_config(config: &mut GlobalConfig, key: &str, value: &str) -> bool {
+    match key {
+        "execution_mode" => {
+            config.execution_mode = ExecutionMode::from_str(value);
+        }
+        "tick_rate" => {
+            if let Ok(ms) = value.parse::<u64>() {
+                config.tick_rate = Duration::from_millis(ms.max(1));
+            }
+        }
+        "stats" => config.stats = is_truthy(value),
+        "wait" => config.wait = is_truthy(value),
+        "notify" => config.notify = is_truthy(value),
+        "history" => config.history = is_truthy(value),
+        "restore" => config.restore = is_truthy(value),
+        "status_bar" => config.status_bar = is_truthy(value),
+        "wrap" => config.wrap = is_truthy(value),
+        "tabs" => config.tabs = is_truthy(value),
+        "sort" => config.sort = SortOrder::parse(value),
+        "quick_select" => config.quick_select = QuickSelect::from_str(value),
+        "columns" => config.columns = Columns::parse(value),
+        "shell" => config.shell = Shell::parse(&unquote(value)),
+        "elevate_with" => {
+            config.elevate_with = shlex::split(&unquote(value)).filter(|words| !words.is_empty())
+        }
+        "terminal" => {
+            config.terminal = shlex::split(&unquote(value)).filter(|words| !words.is_empty())
+        }
+        "before_exec" => config.before_exec = Some(unquote(value)),
+        "after_exec" => config.after_exec = Some(unquote(value)),
+        "date_format" => config.date_format = Some(unquote(value)),
+        "time_format" => config.time_format = Some(unquote(value)),
+        _ => return false,
+    }
+    true
+}
+
+/// Parser común: `file` es el archivo de origen, si hay, y queda registrado
+/// en cada ítem junto con su línea.
+fn parse_toon_source(
+    content: &str,
+    base_dir: &Path,
+    file: Option<&Path>,
+    defaults: GlobalConfig,
+) -> (GlobalConfig, String, MenuItems) {
+    let mut config = defaults;
     let mut main_title = String::from("Menu Principal");
-    let mut stack: Vec<(String, Vec<MenuItem>, usize)> = Vec::new();
+    // Submenús abiertos: el ítem que los representa, sus hijos y su nivel
+    let mut stack: Vec<(MenuItem, Vec<MenuItem>, usize)> = Vec::new();
     let mut root_items: Vec<MenuItem> = Vec::new();
     let mut indent_levels: Vec<usize> = Vec::new();
 
-    let mut config_base_indent: Option<usize> = None;
+    // Sección `config:` o `defaults:` abierta y su indentación
+    let mut section: Option<(Section, usize)> = None;
+    // Flags de `defaults:`, aplicados a cada ítem antes que sus propios flags
+    let mut defaults: Vec<(String, String)> = Vec::new();
+    // Nivel del último ítem de comando, que puede recibir pasos `- comando`
+    let mut last_command_level: Option<usize> = None;
 
-    for line in content.lines() {
+    for (index, line) in content.lines().enumerate() {
+        let source = SourceLocation {
+            file: file.map(Path::to_path_buf),
+            line: index + 1,
+        };
         let normalized = line.replace('\t', "    ");
-        if normalized.trim().is_empty() {
+        // Líneas vacías y comentarios `#` (incluye el shebang `#!/usr/bin/env tmenu`)
+        if normalized.trim().is_empty() || normalized.trim_start().starts_with('#') {
             continue;
         }
 
@@ -44,32 +229,52 @@ pub fn parse_toon_file(path: &Path) -> Result<(GlobalConfig, String, Vec<MenuIte
 
         // ========== PARSING DE CONFIGURACIÓN ==========
         if trimmed.starts_with("config:") {
-            config_base_indent = Some(raw_indent);
+            section = Some((Section::Config, raw_indent));
+            continue;
+        }
+        if trimmed == "defaults:" {
+            section = Some((Section::Defaults, raw_indent));
+            continue;
+        }
+        if trimmed == "env:" {
+            section = Some((Section::Env, raw_indent));
+            continue;
+        }
+        if trimmed == "keys:" {
+            section = Some((Section::Keys, raw_indent));
             continue;
         }
 
-        // Si estamos en la sección de configuración (hay config_base_indent)
-        if let Some(config_indent) = config_base_indent {
-            // Si encontramos una línea indentada bajo config: con ":", es una opción
-            if raw_indent > config_indent && trimmed.contains(':') && !trimmed.ends_with(':') {
+        // Si estamos en una sección (config: o defaults:)
+        if let Some((kind, section_indent)) = section {
+            // Si encontramos una línea indentada bajo la sección con ":", es una opción
+            if raw_indent > section_indent && trimmed.contains(':') && !trimmed.ends_with(':') {
                 // Parsear: "execution_mode: clean"
                 if let Some(pos) = trimmed.find(':') {
                     let key = trimmed[..pos].trim();
                     let value = trimmed[pos + 1..].trim();
 
-                    match key {
-                        "execution_mode" => {
-                            config.execution_mode = ExecutionMode::from_str(value);
+                    match kind {
+                        Section::Config => {
+                            apply_config(&mut config, key, value);
+                        }
+                        Section::Defaults => {
+                            defaults.push((key.to_string(), unquote(value)));
+                        }
+                        Section::Env => {
+                            set_env(&mut config.env, key, &expand_home(&unquote(value)));
+                        }
+                        Section::Keys => {
+                            config.keys.push((key.to_string(), unquote(value)));
                         }
-                        _ => {}
                     }
                 }
                 continue;
             }
 
-            // Si encontramos algo al mismo nivel o menor que config:, salimos de la sección
-            if raw_indent <= config_indent && !trimmed.starts_with("config") {
-                config_base_indent = None;
+            // Si encontramos algo al mismo nivel o menor que la sección, salimos de ella
+            if raw_indent <= section_indent {
+                section = None;
             }
         }
 
@@ -86,42 +291,94 @@ pub fn parse_toon_file(path: &Path) -> Result<(GlobalConfig, String, Vec<MenuIte
             .unwrap_or(0);
 
         // Titulo principal (nivel 0, termina en ':' fuera de comillas)
-        if level == 0 && ends_with_separator_colon(trimmed) {
-            let pos = find_separator_colon(trimmed).unwrap();
-            main_title = trimmed[..pos].trim_matches('"').trim().to_string();
+        if level == 0
+            && ends_with_separator_colon(trimmed)
+            && let Some(pos) = find_separator_colon(trimmed)
+        {
+            main_title = unquote(trimmed[..pos].trim()).trim().to_string();
+            continue;
+        }
+
+        // Paso de una secuencia: `- comando` bajo el ítem de comando anterior
+        if let Some(step) = trimmed.strip_prefix("- ")
+            && last_command_level.is_some_and(|last| level > last)
+        {
+            let last = match stack.last_mut() {
+                Some(parent) => parent.1.last_mut(),
+                None => root_items.last_mut(),
+            };
+            if let Some(item) = last {
+                item.steps.push(unquote(step.trim()));
+            }
             continue;
         }
 
         // Buscar ':' separador fuera de comillas
         if let Some(pos) = find_separator_colon(trimmed) {
-            let key = trimmed[..pos].trim_matches('"').trim().to_string();
+            let (key, hotkey) = split_hotkey(trimmed[..pos].trim());
+            let key = unquote(key.trim()).trim().to_string();
             let value_with_flag = trimmed[pos + 1..].trim();
 
-            // Extraer flag [confirm=...] si existe
-            let (value, require_confirmation) = extract_confirm_flag(value_with_flag);
+            // Extraer flags [clave=valor] si existen
+            let (value, flags) = extract_flags(value_with_flag);
             let value = value.trim();
 
             if value.is_empty() {
                 // Es un submenu: cerrar los niveles iguales o mayores
-                while stack.last().map_or(false, |e| e.2 >= level) {
+                while stack.last().is_some_and(|e| e.2 >= level) {
                     pop_and_insert(&mut stack, &mut root_items);
                 }
-                stack.push((key, Vec::new(), level));
+                last_command_level = None;
+                let mut submenu = MenuItem::new(key, MenuAction::OpenSubmenu(Rc::default()));
+                submenu.source = Some(source);
+                submenu.hotkey = hotkey;
+                // De los flags, un submenú solo usa el orden de sus ítems y si está oculto
+                for (flag, flag_value) in &flags {
+                    match flag.as_str() {
+                        "sort" => submenu.sort = Some(SortOrder::parse(flag_value)),
+                        "hidden" => submenu.hidden = is_truthy(flag_value),
+                        _ => {}
+                    }
+                }
+                stack.push((submenu, Vec::new(), level));
             } else {
-                while stack.last().map_or(false, |e| e.2 >= level) {
+                while stack.last().is_some_and(|e| e.2 >= level) {
                     pop_and_insert(&mut stack, &mut root_items);
                 }
-                let raw_value = value.trim_matches('"').to_string();
+                let raw_value = unquote(value);
                 let action = if raw_value == "exit" {
                     MenuAction::Quit
+                } else if let Some(dir) = raw_value.strip_prefix("dir:") {
+                    MenuAction::Directory(resolve_path(base_dir, dir))
+                } else if let Some(menu) = raw_value.strip_prefix("menu:") {
+                    MenuAction::MenuFile(resolve_path(base_dir, menu))
+                } else if raw_value == "favorites:" {
+                    MenuAction::Favorites
+                } else if let Some(limit) = raw_value.strip_prefix("recent:") {
+                    MenuAction::Recent(limit.trim().parse().unwrap_or(DEFAULT_RECENT_LIMIT))
+                } else if let Some(limit) = raw_value.strip_prefix("history:") {
+                    MenuAction::History(limit.trim().parse().unwrap_or(DEFAULT_HISTORY_LIMIT))
+                } else if let Some(command) = raw_value.strip_prefix("plugin:") {
+                    MenuAction::Plugin(command.trim().to_string())
+                } else if raw_value == "ssh:" {
+                    MenuAction::SshHosts
+                } else if let Some(code) = raw_value.strip_prefix("script:") {
+                    MenuAction::Script(resolve_script(base_dir, code))
+                } else if let Some(source) = raw_value.strip_prefix("list:") {
+                    MenuAction::Dynamic {
+                        source: source.trim().to_string(),
+                        template: None,
+                    }
                 } else {
                     MenuAction::Execute(raw_value)
                 };
-                let item = MenuItem {
-                    label: key,
-                    action,
-                    require_confirmation,
-                };
+                last_command_level = matches!(action, MenuAction::Execute(_)).then_some(level);
+                let mut item = MenuItem::new(key, action);
+                item.source = Some(source);
+                item.hotkey = hotkey;
+                for (flag, flag_value) in defaults.iter().chain(&flags) {
+                    apply_flag(&mut item, flag, flag_value, base_dir);
+                }
                 if let Some(parent) = stack.last_mut() {
                     parent.1.push(item);
                 } else {
@@ -135,36 +392,218 @@ pub fn parse_toon_file(path: &Path) -> Result<(GlobalConfig, String, Vec<MenuIte
     while !stack.is_empty() {
         pop_and_insert(&mut stack, &mut root_items);
     }
+    // Recién acá se conoce el `sort:` global, que puede estar en cualquier lugar
+    sort_tree(&mut root_items, config.sort, config.sort);
 
-    Ok((config, main_title, root_items))
+    (config, main_title, Rc::new(root_items))
 }
 
-/// Extrae la flag [confirm=true/false] de una línea si existe.
-/// Retorna (línea sin flag, require_confirmation).
-/// Default: false (no confirmación por defecto, pero puede ser true).
-fn extract_confirm_flag(s: &str) -> (&str, bool) {
-    // Buscar [confirm=...] al final
-    if let Some(bracket_pos) = s.rfind('[') {
-        let rest = &s[bracket_pos..];
-        if rest.starts_with("[confirm=") {
-            let inner = rest
-                .strip_prefix("[confirm=")
-                .and_then(|s| s.strip_suffix("]"));
-            if let Some(flag_str) = inner {
-                let flag_str = flag_str.trim();
-                let line_without_flag = s[..bracket_pos].trim();
-
-                let require_conf = match flag_str {
-                    "false" | "False" | "FALSE" | "no" | "No" | "NO" => false,
-                    _ => true, // default: true (incluye "true", typos, etc)
-                };
+/// Separa la tecla de acceso rápido del nombre de un ítem: `"Deploy"[2]` o
+/// `Deploy [d]` retornan `("\"Deploy\"", Some('2'))`/`("Deploy", Some('d'))`.
+/// Solo cuenta un carácter alfanumérico entre corchetes.
+fn split_hotkey(key: &str) -> (&str, Option<char>) {
+    let Some(rest) = key.strip_suffix(']') else {
+        return (key, None);
+    };
+    let Some(open) = rest.rfind('[').filter(|&open| open > 0) else {
+        return (key, None);
+    };
+    let mut chars = rest[open + 1..].chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphanumeric() => (key[..open].trim_end(), Some(c)),
+        _ => (key, None),
+    }
+}
+
+/// Extrae los flags `[clave=valor]` del final de una línea.
+/// Se aceptan varios flags en un mismo corchete separados por coma
+/// (`[confirm=true, run=ssh {line}]`) o en corchetes consecutivos
+/// (`[confirm=true] [run=ssh {line}]`). Los valores pueden ir entre comillas
+/// para incluir comas.
+///
+/// Retorna (línea sin flags, flags en el orden en que aparecen).
+/// Un corchete que no tiene forma `clave=valor` (ej. `echo [1]`) se deja en la línea.
+fn extract_flags(s: &str) -> (&str, Vec<(String, String)>) {
+    let mut rest = s.trim_end();
+    let mut groups = Vec::new();
+
+    while rest.ends_with(']') {
+        // El `[` del último grupo, sin contar los que están en valores entre comillas
+        let Some(open) = unquoted_chars(rest)
+            .filter(|&(_, c)| c == '[')
+            .last()
+            .map(|(i, _)| i)
+            .or_else(|| rest.rfind('['))
+        else {
+            break;
+        };
+        let Some(flags) = parse_flag_group(&rest[open + 1..rest.len() - 1]) else {
+            break;
+        };
+        groups.push(flags);
+        rest = rest[..open].trim_end();
+    }
+
+    groups.reverse();
+    (rest, groups.into_iter().flatten().collect())
+}
+
+/// Parsea el contenido de un corchete de flags: `clave=valor, clave2=valor2`.
+/// Retorna `None` si alguna parte no tiene la forma `clave=valor`.
+fn parse_flag_group(inner: &str) -> Option<Vec<(String, String)>> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, _) in unquoted_chars(inner).filter(|&(_, c)| c == ',') {
+        parts.push(&inner[start..i]);
+        start = i + 1;
+    }
+    parts.push(&inner[start..]);
+
+    parts
+        .into_iter()
+        .map(|part| {
+            let (key, value) = part.split_once('=')?;
+            let key = key.trim();
+            let valid_key =
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            valid_key.then(|| (key.to_string(), unquote(value.trim())))
+        })
+        .collect()
+}
+
+/// Interpreta el valor de un flag booleano.
+/// Solo `false`/`no` desactivan; cualquier otro valor (incluidos typos) activa,
+/// para que una confirmación mal escrita falle del lado seguro.
+fn is_truthy(value: &str) -> bool {
+    !matches!(value, "false" | "False" | "FALSE" | "no" | "No" | "NO")
+}
 
-                return (line_without_flag, require_conf);
+/// Valores que activan un flag booleano sin ser un texto propio
+/// (`confirm=true` pide confirmación; `confirm=¿Seguro?` además muestra ese texto).
+fn is_true_word(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "true" | "yes" | "si" | "sí" | "1"
+    )
+}
+
+/// Aplica un flag `[clave=valor]` a un ítem. Las claves desconocidas se ignoran.
+/// Las rutas se resuelven desde `base_dir` (la carpeta del archivo `.toon`).
+fn apply_flag(item: &mut MenuItem, key: &str, value: &str, base_dir: &Path) {
+    match key {
+        "confirm" => {
+            item.require_confirmation = is_truthy(value);
+            item.confirm_message =
+                (item.require_confirmation && !is_true_word(value)).then(|| value.to_string());
+        }
+        "confirm_text" => item.confirm_text = Some(value.to_string()),
+        "cwd" => item.cwd = Some(resolve_path(base_dir, value)),
+        "shell" => item.shell = Some(Shell::parse(value)),
+        "wait" | "pause" => item.wait = Some(is_truthy(value)),
+        "exit" => item.exit_after = is_truthy(value),
+        "background" => item.background = is_truthy(value),
+        "detach" => item.detach = Detach::parse(value),
+        "terminal" => item.terminal = shlex::split(value).filter(|words| !words.is_empty()),
+        "tmux" => item.tmux = TmuxTarget::parse(value),
+        "elevate" => item.elevate = is_truthy(value),
+        "disabled" => item.disabled = is_truthy(value),
+        "hidden" => item.hidden = is_truthy(value),
+        "enabled_if" => {
+            // El archivo de un `script:` se resuelve ahora, desde la carpeta del menú
+            item.enabled_if = Some(match value.strip_prefix("script:") {
+                Some(code) => format!("script:{}", resolve_script(base_dir, code).source()),
+                None => value.to_string(),
+            })
+        }
+        "output_filter" => {
+            let code = value.strip_prefix("script:").unwrap_or(value);
+            item.output_filter = Some(resolve_script(base_dir, code));
+        }
+        "status" => item.status = Some(value.to_string()),
+        "sort" => item.sort = Some(SortOrder::parse(value)),
+        "execution_mode" => item.execution_mode = Some(ExecutionMode::from_str(value)),
+        "cooldown" => item.cooldown = parse_duration(value),
+        "timeout" => item.timeout = parse_duration(value),
+        "on_success" => item.on_success = Some(FollowUp::parse(value)),
+        "on_failure" => item.on_failure = Some(FollowUp::parse(value)),
+        "schedule" => item.schedule = Some(value.to_string()),
+        // `\n` en el texto es un salto de línea: el flag tiene que entrar en una línea
+        "help" => item.help = Some(ItemHelp::Text(value.replace("\\n", "\n"))),
+        "help_file" => item.help = Some(ItemHelp::File(resolve_path(base_dir, value))),
+        // Se suma a lo que haya puesto `defaults:`; la misma variable se reemplaza
+        "env" => {
+            for (name, val) in parse_env(value) {
+                set_env(&mut item.env, &name, &val);
             }
         }
+        "run" => {
+            if let MenuAction::Dynamic { template, .. } = &mut item.action {
+                *template = Some(value.to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parsea una duración como `60s`, `5m`, `1h`, `250ms` o `90` (segundos).
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    match unit.trim() {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number * 60)),
+        "h" => Some(Duration::from_secs(number * 3600)),
+        _ => None,
+    }
+}
+
+/// Quita un par de comillas que envuelvan todo el valor (`"echo "hola""` -> `echo "hola"`).
+/// A diferencia de `trim_matches`, conserva las comillas propias del comando.
+/// Dentro de las comillas `\"` es una comilla y `\\` una barra, como las
+/// escribe `writer`; cualquier otra barra queda tal cual.
+fn unquote(s: &str) -> String {
+    let Some(inner) = s
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    else {
+        return s.to_string();
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next @ ('"' | '\\'))) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
     }
-    // No hay flag, default a false
-    (s, false)
+    out
+}
+
+/// Posiciones de los caracteres de `s` que están fuera de comillas, saltando
+/// las comillas escapadas (`\"`) de adentro.
+fn unquoted_chars(s: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    s.char_indices().filter(move |&(_, c)| {
+        if escaped {
+            escaped = false;
+            return false;
+        }
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ => return !in_quotes,
+        }
+        false
+    })
 }
 
 /// Busca el ':' separador fuera de comillas en una cadena.
@@ -174,35 +613,34 @@ fn extract_confirm_flag(s: &str) -> (&str, bool) {
 ///   `"Item": "echo algo"` -> Some(6)
 ///   `Submenu:` -> Some(7)
 fn find_separator_colon(s: &str) -> Option<usize> {
-    let mut in_quotes = false;
-
-    for (i, c) in s.char_indices() {
-        match c {
-            '"' => in_quotes = !in_quotes,
-            ':' if !in_quotes => return Some(i),
-            _ => {}
-        }
-    }
-    None
+    unquoted_chars(s).find(|&(_, c)| c == ':').map(|(i, _)| i)
 }
 
 /// Verifica si el ':' de la cadena es el ultimo caracter y esta fuera de comillas.
 fn ends_with_separator_colon(s: &str) -> bool {
-    find_separator_colon(s).map_or(false, |pos| {
+    find_separator_colon(s).is_some_and(|pos| {
         // El ':' debe ser el ultimo caracter (o solo seguido de espacios)
         s[pos + 1..].trim().is_empty()
     })
 }
 
+/// Ordena un nivel con `order` y cada submenú estático con el suyo propio o,
+/// si no tiene, con el global.
+pub(crate) fn sort_tree(items: &mut [MenuItem], order: SortOrder, global: SortOrder) {
+    order.apply(items);
+    for item in items {
+        let order = item.sort.unwrap_or(global);
+        if let MenuAction::OpenSubmenu(children) = &mut item.action {
+            sort_tree(Rc::make_mut(children).as_mut_slice(), order, global);
+        }
+    }
+}
+
 /// Saca el tope de la pila y lo inserta como submenu en el nivel superior
 /// o en los items raiz si la pila quedo vacia.
-fn pop_and_insert(stack: &mut Vec<(String, Vec<MenuItem>, usize)>, root: &mut Vec<MenuItem>) {
-    if let Some((name, items, _)) = stack.pop() {
-        let submenu = MenuItem {
-            label: name,
-            action: MenuAction::OpenSubmenu(items),
-            require_confirmation: false,
-        };
+fn pop_and_insert(stack: &mut Vec<(MenuItem, Vec<MenuItem>, usize)>, root: &mut Vec<MenuItem>) {
+    if let Some((mut submenu, items, _)) = stack.pop() {
+        submenu.action = MenuAction::OpenSubmenu(Rc::new(items));
 
         if let Some(parent) = stack.last_mut() {
             parent.1.push(submenu);
@@ -212,41 +650,162 @@ fn pop_and_insert(stack: &mut Vec<(String, Vec<MenuItem>, usize)>, root: &mut Ve
     }
 }
 
-/// Extrae todos los parámetros únicos `{{text: Etiqueta}}` de un comando.
-/// Si el mismo placeholder aparece más de una vez, se retorna una sola entrada.
+/// Parsea las asignaciones de `[env=...]`: `NOMBRE=valor` separadas por
+/// espacios, con comillas de shell para los valores que los contengan.
+/// Las palabras sin `=` se ignoran.
+pub fn parse_env(value: &str) -> Vec<(String, String)> {
+    let words = shlex::split(value)
+        .unwrap_or_else(|| value.split_whitespace().map(str::to_string).collect());
+    words
+        .iter()
+        .filter_map(|word| word.split_once('='))
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, val)| (name.to_string(), expand_home(val)))
+        .collect()
+}
+
+/// Define `name` en `env`, reemplazando el valor anterior si ya estaba.
+fn set_env(env: &mut Vec<(String, String)>, name: &str, value: &str) {
+    match env.iter_mut().find(|(n, _)| n == name) {
+        Some((_, v)) => *v = value.to_string(),
+        None => env.push((name.to_string(), value.to_string())),
+    }
+}
+
+/// Expande un `~` inicial (`~` o `~/...`) al home del usuario.
+fn expand_home(raw: &str) -> String {
+    match raw.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs::home_dir()
+            .map(|home| {
+                home.join(rest.trim_start_matches('/'))
+                    .display()
+                    .to_string()
+            })
+            .unwrap_or_else(|| raw.to_string()),
+        _ => raw.to_string(),
+    }
+}
+
+/// Resuelve una ruta escrita en el menú: expande `~` al home del usuario y
+/// toma las rutas relativas desde `base_dir` (la carpeta del archivo `.toon`).
+pub fn resolve_path(base_dir: &Path, raw: &str) -> PathBuf {
+    let raw = raw.trim().trim_matches('"');
+    let expanded = PathBuf::from(expand_home(raw));
+    if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
+    }
+}
+
+/// El código de un `script:`, con la ruta del archivo `.rhai` resuelta como
+/// las demás rutas del menú.
+fn resolve_script(base_dir: &Path, code: &str) -> Script {
+    match Script::parse(code) {
+        Script::File(path) => Script::File(resolve_path(base_dir, &path.to_string_lossy())),
+        inline => inline,
+    }
+}
+
+/// Extrae todos los parámetros únicos de un comando: `{{text: Etiqueta}}`,
+/// la forma corta `{input:Etiqueta}` o `{pick:comando}`. Si el mismo
+/// placeholder aparece más de una vez, se retorna una sola entrada.
 pub fn extract_params(cmd: &str) -> Vec<CommandParam> {
-    let mut seen: Vec<String> = Vec::new();
-    let mut params = Vec::new();
-    let mut rest = cmd;
-
-    while let Some(start) = rest.find("{{") {
-        rest = &rest[start + 2..];
-        if let Some(end) = rest.find("}}") {
-            let inner = rest[..end].trim();
-            rest = &rest[end + 2..];
-
-            // Solo manejar el tipo "text:" por ahora; extensible a otros tipos
-            if let Some(label) = inner.strip_prefix("text:") {
-                let label = label.trim().to_string();
-                let placeholder = format!("{{{{text: {}}}}}", label);
-
-                // Deduplicar: si ya vimos este placeholder, no agregarlo de nuevo
-                if !seen.contains(&placeholder) {
-                    seen.push(placeholder.clone());
-                    params.push(CommandParam { label, placeholder });
+    let mut params: Vec<CommandParam> = Vec::new();
+    let mut pos = 0;
+
+    while let Some((end, param)) = next_param(&cmd[pos..]) {
+        pos += end;
+        // Deduplicar: si ya vimos este placeholder, no agregarlo de nuevo
+        if !params.iter().any(|p| p.placeholder == param.placeholder) {
+            params.push(param);
+        }
+    }
+    params
+}
+
+/// Busca el próximo parámetro en `s` y retorna dónde termina (exclusivo) junto
+/// con el parámetro. Los `{{...}}` de otros tipos se saltean; un placeholder
+/// sin cerrar termina la búsqueda.
+fn next_param(s: &str) -> Option<(usize, CommandParam)> {
+    const SHORT: [&str; 2] = ["{input:", "{pick:"];
+    let mut from = 0;
+    loop {
+        let rest = &s[from..];
+        let start = from
+            + std::iter::once("{{")
+                .chain(SHORT)
+                .filter_map(|open| rest.find(open))
+                .min()?;
+
+        if let Some(open) = SHORT.iter().find(|open| s[start..].starts_with(**open)) {
+            let end = start + closing_brace(&s[start..])? + 1;
+            // El placeholder es el texto tal cual está en el comando, para reemplazarlo exacto
+            let placeholder = s[start..end].to_string();
+            let inner = s[start + open.len()..end - 1].trim().to_string();
+            let param = if *open == "{pick:" {
+                CommandParam {
+                    label: inner.clone(),
+                    placeholder,
+                    pick: Some(inner),
+                }
+            } else {
+                CommandParam {
+                    label: inner,
+                    placeholder,
+                    pick: None,
+                }
+            };
+            return Some((end, param));
+        }
+
+        let close = start + 2 + s[start + 2..].find("}}")?;
+        let inner = s[start + 2..close].trim();
+        if let Some(label) = inner.strip_prefix("text:") {
+            let param = CommandParam {
+                label: label.trim().to_string(),
+                placeholder: s[start..close + 2].to_string(),
+                pick: None,
+            };
+            return Some((close + 2, param));
+        }
+        from = close + 2;
+    }
+}
+
+/// Posición de la `}` que cierra la `{` inicial de `s`, contando las llaves
+/// anidadas (el comando de `{pick:...}` puede tener las suyas, como `awk '{print $1}'`).
+fn closing_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
                 }
             }
-        } else {
-            break; // '}}' no encontrado, malformado — ignorar el resto
+            _ => {}
         }
     }
-    params
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Extrae los flags de `s` y retorna (línea sin flags, require_confirmation).
+    fn extract_confirm_flag(s: &str) -> (&str, bool) {
+        let (line, flags) = extract_flags(s);
+        let mut item = MenuItem::new("test", MenuAction::Execute(line.to_string()));
+        for (key, value) in &flags {
+            apply_flag(&mut item, key, value, Path::new("."));
+        }
+        (line, item.require_confirmation)
+    }
+
     #[test]
     fn test_extract_confirm_flag_true() {
         let (line, flag) = extract_confirm_flag("cmd [confirm=true]");
@@ -289,6 +848,193 @@ mod tests {
         assert!(!flag);
     }
 
+    #[test]
+    fn test_confirm_flag_with_message() {
+        let mut item = MenuItem::new("Drop", MenuAction::Execute("dropdb app".to_string()));
+        apply_flag(&mut item, "confirm", "¿Borrar la base app?", Path::new("/"));
+        assert!(item.require_confirmation);
+        assert_eq!(
+            item.confirm_message.as_deref(),
+            Some("¿Borrar la base app?")
+        );
+
+        apply_flag(&mut item, "confirm", "true", Path::new("/"));
+        assert!(item.require_confirmation);
+        assert_eq!(item.confirm_message, None);
+    }
+
+    #[test]
+    fn test_help_flags() {
+        let mut item = MenuItem::new("Rebase", MenuAction::Execute("git rebase".to_string()));
+        apply_flag(&mut item, "help", "Línea 1\\nLínea 2", Path::new("/"));
+        assert_eq!(
+            item.help,
+            Some(ItemHelp::Text("Línea 1\nLínea 2".to_string()))
+        );
+
+        apply_flag(
+            &mut item,
+            "help_file",
+            "docs/rebase.md",
+            Path::new("/etc/menus"),
+        );
+        assert_eq!(
+            item.help,
+            Some(ItemHelp::File(PathBuf::from("/etc/menus/docs/rebase.md")))
+        );
+    }
+
+    #[test]
+    fn test_env_flag_and_block() {
+        let source = "env:\n    AWS_REGION: eu-west-1\n    EDITOR: \"nvim\"\n\"Menu\":\n    Pods: \"kubectl get pods\" [env=\"KUBECONFIG=/etc/kube/prod AWS_PROFILE=prod\"]\n";
+        let (config, _, items) = parse_toon_str(source, Path::new("/"));
+        assert_eq!(
+            config.env,
+            vec![
+                ("AWS_REGION".to_string(), "eu-west-1".to_string()),
+                ("EDITOR".to_string(), "nvim".to_string()),
+            ]
+        );
+        assert_eq!(
+            items[0].env,
+            vec![
+                ("KUBECONFIG".to_string(), "/etc/kube/prod".to_string()),
+                ("AWS_PROFILE".to_string(), "prod".to_string()),
+            ]
+        );
+
+        let env = parse_env("MSG='hola mundo' suelto HOME_DIR=~/x");
+        assert_eq!(env[0], ("MSG".to_string(), "hola mundo".to_string()));
+        assert_eq!(env.len(), 2);
+        assert!(env[1].1.ends_with("/x") && !env[1].1.starts_with('~'));
+    }
+
+    #[test]
+    fn test_extract_params_both_syntaxes() {
+        let cmd = "git push {input:Remoto} {{text:Rama}} {{otro: x}} | awk '{print $1}'";
+        let params = extract_params(cmd);
+        let found: Vec<(&str, &str)> = params
+            .iter()
+            .map(|p| (p.label.as_str(), p.placeholder.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("Remoto", "{input:Remoto}"), ("Rama", "{{text:Rama}}"),]
+        );
+
+        let params = extract_params("git checkout {pick:git branch | awk '{print $1}'}");
+        assert_eq!(params.len(), 1);
+        assert_eq!(
+            params[0].pick.as_deref(),
+            Some("git branch | awk '{print $1}'")
+        );
+        assert_eq!(
+            params[0].placeholder,
+            "{pick:git branch | awk '{print $1}'}"
+        );
+
+        // Placeholders repetidos se piden una sola vez; sin cierre no hay parámetro
+        assert_eq!(extract_params("echo {input:A} {input:A}").len(), 1);
+        assert!(extract_params("echo {input:A").is_empty());
+    }
+
+    #[test]
+    fn test_extract_flags_multiple() {
+        let (line, flags) = extract_flags("cat hosts [confirm=true, run=\"ssh {line}\"] [x=1]");
+        assert_eq!(line, "cat hosts");
+        assert_eq!(
+            flags,
+            vec![
+                ("confirm".to_string(), "true".to_string()),
+                ("run".to_string(), "ssh {line}".to_string()),
+                ("x".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_flags_keeps_non_flag_brackets() {
+        let (line, flags) = extract_flags("echo [1]");
+        assert_eq!(line, "echo [1]");
+        assert!(flags.is_empty());
+    }
+
+    #[test]
+    fn test_defaults_apply_unless_overridden() {
+        let path = std::env::temp_dir().join(format!("tmenu-defaults-{}.toon", std::process::id()));
+        fs::write(
+            &path,
+            "defaults:\n    confirm: true\n    cwd: /tmp\n\"Menu\":\n    A: \"echo a\"\n    B: \"echo b\" [confirm=false]\n",
+        )
+        .unwrap();
+
+        let (_, _, items) = parse_toon_file(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(items.len(), 2);
+        assert!(items[0].require_confirmation);
+        assert!(!items[1].require_confirmation);
+        assert_eq!(items[1].cwd, Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn test_defaults_accept_every_item_key() {
+        let source = "defaults:\n    shell: bash -lc\n    pause: false\n    confirm: \"¿Seguro?\"\n    cwd: /srv\n    terminal: kitty -e\n\"Menu\":\n    A: \"echo a\"\n    B: \"echo b\" [shell=none, pause=true, confirm=false, cwd=/tmp, terminal=foot]\n";
+        let (_, _, items) = parse_toon_str(source, Path::new("/"));
+
+        let a = &items[0];
+        assert_eq!(a.shell, Some(Shell::parse("bash -lc")));
+        assert_eq!(a.wait, Some(false));
+        assert!(a.require_confirmation);
+        assert_eq!(a.confirm_message.as_deref(), Some("¿Seguro?"));
+        assert_eq!(a.cwd, Some(PathBuf::from("/srv")));
+        assert_eq!(
+            a.terminal,
+            Some(vec!["kitty".to_string(), "-e".to_string()])
+        );
+
+        let b = &items[1];
+        assert_eq!(b.shell, Some(Shell::None));
+        assert_eq!(b.wait, Some(true));
+        assert!(!b.require_confirmation);
+        assert_eq!(b.cwd, Some(PathBuf::from("/tmp")));
+        assert_eq!(b.terminal, Some(vec!["foot".to_string()]));
+    }
+
+    #[test]
+    fn test_shebang_and_comments_are_ignored() {
+        let source = "#!/usr/bin/env tmenu\n# Nota: esto no es un ítem\n\"Deploy\":\n    # Otra: nota\n    Prod: \"./deploy.sh prod\"\n";
+        let (_, title, items) = parse_toon_str(source, Path::new("."));
+        assert_eq!(title, "Deploy");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "Prod");
+    }
+
+    #[test]
+    fn test_unquote_keeps_inner_quotes() {
+        assert_eq!(unquote("\"echo \"hola\"\""), "echo \"hola\"");
+        assert_eq!(unquote("echo hola"), "echo hola");
+    }
+
+    #[test]
+    fn test_resolve_path_relative_to_menu_dir() {
+        let path = resolve_path(Path::new("/etc/menus"), "scripts");
+        assert_eq!(path, PathBuf::from("/etc/menus/scripts"));
+    }
+
+    #[test]
+    fn test_resolve_path_absolute() {
+        let path = resolve_path(Path::new("/etc/menus"), "/opt/tools");
+        assert_eq!(path, PathBuf::from("/opt/tools"));
+    }
+
+    #[test]
+    fn test_resolve_path_home() {
+        let path = resolve_path(Path::new("/etc/menus"), "~/bin/tools");
+        assert!(path.is_absolute());
+        assert!(path.ends_with("bin/tools"));
+    }
+
     #[test]
     fn test_execution_mode_from_str_clean() {
         assert_eq!(ExecutionMode::from_str("clean"), ExecutionMode::Clean);
@@ -303,4 +1049,86 @@ mod tests {
     fn test_execution_mode_from_str_default() {
         assert_eq!(ExecutionMode::from_str("unknown"), ExecutionMode::Inherit);
     }
+
+    #[test]
+    fn test_shell_parse() {
+        assert_eq!(Shell::parse("none"), Shell::None);
+        assert_eq!(
+            Shell::parse("fish").command_line("mi_funcion arg"),
+            vec!["fish", "-c", "mi_funcion arg"]
+        );
+        assert_eq!(
+            Shell::parse("pwsh").command_line("Get-Date"),
+            vec!["pwsh", "-Command", "Get-Date"]
+        );
+        assert_eq!(
+            Shell::parse("powershell.exe").command_line("Get-Service | Where Status -eq Running"),
+            vec![
+                "powershell.exe",
+                "-Command",
+                "Get-Service | Where Status -eq Running"
+            ]
+        );
+        assert_eq!(
+            Shell::parse("bash -lc").command_line("ls"),
+            vec!["bash", "-lc", "ls"]
+        );
+        assert_eq!(
+            Shell::None.command_line("echo \"hola mundo\""),
+            vec!["echo", "hola mundo"]
+        );
+    }
+
+    #[test]
+    fn test_hotkeys() {
+        assert_eq!(split_hotkey("\"Deploy\"[2]"), ("\"Deploy\"", Some('2')));
+        assert_eq!(split_hotkey("Logs [l]"), ("Logs", Some('l')));
+        assert_eq!(split_hotkey("Docs [v2]"), ("Docs [v2]", None));
+        assert_eq!(split_hotkey("[x]"), ("[x]", None));
+
+        let (_, _, items) = parse_toon_str(
+            "\"Menu\":\n    \"Deploy\"[2]: \"./deploy.sh\"\n    Git [g]:\n        Status: \"git status\"\n",
+            Path::new("/"),
+        );
+        assert_eq!(items[0].label, "Deploy");
+        assert_eq!(items[0].hotkey, Some('2'));
+        assert_eq!(items[1].label, "Git");
+        assert_eq!(items[1].hotkey, Some('g'));
+    }
+
+    #[test]
+    fn test_sort_alpha_globally_and_per_submenu() {
+        let labels = |items: &[MenuItem]| -> Vec<String> {
+            items.iter().map(|item| item.label.clone()).collect()
+        };
+        let toon = "\"Menu\":\n    zeta: \"z\"\n    Beta: [sort=alpha]\n        web: \"w\"\n        Api: \"a\"\n    alfa: \"a\"\n";
+        let (_, _, items) = parse_toon_str(toon, Path::new("/"));
+        assert_eq!(labels(&items), ["zeta", "Beta", "alfa"]);
+        let MenuAction::OpenSubmenu(children) = &items[1].action else {
+            panic!("Beta es un submenú");
+        };
+        assert_eq!(labels(children), ["Api", "web"]);
+
+        // El `sort:` global ordena todos los niveles salvo los que piden `file`
+        let toon = toon.replace("[sort=alpha]", "[sort=file]");
+        let (config, _, items) = parse_toon_str(
+            &format!("config:\n    sort: alpha\n{}", toon),
+            Path::new("/"),
+        );
+        assert_eq!(config.sort, SortOrder::Alpha);
+        assert_eq!(labels(&items), ["alfa", "Beta", "zeta"]);
+        let MenuAction::OpenSubmenu(children) = &items[1].action else {
+            panic!("Beta es un submenú");
+        };
+        assert_eq!(labels(children), ["web", "Api"]);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("60s"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("un rato"), None);
+    }
 }
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+/// Nombre del archivo de menú que se busca cuando no se indica uno.
+pub const DEFAULT_MENU_FILE: &str = "tmenu.toon";
+
+/// Carpetas de configuración de tmenu, en orden de prioridad:
+/// `$XDG_CONFIG_HOME/tmenu` y la carpeta de configuración de la plataforma
+/// (`~/.config/tmenu` en Linux, `~/Library/Application Support/tmenu` en macOS,
+/// `%APPDATA%\tmenu` en Windows).
+pub fn config_dirs() -> Vec<PathBuf> {
+    let mut dirs_found = Vec::new();
+    if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        dirs_found.push(PathBuf::from(xdg).join("tmenu"));
+    }
+    if let Some(platform) = dirs::config_dir() {
+        let dir = platform.join("tmenu");
+        if !dirs_found.contains(&dir) {
+            dirs_found.push(dir);
+        }
+    }
+    dirs_found
+}
+
+/// Archivo de menú a usar cuando no se pasa ninguno por línea de comandos:
+/// el primero que exista entre las carpetas de configuración y, por último,
+/// `./tmenu.toon`. Si ninguno existe se retorna `./tmenu.toon` para que el
+/// error indique el archivo esperado en el directorio actual.
+pub fn default_menu_file() -> PathBuf {
+    config_dirs()
+        .into_iter()
+        .map(|dir| dir.join(DEFAULT_MENU_FILE))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_MENU_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_dirs_end_with_tmenu() {
+        for dir in config_dirs() {
+            assert!(dir.ends_with("tmenu"));
+        }
+    }
+
+    #[test]
+    fn test_default_menu_file_name() {
+        assert!(default_menu_file().ends_with(DEFAULT_MENU_FILE));
+    }
+}
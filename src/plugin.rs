@@ -0,0 +1,221 @@
+//! Protocolo de plugins: un ítem `plugin:comando` abre un submenú con los
+//! ítems que devuelve un programa externo, sin cambiar tmenu.
+//!
+//! Al abrir el submenú, tmenu ejecuta `comando` y le escribe en la entrada
+//! estándar una línea JSON con el pedido:
+//!
+//! ```json
+//! {"tmenu": 1, "request": "items"}
+//! ```
+//!
+//! El plugin responde por la salida estándar con los ítems, o con un error:
+//!
+//! ```json
+//! {"items": [
+//!   {"label": "Reiniciar nginx", "command": "systemctl restart nginx", "confirm": true},
+//!   {"label": "Abrir panel", "action": "open-url", "data": "https://grafana.local"},
+//!   {"label": "Logs", "items": [{"label": "nginx", "command": "journalctl -u nginx"}]}
+//! ]}
+//! {"error": "no hay conexión con el cluster"}
+//! ```
+//!
+//! Cada ítem tiene `label` y uno de:
+//! - `command`: un comando que tmenu ejecuta como cualquier otro.
+//! - `items`: un submenú con más ítems.
+//! - `action` (y opcionalmente `data`): un tipo de acción propio del plugin.
+//!   Al elegirlo, tmenu vuelve a ejecutar el plugin con la terminal, como un
+//!   comando más, y le pasa la acción en las variables `TMENU_PLUGIN_ACTION`
+//!   y `TMENU_PLUGIN_DATA`; la entrada estándar queda libre para el usuario.
+//!
+//! Opcionales: `help` (texto), `confirm` (`true` o el texto del diálogo) y
+//! `disabled`.
+use serde_json::{Value, json};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+use crate::error::TmenuError;
+use crate::model::{ItemHelp, MenuAction, MenuItem};
+
+/// Versión del protocolo que se envía en cada pedido.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Ejecuta el plugin `command` y arma los ítems de su respuesta.
+pub fn plugin_items(command: &str) -> Result<Vec<MenuItem>, TmenuError> {
+    let parts = shlex::split(command)
+        .ok_or_else(|| TmenuError::SubmenuError(format!("comando mal formado: {}", command)))?;
+    let Some((bin, args)) = parts.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    let mut child = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| TmenuError::SubmenuError(format!("'{}': {}", bin, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Un plugin que no lee el pedido cierra la tubería: no es un error
+        let request = json!({"tmenu": PROTOCOL_VERSION, "request": "items"});
+        let _ = writeln!(stdin, "{}", request);
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| TmenuError::SubmenuError(format!("'{}': {}", bin, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().next().unwrap_or("").trim();
+        return Err(TmenuError::SubmenuError(format!(
+            "plugin '{}' terminó con {} {}",
+            command, output.status, detail
+        )));
+    }
+    parse_response(command, &String::from_utf8_lossy(&output.stdout))
+        .map_err(|e| TmenuError::SubmenuError(format!("plugin '{}': {}", command, e)))
+}
+
+/// Ítems de la respuesta `text` del plugin `command`.
+fn parse_response(command: &str, text: &str) -> Result<Vec<MenuItem>, String> {
+    let response: Value =
+        serde_json::from_str(text).map_err(|e| format!("JSON inválido: {}", e))?;
+    let Value::Object(fields) = &response else {
+        return Err("la respuesta no es un objeto JSON".to_string());
+    };
+    if let Some(error) = fields.get("error") {
+        return Err(error
+            .as_str()
+            .unwrap_or("error sin descripción")
+            .to_string());
+    }
+    match fields.get("items") {
+        Some(items) => items_from(command, items),
+        None => Err("la respuesta no tiene \"items\"".to_string()),
+    }
+}
+
+fn items_from(command: &str, items: &Value) -> Result<Vec<MenuItem>, String> {
+    let Value::Array(items) = items else {
+        return Err("\"items\" no es una lista".to_string());
+    };
+    items.iter().map(|item| item_from(command, item)).collect()
+}
+
+fn item_from(command: &str, item: &Value) -> Result<MenuItem, String> {
+    let Value::Object(fields) = item else {
+        return Err("un ítem no es un objeto JSON".to_string());
+    };
+    let text = |key: &str| fields.get(key).and_then(Value::as_str);
+    let label = text("label").ok_or("un ítem no tiene \"label\"")?;
+
+    let mut env = Vec::new();
+    let action = if let Some(cmd) = text("command") {
+        MenuAction::Execute(cmd.to_string())
+    } else if let Some(children) = fields.get("items") {
+        MenuAction::OpenSubmenu(Rc::new(items_from(command, children)?))
+    } else if let Some(action) = text("action") {
+        env.push(("TMENU_PLUGIN_ACTION".to_string(), action.to_string()));
+        env.push((
+            "TMENU_PLUGIN_DATA".to_string(),
+            text("data").unwrap_or_default().to_string(),
+        ));
+        MenuAction::Execute(command.to_string())
+    } else {
+        return Err(format!(
+            "el ítem '{}' no tiene \"command\", \"items\" ni \"action\"",
+            label
+        ));
+    };
+
+    let mut menu_item = MenuItem::new(label, action);
+    menu_item.env = env;
+    menu_item.help = text("help").map(|help| ItemHelp::Text(help.to_string()));
+    match fields.get("confirm") {
+        Some(Value::Bool(confirm)) => menu_item.require_confirmation = *confirm,
+        Some(Value::String(message)) => {
+            menu_item.require_confirmation = true;
+            menu_item.confirm_message = Some(message.clone());
+        }
+        _ => {}
+    }
+    menu_item.disabled = matches!(fields.get("disabled"), Some(Value::Bool(true)));
+    Ok(menu_item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response_builds_items() {
+        let items = parse_response(
+            "tmenu-k8s --prod",
+            r#"{"items": [
+                {"label": "Reiniciar", "command": "kubectl rollout restart", "confirm": "¿Seguro?"},
+                {"label": "Panel é 🚀", "action": "open-url", "data": "https://x", "disabled": true},
+                {"label": "Pods", "items": [{"label": "web", "command": "kubectl logs web"}], "n": [1, -2.5e1, null]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(
+            matches!(&items[0].action, MenuAction::Execute(cmd) if cmd == "kubectl rollout restart")
+        );
+        assert!(items[0].require_confirmation);
+        assert_eq!(items[0].confirm_message.as_deref(), Some("¿Seguro?"));
+
+        assert_eq!(items[1].label, "Panel é 🚀");
+        assert!(matches!(&items[1].action, MenuAction::Execute(cmd) if cmd == "tmenu-k8s --prod"));
+        assert_eq!(
+            items[1].env,
+            vec![
+                ("TMENU_PLUGIN_ACTION".to_string(), "open-url".to_string()),
+                ("TMENU_PLUGIN_DATA".to_string(), "https://x".to_string()),
+            ]
+        );
+        assert!(items[1].disabled);
+
+        let MenuAction::OpenSubmenu(children) = &items[2].action else {
+            panic!("se esperaba un submenú");
+        };
+        assert_eq!(children[0].label, "web");
+
+        assert_eq!(
+            parse_response("p", r#"{"error": "sin conexión"}"#).unwrap_err(),
+            "sin conexión"
+        );
+        assert!(parse_response("p", r#"{"items": [{"label": "x"}]}"#).is_err());
+        assert!(parse_response("p", r#"{"items": [}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_checks_unicode_escapes() {
+        let items = parse_response(
+            "p",
+            r#"{"items": [{"label": "\u00e9 \ud83d\ude80", "command": "x"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(items[0].label, "é 🚀");
+        // Sustitutos sin su par o con un segundo que no es bajo
+        for label in [r#""\ud83d""#, r#""\ud83d\u0041""#, r#""\ude80""#] {
+            let response = format!(r#"{{"items": [{{"label": {}, "command": "x"}}]}}"#, label);
+            let error = parse_response("p", &response).unwrap_err();
+            assert!(error.starts_with("JSON inválido"), "{}", error);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_items_sends_request_on_stdin() {
+        // El plugin responde solo si recibió el pedido de ítems
+        let items = plugin_items(
+            r#"sh -c 'read -r req; case "$req" in *'"'"'"request":"items"'"'"'*) echo "{\"items\": [{\"label\": \"ok\", \"command\": \"true\"}]}";; esac'"#,
+        )
+        .unwrap();
+        assert_eq!(items[0].label, "ok");
+
+        let result = plugin_items("sh -c 'exit 3'");
+        assert!(matches!(result, Err(TmenuError::SubmenuError(_))));
+    }
+}
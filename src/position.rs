@@ -0,0 +1,119 @@
+//! Último lugar de cada menú entre sesiones, en
+//! `~/.local/share/tmenu/positions.log`: al volver a abrir un archivo de menú
+//! se entra en el submenú donde se salió, con el mismo ítem seleccionado.
+//! Cada línea es `archivo<TAB>etiqueta<TAB>etiqueta…`, la más reciente primero.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::TmenuError;
+
+/// Menús cuya posición se recuerda.
+pub const POSITIONS_LIMIT: usize = 100;
+
+/// Retorna la ruta al archivo de posiciones: `~/.local/share/tmenu/positions.log`
+fn positions_file_path() -> Result<PathBuf, TmenuError> {
+    let home = dirs::home_dir().ok_or_else(|| {
+        TmenuError::PositionError("No se pudo determinar el directorio home".to_string())
+    })?;
+    Ok(home.join(".local/share/tmenu/positions.log"))
+}
+
+/// Clave de un archivo de menú: su ruta absoluta, para que abrirlo desde otra
+/// carpeta o con otra ruta relativa encuentre la misma posición.
+fn menu_key(menu: &Path) -> String {
+    fs::canonicalize(menu)
+        .unwrap_or_else(|_| menu.to_path_buf())
+        .display()
+        .to_string()
+}
+
+fn read_entries(path: &Path) -> Result<Vec<(String, Vec<String>)>, TmenuError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(parse(&content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(TmenuError::PositionError(format!(
+            "No se pudo leer {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Camino de etiquetas (submenús y el ítem seleccionado) donde quedó `menu`,
+/// o `None` si nunca se guardó.
+pub fn load(menu: &Path) -> Result<Option<Vec<String>>, TmenuError> {
+    let key = menu_key(menu);
+    Ok(read_entries(&positions_file_path()?)?
+        .into_iter()
+        .find(|(file, _)| *file == key)
+        .map(|(_, labels)| labels))
+}
+
+/// Guarda `labels` como la posición de `menu`.
+pub fn save(menu: &Path, labels: &[String]) -> Result<(), TmenuError> {
+    let path = positions_file_path()?;
+    let mut entries = read_entries(&path)?;
+    let key = menu_key(menu);
+    entries.retain(|(file, _)| *file != key);
+    entries.insert(0, (key, labels.to_vec()));
+    entries.truncate(POSITIONS_LIMIT);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| {
+            TmenuError::PositionError(format!("No se pudo crear directorio: {}", e))
+        })?;
+    }
+    fs::write(&path, format(&entries)).map_err(|e| {
+        TmenuError::PositionError(format!("No se pudo escribir {}: {}", path.display(), e))
+    })
+}
+
+/// Entradas del archivo; las líneas sin etiquetas se ignoran.
+fn parse(content: &str) -> Vec<(String, Vec<String>)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let file = fields.next()?.to_string();
+            let labels: Vec<String> = fields.map(str::to_string).collect();
+            (!labels.is_empty()).then_some((file, labels))
+        })
+        .collect()
+}
+
+fn format(entries: &[(String, Vec<String>)]) -> String {
+    entries
+        .iter()
+        .map(|(file, labels)| {
+            std::iter::once(file)
+                .chain(labels)
+                .map(|field| field.replace(['\t', '\n'], " "))
+                .collect::<Vec<_>>()
+                .join("\t")
+                + "\n"
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions_round_trip() {
+        let entries = vec![
+            (
+                "/home/ana/ops.toon".to_string(),
+                vec!["Deploy".to_string(), "Staging".to_string()],
+            ),
+            ("/srv/tmenu.toon".to_string(), vec!["Logs\tapp".to_string()]),
+        ];
+        let content = format(&entries);
+        assert_eq!(
+            content,
+            "/home/ana/ops.toon\tDeploy\tStaging\n/srv/tmenu.toon\tLogs app\n"
+        );
+        assert_eq!(parse(&content)[0], entries[0]);
+        assert!(parse("/sin/etiquetas.toon\n").is_empty());
+    }
+}
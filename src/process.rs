@@ -0,0 +1,162 @@
+//! Límite de tiempo de los comandos (`[timeout=30s]`).
+//!
+//! Un comando con límite se lanza en su propio grupo de procesos: al vencer
+//! se termina el grupo entero, así no quedan vivos los procesos que haya
+//! lanzado (un `sh -c` con hijos, un `make` con sus compiladores). En primer
+//! plano el grupo recibe la terminal, para que el comando pueda leer de ella.
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Cada cuánto se revisa si el proceso terminó.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Tiempo que se le da al grupo para cerrar tras SIGTERM antes del SIGKILL.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// Lanza el comando en un grupo de procesos propio. Con `foreground` el grupo
+/// pasa a ser el dueño de la terminal (como hace un shell con sus trabajos).
+#[cfg(unix)]
+pub fn isolate(command: &mut Command, foreground: bool) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+    if foreground && unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        // SAFETY: solo llamadas async-signal-safe entre fork y exec
+        unsafe {
+            command.pre_exec(|| {
+                // Un proceso en segundo plano recibe SIGTTOU al tomar la terminal
+                libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+                libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpid());
+                libc::signal(libc::SIGTTOU, libc::SIG_DFL);
+                Ok(())
+            });
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn isolate(_command: &mut Command, _foreground: bool) {}
+
+/// Devuelve la terminal al grupo de tmenu después de un comando lanzado con
+/// `isolate(.., true)`.
+#[cfg(unix)]
+pub fn reclaim_terminal() {
+    // SAFETY: llamadas a libc sin punteros; SIGTTOU se ignora mientras tanto
+    unsafe {
+        if libc::isatty(libc::STDIN_FILENO) != 1 {
+            return;
+        }
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn reclaim_terminal() {}
+
+/// Espera a que `child` termine. Si pasa `timeout` se termina su grupo y
+/// retorna `Ok(None)`. Sin límite es un `wait` común.
+pub fn wait_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> io::Result<Option<ExitStatus>> {
+    let Some(timeout) = timeout else {
+        return child.wait().map(Some);
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            kill_tree(child);
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Como `Command::output`, con límite de tiempo: stdout, stderr y el estado
+/// de salida (`None` si se venció el límite).
+pub fn output_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<(Vec<u8>, Vec<u8>, Option<ExitStatus>)> {
+    if timeout.is_some() {
+        isolate(command, false);
+    }
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Los pipes se leen en paralelo: si se llena uno, el hijo se bloquea
+    let stdout = child.stdout.take().map(read_all);
+    let stderr = child.stderr.take().map(read_all);
+    let status = wait_timeout(&mut child, timeout)?;
+    let join = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    };
+    Ok((join(stdout), join(stderr), status))
+}
+
+fn read_all(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// Termina `child` y su grupo de procesos: SIGTERM, y SIGKILL si no cerró
+/// en `KILL_GRACE`. Si el hijo no lidera un grupo propio se termina solo él.
+pub fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        let group = -(child.id() as libc::pid_t);
+        // SAFETY: kill no toma punteros
+        if unsafe { libc::kill(group, libc::SIGTERM) } == 0 {
+            let deadline = Instant::now() + KILL_GRACE;
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    // El líder terminó; los demás del grupo pueden seguir vivos
+                    unsafe { libc::kill(group, libc::SIGKILL) };
+                    return;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            unsafe { libc::kill(group, libc::SIGKILL) };
+            let _ = child.wait();
+            return;
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_timeout_kills_group() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo antes; sleep 30 & sleep 30; echo despues"]);
+        let started = Instant::now();
+        let (stdout, _, status) =
+            output_timeout(&mut command, Some(Duration::from_millis(200))).unwrap();
+        assert!(status.is_none());
+        assert_eq!(stdout, b"antes\n");
+        // El `sleep` en segundo plano también se terminó: si no, el pipe
+        // seguiría abierto y la lectura esperaría los 30 segundos
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "exit 2"]);
+        let (_, _, status) = output_timeout(&mut command, Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(status.and_then(|s| s.code()), Some(2));
+    }
+}
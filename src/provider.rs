@@ -0,0 +1,436 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::error::TmenuError;
+use crate::history;
+use crate::model::{MenuAction, MenuItem, MenuItems, Shell};
+use crate::parser::parse_toon_file;
+
+/// Genera los ítems de un submenú `dir:` con los archivos ejecutables de `dir`,
+/// ordenados por nombre. Cada ítem ejecuta el script correspondiente.
+///
+/// Los archivos ocultos (que empiezan con `.`) y los subdirectorios se ignoran.
+pub fn directory_items(dir: &Path) -> Result<Vec<MenuItem>, TmenuError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| TmenuError::SubmenuError(format!("{}: {}", dir.display(), e)))?;
+
+    let mut scripts: Vec<(String, String)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| is_executable(&entry.path()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                return None;
+            }
+            let path = entry.path().to_string_lossy().into_owned();
+            // Citar la ruta para que shlex la trate como un solo argumento
+            let cmd = shlex::try_quote(&path).ok()?.into_owned();
+            Some((name, cmd))
+        })
+        .collect();
+    scripts.sort();
+
+    Ok(scripts
+        .into_iter()
+        .map(|(name, cmd)| MenuItem::new(name, MenuAction::Execute(cmd)))
+        .collect())
+}
+
+/// Genera los ítems de un submenú `history:` con los últimos `limit` comandos
+/// distintos del historial, el más reciente primero. La etiqueta muestra
+/// cuándo se ejecutó y cómo terminó.
+pub fn history_items(limit: usize) -> Result<Vec<MenuItem>, TmenuError> {
+    let mut seen = std::collections::HashSet::new();
+    Ok(history::read_entries()?
+        .into_iter()
+        .filter(|entry| seen.insert(entry.cmd.clone()))
+        .take(limit)
+        .map(|entry| {
+            let status = match entry.code {
+                Some(0) => "✓".to_string(),
+                Some(code) => format!("✗ {}", code),
+                None => "·".to_string(),
+            };
+            let label = format!("{}  {}  {}", entry.timestamp, status, entry.cmd);
+            MenuItem::new(label, MenuAction::Execute(entry.cmd))
+        })
+        .collect())
+}
+
+/// Carga los ítems raíz de otro archivo de menú para un ítem `menu:`.
+/// El archivo se parsea recién al abrir el submenú, así que los cambios
+/// en el archivo enlazado se ven la próxima vez que se entra.
+pub fn menu_file_items(path: &Path) -> Result<MenuItems, TmenuError> {
+    let (_, _, items) = parse_toon_file(path)
+        .map_err(|e| TmenuError::SubmenuError(format!("{}: {}", path.display(), e)))?;
+    Ok(items)
+}
+
+/// Genera los ítems de un submenú `list:` ejecutando `source` con `shell`,
+/// como los comandos de los ítems, y tomando cada línea no vacía de su
+/// salida estándar como una entrada.
+///
+/// Con `template`, cada entrada ejecuta el template con `{line}` reemplazado por
+/// la línea (citada para que sea un solo argumento); sin template, la línea
+/// misma es el comando a ejecutar.
+pub fn command_items(
+    source: &str,
+    template: Option<&str>,
+    shell: &Shell,
+) -> Result<Vec<MenuItem>, TmenuError> {
+    let parts = shell.command_line(source);
+    let Some((bin, args)) = parts.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    // stdin/stderr no pueden heredarse: la TUI está ocupando la terminal
+    let output = Command::new(bin)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| TmenuError::SubmenuError(format!("'{}': {}", bin, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().next().unwrap_or("").trim();
+        return Err(TmenuError::SubmenuError(format!(
+            "'{}' terminó con {} {}",
+            source, output.status, detail
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let cmd = match template {
+                Some(template) => {
+                    let quoted = shlex::try_quote(line)
+                        .map(|q| q.into_owned())
+                        .unwrap_or_else(|_| line.to_string());
+                    template.replace("{line}", &quoted)
+                }
+                None => line.to_string(),
+            };
+            MenuItem::new(line, MenuAction::Execute(cmd))
+        })
+        .collect())
+}
+
+/// Niveles de `Include` que se siguen en `~/.ssh/config`, para no entrar en
+/// un ciclo.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Genera los ítems de un submenú `ssh:` con los hosts de `~/.ssh/config`
+/// (y de los archivos de sus `Include`) en el orden del archivo, seguidos de
+/// los de `~/.ssh/known_hosts` ordenados, sin repetir. Cada ítem ejecuta
+/// `ssh host`.
+///
+/// Los patrones (`Host *`, `!host`) y las entradas cifradas de known_hosts
+/// (`HashKnownHosts yes`) se ignoran.
+pub fn ssh_items() -> Result<Vec<MenuItem>, TmenuError> {
+    let home = dirs::home_dir().ok_or_else(|| {
+        TmenuError::SubmenuError("No se pudo determinar el directorio home".to_string())
+    })?;
+    ssh_dir_items(&home.join(".ssh"))
+}
+
+fn ssh_dir_items(dir: &Path) -> Result<Vec<MenuItem>, TmenuError> {
+    let quote = |host: &str| {
+        shlex::try_quote(host)
+            .map(|q| q.into_owned())
+            .unwrap_or_else(|_| host.to_string())
+    };
+    let mut config = Vec::new();
+    config_hosts(&dir.join("config"), dir, 0, &mut config);
+    let mut hosts: Vec<(String, String)> = config
+        .into_iter()
+        .map(|host| {
+            let cmd = format!("ssh {}", quote(&host));
+            (host, cmd)
+        })
+        .collect();
+
+    let content = fs::read_to_string(dir.join("known_hosts")).unwrap_or_default();
+    let mut known: Vec<(String, Option<&str>)> = known_hosts(&content);
+    known.sort();
+    for (host, port) in known {
+        let (label, cmd) = match port {
+            Some(port) => (
+                format!("{}:{}", host, port),
+                format!("ssh -p {} {}", quote(port), quote(&host)),
+            ),
+            None => (host.clone(), format!("ssh {}", quote(&host))),
+        };
+        if !hosts.iter().any(|(seen, _)| *seen == label) {
+            hosts.push((label, cmd));
+        }
+    }
+
+    if hosts.is_empty() {
+        return Err(TmenuError::SubmenuError(format!(
+            "no hay hosts en {} ni en {}",
+            dir.join("config").display(),
+            dir.join("known_hosts").display()
+        )));
+    }
+    Ok(hosts
+        .into_iter()
+        .map(|(label, cmd)| MenuItem::new(label, MenuAction::Execute(cmd)))
+        .collect())
+}
+
+/// Agrega a `hosts` los nombres de las líneas `Host` de `path` que no son
+/// patrones, siguiendo sus `Include`. Un archivo que no se puede leer se saltea.
+fn config_hosts(path: &Path, ssh_dir: &Path, depth: usize, hosts: &mut Vec<String>) {
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+    for line in content.lines() {
+        let line = line.trim();
+        // `Host web1 web2` o `Host=web1`
+        let (key, value) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let value = value.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+        match key.to_lowercase().as_str() {
+            "host" => {
+                for host in value.split_whitespace() {
+                    if !host.contains(['*', '?', '!']) && !hosts.iter().any(|h| h == host) {
+                        hosts.push(host.to_string());
+                    }
+                }
+            }
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in value.split_whitespace() {
+                    for file in include_files(pattern, ssh_dir) {
+                        config_hosts(&file, ssh_dir, depth + 1, hosts);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Archivos de un `Include`: relativo a `~/.ssh` salvo que sea absoluto o
+/// empiece con `~/`. Los comodines (`*`, `?`) solo se aceptan en el nombre
+/// del archivo, no en las carpetas.
+fn include_files(pattern: &str, ssh_dir: &Path) -> Vec<std::path::PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => return Vec::new(),
+        },
+        None => ssh_dir.join(pattern),
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(Ok(entries)) = path.parent().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let pattern: Vec<char> = name.chars().collect();
+    let mut files: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+            wildcard_match(&pattern, &name)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Si `name` cumple `pattern`, con `*` (cualquier texto) y `?` (un carácter).
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| wildcard_match(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && wildcard_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && wildcard_match(rest, &name[1..]),
+    }
+}
+
+/// Host (y puerto, si la entrada es `[host]:puerto`) de cada línea de
+/// known_hosts: el primer nombre de la lista, que es el que se escribió al
+/// conectarse; los demás suelen ser su IP. Las líneas `@revoked` son claves
+/// revocadas y no se ofrecen.
+fn known_hosts(content: &str) -> Vec<(String, Option<&str>)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut names = fields.next()?;
+            // `@cert-authority` va antes de los nombres
+            match names {
+                "@revoked" => return None,
+                _ if names.starts_with('@') => names = fields.next()?,
+                _ => {}
+            }
+            if names.starts_with(['#', '|']) {
+                return None;
+            }
+            let name = names.split(',').next()?;
+            if name.contains(['*', '?', '!']) {
+                return None;
+            }
+            Some(
+                match name
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.split_once("]:"))
+                {
+                    Some((host, port)) => (host.to_string(), Some(port)),
+                    None => (name.to_string(), None),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Verifica si `path` es un archivo que el usuario puede ejecutar.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// En sistemas sin bit de ejecución se listan todos los archivos.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_items_missing_dir() {
+        let result = directory_items(Path::new("/ruta/que/no/existe"));
+        assert!(matches!(result, Err(TmenuError::SubmenuError(_))));
+    }
+
+    #[test]
+    fn test_menu_file_items_loads_root_items() {
+        let path = std::env::temp_dir().join(format!("tmenu-link-{}.toon", std::process::id()));
+        fs::write(
+            &path,
+            "\"Otro\":\n    Hola: \"echo hola\"\n    Salir: exit\n",
+        )
+        .unwrap();
+
+        let items = menu_file_items(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].label, "Hola");
+        assert!(matches!(items[1].action, MenuAction::Quit));
+    }
+
+    #[test]
+    fn test_ssh_items_from_config_and_known_hosts() {
+        let dir = std::env::temp_dir().join(format!("tmenu-ssh-{}", std::process::id()));
+        fs::create_dir_all(dir.join("config.d")).unwrap();
+        fs::write(
+            dir.join("config"),
+            "Include config.d/*\n# Host comentado\nHost web1 web2\n    User deploy\nHost *.interno !bastion\nhost=db\n",
+        )
+        .unwrap();
+        fs::write(dir.join("config.d/trabajo"), "Host ci\n").unwrap();
+        fs::write(
+            dir.join("known_hosts"),
+            "web1,10.0.0.1 ssh-ed25519 AAAA\n[git.local]:2222 ssh-rsa AAAA\n|1|abc= ssh-rsa AAAA\n@cert-authority *.corp ssh-rsa AAAA\nalfa ssh-rsa AAAA\n",
+        )
+        .unwrap();
+
+        let items = ssh_dir_items(&dir).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["ci", "web1", "web2", "db", "alfa", "git.local:2222"]
+        );
+        assert!(matches!(&items[1].action, MenuAction::Execute(cmd) if cmd == "ssh web1"));
+        assert!(
+            matches!(&items[5].action, MenuAction::Execute(cmd) if cmd == "ssh -p 2222 git.local")
+        );
+        assert!(matches!(
+            ssh_dir_items(Path::new("/ruta/que/no/existe")),
+            Err(TmenuError::SubmenuError(_))
+        ));
+    }
+
+    #[test]
+    fn test_known_hosts_skips_revoked_keys() {
+        let hosts = known_hosts(
+            "@revoked viejo ssh-rsa AAAA\n@cert-authority ca.corp ssh-rsa AAAA\nviejo2 ssh-rsa AAAA\n",
+        );
+        assert_eq!(
+            hosts,
+            vec![("ca.corp".to_string(), None), ("viejo2".to_string(), None)]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_items_with_template() {
+        let items = command_items(
+            "printf 'web1\\n\\nweb 2\\n'",
+            Some("ssh {line}"),
+            &Shell::None,
+        )
+        .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].label, "web1");
+        assert!(matches!(&items[0].action, MenuAction::Execute(cmd) if cmd == "ssh web1"));
+        assert!(matches!(&items[1].action, MenuAction::Execute(cmd) if cmd == "ssh 'web 2'"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_items_failing_command() {
+        let result = command_items("false", None, &Shell::None);
+        assert!(matches!(result, Err(TmenuError::SubmenuError(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_items_run_through_the_shell() {
+        let source = "printf 'a\\nb\\n' | tr a-z A-Z";
+        let items = command_items(source, None, &Shell::parse("sh")).unwrap();
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["A", "B"]);
+        // Sin shell, `|` es un argumento más de printf
+        let items = command_items(source, None, &Shell::None).unwrap();
+        assert_eq!(items[0].label, "a");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory_items_lists_only_executables() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("tmenu-dir-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("deploy.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("notas.txt"), "no ejecutable").unwrap();
+
+        let items = directory_items(&dir).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "deploy.sh");
+    }
+}
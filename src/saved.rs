@@ -0,0 +1,119 @@
+//! Listas de ítems guardadas entre sesiones en `~/.local/share/tmenu/`: los
+//! favoritos (`favorites.log`, en el orden en que se marcaron) y los usados
+//! hace poco (`recent.log`, el más reciente primero). Cada línea es
+//! `etiqueta<TAB>comando`. Los archivos valen para todos los menús; cada uno
+//! muestra los ítems que encuentra en su árbol.
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::TmenuError;
+
+/// Ítems usados que se recuerdan, entre todos los menús.
+pub const RECENT_LIMIT: usize = 50;
+
+/// Una lista guardada.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum List {
+    Favorites,
+    Recent,
+}
+
+impl List {
+    fn file_name(self) -> &'static str {
+        match self {
+            List::Favorites => "favorites.log",
+            List::Recent => "recent.log",
+        }
+    }
+}
+
+/// Retorna la ruta al archivo de la lista: `~/.local/share/tmenu/<lista>.log`
+fn list_file_path(list: List) -> Result<PathBuf, TmenuError> {
+    let home = dirs::home_dir().ok_or_else(|| {
+        TmenuError::SavedItemsError("No se pudo determinar el directorio home".to_string())
+    })?;
+    Ok(home.join(".local/share/tmenu").join(list.file_name()))
+}
+
+/// Lee una lista, como claves de [`crate::app::item_key`]. Sin archivo, vacía.
+pub fn load(list: List) -> Result<Vec<String>, TmenuError> {
+    let path = list_file_path(list)?;
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(parse(&content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(TmenuError::SavedItemsError(format!(
+            "No se pudo leer {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Reemplaza el contenido de la lista por `keys`.
+pub fn save(list: List, keys: &[String]) -> Result<(), TmenuError> {
+    let path = list_file_path(list)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| {
+            TmenuError::SavedItemsError(format!("No se pudo crear directorio: {}", e))
+        })?;
+    }
+    fs::write(&path, format(keys)).map_err(|e| {
+        TmenuError::SavedItemsError(format!("No se pudo escribir {}: {}", path.display(), e))
+    })
+}
+
+/// Pone `key` primera en `recent`, sin repetirla, y descarta las que pasan
+/// de [`RECENT_LIMIT`].
+pub fn touch(recent: &mut Vec<String>, key: &str) {
+    recent.retain(|k| k != key);
+    recent.insert(0, key.to_string());
+    recent.truncate(RECENT_LIMIT);
+}
+
+/// Claves (`etiqueta\0comando`) de las líneas del archivo; las que no tienen
+/// tab se ignoran.
+fn parse(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(label, cmd)| format!("{}\0{}", label, cmd))
+        .collect()
+}
+
+fn format(keys: &[String]) -> String {
+    keys.iter()
+        .map(|key| key.replace(['\t', '\n'], " ").replacen('\0', "\t", 1) + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_list_round_trip() {
+        let keys = vec![
+            "Deploy\0kubectl apply -f k8s/".to_string(),
+            "Logs\0journalctl -f".to_string(),
+        ];
+        let content = format(&keys);
+        assert_eq!(
+            content,
+            "Deploy\tkubectl apply -f k8s/\nLogs\tjournalctl -f\n"
+        );
+        assert_eq!(parse(&content), keys);
+        assert_eq!(parse("sin tab\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_touch_moves_to_front() {
+        let mut recent = vec!["a".to_string(), "b".to_string()];
+        touch(&mut recent, "b");
+        assert_eq!(recent, vec!["b", "a"]);
+        for i in 0..RECENT_LIMIT {
+            touch(&mut recent, &i.to_string());
+        }
+        assert_eq!(recent.len(), RECENT_LIMIT);
+        assert_eq!(recent[0], (RECENT_LIMIT - 1).to_string());
+    }
+}
@@ -0,0 +1,522 @@
+//! Programación informativa de ítems (`[schedule=nightly 02:00]`).
+//!
+//! tmenu no ejecuta nada por su cuenta: el horario solo se muestra junto al
+//! ítem (próxima ejecución) y `tmenu schedule export` genera las entradas de
+//! crontab o de timers de systemd, para que el menú sea la única fuente de
+//! verdad de las tareas operativas.
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+use std::path::Path;
+
+use crate::commands::load_menu;
+use crate::error::TmenuError;
+use crate::model::{MenuAction, MenuItem, Shell};
+use crate::parser::{GlobalConfig, extract_params};
+use crate::timefmt::TimeFormat;
+use crate::writer::env_assignments;
+
+/// Formatos de `tmenu schedule export`.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Líneas de crontab
+    Crontab,
+    /// Pares de unidades `.service` + `.timer` de systemd
+    Systemd,
+}
+
+/// Expresión cron de 5 campos ya expandida a máscaras de bits.
+#[derive(Debug, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Campos día del mes / día de la semana tal como se escribieron (`*` = cualquiera)
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Traduce un horario legible a cron de 5 campos. Acepta cron directo
+/// (`0 2 * * *`) y las formas `hourly`, `daily|nightly [HH:MM]`,
+/// `weekly [día] [HH:MM]`, `monthly [HH:MM]` y `every 15m|2h`.
+pub fn to_cron(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|w| w.trim_start_matches('@').to_lowercase())
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    let time = |w: Option<&&str>| -> Option<(u32, u32)> {
+        let Some(w) = w else { return Some((0, 0)) };
+        let (h, m) = w.split_once(':')?;
+        let (h, m) = (h.parse().ok()?, m.parse().ok()?);
+        (h < 24 && m < 60).then_some((h, m))
+    };
+
+    let cron = match words.as_slice() {
+        [_, _, _, _, _] => words.join(" "),
+        ["hourly"] => "0 * * * *".to_string(),
+        ["daily" | "nightly", rest @ ..] if rest.len() <= 1 => {
+            let (h, m) = time(rest.first())?;
+            format!("{} {} * * *", m, h)
+        }
+        ["weekly", rest @ ..] if rest.len() <= 2 => {
+            let (day, rest) = match rest.first() {
+                Some(w) if !w.contains(':') => (weekday_number(w)?, &rest[1..]),
+                _ => (0, rest),
+            };
+            let (h, m) = time(rest.first())?;
+            format!("{} {} * * {}", m, h, day)
+        }
+        ["monthly", rest @ ..] if rest.len() <= 1 => {
+            let (h, m) = time(rest.first())?;
+            format!("{} {} 1 * *", m, h)
+        }
+        ["every", every] => {
+            let (n, unit) = every.split_at(every.len().checked_sub(1)?);
+            let n: u32 = n.parse().ok().filter(|n| *n > 0)?;
+            match unit {
+                "m" if n < 60 => format!("*/{} * * * *", n),
+                "h" if n < 24 => format!("0 */{} * * *", n),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Cron::parse(&cron).map(|_| cron)
+}
+
+/// Número de día de la semana (`mon` = 1, `sun` = 0) a partir del nombre.
+fn weekday_number(name: &str) -> Option<u32> {
+    let prefix = name.get(..3)?;
+    WEEKDAYS.iter().position(|d| *d == prefix).map(|i| i as u32)
+}
+
+impl Cron {
+    /// Parsea una expresión cron de 5 campos (`min hora día mes día_semana`).
+    pub fn parse(expr: &str) -> Option<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [min, hour, day, month, weekday] = fields[..] else {
+            return None;
+        };
+        Some(Cron {
+            minutes: parse_field(min, 0, 59)?,
+            hours: parse_field(hour, 0, 23)? as u32,
+            days: parse_field(day, 1, 31)? as u32,
+            months: parse_field(month, 1, 12)? as u16,
+            // 7 también es domingo
+            weekdays: {
+                let mask = parse_field(&weekday_names_to_numbers(weekday), 0, 7)?;
+                ((mask | (mask >> 7)) & 0x7f) as u8
+            },
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// Próximo minuto estrictamente posterior a `after` que cumple la expresión.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for offset in 0..=366 {
+            let date = start.date() + Duration::days(offset);
+            if !self.matches_date(
+                date.day(),
+                date.month(),
+                date.weekday().num_days_from_sunday(),
+            ) {
+                continue;
+            }
+            let first_hour = if offset == 0 { start.hour() } else { 0 };
+            for hour in first_hour..24 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+                let first_minute = if offset == 0 && hour == start.hour() {
+                    start.minute()
+                } else {
+                    0
+                };
+                if let Some(minute) = (first_minute..60).find(|m| self.minutes & (1 << m) != 0) {
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+        }
+        None
+    }
+
+    /// Como en cron, si se restringen día del mes y de la semana alcanza con uno.
+    fn matches_date(&self, day: u32, month: u32, weekday: u32) -> bool {
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        let day_ok = self.days & (1 << day) != 0;
+        let weekday_ok = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_ok || weekday_ok,
+            _ => day_ok && weekday_ok,
+        }
+    }
+
+    /// Expresión `OnCalendar=` equivalente para systemd.
+    ///
+    /// systemd combina día del mes y de la semana con AND (cron usa OR), así que
+    /// con ambos restringidos el timer resultante es más estricto que el cron.
+    pub fn on_calendar(&self) -> String {
+        let list = |mask: u64, min: u32, max: u32| -> String {
+            let all = (min..=max).all(|v| mask & (1 << v) != 0);
+            if all {
+                return "*".to_string();
+            }
+            (min..=max)
+                .filter(|v| mask & (1 << v) != 0)
+                .map(|v| format!("{:02}", v))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let weekdays = if self.any_weekday {
+            String::new()
+        } else {
+            let names: Vec<String> = (0..7)
+                .filter(|d| self.weekdays & (1 << d) != 0)
+                .map(|d| {
+                    let name = WEEKDAYS[d];
+                    name[..1].to_uppercase() + &name[1..]
+                })
+                .collect();
+            format!("{} ", names.join(","))
+        };
+        format!(
+            "{}*-{}-{} {}:{}:00",
+            weekdays,
+            list(self.months as u64, 1, 12),
+            list(self.days as u64, 1, 31),
+            list(self.hours as u64, 0, 23),
+            list(self.minutes, 0, 59)
+        )
+    }
+}
+
+/// Reemplaza `mon`..`sun` por su número en el campo día de la semana.
+fn weekday_names_to_numbers(field: &str) -> String {
+    let mut out = field.to_lowercase();
+    for (i, name) in WEEKDAYS.iter().enumerate() {
+        out = out.replace(name, &i.to_string());
+    }
+    out
+}
+
+/// Expande un campo cron (`*`, `5`, `1-5`, `*/15`, `0-30/10`, listas con coma)
+/// a una máscara de bits con los valores entre `min` y `max`.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((lo, hi)) => (lo.parse().ok()?, hi.parse().ok()?),
+                None => {
+                    let v = range.parse().ok()?;
+                    (v, if part.contains('/') { max } else { v })
+                }
+            },
+        };
+        if lo < min || hi > max || lo > hi {
+            return None;
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Some(mask)
+}
+
+/// Texto breve con la próxima ejecución de un ítem programado para mostrar en el menú:
+/// `hoy 02:00`, `mañana 02:00` o la fecha completa. `None` si el horario es inválido.
+pub fn next_run_label(schedule: &str, now: NaiveDateTime, format: &TimeFormat) -> Option<String> {
+    let next = Cron::parse(&to_cron(schedule)?)?.next_after(now)?;
+    let days = (next.date() - now.date()).num_days();
+    Some(match days {
+        0 => format!("hoy {}", format.time(next)),
+        1 => format!("mañana {}", format.time(next)),
+        _ => format.date_time(next),
+    })
+}
+
+/// Ítem programado encontrado al recorrer el menú.
+struct ScheduledItem<'a> {
+    /// Camino de etiquetas desde la raíz (`Backups › Nightly`)
+    path: String,
+    item: &'a MenuItem,
+    cmd: &'a str,
+    cron: String,
+}
+
+/// Recorre los submenús estáticos y junta los comandos con `schedule`.
+/// Los horarios inválidos o los comandos con parámetros (que necesitan a
+/// alguien que los complete) se informan como comentario en `skipped`.
+fn collect<'a>(
+    items: &'a [MenuItem],
+    parents: &mut Vec<&'a str>,
+    found: &mut Vec<ScheduledItem<'a>>,
+    skipped: &mut Vec<String>,
+) {
+    for item in items {
+        parents.push(&item.label);
+        match &item.action {
+            MenuAction::OpenSubmenu(children) => collect(children, parents, found, skipped),
+            MenuAction::Execute(cmd) => {
+                if let Some(schedule) = &item.schedule {
+                    let path = parents.join(" › ");
+                    match to_cron(schedule) {
+                        _ if !extract_params(cmd).is_empty() => {
+                            skipped.push(format!("{}: requiere parámetros", path))
+                        }
+                        _ if !item.steps.is_empty() => {
+                            skipped.push(format!("{}: tiene varios pasos", path))
+                        }
+                        Some(cron) => found.push(ScheduledItem {
+                            path,
+                            item,
+                            cmd,
+                            cron,
+                        }),
+                        None => skipped.push(format!("{}: horario inválido '{}'", path, schedule)),
+                    }
+                }
+            }
+            _ => {}
+        }
+        parents.pop();
+    }
+}
+
+/// Nombre de unidad a partir del camino: minúsculas, alfanuméricos y guiones.
+fn slug(path: &str) -> String {
+    let mut out = String::new();
+    for c in path.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.ends_with('-') && !out.is_empty() {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+/// Línea de comando para lanzar el ítem fuera de tmenu. Sin shell el comando
+/// queda tal cual (cron y systemd lo dividen en palabras igual que shlex).
+fn command_line(item: &MenuItem, cmd: &str, shell: &Shell) -> String {
+    match item.shell.as_ref().unwrap_or(shell) {
+        Shell::None => cmd.to_string(),
+        program => {
+            let parts = program.command_line(cmd);
+            shlex::try_join(parts.iter().map(String::as_str)).unwrap_or_else(|_| parts.join(" "))
+        }
+    }
+}
+
+/// Variables de entorno de un ítem programado: las globales de `env:` y
+/// luego las propias, que pisan a las del mismo nombre.
+fn item_env<'a>(item: &'a MenuItem, config: &'a GlobalConfig) -> Vec<(&'a str, &'a str)> {
+    let mut env: Vec<(&str, &str)> = Vec::new();
+    for (name, value) in config.env.iter().chain(&item.env) {
+        env.retain(|(n, _)| n != name);
+        env.push((name, value));
+    }
+    env
+}
+
+/// Genera las entradas de crontab o systemd para los ítems programados del menú.
+pub fn export(items: &[MenuItem], config: &GlobalConfig, format: ExportFormat) -> String {
+    let mut found = Vec::new();
+    let mut skipped = Vec::new();
+    collect(items, &mut Vec::new(), &mut found, &mut skipped);
+
+    let mut out = String::new();
+    for reason in &skipped {
+        out.push_str(&format!("# omitido: {}\n", reason));
+    }
+
+    for entry in &found {
+        let line = command_line(entry.item, entry.cmd, &config.shell);
+        let env = item_env(entry.item, config);
+        match format {
+            ExportFormat::Crontab => {
+                // En crontab `%` es un salto de línea
+                let mut line = line.replace('%', "\\%");
+                if !env.is_empty() {
+                    let assignments = env_assignments(env.iter().copied()).replace('%', "\\%");
+                    line = format!("env {} {}", assignments, line);
+                }
+                if let Some(cwd) = &entry.item.cwd {
+                    let dir = cwd.display().to_string();
+                    let dir = shlex::try_quote(&dir)
+                        .map(|q| q.into_owned())
+                        .unwrap_or(dir);
+                    line = format!("cd {} && {}", dir, line);
+                }
+                out.push_str(&format!(
+                    "# tmenu: {}\n{} {}\n",
+                    entry.path, entry.cron, line
+                ));
+            }
+            ExportFormat::Systemd => {
+                let name = format!("tmenu-{}", slug(&entry.path));
+                // En las unidades `%` introduce especificadores
+                let line = line.replace('%', "%%");
+                let cwd = entry
+                    .item
+                    .cwd
+                    .as_ref()
+                    .map(|dir| format!("WorkingDirectory={}\n", dir.display()))
+                    .unwrap_or_default();
+                let environment: String = env
+                    .iter()
+                    .map(|(name, value)| {
+                        format!("Environment=\"{}={}\"\n", name, value.replace('%', "%%"))
+                    })
+                    .collect();
+                let calendar = Cron::parse(&entry.cron)
+                    .map(|cron| cron.on_calendar())
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "# ===== {name}.service =====\n\
+                     [Unit]\n\
+                     Description=tmenu: {path}\n\n\
+                     [Service]\n\
+                     Type=oneshot\n\
+                     {cwd}{environment}ExecStart={line}\n\n\
+                     # ===== {name}.timer =====\n\
+                     [Unit]\n\
+                     Description=tmenu: {path} ({schedule})\n\n\
+                     [Timer]\n\
+                     OnCalendar={calendar}\n\
+                     Persistent=true\n\n\
+                     [Install]\n\
+                     WantedBy=timers.target\n\n",
+                    path = entry.path,
+                    schedule = entry.item.schedule.as_deref().unwrap_or_default(),
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// `tmenu schedule export`: imprime las entradas de los ítems programados.
+pub fn print_export(menu_file: &Path, format: ExportFormat) -> Result<(), TmenuError> {
+    let (config, _, items) = load_menu(menu_file)?;
+    print!("{}", export(&items, &config, format));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_toon_str;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_to_cron_shorthands() {
+        assert_eq!(to_cron("nightly 02:00").as_deref(), Some("0 2 * * *"));
+        assert_eq!(to_cron("weekly mon 09:30").as_deref(), Some("30 9 * * 1"));
+        assert_eq!(to_cron("every 15m").as_deref(), Some("*/15 * * * *"));
+        assert_eq!(to_cron("@hourly").as_deref(), Some("0 * * * *"));
+        assert_eq!(to_cron("0 3 * * 1-5").as_deref(), Some("0 3 * * 1-5"));
+        assert_eq!(to_cron("nightly 25:00"), None);
+        assert_eq!(to_cron("a veces"), None);
+    }
+
+    #[test]
+    fn test_next_after() {
+        let cron = Cron::parse("0 2 * * *").unwrap();
+        assert_eq!(
+            cron.next_after(at(2026, 10, 14, 1, 30)),
+            Some(at(2026, 10, 14, 2, 0))
+        );
+        assert_eq!(
+            cron.next_after(at(2026, 10, 14, 2, 0)),
+            Some(at(2026, 10, 15, 2, 0))
+        );
+
+        // 2026-10-14 es miércoles: el próximo lunes es el 19
+        let monday = Cron::parse("30 9 * * mon").unwrap();
+        assert_eq!(
+            monday.next_after(at(2026, 10, 14, 12, 0)),
+            Some(at(2026, 10, 19, 9, 30))
+        );
+    }
+
+    #[test]
+    fn test_next_run_label() {
+        let now = at(2026, 10, 14, 23, 0);
+        let format = TimeFormat {
+            date: "%Y-%m-%d".to_string(),
+            time: "%H:%M".to_string(),
+        };
+        assert_eq!(
+            next_run_label("nightly 02:00", now, &format).as_deref(),
+            Some("mañana 02:00")
+        );
+        assert_eq!(
+            next_run_label("every 15m", now, &format).as_deref(),
+            Some("hoy 23:15")
+        );
+        assert_eq!(
+            next_run_label("weekly mon 09:30", now, &format).as_deref(),
+            Some("2026-10-19 09:30")
+        );
+    }
+
+    #[test]
+    fn test_on_calendar() {
+        assert_eq!(
+            Cron::parse("0 2 * * *").unwrap().on_calendar(),
+            "*-*-* 02:00:00"
+        );
+        assert_eq!(
+            Cron::parse("30 9 * * 1,5").unwrap().on_calendar(),
+            "Mon,Fri *-*-* 09:30:00"
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let source = r#""Ops":
+    Backups:
+        Nightly: "pg_dump -f /tmp/db.sql" [schedule=nightly 02:00, cwd=/srv, env="PGUSER=backup"]
+        Manual: "pg_dump"
+    Rotar: "logrotate {{text: Archivo}}" [schedule=daily]
+    Roto: "date +%F" [schedule=a veces]
+"#;
+        let (config, _, items) = parse_toon_str(source, Path::new("/"));
+
+        let crontab = export(&items, &config, ExportFormat::Crontab);
+        assert!(crontab.contains("# omitido: Rotar: requiere parámetros\n"));
+        assert!(crontab.contains("# omitido: Roto: horario inválido 'a veces'\n"));
+        assert!(
+            crontab.contains(
+                "# tmenu: Backups › Nightly\n0 2 * * * cd /srv && env PGUSER=backup pg_dump -f /tmp/db.sql\n"
+            )
+        );
+        assert!(!crontab.contains("Manual"));
+
+        let systemd = export(&items, &config, ExportFormat::Systemd);
+        assert!(systemd.contains("# ===== tmenu-backups-nightly.timer ====="));
+        assert!(systemd.contains("WorkingDirectory=/srv\nEnvironment=\"PGUSER=backup\"\nExecStart=pg_dump -f /tmp/db.sql\n"));
+        assert!(systemd.contains("OnCalendar=*-*-* 02:00:00\n"));
+    }
+}
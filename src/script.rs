@@ -0,0 +1,251 @@
+//! Scripts Rhai dentro del menú (feature `scripting`): submenús que arma un
+//! script (`script:...`), condiciones (`[enabled_if=script:...]`) y filtros
+//! de la salida capturada (`[output_filter=script:...]`).
+//!
+//! Además del lenguaje, los scripts tienen:
+//! - `env(nombre)`: la variable de entorno, o `""` si no está definida.
+//! - `run(comando)`: ejecuta el comando (sin shell, como `list:`) y retorna
+//!   su salida estándar.
+//! - `exists(ruta)`: si la ruta existe.
+//!
+//! Un submenú se arma con la lista que retorna el script: cada elemento es
+//! un texto (etiqueta y comando a la vez) o un mapa con `label` y `command`,
+//! o `label` e `items` (otra lista) para un submenú. Sin la feature, los tres
+//! usos fallan con un [`crate::error::TmenuError::ScriptError`] que lo explica.
+#[cfg(feature = "scripting")]
+pub use engine::{condition, filter_output, items};
+#[cfg(not(feature = "scripting"))]
+pub use unavailable::{condition, filter_output, items};
+
+#[cfg(feature = "scripting")]
+mod engine {
+    use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope};
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+    use std::rc::Rc;
+
+    use crate::error::TmenuError;
+    use crate::model::{MenuAction, MenuItem, Script};
+
+    /// Operaciones que puede hacer un script antes de cortarse: un bucle
+    /// infinito no puede colgar la interfaz.
+    const MAX_OPERATIONS: u64 = 1_000_000;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.register_fn("env", |name: &str| std::env::var(name).unwrap_or_default());
+        engine.register_fn("exists", |path: &str| Path::new(path).exists());
+        engine.register_fn("run", run);
+        engine
+    }
+
+    /// `run(comando)`: la salida estándar de `cmd`, que corre sin entrada.
+    fn run(cmd: &str) -> Result<String, Box<EvalAltResult>> {
+        let parts = shlex::split(cmd).ok_or_else(|| format!("comando mal formado: {}", cmd))?;
+        let Some((bin, args)) = parts.split_first() else {
+            return Ok(String::new());
+        };
+        let output = Command::new(bin)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("'{}': {}", bin, e))?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn eval(script: &Script, scope: &mut Scope) -> Result<Dynamic, TmenuError> {
+        let engine = engine();
+        match script {
+            Script::Inline(code) => engine.eval_with_scope::<Dynamic>(scope, code),
+            Script::File(path) => engine.eval_file_with_scope::<Dynamic>(scope, path.clone()),
+        }
+        .map_err(|e| TmenuError::ScriptError(format!("{}: {}", script.source(), e)))
+    }
+
+    /// Ítems del submenú que arma `script`.
+    pub fn items(script: &Script) -> Result<Vec<MenuItem>, TmenuError> {
+        let value = eval(script, &mut Scope::new())?;
+        items_from(value)
+            .map_err(|e| TmenuError::ScriptError(format!("{}: {}", script.source(), e)))
+    }
+
+    fn items_from(value: Dynamic) -> Result<Vec<MenuItem>, String> {
+        let Some(list) = value.try_cast::<Array>() else {
+            return Err("el script no retornó una lista".to_string());
+        };
+        list.into_iter().map(item_from).collect()
+    }
+
+    fn item_from(value: Dynamic) -> Result<MenuItem, String> {
+        if value.is_string() {
+            let line = value.into_string().unwrap_or_default();
+            return Ok(MenuItem::new(line.clone(), MenuAction::Execute(line)));
+        }
+        let Some(mut fields) = value.try_cast::<Map>() else {
+            return Err("un ítem no es un texto ni un mapa".to_string());
+        };
+        let mut text = |key: &str| fields.remove(key).and_then(|v| v.into_string().ok());
+        let label = text("label").ok_or("un ítem no tiene \"label\"")?;
+        let action = if let Some(cmd) = text("command") {
+            MenuAction::Execute(cmd)
+        } else if let Some(children) = fields.remove("items") {
+            MenuAction::OpenSubmenu(Rc::new(items_from(children)?))
+        } else {
+            return Err(format!(
+                "el ítem '{}' no tiene \"command\" ni \"items\"",
+                label
+            ));
+        };
+        Ok(MenuItem::new(label, action))
+    }
+
+    /// Si `script` (una condición de `enabled_if`) retorna `true`.
+    pub fn condition(script: &Script) -> Result<bool, TmenuError> {
+        eval(script, &mut Scope::new())?.as_bool().map_err(|_| {
+            TmenuError::ScriptError(format!(
+                "{}: la condición no retornó true ni false",
+                script.source()
+            ))
+        })
+    }
+
+    /// La salida `output` de un comando reescrita por `script`, que la
+    /// recibe en `output` (y en `success` si el comando terminó bien) y
+    /// retorna un texto o una lista de líneas.
+    pub fn filter_output(
+        script: &Script,
+        output: &str,
+        success: bool,
+    ) -> Result<String, TmenuError> {
+        let mut scope = Scope::new();
+        scope.push("output", output.to_string());
+        scope.push("success", success);
+        let value = eval(script, &mut scope)?;
+        if value.is_string() {
+            return Ok(value.into_string().unwrap_or_default());
+        }
+        match value.try_cast::<Array>() {
+            Some(lines) => Ok(lines
+                .into_iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")),
+            None => Err(TmenuError::ScriptError(format!(
+                "{}: el filtro no retornó un texto ni una lista",
+                script.source()
+            ))),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_items_from_strings_and_maps() {
+            let script = Script::parse(
+                r#"[
+                    "echo uno",
+                    #{label: "Dos", command: "echo " + (1 + 1)},
+                    #{label: "Más", items: ["echo tres"]},
+                ]"#,
+            );
+            let generated = items(&script).unwrap();
+            assert_eq!(generated[0].label, "echo uno");
+            assert_eq!(
+                generated[0].action,
+                MenuAction::Execute("echo uno".to_string())
+            );
+            assert_eq!(
+                generated[1].action,
+                MenuAction::Execute("echo 2".to_string())
+            );
+            let MenuAction::OpenSubmenu(children) = &generated[2].action else {
+                panic!("'Más' es un submenú");
+            };
+            assert_eq!(children[0].label, "echo tres");
+
+            let error = items(&Script::parse("#{label: \"x\"}")).unwrap_err();
+            assert!(error.to_string().contains("no retornó una lista"));
+        }
+
+        #[test]
+        fn test_items_from_a_file_with_run() {
+            let path = std::env::temp_dir().join(format!("tmenu-{}.rhai", std::process::id()));
+            std::fs::write(
+                &path,
+                "run(\"printf 'a\\nb\\n'\").split(\"\\n\").filter(|l| l != \"\")",
+            )
+            .unwrap();
+            let result = items(&Script::parse(&path.to_string_lossy()));
+            let _ = std::fs::remove_file(&path);
+            let labels: Vec<String> = result.unwrap().into_iter().map(|i| i.label).collect();
+            assert_eq!(labels, ["a", "b"]);
+        }
+
+        #[test]
+        fn test_condition_and_limits() {
+            assert!(
+                condition(&Script::parse(
+                    "exists(\"/\") && env(\"TMENU_NO_EXISTE\") == \"\""
+                ))
+                .unwrap()
+            );
+            assert!(!condition(&Script::parse("1 > 2")).unwrap());
+            assert!(condition(&Script::parse("\"texto\"")).is_err());
+            // Un bucle infinito se corta por la cantidad de operaciones
+            assert!(condition(&Script::parse("loop {}")).is_err());
+        }
+
+        #[test]
+        fn test_filter_output() {
+            let script = Script::parse(
+                "output.split(\"\\n\").filter(|l| l.contains(\"ERROR\")).map(|l| l.to_upper())",
+            );
+            assert_eq!(
+                filter_output(&script, "ok\nERROR uno\nok\nERROR dos", true).unwrap(),
+                "ERROR UNO\nERROR DOS"
+            );
+            let status = Script::parse("if success { \"bien\" } else { output }");
+            assert_eq!(filter_output(&status, "falló", false).unwrap(), "falló");
+            assert!(filter_output(&Script::parse("42"), "", true).is_err());
+        }
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+mod unavailable {
+    use crate::error::TmenuError;
+    use crate::model::{MenuItem, Script};
+
+    fn error(script: &Script) -> TmenuError {
+        TmenuError::ScriptError(format!(
+            "{}: tmenu se compiló sin scripts (feature `scripting`)",
+            script.source()
+        ))
+    }
+
+    pub fn items(script: &Script) -> Result<Vec<MenuItem>, TmenuError> {
+        Err(error(script))
+    }
+
+    pub fn condition(script: &Script) -> Result<bool, TmenuError> {
+        Err(error(script))
+    }
+
+    pub fn filter_output(script: &Script, _: &str, _: bool) -> Result<String, TmenuError> {
+        Err(error(script))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_scripts_need_the_feature() {
+            let error = items(&Script::parse("[]")).unwrap_err();
+            assert!(error.to_string().contains("feature `scripting`"));
+        }
+    }
+}
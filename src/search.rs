@@ -29,23 +29,94 @@ pub fn filter_recursive(items: &[MenuItem], query: &str, depth: usize) -> Vec<Me
             MenuAction::OpenSubmenu(sub_items) => {
                 results.extend(filter_recursive(sub_items, query, depth + 1));
             }
+            // Los submenús generados no se expanden al buscar (leerían disco en cada tecla)
+            MenuAction::Directory(_)
+            | MenuAction::MenuFile(_)
+            | MenuAction::Dynamic { .. }
+            | MenuAction::Plugin(_)
+            | MenuAction::SshHosts
+            | MenuAction::Script(_)
+            | MenuAction::History(_)
+            | MenuAction::Favorites
+            | MenuAction::Recent(_) => {}
         }
     }
     results
 }
 
+/// Un resultado de la búsqueda global (Ctrl+F): el ítem y las etiquetas de
+/// los submenús que llevan hasta él desde la raíz.
+#[derive(Clone, Debug)]
+pub struct TreeMatch {
+    pub path: Vec<String>,
+    pub item: MenuItem,
+}
+
+/// Busca en todo el árbol, submenús incluidos, comparando la etiqueta con
+/// su camino adelante (`Docker › Logs`). Primero van los ítems cuya etiqueta
+/// coincide sola y después los que coinciden gracias al camino, cada grupo
+/// en el orden del menú.
+pub fn filter_tree(items: &[MenuItem], query: &str) -> Vec<TreeMatch> {
+    let mut results = Vec::new();
+    collect_tree(items, query, &mut Vec::new(), &mut results, 0);
+    results.sort_by_key(|found| !is_fuzzy_match(&found.item.label, query));
+    results
+}
+
+fn collect_tree(
+    items: &[MenuItem],
+    query: &str,
+    path: &mut Vec<String>,
+    results: &mut Vec<TreeMatch>,
+    depth: usize,
+) {
+    if depth > 32 {
+        return; // prevenir recursión excesiva
+    }
+    for item in items {
+        let mut text = path.join(" › ");
+        if !text.is_empty() {
+            text.push_str(" › ");
+        }
+        text.push_str(&item.label);
+        if is_fuzzy_match(&text, query) {
+            results.push(TreeMatch {
+                path: path.clone(),
+                item: item.clone(),
+            });
+        }
+        // Como en `filter_recursive`, los submenús generados no se expanden
+        if let MenuAction::OpenSubmenu(sub_items) = &item.action {
+            path.push(item.label.clone());
+            collect_tree(sub_items, query, path, results, depth + 1);
+            path.pop();
+        }
+    }
+}
+
+/// Filtra solo el nivel `items` (el filtro de `/`): ítems ejecutables y
+/// submenús cuya etiqueta coincide con `query`, en el orden del menú.
+pub fn filter_level(items: &[MenuItem], query: &str) -> Vec<MenuItem> {
+    items
+        .iter()
+        .filter(|item| is_fuzzy_match(&item.label, query))
+        .cloned()
+        .collect()
+}
+
 /// Retorna el primer ítem ejecutable encontrado en la lista, recorriendo
 /// submenús recursivamente. Usado como fallback cuando la búsqueda no encuentra resultados.
 pub fn find_first_command(items: &[MenuItem]) -> Option<MenuItem> {
     for item in items {
         match &item.action {
             MenuAction::Execute(_) => return Some(item.clone()),
-            MenuAction::Quit => {} // no usar como fallback de búsqueda
             MenuAction::OpenSubmenu(sub_items) => {
                 if let Some(found) = find_first_command(sub_items) {
                     return Some(found);
                 }
             }
+            // Quit y submenús generados no se usan como fallback de búsqueda
+            _ => {}
         }
     }
     None
@@ -75,4 +146,57 @@ mod tests {
     fn test_fuzzy_no_match() {
         assert!(!is_fuzzy_match("hola", "xyz"));
     }
+
+    #[test]
+    fn test_filter_level_keeps_submenus_and_skips_children() {
+        let items = vec![
+            MenuItem::new("Deploy prod", MenuAction::Execute("./deploy prod".into())),
+            MenuItem::new(
+                "Docker",
+                MenuAction::OpenSubmenu(
+                    vec![MenuItem::new("Desplegar", MenuAction::Execute("d".into()))].into(),
+                ),
+            ),
+            MenuItem::new("Salir", MenuAction::Quit),
+        ];
+        let labels: Vec<String> = filter_level(&items, "dp")
+            .into_iter()
+            .map(|item| item.label)
+            .collect();
+        assert_eq!(labels, vec!["Deploy prod"]);
+        assert_eq!(filter_level(&items, "do").len(), 2);
+    }
+
+    #[test]
+    fn test_filter_tree_matches_labels_and_paths() {
+        let items = vec![
+            MenuItem::new(
+                "Docker",
+                MenuAction::OpenSubmenu(
+                    vec![
+                        MenuItem::new("Logs", MenuAction::Execute("docker logs".into())),
+                        MenuItem::new("Limpiar", MenuAction::Execute("docker prune".into())),
+                    ]
+                    .into(),
+                ),
+            ),
+            MenuItem::new("Ver logs", MenuAction::Execute("tail log".into())),
+        ];
+        let found: Vec<(String, String)> = filter_tree(&items, "logs")
+            .into_iter()
+            .map(|found| (found.path.join("/"), found.item.label))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Docker".to_string(), "Logs".to_string()),
+                (String::new(), "Ver logs".to_string()),
+            ]
+        );
+        // El camino también cuenta, y el submenú es un resultado más
+        let found = filter_tree(&items, "dock lim");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item.label, "Limpiar");
+        assert_eq!(filter_tree(&items, "dck")[0].item.label, "Docker");
+    }
 }
@@ -0,0 +1,214 @@
+//! Preferencias del usuario en `config.toml`, dentro de las carpetas de
+//! configuración (`~/.config/tmenu/config.toml`).
+//!
+//! A diferencia del `.toon`, que describe un menú, este archivo guarda cómo se
+//! ve tmenu para ese usuario en cualquier menú. Es TOML: las preferencias son
+//! las claves de cada sección `[nombre]` (o con claves punteadas,
+//! `theme.border = ...`), con textos, números, booleanos y listas de textos.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::paths::config_dirs;
+
+/// Nombre del archivo de preferencias en las carpetas de configuración.
+pub const SETTINGS_FILE: &str = "config.toml";
+
+/// Un valor de `config.toml`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+/// Claves de cada sección, en el orden del archivo. Las claves anteriores a
+/// la primera sección quedan en la sección `""`.
+#[derive(Debug, Default)]
+pub struct Settings {
+    sections: HashMap<String, Vec<(String, Value)>>,
+    /// Carpeta del archivo leído, desde donde se resuelven sus rutas relativas
+    dir: Option<PathBuf>,
+}
+
+impl Settings {
+    /// Lee el primer `config.toml` que exista en las carpetas de configuración.
+    /// Sin archivo se retornan preferencias vacías; un archivo que no se puede
+    /// leer o con líneas inválidas agrega los errores al segundo valor.
+    pub fn load() -> (Self, Vec<String>) {
+        let Some(path) = config_dirs()
+            .into_iter()
+            .map(|dir| dir.join(SETTINGS_FILE))
+            .find(|path| path.is_file())
+        else {
+            return (Settings::default(), Vec::new());
+        };
+        Settings::load_from(&path)
+    }
+
+    /// Lee `path` en lugar de buscar en las carpetas de configuración
+    /// (`--config`). Los errores llevan la ruta del archivo.
+    pub fn load_from(path: &Path) -> (Self, Vec<String>) {
+        let (mut settings, errors) = match std::fs::read_to_string(path) {
+            Ok(content) => Settings::parse(&content),
+            Err(e) => (Settings::default(), vec![e.to_string()]),
+        };
+        settings.dir = path.parent().map(Path::to_path_buf);
+        let errors = errors
+            .into_iter()
+            .map(|e| format!("{}: {}", path.display(), e))
+            .collect();
+        (settings, errors)
+    }
+
+    /// Parsea el contenido de un `config.toml`. Un archivo que no es TOML
+    /// válido no aporta nada; las claves con valores que tmenu no usa se
+    /// ignoran. En ambos casos el error va en el segundo valor.
+    pub fn parse(content: &str) -> (Self, Vec<String>) {
+        let mut settings = Settings::default();
+        let table = match content.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(e) => {
+                let line = e
+                    .span()
+                    .map_or(1, |span| content[..span.start].matches('\n').count() + 1);
+                return (settings, vec![format!("línea {}: {}", line, e.message())]);
+            }
+        };
+        let mut errors = Vec::new();
+        for (key, value) in table {
+            match value {
+                toml::Value::Table(keys) => {
+                    for (name, value) in keys {
+                        settings.insert(&key, name, value, &mut errors);
+                    }
+                }
+                value => settings.insert("", key, value, &mut errors),
+            }
+        }
+        (settings, errors)
+    }
+
+    fn insert(&mut self, section: &str, key: String, value: toml::Value, errors: &mut Vec<String>) {
+        match Value::from_toml(value) {
+            Ok(value) => self
+                .sections
+                .entry(section.to_string())
+                .or_default()
+                .push((key, value)),
+            Err(e) if section.is_empty() => errors.push(format!("{}: {}", key, e)),
+            Err(e) => errors.push(format!("[{}] {}: {}", section, key, e)),
+        }
+    }
+
+    /// Claves de la sección `name` (`theme` para `[theme]`), vacía si no está.
+    pub fn section(&self, name: &str) -> &[(String, Value)] {
+        self.sections.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Carpeta del archivo leído; `.` si las preferencias no vienen de un archivo.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_deref().unwrap_or(Path::new("."))
+    }
+}
+
+impl Value {
+    fn from_toml(value: toml::Value) -> Result<Self, &'static str> {
+        match value {
+            toml::Value::String(text) => Ok(Value::Str(text)),
+            toml::Value::Integer(n) => Ok(Value::Int(n)),
+            toml::Value::Float(x) => Ok(Value::Float(x)),
+            toml::Value::Boolean(flag) => Ok(Value::Bool(flag)),
+            toml::Value::Array(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    toml::Value::String(text) => Ok(text),
+                    _ => Err("las listas solo pueden tener textos"),
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            toml::Value::Datetime(_) => Err("tmenu no usa fechas"),
+            toml::Value::Table(_) => Err("las secciones anidadas no se usan"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        let (settings, errors) = Settings::parse(
+            "# preferencias\n\
+             menu = '~/menús/#ops.toon'\n\
+             [theme]\n\
+             border = \"#6482a0\" # azul\n\
+             highlight_symbol = ' > '\n\
+             padding = 0\n\
+             level_borders = [\"blue\", # raíz\n\
+                 \"#a08264\",\n\
+             ]\n\
+             bold = true\n\
+             [keys]\n\
+             quit = [\"a,b\", \"q\"]\n\
+             [window]\n\
+             ratio = 0.5\n\
+             desde = 2024-01-01\n",
+        );
+        assert_eq!(errors, vec!["[window] desde: tmenu no usa fechas"]);
+        assert_eq!(
+            settings.section("")[0].1,
+            Value::Str("~/menús/#ops.toon".to_string())
+        );
+        let theme = settings.section("theme");
+        assert_eq!(
+            theme[0],
+            ("border".to_string(), Value::Str("#6482a0".to_string()))
+        );
+        assert_eq!(theme[1].1, Value::Str(" > ".to_string()));
+        assert_eq!(theme[2].1, Value::Int(0));
+        assert_eq!(
+            theme[3].1,
+            Value::List(vec!["blue".to_string(), "#a08264".to_string()])
+        );
+        assert_eq!(theme[4].1, Value::Bool(true));
+        assert_eq!(
+            settings.section("keys")[0].1,
+            Value::List(vec!["a,b".to_string(), "q".to_string()])
+        );
+        assert_eq!(
+            settings.section("window"),
+            [("ratio".to_string(), Value::Float(0.5))]
+        );
+        assert!(settings.section("hints").is_empty());
+    }
+
+    #[test]
+    fn test_parse_settings_dotted_keys_and_errors() {
+        let (settings, errors) =
+            Settings::parse("theme.border = \"red\"\nhints.back = \"Atrás\"\n");
+        assert!(errors.is_empty());
+        assert_eq!(settings.section("theme")[0].0, "border");
+        assert_eq!(
+            settings.section("hints")[0].1,
+            Value::Str("Atrás".to_string())
+        );
+
+        let (settings, errors) = Settings::parse("[theme]\nborder = \"red\"\nesto no es toml\n");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("línea 3: "), "{}", errors[0]);
+        assert!(settings.section("theme").is_empty());
+
+        let (_, errors) =
+            Settings::parse("[theme]\nlevel_borders = [1, 2]\n[theme.extra]\nx = 1\n");
+        assert_eq!(
+            errors,
+            vec![
+                "[theme] level_borders: las listas solo pueden tener textos",
+                "[theme] extra: las secciones anidadas no se usan",
+            ]
+        );
+    }
+}
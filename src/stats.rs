@@ -0,0 +1,202 @@
+//! Estadísticas de uso locales (opt-in con `stats: true` en `config:`).
+//!
+//! Cada ejecución agrega una línea a `~/.local/share/tmenu/stats.log` con el
+//! formato `fecha<TAB>etiqueta<TAB>ok|fail<TAB>duración_ms<TAB>comando`.
+//! Nada sale de la máquina: `tmenu stats` solo lee ese archivo.
+use chrono::Local;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::TmenuError;
+
+/// Retorna la ruta al archivo de estadísticas: `~/.local/share/tmenu/stats.log`
+fn stats_file_path() -> Result<PathBuf, TmenuError> {
+    let home = dirs::home_dir().ok_or_else(|| {
+        TmenuError::StatsError("No se pudo determinar el directorio home".to_string())
+    })?;
+    Ok(home.join(".local/share/tmenu/stats.log"))
+}
+
+/// Los campos se separan con tabs: se reemplazan los que traiga el texto.
+fn field(s: &str) -> String {
+    s.replace(['\t', '\n'], " ")
+}
+
+/// Registra una ejecución de `label` (`cmd`) con su resultado y duración.
+pub fn record(label: &str, cmd: &str, success: bool, duration: Duration) -> Result<(), TmenuError> {
+    let path = stats_file_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| TmenuError::StatsError(format!("No se pudo crear directorio: {}", e)))?;
+    }
+
+    let entry = format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        field(label),
+        if success { "ok" } else { "fail" },
+        duration.as_millis(),
+        field(cmd)
+    );
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(entry.as_bytes()))
+        .map_err(|e| {
+            TmenuError::StatsError(format!("No se pudo escribir {}: {}", path.display(), e))
+        })
+}
+
+/// Totales de un ítem, agrupados por etiqueta.
+#[derive(Debug, Default, PartialEq)]
+pub struct ItemStats {
+    pub label: String,
+    pub runs: u64,
+    pub failures: u64,
+    pub total_ms: u64,
+}
+
+impl ItemStats {
+    /// Duración promedio por ejecución, en milisegundos.
+    pub fn average_ms(&self) -> u64 {
+        self.total_ms / self.runs.max(1)
+    }
+
+    /// Porcentaje de ejecuciones fallidas (0-100).
+    pub fn failure_rate(&self) -> u64 {
+        self.failures * 100 / self.runs.max(1)
+    }
+}
+
+/// Agrupa las líneas del log por etiqueta. Las líneas mal formadas se ignoran.
+/// El resultado queda ordenado por cantidad de ejecuciones (más usados primero).
+pub fn summarize(log: &str) -> Vec<ItemStats> {
+    let mut by_label: HashMap<&str, ItemStats> = HashMap::new();
+    for line in log.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [_, label, result, ms, ..] = fields[..] else {
+            continue;
+        };
+        let Ok(ms) = ms.parse::<u64>() else { continue };
+
+        let entry = by_label.entry(label).or_insert_with(|| ItemStats {
+            label: label.to_string(),
+            ..Default::default()
+        });
+        entry.runs += 1;
+        entry.total_ms += ms;
+        if result == "fail" {
+            entry.failures += 1;
+        }
+    }
+
+    let mut stats: Vec<ItemStats> = by_label.into_values().collect();
+    stats.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.label.cmp(&b.label)));
+    stats
+}
+
+/// Arma el resumen de `tmenu stats` con los `limit` primeros de cada sección.
+pub fn report(stats: &[ItemStats], limit: usize) -> String {
+    if stats.is_empty() {
+        return "Sin estadísticas registradas. Activalas con `stats: true` en la sección config:\n"
+            .to_string();
+    }
+
+    let mut out = String::from("Más usados:\n");
+    for s in stats.iter().take(limit) {
+        out.push_str(&format!("  {:>6}  {}\n", s.runs, s.label));
+    }
+
+    let mut failing: Vec<&ItemStats> = stats.iter().filter(|s| s.failures > 0).collect();
+    failing.sort_by_key(|s| std::cmp::Reverse(s.failure_rate()));
+    if !failing.is_empty() {
+        out.push_str("\nCon fallos:\n");
+        for s in failing.iter().take(limit) {
+            out.push_str(&format!(
+                "  {:>5}%  {} ({} de {})\n",
+                s.failure_rate(),
+                s.label,
+                s.failures,
+                s.runs
+            ));
+        }
+    }
+
+    let mut slowest: Vec<&ItemStats> = stats.iter().collect();
+    slowest.sort_by_key(|s| std::cmp::Reverse(s.average_ms()));
+    out.push_str("\nMás lentos (promedio):\n");
+    for s in slowest.iter().take(limit) {
+        out.push_str(&format!(
+            "  {:>6}  {}\n",
+            format_ms(s.average_ms()),
+            s.label
+        ));
+    }
+    out
+}
+
+/// Formatea milisegundos como `850ms`, `12.3s` o `4m05s`.
+fn format_ms(ms: u64) -> String {
+    match ms {
+        0..1_000 => format!("{}ms", ms),
+        1_000..60_000 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m{:02}s", ms / 60_000, ms / 1000 % 60),
+    }
+}
+
+/// `tmenu stats`: lee el log local e imprime el resumen.
+pub fn print_report(limit: usize) -> Result<(), TmenuError> {
+    let path = stats_file_path()?;
+    let log = match fs::read_to_string(&path) {
+        Ok(log) => log,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    print!("{}", report(&summarize(&log), limit));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+2026-01-01 10:00:00\tEstado\tok\t120\tgit status
+2026-01-01 10:01:00\tDeploy\tfail\t30000\t./deploy.sh
+2026-01-01 10:02:00\tEstado\tok\t80\tgit status
+2026-01-01 10:03:00\tDeploy\tok\t50000\t./deploy.sh
+línea rota
+2026-01-01 10:04:00\tEstado\tok\t100\tgit status
+";
+
+    #[test]
+    fn test_summarize_groups_by_label() {
+        let stats = summarize(LOG);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].label, "Estado");
+        assert_eq!(stats[0].runs, 3);
+        assert_eq!(stats[0].average_ms(), 100);
+        assert_eq!(stats[1].failures, 1);
+        assert_eq!(stats[1].failure_rate(), 50);
+    }
+
+    #[test]
+    fn test_report_sections() {
+        let report = report(&summarize(LOG), 5);
+        assert!(report.contains("Más usados:\n       3  Estado\n"));
+        assert!(report.contains("Con fallos:\n     50%  Deploy (1 de 2)\n"));
+        assert!(report.contains("Más lentos (promedio):\n   40.0s  Deploy\n"));
+    }
+
+    #[test]
+    fn test_format_ms() {
+        assert_eq!(format_ms(850), "850ms");
+        assert_eq!(format_ms(12_340), "12.3s");
+        assert_eq!(format_ms(245_000), "4m05s");
+    }
+}
@@ -0,0 +1,387 @@
+//! Colores, bordes y símbolos de la interfaz, configurables en la sección
+//! `[theme]` de `config.toml`:
+//!
+//! ```toml
+//! [theme]
+//! highlight_bg = "#283c64"
+//! level_borders = ["#6482a0", "#a08264", "#64a082"]
+//! border_type = "plain"
+//! highlight_symbol = " > "
+//! ```
+//!
+//! Las claves que faltan conservan el valor del tema predefinido elegido con
+//! `preset` (o `--theme`), `dark` si no se elige ninguno.
+//!
+//! `ascii = true` reemplaza bordes, marcas y spinner por caracteres ASCII,
+//! para consolas y fuentes que no tienen los glifos Unicode. Se activa solo
+//! en la consola de Linux (`TERM=linux`) salvo `ascii = false`.
+use std::str::FromStr;
+
+use ratatui::{style::Color, symbols::border};
+
+use crate::settings::{Settings, Value};
+
+/// Aspecto de la interfaz.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Bordes principales (menú, búsqueda, paneles)
+    pub border: Color,
+    /// Bordes de énfasis (wizard, vista de trabajos)
+    pub border_accent: Color,
+    /// Color del borde del menú según la profundidad del submenú, en ciclo;
+    /// vacío = siempre `border`
+    pub level_borders: Vec<Color>,
+    /// Fondo y texto del ítem seleccionado
+    pub highlight_bg: Color,
+    pub highlight_fg: Color,
+    /// Barra de búsqueda con y sin resultados
+    pub search_success: Color,
+    pub search_fail: Color,
+    /// Comandos y teclas
+    pub command: Color,
+    /// Texto secundario, atenuado
+    pub secondary: Color,
+    /// Modal de confirmación
+    pub confirmation: Color,
+    /// Errores y alertas
+    pub error: Color,
+    /// Caracteres del borde de todos los recuadros
+    pub border_set: border::Set<'static>,
+    /// Spinner, barra de desplazamiento e íconos de estado en ASCII
+    pub ascii: bool,
+    /// Marca del ítem seleccionado
+    pub highlight_symbol: String,
+    /// Marca de los submenús y de los ítems que salen de la app
+    pub submenu_symbol: String,
+    pub quit_symbol: String,
+    /// Líneas vacías arriba y abajo de la lista de ítems
+    pub padding: u16,
+}
+
+/// Nombres de los temas predefinidos, para `--theme` y `preset`.
+pub const PRESETS: &[&str] = &["dark", "light", "solarized", "monochrome"];
+
+/// Borde con `+`, `-` y `|`, para terminales sin caracteres de recuadro.
+pub const ASCII_BORDER: border::Set<'static> = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+impl Default for Theme {
+    /// El tema `dark`.
+    fn default() -> Self {
+        Theme {
+            // Azul grisáceo sutil, y uno más oscuro para énfasis
+            border: Color::Rgb(100, 130, 160),
+            border_accent: Color::Rgb(80, 110, 150),
+            level_borders: Vec::new(),
+            // Azul profundo muy suave, con texto blanco ligeramente azulado
+            highlight_bg: Color::Rgb(40, 60, 100),
+            highlight_fg: Color::Rgb(220, 230, 245),
+            // Verde agua y rojo suaves
+            search_success: Color::Rgb(100, 170, 140),
+            search_fail: Color::Rgb(200, 100, 110),
+            command: Color::Rgb(120, 180, 200),
+            secondary: Color::Rgb(140, 150, 170),
+            confirmation: Color::Rgb(209, 85, 85),
+            error: Color::Rgb(210, 110, 120),
+            border_set: border::ROUNDED,
+            ascii: false,
+            highlight_symbol: " \u{27a4} ".to_string(),
+            submenu_symbol: "\u{25b6}".to_string(),
+            quit_symbol: "\u{2717}".to_string(),
+            padding: 1,
+        }
+    }
+}
+
+impl Theme {
+    /// Tema predefinido por nombre (ver [`PRESETS`]).
+    pub fn preset(name: &str) -> Option<Self> {
+        let dark = Theme::default();
+        match name.to_lowercase().as_str() {
+            "dark" => Some(dark),
+            // Colores más oscuros y saturados, legibles sobre fondo claro
+            "light" => Some(Theme {
+                border: Color::Rgb(90, 110, 140),
+                border_accent: Color::Rgb(60, 90, 140),
+                highlight_bg: Color::Rgb(200, 215, 240),
+                highlight_fg: Color::Rgb(20, 30, 50),
+                search_success: Color::Rgb(30, 120, 80),
+                search_fail: Color::Rgb(180, 40, 50),
+                command: Color::Rgb(20, 100, 140),
+                secondary: Color::Rgb(100, 105, 120),
+                confirmation: Color::Rgb(190, 50, 50),
+                error: Color::Rgb(180, 40, 60),
+                ..dark
+            }),
+            // Paleta Solarized (variante oscura)
+            "solarized" => Some(Theme {
+                border: Color::Rgb(38, 139, 210),
+                border_accent: Color::Rgb(108, 113, 196),
+                highlight_bg: Color::Rgb(7, 54, 66),
+                highlight_fg: Color::Rgb(147, 161, 161),
+                search_success: Color::Rgb(133, 153, 0),
+                search_fail: Color::Rgb(220, 50, 47),
+                command: Color::Rgb(42, 161, 152),
+                secondary: Color::Rgb(88, 110, 117),
+                confirmation: Color::Rgb(203, 75, 22),
+                error: Color::Rgb(220, 50, 47),
+                ..dark
+            }),
+            // Los colores de la terminal; la selección, en video inverso
+            "monochrome" => Some(Theme {
+                border: Color::Reset,
+                border_accent: Color::Reset,
+                highlight_bg: Color::White,
+                highlight_fg: Color::Black,
+                search_success: Color::Reset,
+                search_fail: Color::Reset,
+                command: Color::Reset,
+                secondary: Color::Reset,
+                confirmation: Color::Reset,
+                error: Color::Reset,
+                ..dark
+            }),
+            _ => None,
+        }
+    }
+
+    /// Tema de la sección `[theme]` de `config.toml`, o el predefinido si no
+    /// está, para la terminal actual (ASCII en la consola de Linux).
+    /// `preset` (de `--theme`) reemplaza al `preset` del archivo. El segundo
+    /// valor describe las claves inválidas.
+    pub fn load(settings: &Settings, preset: Option<&str>) -> (Self, Vec<String>) {
+        let console = std::env::var("TERM").is_ok_and(|term| term == "linux");
+        Theme::build(settings, preset, console)
+    }
+
+    /// Tema predefinido (`preset`, el `preset` de `[theme]` o `dark`) con las
+    /// demás claves de `[theme]` aplicadas encima.
+    pub fn from_settings(settings: &Settings, preset: Option<&str>) -> (Self, Vec<String>) {
+        Theme::build(settings, preset, false)
+    }
+
+    /// Como [`Theme::from_settings`]; `console` activa `ascii` si la sección
+    /// no lo indica.
+    fn build(settings: &Settings, preset: Option<&str>, console: bool) -> (Self, Vec<String>) {
+        let section = settings.section("theme");
+        let mut errors = Vec::new();
+        let name = preset.or_else(|| {
+            section.iter().find_map(|(key, value)| match value {
+                Value::Str(name) if key == "preset" => Some(name.as_str()),
+                _ => None,
+            })
+        });
+        let mut theme = match name.map(|name| (name, Theme::preset(name))) {
+            Some((_, Some(theme))) => theme,
+            Some((name, None)) => {
+                errors.push(format!(
+                    "tema desconocido '{}' (hay {})",
+                    name,
+                    PRESETS.join(", ")
+                ));
+                Theme::default()
+            }
+            None => Theme::default(),
+        };
+        // `ascii` va antes que las demás claves, que pueden cambiar cada símbolo
+        let ascii = section.iter().find(|(key, _)| key == "ascii");
+        match ascii.map(|(_, value)| value) {
+            Some(Value::Bool(ascii)) => theme.set_ascii(*ascii),
+            Some(_) => errors.push("[theme] ascii: se esperaba true o false".to_string()),
+            None => theme.set_ascii(console),
+        }
+        let skip = ["preset", "ascii"];
+        for (key, value) in section
+            .iter()
+            .filter(|(key, _)| !skip.contains(&key.as_str()))
+        {
+            if let Err(e) = theme.set(key, value) {
+                errors.push(format!("[theme] {}: {}", key, e));
+            }
+        }
+        (theme, errors)
+    }
+
+    /// Con `ascii`, bordes y marcas en ASCII (`+--+`, ` > `, `>`, `x`).
+    fn set_ascii(&mut self, ascii: bool) {
+        if !ascii {
+            return;
+        }
+        self.ascii = true;
+        self.border_set = ASCII_BORDER;
+        self.highlight_symbol = " > ".to_string();
+        self.submenu_symbol = ">".to_string();
+        self.quit_symbol = "x".to_string();
+    }
+
+    /// Color del borde del menú en un submenú de profundidad `depth` (0 = raíz).
+    pub fn level_border(&self, depth: usize) -> Color {
+        match self.level_borders.len() {
+            0 => self.border,
+            n => self.level_borders[depth % n],
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        let color = match key {
+            "border" => &mut self.border,
+            "border_accent" => &mut self.border_accent,
+            "highlight_bg" => &mut self.highlight_bg,
+            "highlight_fg" => &mut self.highlight_fg,
+            "search_success" => &mut self.search_success,
+            "search_fail" => &mut self.search_fail,
+            "command" => &mut self.command,
+            "secondary" => &mut self.secondary,
+            "confirmation" => &mut self.confirmation,
+            "error" => &mut self.error,
+            "level_borders" => {
+                let Value::List(colors) = value else {
+                    return Err("se esperaba una lista de colores".to_string());
+                };
+                self.level_borders = colors
+                    .iter()
+                    .map(|c| parse_color(c))
+                    .collect::<Result<_, _>>()?;
+                return Ok(());
+            }
+            "border_type" => {
+                self.border_set = parse_border_set(string(value)?)?;
+                return Ok(());
+            }
+            "highlight_symbol" => {
+                self.highlight_symbol = string(value)?.to_string();
+                return Ok(());
+            }
+            "submenu_symbol" => {
+                self.submenu_symbol = string(value)?.to_string();
+                return Ok(());
+            }
+            "quit_symbol" => {
+                self.quit_symbol = string(value)?.to_string();
+                return Ok(());
+            }
+            "padding" => {
+                let Value::Int(n) = value else {
+                    return Err("se esperaba un número".to_string());
+                };
+                self.padding = (*n).clamp(0, 3) as u16;
+                return Ok(());
+            }
+            _ => return Err("clave desconocida".to_string()),
+        };
+        *color = parse_color(string(value)?)?;
+        Ok(())
+    }
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    match value {
+        Value::Str(s) => Ok(s),
+        _ => Err("se esperaba un texto entre comillas".to_string()),
+    }
+}
+
+/// `#rrggbb`, un nombre (`blue`, `lightred`, `reset`) o un índice de la
+/// paleta de 256 colores (`"208"`).
+fn parse_color(s: &str) -> Result<Color, String> {
+    Color::from_str(s).map_err(|_| format!("color inválido '{}'", s))
+}
+
+/// `rounded`, `plain`, `double`, `thick` o `ascii`.
+fn parse_border_set(s: &str) -> Result<border::Set<'static>, String> {
+    match s.to_lowercase().as_str() {
+        "rounded" => Ok(border::ROUNDED),
+        "plain" => Ok(border::PLAIN),
+        "double" => Ok(border::DOUBLE),
+        "thick" => Ok(border::THICK),
+        "ascii" => Ok(ASCII_BORDER),
+        _ => Err(format!("tipo de borde inválido '{}'", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_settings() {
+        let (settings, _) = Settings::parse(
+            "[theme]\n\
+             highlight_bg = \"#102030\"\n\
+             level_borders = [\"blue\", \"208\"]\n\
+             border_type = \"double\"\n\
+             padding = 0\n\
+             border = \"azul\"\n\
+             shadow = \"black\"\n",
+        );
+        let (theme, errors) = Theme::from_settings(&settings, None);
+        assert_eq!(theme.highlight_bg, Color::Rgb(0x10, 0x20, 0x30));
+        assert_eq!(theme.border_set, border::DOUBLE);
+        assert_eq!(theme.padding, 0);
+        assert_eq!(theme.level_border(0), Color::Blue);
+        assert_eq!(theme.level_border(3), Color::Indexed(208));
+        // Las claves inválidas conservan el valor por defecto
+        assert_eq!(theme.border, Theme::default().border);
+        assert_eq!(
+            errors,
+            vec![
+                "[theme] border: color inválido 'azul'",
+                "[theme] shadow: clave desconocida"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_presets() {
+        for name in PRESETS {
+            assert!(Theme::preset(name).is_some(), "{}", name);
+        }
+        // La clave del archivo se aplica sobre el preset; --theme gana al del archivo
+        let (settings, _) = Settings::parse("[theme]\npreset = \"solarized\"\nerror = \"red\"\n");
+        let (theme, errors) = Theme::from_settings(&settings, None);
+        assert!(errors.is_empty());
+        assert_eq!(theme.border, Theme::preset("solarized").unwrap().border);
+        assert_eq!(theme.error, Color::Red);
+        let (theme, _) = Theme::from_settings(&settings, Some("light"));
+        assert_eq!(theme.border, Theme::preset("light").unwrap().border);
+        assert_eq!(theme.error, Color::Red);
+
+        let (theme, errors) = Theme::from_settings(&Settings::default(), Some("neon"));
+        assert_eq!(theme.border, Theme::default().border);
+        assert_eq!(
+            errors,
+            vec!["tema desconocido 'neon' (hay dark, light, solarized, monochrome)"]
+        );
+    }
+
+    #[test]
+    fn test_ascii() {
+        // En la consola de Linux se activa solo, salvo `ascii = false`
+        let (theme, _) = Theme::build(&Settings::default(), None, true);
+        assert!(theme.ascii);
+        assert_eq!(theme.border_set, ASCII_BORDER);
+        assert_eq!(theme.submenu_symbol, ">");
+        let (settings, _) = Settings::parse("[theme]\nascii = false\n");
+        let (theme, errors) = Theme::build(&settings, None, true);
+        assert!(errors.is_empty());
+        assert!(!theme.ascii);
+        assert_eq!(theme.submenu_symbol, "\u{25b6}");
+
+        // Las claves sueltas pisan a las de `ascii`, sin importar el orden
+        let (settings, _) = Settings::parse(
+            "[theme]\nquit_symbol = \"q\"\nascii = true\nborder_type = \"plain\"\n",
+        );
+        let (theme, _) = Theme::from_settings(&settings, None);
+        assert_eq!(theme.highlight_symbol, " > ");
+        assert_eq!(theme.quit_symbol, "q");
+        assert_eq!(theme.border_set, border::PLAIN);
+    }
+}
@@ -0,0 +1,129 @@
+//! Formato de las fechas y horas que se muestran en la interfaz.
+//!
+//! `date_format` y `time_format` en `config:` aceptan la sintaxis de strftime
+//! (`%d/%m/%Y`, `%I:%M %p`). Sin configurar, la hora sale en 12 o 24 horas
+//! según el locale (`LC_ALL`, `LC_TIME` o `LANG`). Los logs en disco
+//! (historial, estadísticas) mantienen su formato fijo para poder leerse.
+use chrono::NaiveDateTime;
+use chrono::format::{Item, StrftimeItems};
+
+use crate::parser::GlobalConfig;
+
+const DEFAULT_DATE: &str = "%Y-%m-%d";
+const TIME_24H: &str = "%H:%M";
+const TIME_12H: &str = "%I:%M %p";
+
+/// Locales que usan reloj de 12 horas (por país de la región del locale).
+const REGIONS_12H: &[&str] = &["US", "CA", "AU", "NZ", "PH", "IN", "PK", "EG", "SA"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeFormat {
+    pub date: String,
+    pub time: String,
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        TimeFormat {
+            date: DEFAULT_DATE.to_string(),
+            time: locale_time_format(&current_locale()).to_string(),
+        }
+    }
+}
+
+impl TimeFormat {
+    /// Formatos de `config:`; los que falten o sean inválidos toman el
+    /// valor por defecto, con un aviso.
+    pub fn from_config(config: &GlobalConfig) -> Self {
+        let default = TimeFormat::default();
+        TimeFormat {
+            date: valid_or(config.date_format.as_deref(), "date_format", default.date),
+            time: valid_or(config.time_format.as_deref(), "time_format", default.time),
+        }
+    }
+
+    pub fn time(&self, at: NaiveDateTime) -> String {
+        at.format(&self.time).to_string()
+    }
+
+    pub fn date_time(&self, at: NaiveDateTime) -> String {
+        format!("{} {}", at.format(&self.date), at.format(&self.time))
+    }
+}
+
+/// `format` si chrono lo entiende; si no, `default`. Validar antes evita el
+/// panic de chrono al formatear con un especificador desconocido.
+fn valid_or(format: Option<&str>, key: &str, default: String) -> String {
+    match format {
+        Some(format) if is_valid(format) => format.to_string(),
+        Some(format) => {
+            eprintln!("[warn] {} inválido '{}', se usa '{}'", key, format, default);
+            default
+        }
+        None => default,
+    }
+}
+
+fn is_valid(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| item == Item::Error)
+}
+
+/// Locale de las fechas, con la misma precedencia que POSIX.
+fn current_locale() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+/// Formato de hora por defecto para un locale como `en_US.UTF-8`.
+fn locale_time_format(locale: &str) -> &'static str {
+    let region = locale
+        .split(['.', '@'])
+        .next()
+        .and_then(|name| name.split_once('_'))
+        .map(|(_, region)| region)
+        .unwrap_or("");
+    if REGIONS_12H.contains(&region) {
+        TIME_12H
+    } else {
+        TIME_24H
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_locale_time_format() {
+        assert_eq!(locale_time_format("en_US.UTF-8"), TIME_12H);
+        assert_eq!(locale_time_format("es_AR.UTF-8"), TIME_24H);
+        assert_eq!(locale_time_format("C"), TIME_24H);
+        assert_eq!(locale_time_format(""), TIME_24H);
+    }
+
+    #[test]
+    fn test_from_config_falls_back_on_invalid() {
+        let config = GlobalConfig {
+            date_format: Some("%d/%m/%Y".to_string()),
+            time_format: Some("%Q".to_string()),
+            ..Default::default()
+        };
+        let format = TimeFormat::from_config(&config);
+        assert_eq!(format.date, "%d/%m/%Y");
+        assert_eq!(format.time, TimeFormat::default().time);
+
+        let at = NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(21, 5, 0)
+            .unwrap();
+        let format = TimeFormat {
+            date: "%d/%m/%Y".to_string(),
+            time: TIME_12H.to_string(),
+        };
+        assert_eq!(format.date_time(at), "14/10/2026 09:05 PM");
+    }
+}
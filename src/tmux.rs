@@ -0,0 +1,98 @@
+//! Ejecución en una ventana o panel de tmux (`[tmux=window]`, `[tmux=pane]`).
+//!
+//! Dentro de tmux el comando se manda a una ventana o división nueva en lugar
+//! de suspender la interfaz, así tmenu queda abierto como panel de control
+//! mientras los comandos corren al lado. Fuera de tmux el atributo se ignora.
+use std::path::Path;
+
+use crate::model::TmuxTarget;
+
+/// Si tmenu corre dentro de una sesión de tmux.
+pub fn inside() -> bool {
+    std::env::var_os("TMUX").is_some_and(|value| !value.is_empty())
+}
+
+/// Línea de `tmux` que abre `parts` en `target`, en el directorio `cwd` y con
+/// las variables `env` (el servidor de tmux no hereda el entorno de tmenu).
+/// Con `wait` el panel espera Enter antes de cerrarse, para poder leer la
+/// salida.
+pub fn command_line<'a>(
+    target: TmuxTarget,
+    name: &str,
+    parts: &[String],
+    cwd: &Path,
+    env: impl Iterator<Item = &'a (String, String)>,
+    wait: bool,
+) -> Vec<String> {
+    let mut line = vec!["tmux".to_string()];
+    match target {
+        TmuxTarget::Window => {
+            line.extend(["new-window".to_string(), "-n".to_string(), name.to_string()])
+        }
+        TmuxTarget::Pane => line.push("split-window".to_string()),
+    }
+    line.extend(["-c".to_string(), cwd.display().to_string()]);
+    for (key, value) in env {
+        line.extend(["-e".to_string(), format!("{}={}", key, value)]);
+    }
+    // Con más de un argumento tmux ejecuta el comando directamente, sin shell
+    line.push("--".to_string());
+    if wait {
+        line.extend([
+            "sh".to_string(),
+            "-c".to_string(),
+            "\"$@\"; printf '\\nPresioná Enter para cerrar...'; read _".to_string(),
+            "sh".to_string(),
+        ]);
+    }
+    line.extend(parts.iter().cloned());
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let env = [("AWS_PROFILE".to_string(), "prod".to_string())];
+        let parts = vec!["htop".to_string(), "-d".to_string(), "10".to_string()];
+        assert_eq!(
+            command_line(
+                TmuxTarget::Window,
+                "Monitor",
+                &parts,
+                Path::new("/srv"),
+                env.iter(),
+                false
+            ),
+            vec![
+                "tmux",
+                "new-window",
+                "-n",
+                "Monitor",
+                "-c",
+                "/srv",
+                "-e",
+                "AWS_PROFILE=prod",
+                "--",
+                "htop",
+                "-d",
+                "10"
+            ]
+        );
+        let line = command_line(
+            TmuxTarget::Pane,
+            "Monitor",
+            &parts,
+            Path::new("/srv"),
+            [].iter(),
+            true,
+        );
+        assert_eq!(
+            line[..6],
+            ["tmux", "split-window", "-c", "/srv", "--", "sh"]
+        );
+        assert_eq!(line[line.len() - 4..], ["sh", "htop", "-d", "10"]);
+    }
+}
@@ -0,0 +1,1146 @@
+//! Bucle de eventos de la interfaz: dibuja el menú con [`ui::ui`] y reparte
+//! cada tecla y clic entre los modos de la app (navegación, búsqueda,
+//! paneles y modales).
+//!
+//! [`run_app`] es el bucle completo que usa el binario `tmenu`; una
+//! aplicación ratatui que embebe el menú puede manejar sus propios eventos y
+//! usar directamente los métodos de [`App`] y el widget del menú.
+//!
+//! El bucle sirve para cualquier backend de ratatui y lee los eventos de
+//! [`App::events`], así que con `TestBackend` y [`ScriptedEvents`] se puede
+//! recorrer la interfaz sin terminal y revisar lo dibujado.
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
+use log::debug;
+use ratatui::{Terminal, backend::Backend};
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::app::{App, ClickTarget, OutputInput};
+use crate::control::Request;
+use crate::error::TmenuError;
+use crate::keymap::Action;
+use crate::{editor, model, pager, ui};
+
+/// De dónde lee [`run_app`] las teclas, los clics y los cambios de tamaño.
+pub trait EventSource {
+    /// Si hay un evento para leer antes de `timeout`.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    /// El próximo evento, esperándolo si hace falta.
+    fn read(&mut self) -> io::Result<Event>;
+}
+
+/// Los eventos de la terminal, leídos con crossterm.
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+/// Eventos fijados de antemano, para manejar la interfaz sin terminal (con
+/// el `TestBackend` de ratatui). Al acabarse, la lectura falla y [`run_app`]
+/// termina con error en lugar de esperar para siempre.
+#[derive(Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        ScriptedEvents {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    /// Teclas sin modificadores, una por cada [`KeyCode`].
+    pub fn keys(codes: impl IntoIterator<Item = KeyCode>) -> Self {
+        Self::new(
+            codes
+                .into_iter()
+                .map(|code| Event::Key(KeyEvent::from(code))),
+        )
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn poll(&mut self, _timeout: Duration) -> io::Result<bool> {
+        // Vacía se "lee" igual, para que el error corte el bucle
+        Ok(true)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        self.events
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no quedan eventos"))
+    }
+}
+
+/// Ciclo principal de eventos: dibuja la UI y procesa teclado.
+pub fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<(), TmenuError> {
+    // Solo se redibuja cuando algo cambió: un evento o una tarea periódica
+    let mut needs_redraw = true;
+    let mut last_tick = Instant::now();
+
+    loop {
+        // Comando de on_success / on_failure que quedó pendiente
+        if app.follow_up.is_some() {
+            match app.run_follow_up(terminal) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(e) => app.status_message = Some(e.to_string().trim_end().to_string()),
+            }
+            needs_redraw = true;
+        }
+
+        // Pedidos del socket de control (`--listen`)
+        let connections = app.control.as_ref().map(|c| c.accept()).unwrap_or_default();
+        for connection in connections {
+            needs_redraw = true;
+            debug!("pedido del socket de control: {:?}", connection.request);
+            match connection.request.clone() {
+                Err(e) => connection.reply(Err(e)),
+                Ok(Request::Quit) => {
+                    connection.reply(Ok(()));
+                    return Ok(());
+                }
+                Ok(Request::Reload) => connection.reply(
+                    app.reload()
+                        .map_err(|e| e.to_string().trim_end().to_string()),
+                ),
+                Ok(Request::Select(path)) => connection.reply(select_path(app, &path)),
+                Ok(Request::Run(path)) => {
+                    let found = select_path(app, &path);
+                    let run = found.is_ok();
+                    // Se responde antes de ejecutar: el comando puede tardar
+                    connection.reply(found);
+                    if run {
+                        let items = app.filtered_items();
+                        if app.activate_item(terminal, &items)? {
+                            return Ok(());
+                        }
+                        if app.wizard.is_some() && run_wizard(terminal, app)? {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+
+        if needs_redraw {
+            terminal
+                .draw(|f| ui::ui(f, app))
+                .map_err(|e| TmenuError::TerminalError(e.to_string()))?;
+            needs_redraw = false;
+        }
+
+        // Esperar un evento hasta el próximo tick; al vencer, correr las tareas periódicas
+        let timeout = app.tick_rate.saturating_sub(last_tick.elapsed());
+        if !app
+            .events
+            .poll(timeout)
+            .map_err(|e| TmenuError::EventError(e.to_string()))?
+        {
+            needs_redraw = app.on_tick();
+            last_tick = Instant::now();
+            continue;
+        }
+
+        // Un solo event::read() por iteracion — el KeyCode se pasa a los handlers
+        let event = app
+            .events
+            .read()
+            .map_err(|e| TmenuError::EventError(e.to_string()))?;
+
+        if let Event::Resize(..) = event {
+            needs_redraw = true;
+        }
+
+        // Los movimientos del mouse también llegan como eventos; se ignoran
+        if let Event::Mouse(mouse) = event {
+            let should_quit = match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    handle_click(terminal, app, mouse.column, mouse.row)?
+                }
+                MouseEventKind::ScrollDown => {
+                    handle_scroll(app, 1);
+                    false
+                }
+                MouseEventKind::ScrollUp => {
+                    handle_scroll(app, -1);
+                    false
+                }
+                _ => continue,
+            };
+            needs_redraw = true;
+            app.status_message = None;
+            if app.wizard.is_some() && run_wizard(terminal, app)? {
+                return Ok(());
+            }
+            if should_quit {
+                return Ok(());
+            }
+        }
+
+        if let Event::Key(key) = event {
+            if key.kind != event::KeyEventKind::Press {
+                continue;
+            }
+            if let Some(line) = key_log(app, &key) {
+                debug!("{}", line);
+            }
+            needs_redraw = true;
+            app.status_message = None;
+            // Ayuda y salir funcionan desde cualquier modo, salvo que estén
+            // en una tecla que se escribe (esas solo valen en la navegación)
+            match app.keymap.command(&key) {
+                Some(Action::Help) => {
+                    if open_help(terminal, app)? {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Some(Action::Quit) => return Ok(()),
+                _ => {}
+            }
+
+            // Panel de salida abierto: las teclas lo desplazan o lo cierran
+            if app.output.is_some() {
+                handle_output_mode(terminal, app, key.code)?;
+                continue;
+            }
+            if app.popup.is_some() {
+                handle_popup_mode(terminal, app, key.code);
+                continue;
+            }
+            if app.source_view.is_some() {
+                handle_source_mode(terminal, app, key.code);
+                continue;
+            }
+            if app.jobs_view.is_some() {
+                handle_jobs_mode(app, key.code);
+                continue;
+            }
+            if app.vars_view.is_some() {
+                handle_vars_mode(app, key.code);
+                continue;
+            }
+
+            let should_quit = if app.tree_view.is_some() {
+                handle_tree_mode(terminal, app, key.code)?
+            } else if app.search_mode {
+                handle_search_mode(terminal, app, key)?
+            } else {
+                handle_navigation_mode(terminal, app, key)?
+            };
+            if app.wizard.is_some() {
+                let quit = run_wizard(terminal, app)?;
+                if quit {
+                    return Ok(());
+                }
+            }
+
+            if should_quit {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Lo que `--debug` anota de `key`. Los caracteres que se escriben en un
+/// campo (la búsqueda, el wizard, una frase de confirmación, el valor de una
+/// variable o el pie del panel de salida) no se anotan: pueden ser secretos.
+fn key_log(app: &App, key: &KeyEvent) -> Option<String> {
+    let typing = app.search_mode
+        || app.wizard.is_some()
+        || app
+            .confirmation
+            .as_ref()
+            .is_some_and(|c| c.required_text.is_some())
+        || app
+            .vars_view
+            .as_ref()
+            .is_some_and(|view| view.editing.is_some())
+        || app.output.as_ref().is_some_and(|out| out.input.is_some());
+    let written = matches!(key.code, KeyCode::Char(_))
+        && !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    if typing && written {
+        return None;
+    }
+    Some(format!(
+        "tecla {}{}: {:?}",
+        if key.modifiers.is_empty() {
+            String::new()
+        } else {
+            format!("{}+", key.modifiers)
+        },
+        key.code,
+        app.keymap.action(key)
+    ))
+}
+
+/// Abre el modal de ayuda. Retorna Ok(true) si el usuario eligió salir de la app.
+fn open_help<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<bool, TmenuError> {
+    app.show_help = true;
+    run_help_modal(terminal, app)
+}
+
+/// Loop bloqueante del modal de ayuda.
+/// Retorna Ok(true) si el usuario eligió salir de la app, Ok(false) si cerró la ayuda para volver al menú.
+fn run_help_modal<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+) -> Result<bool, TmenuError> {
+    loop {
+        terminal
+            .draw(|f| ui::ui(f, app))
+            .map_err(|e| TmenuError::TerminalError(e.to_string()))?;
+
+        if let Event::Key(key) = app
+            .events
+            .read()
+            .map_err(|e| TmenuError::EventError(e.to_string()))?
+        {
+            if key.kind != event::KeyEventKind::Press {
+                continue;
+            }
+            match app.keymap.action(&key) {
+                Some(Action::Quit) => return Ok(true), // salir de la app
+                Some(Action::Help | Action::Preview) => {
+                    app.show_help = false;
+                    return Ok(false); // cerrar ayuda, continuar app
+                }
+                _ if key.code == KeyCode::Esc => {
+                    app.show_help = false;
+                    return Ok(false);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Loop bloqueante del wizard de interpolación.
+/// Retorna Ok(true) si el usuario canceló, Ok(false) si completó.
+fn run_wizard<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<bool, TmenuError> {
+    loop {
+        terminal
+            .draw(|f| ui::ui(f, app))
+            .map_err(|e| TmenuError::TerminalError(e.to_string()))?;
+
+        if let Event::Key(key) = app
+            .events
+            .read()
+            .map_err(|e| TmenuError::EventError(e.to_string()))?
+        {
+            if key.kind != event::KeyEventKind::Press {
+                continue;
+            }
+
+            // Ctrl+Q (o la tecla de `quit`) cancela y sale de la app
+            if app.keymap.command(&key) == Some(Action::Quit) {
+                app.wizard = None;
+                return Ok(true); // señal de quit
+            }
+
+            match key.code {
+                KeyCode::Esc => {
+                    // Cancelar: descartar wizard, volver al menú
+                    app.wizard = None;
+                    return Ok(false);
+                }
+                KeyCode::Backspace => {
+                    if let Some(ref mut w) = app.wizard {
+                        w.input.pop();
+                        w.selected = 0;
+                    }
+                }
+                KeyCode::Char(c) => {
+                    if let Some(ref mut w) = app.wizard {
+                        w.input.push(c);
+                        w.selected = 0;
+                    }
+                }
+                // En un {pick:...} las flechas recorren las opciones filtradas
+                KeyCode::Up => {
+                    if let Some(ref mut w) = app.wizard {
+                        w.move_selection(-1);
+                    }
+                }
+                KeyCode::Down => {
+                    if let Some(ref mut w) = app.wizard {
+                        w.move_selection(1);
+                    }
+                }
+                KeyCode::Enter => {
+                    let done = app
+                        .wizard
+                        .as_mut()
+                        .map(|w| w.confirm_current())
+                        .unwrap_or(true);
+
+                    if done {
+                        // Último campo confirmado: ejecutar (puede pedir cerrar la app)
+                        return app.finish_wizard(terminal);
+                    }
+                    // Si no es el último, el loop redibuja con el siguiente campo;
+                    // un {pick:...} que no pudo cargar sus opciones cancela el wizard
+                    app.prepare_wizard_step();
+                    if app.wizard.is_none() {
+                        return Ok(false);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Loop bloqueante del modal de confirmación.
+/// Retorna true si el usuario confirmó (Sí), false si canceló (No).
+pub fn run_confirmation_modal<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+) -> Result<bool, TmenuError> {
+    loop {
+        terminal
+            .draw(|f| ui::ui(f, app))
+            .map_err(|e| TmenuError::TerminalError(e.to_string()))?;
+
+        if let Event::Key(key) = app
+            .events
+            .read()
+            .map_err(|e| TmenuError::EventError(e.to_string()))?
+        {
+            if key.kind != event::KeyEventKind::Press {
+                continue;
+            }
+
+            // Ctrl+Q (o la tecla de `quit`) cancela y sale de la app
+            if app.keymap.command(&key) == Some(Action::Quit) {
+                app.confirmation = None;
+                return Err(TmenuError::EventError("Cancelado por Ctrl+Q".to_string()));
+            }
+
+            // Con frase requerida las teclas escriben en el campo; Enter solo
+            // confirma si la frase coincide exactamente
+            if let Some(conf) = app
+                .confirmation
+                .as_mut()
+                .filter(|c| c.required_text.is_some())
+            {
+                match key.code {
+                    KeyCode::Char(c) => conf.input.push(c),
+                    KeyCode::Backspace => {
+                        conf.input.pop();
+                    }
+                    KeyCode::Enter if conf.is_confirmed() => {
+                        app.confirmation = None;
+                        return Ok(true);
+                    }
+                    KeyCode::Esc => {
+                        app.confirmation = None;
+                        return Ok(false);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                // Up/Down navega entre "Sí" y "No"
+                KeyCode::Up | KeyCode::Left => {
+                    if let Some(ref mut conf) = app.confirmation {
+                        conf.toggle();
+                    }
+                }
+                KeyCode::Down | KeyCode::Right => {
+                    if let Some(ref mut conf) = app.confirmation {
+                        conf.toggle();
+                    }
+                }
+                // Enter confirma la selección actual
+                KeyCode::Enter => {
+                    let confirmed = app
+                        .confirmation
+                        .as_ref()
+                        .map(|c| c.is_confirmed())
+                        .unwrap_or(false);
+                    app.confirmation = None;
+                    return Ok(confirmed);
+                }
+                // Esc o N = cancelar (ir a "No" implícitamente)
+                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+                    app.confirmation = None;
+                    return Ok(false);
+                }
+                // Y = confirmar (ir a "Sí" implícitamente)
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    app.confirmation = None;
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Maneja teclas con el panel de salida capturada abierto.
+fn handle_output_mode<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    key: KeyCode,
+) -> Result<(), TmenuError> {
+    let Some(output) = app.output.as_mut() else {
+        return Ok(());
+    };
+
+    // Escribiendo en el pie: búsqueda o archivo donde guardar
+    if let Some(input) = output.input.as_mut() {
+        let text = match input {
+            OutputInput::Search(text) | OutputInput::Save(text) => text,
+        };
+        match key {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Esc => output.input = None,
+            KeyCode::Enter => match output.input.take() {
+                Some(OutputInput::Search(query)) if !query.is_empty() => {
+                    output.query = Some(query);
+                    if !output.find(true) {
+                        app.status_message = Some("Sin coincidencias".to_string());
+                    }
+                }
+                Some(OutputInput::Save(path)) if !path.is_empty() => {
+                    app.status_message = Some(match output.save(&path) {
+                        Ok(path) => format!("Salida guardada en {}", path.display()),
+                        Err(e) => format!("no se pudo guardar '{}': {}", path, e),
+                    });
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    // Una página: la altura del panel (toda la pantalla o la mitad), aproximada
+    let height = terminal.size().map(|s| s.height).unwrap_or(24);
+    let page = if output.fullscreen {
+        height.saturating_sub(2)
+    } else {
+        height / 2
+    }
+    .max(1) as isize;
+    match key {
+        KeyCode::Down => output.scroll_by(1),
+        KeyCode::Up => output.scroll_by(-1),
+        KeyCode::PageDown => output.scroll_by(page),
+        KeyCode::PageUp => output.scroll_by(-page),
+        KeyCode::Home => output.scroll = 0,
+        KeyCode::End => output.scroll_by(isize::MAX),
+        KeyCode::Char('p') => {
+            let text = output.lines.join("\n");
+            // Un $PAGER que no existe no debe cerrar tmenu: se avisa en la barra
+            if let Err(e) = pager::open_in_pager(terminal, &text) {
+                app.status_message = Some(e.to_string().trim_end().to_string());
+            }
+        }
+        KeyCode::Char('f') => output.fullscreen = !output.fullscreen,
+        KeyCode::Char('/') => output.input = Some(OutputInput::Search(String::new())),
+        // Siguiente (n) o anterior (N) coincidencia de la búsqueda
+        KeyCode::Char(c @ ('n' | 'N')) if output.query.is_some() && !output.find(c == 'n') => {
+            app.status_message = Some("Sin coincidencias".to_string());
+        }
+        KeyCode::Char('w') => {
+            let name = format!("{}.log", output.title.replace(['/', '\\', ' '], "_"));
+            output.input = Some(OutputInput::Save(name));
+        }
+        // Esc primero deja de resaltar la búsqueda, después cierra
+        KeyCode::Esc if output.query.is_some() => output.query = None,
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Left => app.output = None,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Maneja teclas con un popup de texto abierto: desplazar o cerrar.
+fn handle_popup_mode<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, key: KeyCode) {
+    let Some(help) = app.popup.as_mut() else {
+        return;
+    };
+    let page = (terminal.size().map(|s| s.height).unwrap_or(24) / 2).max(1) as isize;
+    match key {
+        KeyCode::Down => help.scroll_by(1),
+        KeyCode::Up => help.scroll_by(-1),
+        KeyCode::PageDown | KeyCode::Char(' ') => help.scroll_by(page),
+        KeyCode::PageUp => help.scroll_by(-page),
+        KeyCode::Home => help.scroll = 0,
+        KeyCode::End => help.scroll_by(isize::MAX),
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Left | KeyCode::Char('?' | 'i' | 'p' | 'q') => {
+            app.popup = None
+        }
+        _ => {}
+    }
+}
+
+/// Maneja teclas con el origen de un ítem abierto: abrir el editor en esa
+/// línea o cerrar. Al volver del editor, la recarga automática toma los cambios.
+fn handle_source_mode<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Char('e') | KeyCode::Enter => {
+            let Some(view) = app.source_view.take() else {
+                return;
+            };
+            let Some(file) = view.location.file else {
+                app.status_message = Some("El menú no se leyó de un archivo".to_string());
+                return;
+            };
+            if let Err(e) = editor::open_in_editor(terminal, &file, view.location.line) {
+                app.status_message = Some(e.to_string().trim_end().to_string());
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('s' | 'q') => app.source_view = None,
+        _ => {}
+    }
+}
+
+/// Maneja teclas con la vista de trabajos abierta. No es un loop bloqueante:
+/// el loop principal sigue con sus ticks y los trabajos se actualizan en vivo.
+fn handle_jobs_mode(app: &mut App, key: KeyCode) {
+    let len = app.jobs.len();
+    let Some(state) = app.jobs_view.as_mut() else {
+        return;
+    };
+    let selected = state.selected().unwrap_or(0);
+    match key {
+        KeyCode::Down | KeyCode::Char('j') => state.select(Some((selected + 1) % len)),
+        KeyCode::Up | KeyCode::Char('k') => state.select(Some((selected + len - 1) % len)),
+        // La salida se abre encima de la vista; al cerrarla se vuelve acá
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => app.show_job_output(selected),
+        KeyCode::Char('x') => app.kill_job(selected),
+        KeyCode::Char('d') => app.dismiss_job(selected),
+        KeyCode::Esc | KeyCode::Left | KeyCode::Char('h' | 'J' | 'q') => app.jobs_view = None,
+        _ => {}
+    }
+}
+
+/// Maneja teclas con la vista de variables abierta. Mientras se edita un
+/// valor las teclas escriben en el campo; Enter guarda y Esc descarta.
+fn handle_vars_mode(app: &mut App, key: KeyCode) {
+    let len = app.vars.len();
+    let Some(view) = app.vars_view.as_mut() else {
+        return;
+    };
+    let selected = view.state.selected().unwrap_or(0);
+
+    if let Some(input) = view.editing.as_mut() {
+        match key {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let value = view.editing.take().unwrap_or_default();
+                app.vars.update(selected, &value);
+            }
+            KeyCode::Esc => view.editing = None,
+            _ => {}
+        }
+        return;
+    }
+
+    match key {
+        KeyCode::Down | KeyCode::Char('j') => view.state.select(Some((selected + 1) % len)),
+        KeyCode::Up | KeyCode::Char('k') => view.state.select(Some((selected + len - 1) % len)),
+        KeyCode::Enter | KeyCode::Char('e') => {
+            view.editing = app.vars.list().get(selected).map(|var| var.value.clone());
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            app.vars.remove(selected);
+            if app.vars.is_empty() {
+                app.vars_view = None;
+            } else {
+                view.state.select(Some(selected.min(app.vars.len() - 1)));
+            }
+        }
+        KeyCode::Esc | KeyCode::Left | KeyCode::Char('v') | KeyCode::Char('q') => {
+            app.vars_view = None
+        }
+        _ => {}
+    }
+}
+
+/// Maneja teclas con la vista de árbol abierta: →/← despliegan y pliegan,
+/// Enter ejecuta el ítem en su lugar del menú. Retorna `true` si la app debe
+/// cerrarse.
+fn handle_tree_mode<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    key: KeyCode,
+) -> Result<bool, TmenuError> {
+    let len = app
+        .tree_view
+        .as_ref()
+        .map_or(0, |view| app.tree_rows(view).len());
+    let selected_row = app.tree_selected();
+    let Some(view) = app.tree_view.as_mut().filter(|_| len > 0) else {
+        app.tree_view = None;
+        return Ok(false);
+    };
+    let selected = view.state.selected().unwrap_or(0);
+    match key {
+        KeyCode::Down | KeyCode::Char('j') => view.state.select(Some((selected + 1) % len)),
+        KeyCode::Up | KeyCode::Char('k') => view.state.select(Some((selected + len - 1) % len)),
+        KeyCode::Right | KeyCode::Char('l') => app.tree_expand(true),
+        KeyCode::Left | KeyCode::Char('h') => app.tree_expand(false),
+        KeyCode::Char('*') => app.tree_expand_all(),
+        // Enter sobre un submenú del archivo lo despliega o lo pliega
+        KeyCode::Enter => match selected_row {
+            Some(row) if matches!(row.item.action, model::MenuAction::OpenSubmenu(_)) => {
+                app.tree_expand(!row.expanded)
+            }
+            Some(_) if app.leave_tree_view() => {
+                let items = app.current_items.clone();
+                return app.activate_item(terminal, &items);
+            }
+            _ => {}
+        },
+        // `g` cierra la vista dejando el menú en el lugar del ítem
+        KeyCode::Char('g') => {
+            app.leave_tree_view();
+        }
+        KeyCode::Esc | KeyCode::Char('t' | 'q') => app.tree_view = None,
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Maneja teclas en modo búsqueda.
+/// Ahora las teclas de navegación (↑↓) funcionan sobre el menú filtrado en vivo.
+/// Recibe la tecla ya leída por el loop — sin segundo event::read().
+fn handle_search_mode<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    key: KeyEvent,
+) -> Result<bool, TmenuError> {
+    // Las acciones en teclas que no se escriben (Tab, F2, F3) siguen
+    // funcionando mientras se busca
+    match app.keymap.command(&key) {
+        // Tab cierra la búsqueda y mantiene el menú actual
+        Some(Action::Search | Action::Filter) => {
+            app.search_mode = false;
+            return Ok(false);
+        }
+        // Ctrl+F pasa a buscar en todo el árbol con el mismo texto, o cierra
+        Some(Action::GlobalSearch) => {
+            app.search_mode = !app.global_search;
+            app.global_search = true;
+            app.filter_level = false;
+            app.state.select(Some(0));
+            return Ok(false);
+        }
+        // Ctrl+G: ir al lugar del resultado sin ejecutarlo
+        Some(Action::Locate) if app.global_search => {
+            if !app.locate_result() {
+                app.status_message = Some("no se encontró el ítem".to_string());
+            }
+            return Ok(false);
+        }
+        // F2: toggle preview (funciona durante búsqueda)
+        Some(Action::Preview) => {
+            app.show_preview = !app.show_preview;
+            return Ok(false);
+        }
+        Some(Action::DryRun) => {
+            toggle_dry_run(app);
+            return Ok(false);
+        }
+        Some(Action::PageDown) => {
+            app.move_page(1);
+            return Ok(false);
+        }
+        Some(Action::PageUp) => {
+            app.move_page(-1);
+            return Ok(false);
+        }
+        Some(action @ (Action::First | Action::Last)) => {
+            app.select_edge(action == Action::Last);
+            return Ok(false);
+        }
+        _ => {}
+    }
+    match key.code {
+        // Esc cancela la búsqueda: limpia el texto y vuelve al menú completo
+        KeyCode::Esc => {
+            app.search_mode = false;
+            app.search_text.clear();
+            app.state.select(Some(0));
+        }
+        // ← →: columna anterior o siguiente, si la lista está en grilla
+        KeyCode::Left => {
+            app.move_column(-1);
+        }
+        KeyCode::Right => {
+            app.move_column(1);
+        }
+        // Backspace: elimina carácter del filtro y reselecciona primer item
+        KeyCode::Backspace => {
+            app.search_text.pop();
+            app.state.select(Some(0));
+        }
+        // ↑ ↓: navegación en el menú filtrado
+        KeyCode::Up => {
+            let filtered = app.filtered_items();
+            app.step(&filtered, false, true);
+        }
+        KeyCode::Down => {
+            let filtered = app.filtered_items();
+            app.step(&filtered, true, true);
+        }
+        // Enter: ejecuta el item filtrado seleccionado. Un submenú hallado
+        // en todo el árbol se abre en su lugar, así la ruta del título es la real
+        KeyCode::Enter => {
+            let filtered = app.filtered_items();
+            let selected = app.state.selected().and_then(|i| filtered.get(i));
+            if app.global_search
+                && selected.is_some_and(|item| item.action.is_submenu())
+                && app.locate_result()
+            {
+                let items = app.current_items.clone();
+                return app.activate_item(terminal, &items);
+            }
+            if !filtered.is_empty() && app.activate_item(terminal, &filtered)? {
+                return Ok(true);
+            }
+        }
+        // Cualquier otro carácter: agregar al filtro y resetear a primer item
+        KeyCode::Char(c) => {
+            app.search_text.push(c);
+            app.state.select(Some(0));
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Clic izquierdo en el menú: selecciona el ítem, lo ejecuta con doble clic
+/// o vuelve al menú anterior desde el `[<-] Volver` del pie. Con un panel o
+/// una vista abierta el clic no hace nada.
+fn handle_click<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    column: u16,
+    row: u16,
+) -> Result<bool, TmenuError> {
+    if app.output.is_some()
+        || app.popup.is_some()
+        || app.source_view.is_some()
+        || app.jobs_view.is_some()
+        || app.vars_view.is_some()
+        || app.tree_view.is_some()
+    {
+        return Ok(false);
+    }
+    match app.click_target(column, row) {
+        Some(ClickTarget::Back) => {
+            app.back();
+        }
+        Some(ClickTarget::Item(index)) if app.click_item(index) => {
+            let items = app.filtered_items();
+            return app.activate_item(terminal, &items);
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Rueda del mouse: desplaza el panel de salida o el popup abierto, o mueve
+/// la selección del menú (sin dar la vuelta al llegar a un extremo).
+fn handle_scroll(app: &mut App, delta: isize) {
+    // Cada paso de la rueda desplaza varias líneas de texto
+    const TEXT_LINES: isize = 3;
+
+    if let Some(output) = &mut app.output {
+        output.scroll_by(delta * TEXT_LINES);
+    } else if let Some(help) = &mut app.popup {
+        help.scroll_by(delta * TEXT_LINES);
+    } else if let Some(view) = &mut app.tree_view {
+        // En la vista de árbol la rueda mueve la selección, sin dar la vuelta
+        let selected = view.state.selected().unwrap_or(0);
+        view.state
+            .select(Some(selected.saturating_add_signed(delta)));
+    } else if app.source_view.is_none() && app.jobs_view.is_none() && app.vars_view.is_none() {
+        app.move_selection(delta);
+    }
+}
+
+/// Maneja teclas en modo navegacion normal.
+fn handle_navigation_mode<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    key: KeyEvent,
+) -> Result<bool, TmenuError> {
+    // `gg` son dos teclas: la primera `g` queda pendiente hasta la siguiente
+    let pending_g = std::mem::take(&mut app.pending_g);
+    match key.code {
+        // Las teclas de acceso rápido del menú tienen prioridad sobre los atajos
+        KeyCode::Char(c) if app.select_hotkey(c) => {
+            let items = app.filtered_items();
+            return app.activate_item(terminal, &items);
+        }
+        // Además de las teclas de movimiento, `gg` va al primer ítem
+        KeyCode::Char('g') if pending_g => {
+            app.select_edge(false);
+            return Ok(false);
+        }
+        KeyCode::Char('g') if app.keymap.action(&key).is_none() => {
+            app.pending_g = true;
+            return Ok(false);
+        }
+        // 1–9: el N-ésimo ítem visible
+        KeyCode::Char(c @ '1'..='9') if app.quick_select(c as usize - '0' as usize) => {
+            let items = app.filtered_items();
+            return app.activate_item(terminal, &items);
+        }
+        // En la grilla las flechas laterales pasan de columna
+        KeyCode::Left if app.move_column(-1) => return Ok(false),
+        KeyCode::Right if app.move_column(1) => return Ok(false),
+        _ => {}
+    }
+    // Con pestañas, sus teclas van antes que las de otras acciones (Tab busca)
+    if !app.tab_labels().is_empty() {
+        for (action, forward) in [(Action::NextTab, true), (Action::PrevTab, false)] {
+            if app.keymap.is_bound(action, &key) {
+                app.switch_tab(forward);
+                return Ok(false);
+            }
+        }
+    }
+    let Some(action) = app.keymap.action(&key) else {
+        return Ok(false);
+    };
+    match action {
+        Action::Search => {
+            app.search_mode = true;
+            app.filter_level = false;
+            app.global_search = false;
+        }
+        Action::Filter => {
+            app.search_mode = true;
+            app.filter_level = true;
+            app.global_search = false;
+        }
+        Action::GlobalSearch => {
+            app.search_mode = true;
+            app.filter_level = false;
+            app.global_search = true;
+        }
+        // Solo tiene sentido sobre un resultado de la búsqueda global
+        Action::Locate => {}
+        Action::Down => app.next(),
+        Action::Up => app.previous(),
+        Action::First => app.select_edge(false),
+        Action::Last => app.select_edge(true),
+        Action::PageDown => app.move_page(1),
+        Action::PageUp => app.move_page(-1),
+        Action::Home => app.go_home(),
+        Action::NextTab => app.switch_tab(true),
+        Action::PrevTab => app.switch_tab(false),
+        Action::Preview => app.show_preview = !app.show_preview,
+        Action::DryRun => toggle_dry_run(app),
+        Action::Help => return open_help(terminal, app),
+        Action::Quit => return Ok(true),
+        Action::Jobs => app.open_jobs_view(),
+        Action::Vars => app.open_vars_view(),
+        Action::Tree => app.open_tree_view(),
+        Action::ShowHidden => app.toggle_hidden(),
+        Action::Details => {
+            let items = app.filtered_items();
+            app.open_command_details(&items);
+        }
+        Action::ItemHelp => {
+            let items = app.filtered_items();
+            app.open_item_help(&items);
+        }
+        Action::Source => {
+            let items = app.filtered_items();
+            app.open_source_view(&items);
+        }
+        Action::Copy => {
+            let items = app.filtered_items();
+            app.copy_command(&items);
+        }
+        Action::Rerun => return app.rerun_last(terminal),
+        Action::Favorite => {
+            let items = app.filtered_items();
+            app.toggle_favorite(&items);
+        }
+        Action::ExpandCommand => app.status_expanded = !app.status_expanded,
+        Action::Refresh => app.refresh_probes(),
+        Action::Enter => {
+            let items = app.filtered_items();
+            return app.activate_item(terminal, &items);
+        }
+        // En el menú raíz, volver sale de la app
+        Action::Back => return Ok(!app.back()),
+    }
+    Ok(false)
+}
+
+/// Navega hasta el ítem de `path` para un pedido `select` o `run`.
+fn select_path(app: &mut App, path: &[String]) -> Result<(), String> {
+    if app.jump_to(path) {
+        Ok(())
+    } else {
+        Err(format!("no existe el ítem '{}'", path.join("/")))
+    }
+}
+
+/// Activa o desactiva el modo simulación (`F3`).
+fn toggle_dry_run(app: &mut App) {
+    app.dry_run = !app.dry_run;
+    app.status_message = Some(if app.dry_run {
+        "Simulación activada: los comandos se muestran sin ejecutarse".to_string()
+    } else {
+        "Simulación desactivada".to_string()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use ratatui::backend::TestBackend;
+
+    const MENU: &str = "\"Menu\":\n    Git:\n        Estado: \"git status\"\n        Log: \"git log\"\n    Salir: exit\n";
+
+    /// Las filas de la pantalla dibujada, como texto.
+    fn screen(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    fn run(app: &mut App, codes: Vec<KeyCode>) -> (Terminal<TestBackend>, Result<(), TmenuError>) {
+        let quit = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        let events = codes
+            .into_iter()
+            .map(|code| Event::Key(KeyEvent::from(code)))
+            .chain([Event::Key(quit)]);
+        app.events = Box::new(ScriptedEvents::new(events));
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        let result = run_app(&mut terminal, app);
+        (terminal, result)
+    }
+
+    #[test]
+    fn test_run_app_navigates_into_a_submenu() {
+        let mut app = App::from_toon_str(MENU, false);
+        let (terminal, result) = run(&mut app, vec![KeyCode::Enter]);
+        assert!(result.is_ok());
+        assert_eq!(app.current_title, "Git");
+        assert_eq!(app.state.selected(), Some(0));
+
+        let rows: Vec<String> = screen(&terminal)
+            .iter()
+            .map(|row| row.trim().to_string())
+            .collect();
+        assert_eq!(
+            rows[5..9],
+            [
+                "╭───── Menu › Git ─────╮",
+                "│                      │",
+                "│ ➤  Estado            │",
+                "│    Log               │",
+            ]
+        );
+        // La barra inferior muestra el comando seleccionado
+        assert_eq!(rows[19], "$ git status");
+
+        // Abajo y de vuelta a la raíz, con la selección en el submenú
+        let (terminal, _) = run(&mut app, vec![KeyCode::Char('j'), KeyCode::Esc]);
+        assert_eq!(app.current_title, "Menu");
+        assert_eq!(app.state.selected(), Some(0));
+        assert!(!screen(&terminal).iter().any(|row| row.contains("Volver")));
+    }
+
+    #[test]
+    fn test_run_app_filters_while_searching() {
+        let mut app = App::from_toon_str(MENU, false);
+        let (terminal, result) = run(
+            &mut app,
+            vec![KeyCode::Char('/'), KeyCode::Char('s'), KeyCode::Char('a')],
+        );
+        assert!(result.is_ok());
+        assert!(app.search_mode);
+        assert_eq!(app.search_text, "sa");
+        let rows = screen(&terminal);
+        assert!(rows.iter().any(|row| row.contains("Salir")));
+        assert!(!rows.iter().any(|row| row.contains("Git ")));
+    }
+
+    #[test]
+    fn test_typed_characters_are_not_logged() {
+        let mut app = App::from_toon_str(MENU, false);
+        let key = |code| KeyEvent::from(code);
+        assert_eq!(
+            key_log(&app, &key(KeyCode::Char('j'))).as_deref(),
+            Some("tecla j: Some(Down)")
+        );
+        app.search_mode = true;
+        assert_eq!(key_log(&app, &key(KeyCode::Char('s'))), None);
+        assert!(key_log(&app, &key(KeyCode::Enter)).is_some());
+        let ctrl_g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        assert!(key_log(&app, &ctrl_g).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_app_answers_control_requests() {
+        use crate::control::ControlSocket;
+        use std::io::{Read, Write};
+        use std::os::unix::net::UnixStream;
+
+        let path = std::env::temp_dir().join(format!("tmenu-tui-{}.sock", std::process::id()));
+        let mut app = App::from_toon_str(MENU, false);
+        app.control = Some(ControlSocket::bind(&path).unwrap());
+        let mut found = UnixStream::connect(&path).unwrap();
+        found.write_all(b"select Git/Log\n").unwrap();
+        let mut missing = UnixStream::connect(&path).unwrap();
+        missing.write_all(b"select Git/Push\n").unwrap();
+
+        let (_, result) = run(&mut app, vec![]);
+        assert!(result.is_ok());
+        let mut reply = String::new();
+        found.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "ok\n");
+        reply.clear();
+        missing.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "error: no existe el ítem 'Git/Push'\n");
+        // El segundo pedido dejó la navegación donde llegó
+        assert_eq!(app.current_title, "Git");
+    }
+
+    #[test]
+    fn test_scripted_events_end_the_loop_when_exhausted() {
+        let mut app = App::from_toon_str(MENU, false);
+        app.events = Box::new(ScriptedEvents::keys([KeyCode::Char('j')]));
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        let result = run_app(&mut terminal, &mut app);
+        assert!(matches!(result, Err(TmenuError::EventError(_))));
+        assert_eq!(app.state.selected(), Some(1));
+    }
+}
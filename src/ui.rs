@@ -1,51 +1,37 @@
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::Line,
-    widgets::{Block, BorderType, Borders, List, ListItem, Padding, Paragraph},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph, Tabs, Wrap},
 };
 
-use crate::app::App;
-use crate::model::MenuAction;
+use crate::app::{self, App, OutputInput};
+use crate::jobs::JobStatus;
+use crate::keymap::Action;
+use crate::model::{Columns, MenuAction, MenuItem};
+use crate::theme::Theme;
+use crate::widget::{TmenuWidget, child_count, spinner_frame, truncate_to_width};
+use unicode_width::UnicodeWidthStr;
 
-// ═══════════════════════════════════════════════════════════════
-// PALETA DE COLORES ELEGANTE Y COHERENTE
-// ═══════════════════════════════════════════════════════════════
-// Inspirada en interfaces modernas minimalistas con tonos sofisticados
+/// Ancho mínimo de terminal con el que la interfaz es utilizable.
+const MIN_TERMINAL_WIDTH: u16 = 30;
 
-/// Color de bordes principal: azul grisáceo sutil
-const COLOR_BORDER_PRIMARY: Color = Color::Rgb(100, 130, 160);
+/// Alto mínimo de terminal: caja mínima del menú más la barra de búsqueda.
+const MIN_TERMINAL_HEIGHT: u16 = 11;
 
-/// Color de bordes secundario: más oscuro, para énfasis
-const COLOR_BORDER_ACCENT: Color = Color::Rgb(80, 110, 150);
-
-/// Color de fondo para selección: azul profundo muy suave
-const COLOR_HIGHLIGHT_BG: Color = Color::Rgb(40, 60, 100);
-
-/// Color de texto resaltado: blanco con ligerísimo azul
-const COLOR_HIGHLIGHT_FG: Color = Color::Rgb(220, 230, 245);
-
-/// Color para búsqueda exitosa: verde agua sutil
-const COLOR_SEARCH_SUCCESS: Color = Color::Rgb(100, 170, 140);
-
-/// Color para búsqueda fallida: rojo suave
-const COLOR_SEARCH_FAIL: Color = Color::Rgb(200, 100, 110);
-
-/// Color para comandos/ejecutables: cyan sutil
-const COLOR_COMMAND: Color = Color::Rgb(120, 180, 200);
-
-/// Color para texto secundario/gris: neutro elegante
-const COLOR_SECONDARY: Color = Color::Rgb(140, 150, 170);
-
-///Color para el dialogo de confirmación: naranja suave
-const COLOR_CONFIRMATION: Color = Color::Rgb(209, 85, 85);
-
-// /// Color para errores y alertas: rojo moderno
-// const COLOR_ERROR: Color = Color::Rgb(210, 110, 120);
+/// Líneas máximas de la barra inferior con el comando completo.
+const MAX_STATUS_BAR_LINES: u16 = 5;
 
 /// Renderiza la interfaz completa en cada ciclo de dibujado.
 pub fn ui(f: &mut Frame, app: &mut App) {
+    let screen = f.area();
+    if screen.width < MIN_TERMINAL_WIDTH || screen.height < MIN_TERMINAL_HEIGHT {
+        render_too_small(f, screen, &app.theme);
+        app.layout = Default::default();
+        return;
+    }
+
     let items_to_render = app.filtered_items();
 
     // Ajustar seleccion si esta fuera de rango (puede pasar al filtrar)
@@ -53,48 +39,176 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         && app
             .state
             .selected()
-            .map_or(false, |i| i >= items_to_render.len())
+            .is_some_and(|i| i >= items_to_render.len())
     {
         app.state.select(Some(0));
     }
+    app.skip_disabled(&items_to_render);
 
     // Clonar titulo para liberar el borrow inmutable antes de pasar app como mutable.
     let title = app.breadcrumb();
 
     // Calcular dimensiones a partir de items_to_render (lo que realmente se dibuja),
-    // no de current_items. Usar chars().count() para ancho visual correcto con Unicode.
-    let title_w = title.chars().count();
-    // Dimensiones basadas en current_items para que el box no salte al filtrar
+    // no de current_items. Se mide en columnas de la terminal (`width()`): los
+    // caracteres CJK y muchos emoji ocupan dos.
+    let title_w = title.width();
+    // Dimensiones basadas en current_items para que el box no salte al filtrar,
+    // con la cantidad de ítems de los submenús (` (12)`)
     let max_label_w = app
         .current_items
         .iter()
-        .map(|item| item.label.chars().count())
+        .map(|item| {
+            let count_w = child_count(item).map_or(0, |count| count.to_string().len() + 3);
+            item.label.width() + count_w
+        })
         .max()
         .unwrap_or(0);
-    let max_w = max_label_w.max(title_w);
+    // Salvo en la búsqueda global, donde los resultados vienen de todo el
+    // árbol y llevan su ubicación al lado
+    let locations = global_locations(app);
+    let max_label_w = items_to_render
+        .iter()
+        .zip(&locations)
+        .map(|(item, location)| item.label.width() + 2 + location.width())
+        .fold(max_label_w, usize::max);
+    // Y los estados de `[status=...]`, que van al lado de la etiqueta
+    let current_items = app.current_items.clone();
+    let max_label_w = current_items
+        .iter()
+        .filter_map(|item| {
+            let badge = app.status_badge(item)?;
+            Some(item.label.width() + 2 + badge.text.width())
+        })
+        .fold(max_label_w, usize::max);
+    // Las teclas de acceso rápido (`[2] `) ocupan 4 columnas antes de la etiqueta
+    let hotkey_w = if app.current_items.iter().any(|item| item.hotkey.is_some()) {
+        4
+    } else {
+        0
+    };
+    let max_w = (max_label_w + hotkey_w).max(title_w);
+    // Con `tabs:`, una fila arriba del menú con las pestañas (` Docker │ Git `)
+    let tabs = app.tab_labels();
+    let tabs_w = tabs.iter().map(|tab| tab.width() + 3).sum::<usize>();
+    let tabs_h = u16::from(!tabs.is_empty());
+
+    // Barra inferior con lo que hará Enter, si entra sin achicar el menú
+    // por debajo del mínimo
+    let fullscreen = app.output.as_ref().is_some_and(|o| o.fullscreen);
+    let status = app
+        .state
+        .selected()
+        .and_then(|i| items_to_render.get(i))
+        .map(|item| enter_preview(item, &app.theme))
+        .filter(|_| app.status_bar && !fullscreen);
+    let status_h = match &status {
+        Some((prefix, text)) if app.status_expanded => {
+            let chars = (prefix.width() + text.width() + 2) as u16;
+            chars.div_ceil(screen.width).clamp(1, MAX_STATUS_BAR_LINES)
+        }
+        Some(_) => 1,
+        None => 0,
+    };
+    let status_h = if screen.height >= MIN_TERMINAL_HEIGHT + status_h {
+        status_h
+    } else {
+        0
+    };
+    let screen = Rect {
+        height: screen.height - status_h,
+        ..screen
+    };
+    if let Some((prefix, text)) = status.filter(|_| status_h > 0) {
+        let area = Rect::new(screen.x, screen.bottom(), screen.width, status_h);
+        render_status_bar(f, app, &prefix, &text, area);
+    }
+
+    // Con el panel de salida abierto, el menú se centra en la parte superior
+    let (menu_screen, output_area) = if fullscreen {
+        (screen, Some(screen))
+    } else if app.output.is_some() {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(MIN_TERMINAL_HEIGHT),
+                Constraint::Percentage(45),
+            ])
+            .split(screen);
+        (split[0], Some(split[1]))
+    } else {
+        (screen, None)
+    };
 
-    let box_width = (max_w + 14).max(24) as u16;
-    // Altura fija al máximo del nivel actual (no al filtrado)
-    let box_height = (app.current_items.len() + 7).max(8) as u16;
+    let len = app.current_items.len();
+    // Alto que no es de ítems: barra de búsqueda, bordes y relleno de la lista
+    let chrome = 5 + 2 * app.theme.padding as usize;
+    let columns = menu_columns(
+        app.columns,
+        len,
+        max_label_w + hotkey_w,
+        (menu_screen.height as usize).saturating_sub(chrome),
+        menu_screen.width as usize,
+    );
+    app.layout.columns = columns;
+    let window = &app.window;
+    let (box_width, box_height) = if columns > 1 {
+        // Cada columna: flecha de selección, etiqueta, marca de submenú y separación
+        let column_w = max_label_w + hotkey_w + GRID_COLUMN_EXTRA;
+        let grid_w = (columns * column_w + 2).max(title_w + window.extra_width as usize);
+        let grid_w = window.box_width(grid_w as u16, menu_screen.width);
+        (grid_w, (len.div_ceil(columns) + chrome) as u16)
+    } else {
+        // Altura fija al máximo del nivel actual (no al filtrado)
+        (
+            window.box_width(max_w as u16, menu_screen.width),
+            window.box_height((len + chrome) as u16, menu_screen.height),
+        )
+    };
 
-    let area = centered_rect(box_width, box_height, f.area());
+    let box_width = box_width.max(tabs_w as u16);
+    let area = window.place(box_width, box_height + tabs_h, menu_screen);
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(3),    // Lista (crece)
-            Constraint::Length(3), // Barra de busqueda (fija)
+            Constraint::Length(tabs_h), // Pestañas (con `tabs:`)
+            Constraint::Min(3),         // Lista (crece)
+            Constraint::Length(3),      // Barra de busqueda (fija)
         ])
         .split(area);
 
+    if !tabs.is_empty() {
+        render_tabs(f, app, tabs, chunks[0]);
+    }
+    let chunks = &chunks[1..];
     let menu_area = chunks[0];
     render_menu_list(f, app, &items_to_render, menu_area, &title);
     render_search_bar(f, app, chunks[1]);
+    render_status_message(f, app, chunks[1]);
+    if app.jobs_view.is_some() {
+        render_jobs_panel(f, app, menu_screen);
+    }
+    if app.vars_view.is_some() {
+        render_vars_panel(f, app, menu_screen);
+    }
+    if app.tree_view.is_some() {
+        render_tree_panel(f, app, menu_screen);
+    }
+    if let Some(output_area) = output_area {
+        f.render_widget(ratatui::widgets::Clear, output_area);
+        render_output_pane(f, app, output_area);
+    }
+    if app.popup.is_some() {
+        render_popup(f, app);
+    }
+    if app.source_view.is_some() {
+        render_source_view(f, app);
+    }
 
     if app.wizard.is_some() {
         render_wizard(f, app);
     } else if app.show_help {
-        render_help_modal(f);
+        render_help_modal(f, app);
     } else if app.confirmation.is_some() {
         render_confirmation_modal(f, app);
     } else {
@@ -103,6 +217,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 }
 
 fn render_wizard(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
     use ratatui::widgets::Clear;
 
     let Some(wizard) = &app.wizard else { return };
@@ -111,13 +226,35 @@ fn render_wizard(f: &mut Frame, app: &App) {
     let total = wizard.params.len();
     let current = wizard.current + 1; // 1-based para el usuario
 
-    // Título con progreso: "Branch name (1/3)"
-    let title = format!(" {} ({}/{}) ", param.label, current, total);
+    // Un {pick:...} muestra debajo del filtro hasta MAX_OPTIONS opciones
+    const MAX_OPTIONS: usize = 8;
+    let options = if wizard.is_picking() {
+        wizard.filtered_options()
+    } else {
+        Vec::new()
+    };
+    let list_h = if wizard.is_picking() {
+        options.len().clamp(1, MAX_OPTIONS) as u16
+    } else {
+        0
+    };
+
+    // Título con progreso: "Branch name (1/3)"; los pick llevan el comando como etiqueta
+    let title = if wizard.is_picking() {
+        format!(" Elegir ({}/{}) ", current, total)
+    } else {
+        format!(" {} ({}/{}) ", param.label, current, total)
+    };
+    let hints = if wizard.is_picking() {
+        " [\u{2191}\u{2193}] Elegir  [Enter] Confirmar  [Esc] Cancelar "
+    } else {
+        " [Enter] Confirmar  [Esc] Cancelar "
+    };
 
     let input_line = wizard.input.as_str();
 
     let popup_w: u16 = 54;
-    let popup_h: u16 = 9; // título + cmd preview + separador + input + hints + bordes
+    let popup_h: u16 = 9 + list_h; // título + cmd preview + separador + input + opciones + hints + bordes
     let area = centered_rect(popup_w, popup_h, f.area());
 
     f.render_widget(Clear, area);
@@ -125,10 +262,11 @@ fn render_wizard(f: &mut Frame, app: &App) {
     let inner = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(2), // línea de contexto del comando
-            Constraint::Length(1), // label del placeholder  ← NUEVO
-            Constraint::Length(3), // campo de input
-            Constraint::Length(1), // hints
+            Constraint::Length(2),      // línea de contexto del comando
+            Constraint::Length(1),      // label del placeholder  ← NUEVO
+            Constraint::Length(3),      // campo de input
+            Constraint::Length(list_h), // opciones del pick
+            Constraint::Length(1),      // hints
         ])
         .margin(1)
         .split(area);
@@ -137,28 +275,19 @@ fn render_wizard(f: &mut Frame, app: &App) {
     let block = Block::default()
         .title(title)
         .title_alignment(Alignment::Center)
-        .title_bottom(Line::from(" [Enter] Confirmar  [Esc] Cancelar ").centered())
+        .title_bottom(Line::from(hints).centered())
         .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(COLOR_BORDER_ACCENT));
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(theme.border_accent));
     f.render_widget(block, area);
 
     // Ancho disponible: ancho del popup menos márgenes y borde (popup_w - 4)
     let available_w = (popup_w as usize).saturating_sub(3);
     let cmd_str = format!("cmd: {}", wizard.original_cmd);
 
-    let cmd_display = if cmd_str.chars().count() > available_w {
-        // Truncar dejando espacio para "..."
-        let truncated: String = cmd_str
-            .chars()
-            .take(available_w.saturating_sub(3))
-            .collect();
-        format!("{}...", truncated)
-    } else {
-        cmd_str
-    };
+    let cmd_display = truncate_to_width(&cmd_str, available_w, "...");
 
-    let cmd_widget = Paragraph::new(cmd_display).style(Style::default().fg(COLOR_SECONDARY));
+    let cmd_widget = Paragraph::new(cmd_display).style(Style::default().fg(theme.secondary));
     f.render_widget(cmd_widget, inner[0]);
 
     // Label del campo actual: "Ingrese un nombre:"
@@ -174,7 +303,7 @@ fn render_wizard(f: &mut Frame, app: &App) {
     };
     let label_widget = Paragraph::new(label_text).style(
         Style::default()
-            .fg(COLOR_HIGHLIGHT_FG)
+            .fg(theme.highlight_fg)
             .add_modifier(Modifier::BOLD),
     );
     f.render_widget(label_widget, inner[1]);
@@ -184,17 +313,80 @@ fn render_wizard(f: &mut Frame, app: &App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(COLOR_COMMAND)),
+                .border_set(theme.border_set)
+                .border_style(Style::default().fg(theme.command)),
         )
-        .style(Style::default().fg(COLOR_COMMAND));
+        .style(Style::default().fg(theme.command));
     f.render_widget(input_widget, inner[2]);
 
+    if wizard.is_picking() {
+        // Ventana de opciones que siempre incluye la resaltada
+        let offset = wizard.selected.saturating_sub(MAX_OPTIONS - 1);
+        let lines: Vec<Line> = if options.is_empty() {
+            vec![Line::from(" (sin coincidencias)").style(Style::default().fg(theme.search_fail))]
+        } else {
+            options
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(MAX_OPTIONS)
+                .map(|(i, option)| {
+                    if i == wizard.selected {
+                        Line::from(format!("{}{}", theme.highlight_symbol, option)).style(
+                            Style::default()
+                                .bg(theme.highlight_bg)
+                                .fg(theme.highlight_fg)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        let pad = theme.highlight_symbol.width();
+                        Line::from(format!("{}{}", " ".repeat(pad), option))
+                    }
+                })
+                .collect()
+        };
+        f.render_widget(Paragraph::new(lines), inner[3]);
+    }
+
     // Cursor dentro del campo de input
-    let cursor_x = inner[2].x + wizard.input.chars().count() as u16 + 1;
+    let cursor_x = inner[2].x + wizard.input.width() as u16 + 1;
     let cursor_y = inner[2].y + 1;
     f.set_cursor_position((cursor_x, cursor_y));
 }
+
+/// Columnas de más por ítem en la grilla, además de la etiqueta: la flecha
+/// de selección, la marca de submenú y la separación con la siguiente.
+const GRID_COLUMN_EXTRA: usize = 7;
+
+/// Columnas de la lista según `columns:`: con `auto`, las que hagan falta para
+/// que los `len` ítems entren en `rows` filas. Nunca más de las que entran en
+/// `width`.
+fn menu_columns(columns: Columns, len: usize, label_w: usize, rows: usize, width: usize) -> usize {
+    let wanted = match columns {
+        Columns::Fixed(n) => n,
+        Columns::Auto => len.div_ceil(rows.max(1)),
+    };
+    let fit = width.saturating_sub(2) / (label_w + GRID_COLUMN_EXTRA);
+    wanted.min(fit).min(len).max(1)
+}
+
+/// Pestañas de `tabs:`, con la del nivel actual resaltada.
+fn render_tabs(f: &mut Frame, app: &App, labels: Vec<String>, area: Rect) {
+    let theme = &app.theme;
+    let divider = if theme.ascii { "|" } else { "│" };
+    let tabs = Tabs::new(labels)
+        .select(app.active_tab())
+        .divider(divider)
+        .style(Style::default().fg(theme.secondary))
+        .highlight_style(
+            Style::default()
+                .bg(theme.highlight_bg)
+                .fg(theme.highlight_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(tabs, area);
+}
+
 /// Renderiza la lista de items del menu.
 fn render_menu_list(
     f: &mut Frame,
@@ -203,75 +395,146 @@ fn render_menu_list(
     area: Rect,
     title: &str,
 ) {
-    let list_items: Vec<ListItem> = items_to_render
+    // Los ocultos, cuando se muestran, se atenúan como los deshabilitados
+    let disabled: Vec<bool> = items_to_render
         .iter()
-        .map(|item| {
-            let symbol = match item.action {
-                MenuAction::OpenSubmenu(_) => " \u{25b6}",
-                MenuAction::Quit => " \u{2717}", // ✗ símbolo de salida
-                _ => "",
-            };
-            ListItem::new(format!(" {}{}", item.label, symbol))
+        .map(|item| app.is_disabled(item) || item.hidden)
+        .collect();
+    let statuses: Vec<_> = items_to_render
+        .iter()
+        .map(|item| app.status_badge(item))
+        .collect();
+    let theme = &app.theme;
+    // Si el ítem seleccionado tiene ayuda propia, se avisa en el pie
+    let has_help = app
+        .state
+        .selected()
+        .and_then(|i| items_to_render.get(i))
+        .is_some_and(|item| item.help.is_some());
+    let footer = app
+        .hints
+        .footer(&app.keymap, !app.at_root(), has_help, app.dry_run);
+    let hint = footer.text;
+
+    let cooldowns: Vec<_> = items_to_render
+        .iter()
+        .map(|item| app.cooldown_remaining(item))
+        .collect();
+    let running: Vec<bool> = items_to_render
+        .iter()
+        .map(|item| app.is_running(item))
+        .collect();
+    let locations = global_locations(app);
+    let badges: Vec<_> = statuses
+        .iter()
+        .map(|badge| {
+            badge.as_ref().map(|badge| {
+                let color = if badge.ok {
+                    theme.search_success
+                } else {
+                    theme.search_fail
+                };
+                (badge.text.as_str(), Style::default().fg(color))
+            })
         })
         .collect();
 
-    let depth_hint = if app.history.is_empty() {
-        String::from(" [Ctrl+q] Salir ")
-    } else {
-        String::from(" [<-] Volver [Ctrl+q] Salir ")
-    };
-
-    let list = List::new(list_items)
-        .block(
-            Block::default()
-                .title(format!(" {} ", title))
-                .title_alignment(Alignment::Center)
-                .title_bottom(Line::from(depth_hint).right_aligned())
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(COLOR_BORDER_PRIMARY))
-                .padding(Padding::new(0, 0, 1, 1)),
-        )
+    let list = TmenuWidget::new(items_to_render)
+        .title(title)
+        .columns(app.layout.columns)
+        .cooldowns(&cooldowns)
+        .running(&running)
+        .disabled(&disabled)
+        .badges(&badges)
+        .locations(&locations)
+        .time_format(&app.time_format)
+        .hint(&hint)
+        .highlight_symbol(&theme.highlight_symbol)
+        .markers(&theme.submenu_symbol, &theme.quit_symbol)
+        .border_set(theme.border_set)
+        .ascii(theme.ascii)
+        .padding(theme.padding)
+        .border_style(Style::default().fg(theme.level_border(app.history.len())))
         .highlight_style(
             Style::default()
-                .bg(COLOR_HIGHLIGHT_BG)
-                .fg(COLOR_HIGHLIGHT_FG)
+                .bg(theme.highlight_bg)
+                .fg(theme.highlight_fg)
                 .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(" \u{27a4} "); // flecha
+        );
 
     f.render_stateful_widget(list, area, &mut app.state);
+
+    // Zonas para los clics: las filas de ítems (dentro del borde y el
+    // relleno del widget) y el `[Esc] Volver` del pie, alineado a la derecha
+    let padding = app.theme.padding;
+    app.layout.list = Rect::new(
+        area.x + 1,
+        area.y + 1 + padding,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2 + 2 * padding),
+    );
+    app.layout.back = footer.back.map(|(column, width)| {
+        let hint_x = area.right().saturating_sub(1 + hint.width() as u16);
+        Rect::new(
+            hint_x + column as u16,
+            area.bottom().saturating_sub(1),
+            width as u16,
+            1,
+        )
+    });
+}
+
+/// Submenú de cada resultado de la búsqueda global (`Docker › Logs`), vacío
+/// fuera de ella.
+fn global_locations(app: &App) -> Vec<String> {
+    if !app.search_mode || !app.global_search || app.search_text.is_empty() {
+        return Vec::new();
+    }
+    app.tree_results()
+        .into_iter()
+        .map(|found| found.path.join(" › "))
+        .collect()
 }
 
 /// Renderiza la barra de busqueda (solo en modo busqueda).
 fn render_search_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     if !app.search_mode {
         return;
     }
 
     // Contar resultados reales (sin el fallback)
-    let result_count =
-        crate::search::filter_recursive(&app.current_items, &app.search_text, 0).len();
+    let result_count = app.search_results().len();
+    let name = if app.global_search {
+        "Todo el menú"
+    } else if app.filter_level {
+        "Filtro"
+    } else {
+        "Búsqueda"
+    };
 
     let (title, border_color, subtitle) = if result_count > 0 && !app.search_text.is_empty() {
         (
-            format!(" 🔍 Búsqueda: {} resultados ", result_count),
-            COLOR_SEARCH_SUCCESS,
+            format!(" 🔍 {}: {} resultados ", name, result_count),
+            theme.search_success,
             " [Tab] Cerrar  [Esc] Limpiar ",
         )
     } else if app.search_text.is_empty() {
-        (
-            String::from(" 🔍 Búsqueda "),
-            COLOR_COMMAND,
-            " [Tab] Cerrar ",
-        )
+        (format!(" 🔍 {} ", name), theme.command, " [Tab] Cerrar ")
     } else {
         (
             String::from(" 🔍 Sin resultados "),
-            COLOR_SEARCH_FAIL,
+            theme.search_fail,
             " [Esc] Limpiar  [Tab] Cerrar ",
         )
     };
+    // Con resultados de todo el árbol, se indica cómo ir a su lugar
+    let subtitle = match app.keymap.key_label(Action::Locate) {
+        Some(key) if app.global_search && result_count > 0 && !app.search_text.is_empty() => {
+            format!(" [{}] Ir {}", key, subtitle)
+        }
+        _ => subtitle.to_string(),
+    };
 
     let input_panel = Paragraph::new(app.search_text.as_str())
         .block(
@@ -280,25 +543,97 @@ fn render_search_bar(f: &mut Frame, app: &App, area: Rect) {
                 .title_alignment(Alignment::Center)
                 .title_bottom(Line::from(subtitle).right_aligned())
                 .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
+                .border_set(theme.border_set)
                 .border_style(Style::default().fg(border_color)),
         )
-        .style(Style::default().fg(COLOR_COMMAND));
+        .style(Style::default().fg(theme.command));
 
     let cursor_x = area
         .x
-        .saturating_add(app.search_text.chars().count() as u16)
+        .saturating_add(app.search_text.width() as u16)
         .saturating_add(1);
     f.set_cursor_position((cursor_x, area.y + 1));
     f.render_widget(input_panel, area);
 }
 
+/// Qué hará Enter sobre `item`: una marca (`$` para los comandos) y el texto
+/// de la barra inferior.
+fn enter_preview(item: &MenuItem, theme: &Theme) -> (String, String) {
+    let submenu = theme.submenu_symbol.clone();
+    match &item.action {
+        MenuAction::Execute(_) => ("$".to_string(), app::command_line(item).unwrap_or_default()),
+        MenuAction::Quit => (theme.quit_symbol.clone(), "Salir de tmenu".to_string()),
+        MenuAction::OpenSubmenu(items) => (
+            submenu,
+            match items.len() {
+                1 => format!("Submenú {} (1 ítem)", item.label),
+                n => format!("Submenú {} ({} ítems)", item.label, n),
+            },
+        ),
+        MenuAction::Directory(path) => (submenu, format!("Ejecutables de {}", path.display())),
+        MenuAction::MenuFile(path) => (submenu, format!("Menú de {}", path.display())),
+        MenuAction::Dynamic { source, .. } => (submenu, format!("Opciones de: {}", source)),
+        MenuAction::Plugin(command) => (submenu, format!("Plugin: {}", command)),
+        MenuAction::SshHosts => (submenu, "Hosts de ~/.ssh".to_string()),
+        MenuAction::Script(script) => (submenu, format!("Script: {}", script.source())),
+        MenuAction::History(limit) => (submenu, format!("Últimos {} comandos", limit)),
+        MenuAction::Favorites => (submenu, "Ítems favoritos".to_string()),
+        MenuAction::Recent(limit) => (submenu, format!("Últimos {} ítems usados", limit)),
+    }
+}
+
+/// Barra inferior con lo que hará Enter. En una línea se corta con `…` y
+/// la tecla de `expand_command` (indicada a la derecha) la muestra completa.
+fn render_status_bar(f: &mut Frame, app: &App, prefix: &str, text: &str, area: Rect) {
+    let theme = &app.theme;
+    let prefix = Span::styled(format!(" {} ", prefix), Style::default().fg(theme.command));
+    let room = (area.width as usize).saturating_sub(prefix.width());
+    let mut spans = vec![prefix];
+    if app.status_expanded || text.width() <= room {
+        spans.push(Span::styled(text, Style::default().fg(theme.secondary)));
+    } else {
+        let hint = app
+            .keymap
+            .key_label(Action::ExpandCommand)
+            .map(|key| format!(" [{}] ver todo ", key))
+            .unwrap_or_default();
+        let cut = truncate_to_width(text, room.saturating_sub(hint.width()), "…");
+        spans.push(Span::styled(cut, Style::default().fg(theme.secondary)));
+        spans.push(Span::styled(hint, Style::default().fg(theme.command)));
+    }
+    let bar = Paragraph::new(Line::from(spans)).wrap(Wrap { trim: false });
+    f.render_widget(bar, area);
+}
+
+/// Renderiza el mensaje de estado en el lugar de la barra de búsqueda
+/// (solo fuera del modo búsqueda).
+fn render_status_message(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    if app.search_mode {
+        return;
+    }
+    let Some(message) = &app.status_message else {
+        return;
+    };
+
+    let panel = Paragraph::new(message.as_str())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(theme.border_set)
+                .border_style(Style::default().fg(theme.error)),
+        )
+        .style(Style::default().fg(theme.error));
+    f.render_widget(panel, area);
+}
+
 fn render_preview_popup(
     f: &mut Frame,
     app: &App,
     items: &[crate::model::MenuItem],
     menu_area: Rect,
 ) {
+    let theme = &app.theme;
     use ratatui::widgets::Clear;
 
     if !app.show_preview {
@@ -310,7 +645,7 @@ fn render_preview_popup(
         .state
         .selected()
         .and_then(|i| items.get(i))
-        .map(|item| matches!(&item.action, MenuAction::Execute(_)))
+        .map(|item| matches!(&item.action, MenuAction::Execute(cmd) if !cmd.is_empty()))
         .unwrap_or(false);
 
     if !is_executable_command {
@@ -322,13 +657,15 @@ fn render_preview_popup(
         .selected()
         .and_then(|i| items.get(i))
         .and_then(|item| match &item.action {
-            MenuAction::Execute(cmd) => Some(format!("$ {}", cmd)),
+            MenuAction::Execute(cmd) => {
+                Some(format!("$ {}{}", cmd, steps_suffix(item.steps.len())))
+            }
             _ => None,
         })
         .unwrap_or_else(|| String::from("(sin selección)"));
 
     let screen = f.area();
-    let popup_w = (cmd_text.chars().count() as u16 + 6)
+    let popup_w = (cmd_text.width() as u16 + 6)
         .max(24)
         .min(screen.width.saturating_sub(4));
     let popup_h: u16 = 3; // borde top + 1 línea de texto + borde bottom
@@ -340,7 +677,7 @@ fn render_preview_popup(
 
     // Intentar colocar debajo; si no entra, colocar encima
     let popup_y = if item_y + 1 + popup_h <= screen.height {
-        item_y + 0
+        item_y
     } else {
         item_y.saturating_sub(popup_h)
     };
@@ -348,7 +685,11 @@ fn render_preview_popup(
     // Alinear horizontalmente con el menú, sin salirse de pantalla
     let popup_x = menu_area.x.min(screen.width.saturating_sub(popup_w)) + 5;
 
-    let popup_area = Rect::new(popup_x, popup_y, popup_w, popup_h);
+    // Recortar a la pantalla: el corrimiento horizontal puede dejarlo afuera
+    let popup_area = Rect::new(popup_x, popup_y, popup_w, popup_h).intersection(screen);
+    if popup_area.is_empty() {
+        return;
+    }
 
     f.render_widget(Clear, popup_area);
     let popup = Paragraph::new(cmd_text)
@@ -357,33 +698,31 @@ fn render_preview_popup(
                 .title(" Comando a ejecutar ")
                 .title_alignment(Alignment::Center)
                 .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(COLOR_COMMAND))
+                .border_set(theme.border_set)
+                .border_style(Style::default().fg(theme.command))
                 .padding(Padding::new(1, 1, 0, 0)),
         )
-        .style(Style::default().fg(COLOR_HIGHLIGHT_FG));
+        .style(Style::default().fg(theme.highlight_fg));
 
     f.render_widget(popup, popup_area);
 }
 
-/// Ventana de ayuda bloqueante con todos los atajos de teclado.
-fn render_help_modal(f: &mut Frame) {
+/// Ventana de ayuda bloqueante con el archivo del menú y todos los atajos de
+/// teclado en uso.
+fn render_help_modal(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
     use ratatui::{
         text::Span,
         widgets::{Cell, Clear, Row, Table},
     };
 
-    let shortcuts: &[(&str, &str)] = &[
-        ("↑ / ↓", "Navegar ítems (↑↓ funciona en búsqueda)"),
-        ("Enter / →", "Seleccionar / entrar al submenú"),
-        ("Esc / ←", "Volver al menú anterior / limpiar búsqueda"),
-        ("Inicio", "Ir al menú raíz"),
-        ("Tab", "Activar / cerrar búsqueda"),
-        ("Buscar", "Escribe para filtrar en vivo"),
-        ("Ctrl+Q", "Salir de la aplicación"),
-        ("F2", "Mostrar / ocultar vista previa"),
-        ("F1", "Mostrar / cerrar esta ayuda"),
-    ];
+    // Las filas salen del keymap, así muestran las teclas de `keys:`
+    let mut shortcuts = app.keymap.help_rows();
+    shortcuts.extend([
+        ("gg".to_string(), "Primer ítem (estilo vim)"),
+        ("1–9".to_string(), "El N-ésimo ítem visible"),
+        ("Buscar".to_string(), "Escribe para filtrar en vivo"),
+    ]);
 
     let rows: Vec<Row> = shortcuts
         .iter()
@@ -392,7 +731,7 @@ fn render_help_modal(f: &mut Frame) {
                 Cell::from(Span::styled(
                     format!(" › {} ", key),
                     Style::default()
-                        .fg(COLOR_COMMAND)
+                        .fg(theme.command)
                         .add_modifier(Modifier::BOLD),
                 )),
                 Cell::from(Span::raw(format!(" {} ", desc))),
@@ -400,28 +739,543 @@ fn render_help_modal(f: &mut Frame) {
         })
         .collect();
 
-    let table = Table::new(rows, [Constraint::Length(18), Constraint::Min(50)])
+    let table = Table::new(rows, [Constraint::Length(20), Constraint::Min(48)]).column_spacing(1);
+
+    let popup_w: u16 = 72;
+    // Archivo del menú + separación + atajos + bordes
+    let popup_h: u16 = shortcuts.len() as u16 + 4;
+    let area = centered_rect(popup_w, popup_h, f.area());
+
+    let close = app.keymap.key_label(Action::Help).map_or_else(
+        || " [Esc] Cerrar ".to_string(),
+        |key| format!(" [Esc] [{}] Cerrar ", key),
+    );
+    let block = Block::default()
+        .title(" Ayuda — Atajos de teclado ")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(close).right_aligned())
+        .borders(Borders::ALL)
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(theme.border));
+    let inner = block.inner(area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+
+    // La ruta se recorta por la izquierda: lo importante es el archivo
+    let path = app.menu_path.as_ref().map_or_else(
+        || "(sin archivo)".to_string(),
+        |path| path.display().to_string(),
+    );
+    let room = (inner.width as usize).saturating_sub(10);
+    // Se corta por la izquierda: el nombre del archivo es lo que importa
+    let path = if path.width() > room {
+        let reversed: String = path.chars().rev().collect();
+        let tail = truncate_to_width(&reversed, room, "…");
+        tail.chars().rev().collect()
+    } else {
+        path
+    };
+    let menu = Line::from(vec![
+        Span::styled(" Menú: ", Style::default().fg(theme.secondary)),
+        Span::raw(path),
+    ]);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    f.render_widget(Paragraph::new(menu), chunks[0]);
+    f.render_widget(table, chunks[2]);
+}
+
+/// Popup de texto: la ayuda extendida de un ítem (`?`) o una simulación. El
+/// Markdown se muestra de forma simple: títulos resaltados, viñetas y bloques
+/// de código atenuados.
+fn render_popup(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    use ratatui::widgets::{Clear, Wrap};
+
+    let Some(help) = &app.popup else { return };
+
+    let screen = f.area();
+    let width = screen.width.saturating_sub(4).min(76);
+    let height = (help.lines.len() as u16 + 2).clamp(5, screen.height.saturating_sub(4).max(5));
+    let area = centered_rect(width, height, screen);
+
+    // Se recorren todas las líneas para saber si la primera visible cae dentro
+    // de un bloque de código
+    let mut in_code = false;
+    let styled: Vec<Line> = help
+        .lines
+        .iter()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                return Line::from("");
+            }
+            if in_code {
+                return Line::from(format!("  {}", line)).style(Style::default().fg(theme.command));
+            }
+            if let Some(heading) = line.strip_prefix('#') {
+                return Line::from(heading.trim_start_matches('#').trim().to_string()).style(
+                    Style::default()
+                        .fg(theme.highlight_fg)
+                        .add_modifier(Modifier::BOLD),
+                );
+            }
+            match line
+                .trim_start()
+                .strip_prefix("- ")
+                .or_else(|| line.trim_start().strip_prefix("* "))
+            {
+                Some(rest) => {
+                    let indent = line.len() - line.trim_start().len();
+                    Line::from(format!("{}• {}", " ".repeat(indent), rest))
+                }
+                None => Line::from(line.as_str()),
+            }
+        })
+        .collect();
+    let lines: Vec<Line> = styled.into_iter().skip(help.scroll).collect();
+
+    let position = format!(
+        " {}/{} ",
+        (help.scroll + 1).min(help.lines.len()),
+        help.lines.len()
+    );
+    let block = Block::default()
+        .title(format!(" {} ", help.title))
+        .title_alignment(Alignment::Center)
+        .title(Line::from(position).right_aligned())
+        .title_bottom(Line::from(" [\u{2191}\u{2193}] Desplazar  [Esc] Cerrar ").centered())
+        .borders(Borders::ALL)
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(theme.border_accent))
+        .padding(Padding::horizontal(1));
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+/// Popup con el origen del ítem seleccionado (`s`): archivo, línea y el texto
+/// de esa línea. Solo ofrece el editor si el menú se leyó de un archivo.
+fn render_source_view(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    use ratatui::{
+        text::Span,
+        widgets::{Clear, Wrap},
+    };
+
+    let Some(view) = &app.source_view else { return };
+
+    let file = view
+        .location
+        .file
+        .as_ref()
+        .map(|file| file.display().to_string())
+        .unwrap_or_else(|| "(entrada estándar)".to_string());
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Archivo: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(file),
+        ]),
+        Line::from(vec![
+            Span::styled("Línea:   ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(view.location.line.to_string()),
+        ]),
+    ];
+    if let Some(snippet) = &view.snippet {
+        lines.push(Line::from(""));
+        lines
+            .push(Line::from(snippet.trim().to_string()).style(Style::default().fg(theme.command)));
+    }
+
+    let hint = if view.location.file.is_some() {
+        " [e] Abrir en el editor  [Esc] Cerrar "
+    } else {
+        " [Esc] Cerrar "
+    };
+    let screen = f.area();
+    let width = screen.width.saturating_sub(4).min(76);
+    let area = centered_rect(width, lines.len() as u16 + 2, screen);
+    let block = Block::default()
+        .title(format!(" {} ", view.label))
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(hint).centered())
+        .borders(Borders::ALL)
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(theme.border_accent))
+        .padding(Padding::horizontal(1));
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+/// Vista de trabajos en segundo plano (`J`): estado, etiqueta y duración de
+/// cada uno, el más reciente al final.
+fn render_jobs_panel(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    use ratatui::text::Span;
+    use ratatui::widgets::{Clear, List, ListItem};
+
+    let items: Vec<ListItem> = app
+        .jobs
+        .list()
+        .iter()
+        .map(|job| {
+            let (symbol, status, color) = match job.status {
+                JobStatus::Running => (spinner_frame(theme.ascii), "en curso", theme.command),
+                JobStatus::Finished(true) => ("✓", "ok", theme.search_success),
+                JobStatus::Finished(false) => ("✗", "falló", theme.search_fail),
+                JobStatus::Killed => ("■", "terminado", theme.secondary),
+                JobStatus::TimedOut => ("⏱", "tiempo límite", theme.search_fail),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {} ", symbol), Style::default().fg(color)),
+                Span::raw(job.item.label.clone()),
+                Span::styled(
+                    format!(
+                        "  {} · {} · {}",
+                        app.time_format.time(job.started_at),
+                        format_elapsed(job.elapsed()),
+                        status
+                    ),
+                    Style::default().fg(theme.secondary),
+                ),
+            ]))
+        })
+        .collect();
+
+    let width = app
+        .jobs
+        .list()
+        .iter()
+        .map(|job| job.item.label.width() + 34)
+        .max()
+        .unwrap_or(0)
+        .max(60) as u16;
+    let height = items.len() as u16 + 2;
+    let popup = centered_rect(width, height, area);
+
+    let list = List::new(items)
         .block(
             Block::default()
-                .title(" Ayuda — Atajos de teclado ")
+                .title(" Trabajos ")
                 .title_alignment(Alignment::Center)
-                .title_bottom(Line::from(" [Esc] [F1] Cerrar ").right_aligned())
+                .title_bottom(
+                    Line::from(" [Enter] Salida  [x] Terminar  [d] Descartar  [Esc] Cerrar ")
+                        .centered(),
+                )
                 .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(COLOR_BORDER_PRIMARY)),
+                .border_set(theme.border_set)
+                .border_style(Style::default().fg(theme.border_accent)),
         )
-        .column_spacing(1);
+        .highlight_style(
+            Style::default()
+                .bg(theme.highlight_bg)
+                .fg(theme.highlight_fg)
+                .add_modifier(Modifier::BOLD),
+        );
 
-    let popup_w: u16 = 60;
-    let popup_h: u16 = shortcuts.len() as u16 + 2; // filas + bordes + padding
-    let area = centered_rect(popup_w, popup_h, f.area());
+    f.render_widget(Clear, popup);
+    if let Some(state) = app.jobs_view.as_mut() {
+        f.render_stateful_widget(list, popup, state);
+    }
+}
 
-    f.render_widget(Clear, area);
-    f.render_widget(table, area);
+/// Vista de árbol (`t`): cada ítem con la sangría de su nivel, los submenús
+/// con su marca de desplegado y los comandos con su línea atenuada.
+fn render_tree_panel(f: &mut Frame, app: &mut App, area: Rect) {
+    use ratatui::widgets::{Clear, List, ListItem};
+
+    let Some(view) = &app.tree_view else { return };
+    let rows = app.tree_rows(view);
+    let theme = &app.theme;
+    let (open, closed) = if theme.ascii {
+        ("-", "+")
+    } else {
+        ("▾", "▸")
+    };
+    let lines: Vec<(String, String)> = rows
+        .iter()
+        .map(|row| {
+            let marker = match &row.item.action {
+                MenuAction::OpenSubmenu(_) if row.expanded => open,
+                MenuAction::OpenSubmenu(_) => closed,
+                action if action.is_submenu() => theme.submenu_symbol.as_str(),
+                MenuAction::Quit => theme.quit_symbol.as_str(),
+                _ => " ",
+            };
+            let label = format!(
+                " {}{} {}",
+                "  ".repeat(row.path.len()),
+                marker,
+                row.item.label
+            );
+            let detail = app::command_line(&row.item).unwrap_or_default();
+            (label, detail)
+        })
+        .collect();
+    let label_w = lines
+        .iter()
+        .map(|(label, _)| label.width())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = lines
+        .into_iter()
+        .map(|(label, detail)| {
+            let pad = label_w - label.width();
+            ListItem::new(Line::from(vec![
+                Span::raw(label),
+                Span::styled(
+                    format!("{}  {}", " ".repeat(pad), detail),
+                    Style::default().fg(theme.secondary),
+                ),
+            ]))
+        })
+        .collect();
+
+    let width = area.width.saturating_sub(4).max(44);
+    let height = (items.len() as u16 + 2).min(area.height);
+    let popup = centered_rect(width, height, area);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(" {} ", app.root_title))
+                .title_alignment(Alignment::Center)
+                .title_bottom(
+                    Line::from(" [→/←] Desplegar/plegar  [*] Todo  [Enter] Ejecutar  [g] Ir  [Esc] Cerrar ")
+                        .centered(),
+                )
+                .borders(Borders::ALL)
+                .border_set(theme.border_set)
+                .border_style(Style::default().fg(theme.border_accent)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.highlight_bg)
+                .fg(theme.highlight_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    f.render_widget(Clear, popup);
+    if let Some(view) = app.tree_view.as_mut() {
+        f.render_stateful_widget(list, popup, &mut view.state);
+    }
+}
+
+/// Vista de variables de sesión (`v`): nombre, origen y valor de cada una.
+/// Con una edición en curso se agrega el campo de texto al pie.
+fn render_vars_panel(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    use ratatui::text::Span;
+    use ratatui::widgets::{Clear, List, ListItem};
+
+    const MAX_VALUE_W: usize = 40;
+
+    let name_w = app
+        .vars
+        .list()
+        .iter()
+        .map(|var| var.name.width())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = app
+        .vars
+        .list()
+        .iter()
+        .map(|var| {
+            // Las salidas capturadas pueden tener varias líneas: se muestra la primera
+            let first = var.value.lines().next().unwrap_or("");
+            let mut value = truncate_to_width(first, MAX_VALUE_W, "");
+            // Es un prefijo del valor: si es más corto, se cortó o tenía más líneas
+            if value.len() < var.value.len() {
+                value.push('…');
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!(" {}{} ", var.name, " ".repeat(name_w - var.name.width())),
+                    Style::default().fg(theme.command),
+                ),
+                Span::styled(
+                    format!("{:<7}", var.source.as_str()),
+                    Style::default().fg(theme.secondary),
+                ),
+                Span::raw(value),
+            ]))
+        })
+        .collect();
+
+    let Some(view) = app.vars_view.as_mut() else {
+        return;
+    };
+    let editing = view.editing.is_some();
+    let width = (name_w + MAX_VALUE_W + 14).max(44) as u16;
+    let height = items.len() as u16 + 2 + if editing { 3 } else { 0 };
+    let popup = centered_rect(width, height, area);
+    let hints = if editing {
+        " [Enter] Guardar  [Esc] Descartar "
+    } else {
+        " [Enter] Editar  [d] Borrar  [Esc] Cerrar "
+    };
+
+    let block = Block::default()
+        .title(" Variables de sesión ")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(hints).centered())
+        .borders(Borders::ALL)
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(theme.border_accent));
+    let inner = block.inner(popup);
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(if editing { 3 } else { 0 }),
+        ])
+        .split(inner);
+
+    let list = List::new(items).highlight_style(
+        Style::default()
+            .bg(theme.highlight_bg)
+            .fg(theme.highlight_fg)
+            .add_modifier(Modifier::BOLD),
+    );
+    f.render_stateful_widget(list, chunks[0], &mut view.state);
+
+    if let Some(input) = &view.editing {
+        let field = Paragraph::new(input.as_str()).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(theme.border_set)
+                .border_style(Style::default().fg(theme.border)),
+        );
+        f.render_widget(field, chunks[1]);
+        let cursor_x = chunks[1].x + input.width() as u16 + 1;
+        f.set_cursor_position((cursor_x, chunks[1].y + 1));
+    }
+}
+
+/// Duración corta para la vista de trabajos: `42s` o `3m07s`.
+fn format_elapsed(elapsed: std::time::Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Indicación de los pasos que siguen al comando mostrado (`(+2 pasos)`).
+fn steps_suffix(more: usize) -> String {
+    match more {
+        0 => String::new(),
+        1 => " (+1 paso)".to_string(),
+        n => format!(" (+{} pasos)", n),
+    }
+}
+
+/// Panel con la salida capturada del último comando (`execution_mode: capture`).
+/// El borde indica si el comando terminó bien o con error.
+fn render_output_pane(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let Some(output) = &app.output else { return };
+
+    let border = if output.success {
+        theme.search_success
+    } else {
+        theme.search_fail
+    };
+    let position = format!(
+        " {}/{} ",
+        (output.scroll + 1).min(output.lines.len()),
+        output.lines.len()
+    );
+    let footer = match &output.input {
+        Some(OutputInput::Search(text)) => Line::from(format!(" /{}\u{258f} ", text)),
+        Some(OutputInput::Save(text)) => Line::from(format!(" Guardar en: {}\u{258f} ", text)),
+        // Los avisos (guardado, sin coincidencias) se ven aunque el panel tape el pie
+        None => match &app.status_message {
+            Some(message) => Line::from(format!(" {} ", message)).centered(),
+            None => Line::from(
+                " [\u{2191}\u{2193}/PgUp/PgDn] Desplazar  [/] Buscar  [n/N] Siguiente  [w] Guardar  [f] Pantalla completa  [p] Pager  [Esc] Cerrar ",
+            )
+            .centered(),
+        },
+    };
+    let block = Block::default()
+        .title(format!(" {} ", output.title))
+        .title(Line::from(position).right_aligned())
+        .title_bottom(footer)
+        .borders(Borders::ALL)
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(border));
+
+    let lines: Vec<Line> = output
+        .lines
+        .iter()
+        .skip(output.scroll)
+        .map(|line| match &output.query {
+            Some(query) => highlight_matches(line, query, theme),
+            None => Line::from(line.as_str()),
+        })
+        .collect();
+    let text = if lines.is_empty() && output.scroll == 0 {
+        vec![Line::from("(sin salida)").style(Style::default().fg(theme.secondary))]
+    } else {
+        lines
+    };
+    f.render_widget(Paragraph::new(text).block(block), area);
+}
+
+/// Línea con las apariciones de `query` resaltadas, sin distinguir mayúsculas.
+fn highlight_matches<'a>(line: &'a str, query: &str, theme: &Theme) -> Line<'a> {
+    use ratatui::text::Span;
+
+    let lower = line.to_lowercase();
+    let query = query.to_lowercase();
+    // Si pasar a minúsculas cambia las longitudes, los índices no sirven
+    if query.is_empty() || lower.len() != line.len() {
+        return Line::from(line);
+    }
+    let style = Style::default()
+        .fg(theme.highlight_fg)
+        .bg(theme.highlight_bg)
+        .add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (start, _) in lower.match_indices(&query) {
+        if start < pos || !line.is_char_boundary(start) {
+            continue;
+        }
+        spans.push(Span::raw(&line[pos..start]));
+        spans.push(Span::styled(&line[start..start + query.len()], style));
+        pos = start + query.len();
+    }
+    spans.push(Span::raw(&line[pos..]));
+    Line::from(spans)
 }
 
 /// Modal de confirmación: muestra el comando y opciones Sí/No con navegación.
 fn render_confirmation_modal(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
     use ratatui::text::Span;
     use ratatui::widgets::Clear;
 
@@ -429,21 +1283,23 @@ fn render_confirmation_modal(f: &mut Frame, app: &App) {
         return;
     };
 
-    // Truncar comando muy largo
-    let cmd_text = &confirmation.cmd;
+    // Truncar comando muy largo; de una secuencia se muestra el primer paso
+    let mut steps = confirmation.cmd.lines();
+    let cmd_text = format!(
+        "{}{}",
+        steps.next().unwrap_or_default(),
+        steps_suffix(steps.count())
+    );
     let max_cmd_width = 50;
-    let cmd_display = if cmd_text.chars().count() > max_cmd_width {
-        let truncated: String = cmd_text
-            .chars()
-            .take(max_cmd_width.saturating_sub(3))
-            .collect();
-        format!("{}...", truncated)
-    } else {
-        cmd_text.clone()
-    };
+    let cmd_display = truncate_to_width(&cmd_text, max_cmd_width, "...");
 
     let popup_w: u16 = 64;
-    let popup_h: u16 = 8; // título + cmd + separador + opciones + bordes + padding
+    // Mensaje propio del ítem: hasta 3 líneas envueltas sobre el comando
+    let message_h: u16 = match &confirmation.message {
+        Some(message) => (message.width() as u16).div_ceil(popup_w - 4).clamp(1, 3),
+        None => 0,
+    };
+    let popup_h: u16 = 8 + message_h; // título + cmd + separador + opciones + bordes + padding
     let area = centered_rect(popup_w, popup_h, f.area());
 
     f.render_widget(Clear, area);
@@ -451,9 +1307,10 @@ fn render_confirmation_modal(f: &mut Frame, app: &App) {
     let inner = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(2), // línea del comando
-            Constraint::Length(1), // separador
-            Constraint::Length(3), // opciones Sí/No
+            Constraint::Length(message_h), // mensaje del ítem (opcional)
+            Constraint::Length(2),         // línea del comando
+            Constraint::Length(1),         // separador (o indicación de la frase)
+            Constraint::Length(3),         // opciones Sí/No (o campo de la frase)
         ])
         .margin(1)
         .split(area);
@@ -464,31 +1321,69 @@ fn render_confirmation_modal(f: &mut Frame, app: &App) {
         .title_alignment(Alignment::Center)
         .title_bottom(Line::from(" [Enter] Confirmar  [Esc] Cancelar ").centered())
         .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(COLOR_CONFIRMATION));
+        .border_set(theme.border_set)
+        .border_style(Style::default().fg(theme.confirmation));
     f.render_widget(block, area);
 
+    if let Some(message) = &confirmation.message {
+        let message_widget = Paragraph::new(message.as_str())
+            .style(
+                Style::default()
+                    .fg(theme.confirmation)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        f.render_widget(message_widget, inner[0]);
+    }
+
     // Mostrar el comando
-    let cmd_widget = Paragraph::new(cmd_display).style(Style::default().fg(COLOR_COMMAND));
-    f.render_widget(cmd_widget, inner[0]);
+    let cmd_widget = Paragraph::new(cmd_display).style(Style::default().fg(theme.command));
+    f.render_widget(cmd_widget, inner[1]);
+
+    // Frase requerida: en lugar de Sí/No, un campo donde escribirla
+    if let Some(text) = &confirmation.required_text {
+        let hint = Paragraph::new(format!("Escribí \"{}\" para confirmar:", text))
+            .style(Style::default().fg(theme.secondary));
+        f.render_widget(hint, inner[2]);
+
+        // El borde cambia de color cuando la frase coincide
+        let border = if confirmation.is_confirmed() {
+            theme.confirmation
+        } else {
+            theme.secondary
+        };
+        let input_widget = Paragraph::new(confirmation.input.as_str())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(theme.border_set)
+                    .border_style(Style::default().fg(border)),
+            )
+            .style(Style::default().fg(theme.command));
+        f.render_widget(input_widget, inner[3]);
+
+        let cursor_x = inner[3].x + confirmation.input.width() as u16 + 1;
+        f.set_cursor_position((cursor_x, inner[3].y + 1));
+        return;
+    }
 
     // Opciones: "[ Sí ]  [ No ]" con la selección destacada
     let si_style = if confirmation.selected == 0 {
         Style::default()
-            .bg(COLOR_HIGHLIGHT_BG)
-            .fg(COLOR_CONFIRMATION)
+            .bg(theme.highlight_bg)
+            .fg(theme.confirmation)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(COLOR_CONFIRMATION)
+        Style::default().fg(theme.confirmation)
     };
 
     let no_style = if confirmation.selected == 1 {
         Style::default()
-            .bg(COLOR_HIGHLIGHT_BG)
-            .fg(COLOR_CONFIRMATION)
+            .bg(theme.highlight_bg)
+            .fg(theme.confirmation)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(COLOR_SECONDARY)
+        Style::default().fg(theme.secondary)
     };
 
     let options = vec![
@@ -498,7 +1393,24 @@ fn render_confirmation_modal(f: &mut Frame, app: &App) {
     ];
 
     let options_widget = Paragraph::new(Line::from(options)).alignment(Alignment::Center);
-    f.render_widget(options_widget, inner[2]);
+    f.render_widget(options_widget, inner[3]);
+}
+
+/// Aviso que reemplaza a toda la interfaz cuando la terminal es más chica
+/// que el mínimo utilizable.
+fn render_too_small(f: &mut Frame, screen: Rect, theme: &Theme) {
+    let text = format!(
+        "Terminal demasiado chica: {}x{} (se necesita {}x{})",
+        screen.width, screen.height, MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT
+    );
+    let warning = Paragraph::new(text)
+        .style(Style::default().fg(theme.error))
+        .alignment(Alignment::Center)
+        .wrap(ratatui::widgets::Wrap { trim: true });
+    // Centrar verticalmente el aviso (puede ocupar varias líneas al envolverse)
+    let y = screen.height.saturating_sub(2) / 2;
+    let area = Rect::new(screen.x, screen.y + y, screen.width, screen.height - y);
+    f.render_widget(warning, area);
 }
 
 /// Calcula un Rect centrado dentro de `r` con el tamano indicado,
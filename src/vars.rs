@@ -0,0 +1,111 @@
+//! Variables de sesión: valores que tmenu recuerda mientras está abierto.
+//!
+//! Se cargan solas al responder los prompts del wizard (`{{text: ...}}`) y al
+//! ejecutar comandos en modo `capture`. Las respuestas guardadas se proponen
+//! de nuevo la próxima vez que se pide el mismo parámetro. La vista de
+//! variables (`v`) permite revisarlas, editarlas o borrarlas.
+
+/// De dónde salió el valor de una variable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VarSource {
+    /// Respuesta a un prompt del wizard
+    Prompt,
+    /// Salida de un comando ejecutado en modo `capture`
+    Output,
+}
+
+impl VarSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VarSource::Prompt => "prompt",
+            VarSource::Output => "salida",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionVar {
+    pub name: String,
+    pub value: String,
+    pub source: VarSource,
+}
+
+/// Variables de sesión, en el orden en que se definieron.
+#[derive(Default)]
+pub struct SessionVars {
+    vars: Vec<SessionVar>,
+}
+
+impl SessionVars {
+    /// Define `name`, o actualiza su valor si ya existía (conserva la posición).
+    pub fn set(&mut self, name: &str, value: &str, source: VarSource) {
+        match self.vars.iter_mut().find(|var| var.name == name) {
+            Some(var) => {
+                var.value = value.to_string();
+                var.source = source;
+            }
+            None => self.vars.push(SessionVar {
+                name: name.to_string(),
+                value: value.to_string(),
+                source,
+            }),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .find(|var| var.name == name)
+            .map(|var| var.value.as_str())
+    }
+
+    /// Cambia el valor de la variable `index` sin tocar su origen.
+    pub fn update(&mut self, index: usize, value: &str) {
+        if let Some(var) = self.vars.get_mut(index) {
+            var.value = value.to_string();
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.vars.len() {
+            self.vars.remove(index);
+        }
+    }
+
+    pub fn list(&self) -> &[SessionVar] {
+        &self.vars
+    }
+
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_updates_in_place() {
+        let mut vars = SessionVars::default();
+        vars.set("Rama", "main", VarSource::Prompt);
+        vars.set("Estado", "limpio", VarSource::Output);
+        vars.set("Rama", "dev", VarSource::Prompt);
+
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars.get("Rama"), Some("dev"));
+        assert_eq!(vars.list()[0].name, "Rama");
+
+        vars.update(1, "sucio");
+        assert_eq!(vars.get("Estado"), Some("sucio"));
+        assert_eq!(vars.list()[1].source, VarSource::Output);
+
+        vars.remove(0);
+        assert_eq!(vars.get("Rama"), None);
+        assert_eq!(vars.len(), 1);
+    }
+}
@@ -0,0 +1,549 @@
+//! Widget de ratatui que dibuja un nivel del menú, reutilizable fuera de la app.
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Margin, Rect},
+    style::{Modifier, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, List, ListItem, ListState, Padding, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, StatefulWidget, Widget,
+    },
+};
+
+use std::time::Duration;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::model::{MenuAction, MenuItem};
+use crate::schedule;
+use crate::timefmt::TimeFormat;
+
+/// Cuadros del indicador de trabajo en segundo plano.
+const SPINNER: [&str; 8] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧"];
+const SPINNER_ASCII: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Cuadro del spinner según la hora, para que avance en cada redibujado.
+pub fn spinner_frame(ascii: bool) -> &'static str {
+    let millis = chrono::Local::now().timestamp_subsec_millis();
+    let frames: &[&str] = if ascii { &SPINNER_ASCII } else { &SPINNER };
+    frames[(millis / 125) as usize % frames.len()]
+}
+
+/// Corta `text` para que ocupe a lo sumo `width` columnas de la terminal
+/// (los caracteres CJK y muchos emoji ocupan dos), terminando en `ellipsis`
+/// si hubo que cortarlo. El resultado, `ellipsis` incluido, entra en `width`.
+pub fn truncate_to_width(text: &str, width: usize, ellipsis: &str) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let room = width.saturating_sub(ellipsis.width());
+    let mut used = 0;
+    let mut cut: String = text
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= room
+        })
+        .collect();
+    cut.push_str(ellipsis);
+    cut
+}
+
+/// Cantidad de ítems de un submenú del árbol, que se dibuja atenuada junto a
+/// su etiqueta (`Deploy (12)`). `None` en los comandos y en los submenús
+/// generados (`dir:`, `list:`…), que no se conocen hasta abrirlos.
+pub fn child_count(item: &MenuItem) -> Option<usize> {
+    match &item.action {
+        MenuAction::OpenSubmenu(children) => Some(children.len()),
+        _ => None,
+    }
+}
+
+/// Lista de ítems de un nivel del menú con el mismo aspecto que `tmenu`:
+/// marca de submenú (`▶`) con la cantidad de ítems de los submenús del
+/// árbol, marca de salida (`✗`), título centrado y ayuda al pie.
+/// Si los ítems no entran en el área, la lista acompaña a la selección y se
+/// muestran una barra de desplazamiento y la posición (`12/60`).
+///
+/// La selección vive en un `ListState` que maneja quien lo embebe, así que el
+/// widget se puede dibujar en cualquier área de otra aplicación ratatui:
+///
+/// ```ignore
+/// let widget = TmenuWidget::new(&items).title("Deploy");
+/// frame.render_stateful_widget(widget, area, &mut list_state);
+/// ```
+pub struct TmenuWidget<'a> {
+    items: &'a [MenuItem],
+    title: &'a str,
+    hint: &'a str,
+    border_style: Style,
+    highlight_style: Style,
+    highlight_symbol: &'a str,
+    submenu_symbol: &'a str,
+    quit_symbol: &'a str,
+    border_set: border::Set<'a>,
+    ascii: bool,
+    padding: u16,
+    cooldowns: &'a [Option<Duration>],
+    running: &'a [bool],
+    disabled: &'a [bool],
+    badges: &'a [Option<(&'a str, Style)>],
+    locations: &'a [String],
+    time_format: Option<&'a TimeFormat>,
+    columns: usize,
+}
+
+impl<'a> TmenuWidget<'a> {
+    pub fn new(items: &'a [MenuItem]) -> Self {
+        TmenuWidget {
+            items,
+            title: "",
+            hint: "",
+            border_style: Style::default(),
+            highlight_style: Style::default().add_modifier(Modifier::REVERSED),
+            highlight_symbol: " \u{27a4} ", // flecha
+            submenu_symbol: "\u{25b6}",
+            quit_symbol: "\u{2717}", // ✗ símbolo de salida
+            border_set: border::ROUNDED,
+            ascii: false,
+            padding: 1,
+            cooldowns: &[],
+            running: &[],
+            disabled: &[],
+            badges: &[],
+            locations: &[],
+            time_format: None,
+            columns: 1,
+        }
+    }
+
+    /// Formato de la próxima ejecución de los ítems programados.
+    /// Sin indicarlo se usa el del locale.
+    pub fn time_format(mut self, format: &'a TimeFormat) -> Self {
+        self.time_format = Some(format);
+        self
+    }
+
+    /// Título centrado en el borde superior.
+    pub fn title(mut self, title: &'a str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Texto de ayuda alineado a la derecha en el borde inferior.
+    pub fn hint(mut self, hint: &'a str) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn border_style(mut self, style: Style) -> Self {
+        self.border_style = style;
+        self
+    }
+
+    pub fn highlight_style(mut self, style: Style) -> Self {
+        self.highlight_style = style;
+        self
+    }
+
+    /// Marca del ítem seleccionado (por defecto ` ➤ `).
+    pub fn highlight_symbol(mut self, symbol: &'a str) -> Self {
+        self.highlight_symbol = symbol;
+        self
+    }
+
+    /// Marcas de los submenús (`▶`) y de los ítems que salen (`✗`).
+    pub fn markers(mut self, submenu: &'a str, quit: &'a str) -> Self {
+        self.submenu_symbol = submenu;
+        self.quit_symbol = quit;
+        self
+    }
+
+    /// Caracteres del borde (por defecto `border::ROUNDED`).
+    pub fn border_set(mut self, border_set: border::Set<'a>) -> Self {
+        self.border_set = border_set;
+        self
+    }
+
+    /// Spinner, barra de desplazamiento e íconos de programación y cooldown
+    /// en ASCII, para terminales sin esos glifos. Las marcas y el borde se
+    /// eligen aparte.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Líneas vacías arriba y abajo de los ítems (por defecto una).
+    pub fn padding(mut self, padding: u16) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Tiempo restante de cooldown de cada ítem, en el mismo orden que los ítems:
+    /// los que lo tienen se dibujan atenuados con la cuenta regresiva.
+    pub fn cooldowns(mut self, cooldowns: &'a [Option<Duration>]) -> Self {
+        self.cooldowns = cooldowns;
+        self
+    }
+
+    /// Qué ítems tienen un trabajo en segundo plano en curso, en el mismo orden
+    /// que los ítems: se marcan con un spinner.
+    pub fn running(mut self, running: &'a [bool]) -> Self {
+        self.running = running;
+        self
+    }
+
+    /// Qué ítems no se pueden elegir ahora, en el mismo orden que los ítems:
+    /// se dibujan atenuados.
+    pub fn disabled(mut self, disabled: &'a [bool]) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Estado de cada ítem (`✓`, `active`) con su estilo, en el mismo orden
+    /// que los ítems: se dibuja después de la etiqueta.
+    pub fn badges(mut self, badges: &'a [Option<(&'a str, Style)>]) -> Self {
+        self.badges = badges;
+        self
+    }
+
+    /// Submenú donde está cada ítem (`Docker › Logs`), en el mismo orden que
+    /// los ítems: se dibuja atenuado después de la etiqueta. Vacío en los
+    /// ítems del nivel raíz.
+    pub fn locations(mut self, locations: &'a [String]) -> Self {
+        self.locations = locations;
+        self
+    }
+
+    /// Reparte los ítems en `columns` columnas del mismo ancho, de arriba
+    /// abajo y luego a la derecha (como `ls`). El offset del `ListState` es
+    /// entonces el primer ítem de la primera columna visible.
+    pub fn columns(mut self, columns: usize) -> Self {
+        self.columns = columns.max(1);
+        self
+    }
+}
+
+impl StatefulWidget for TmenuWidget<'_> {
+    type State = ListState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut ListState) {
+        let now = chrono::Local::now().naive_local();
+        let spinner = spinner_frame(self.ascii);
+        let (schedule_icon, cooldown_icon) = if self.ascii {
+            ("@", "~")
+        } else {
+            ("\u{23f1}", "\u{23f3}")
+        };
+        let default_format;
+        let time_format = match self.time_format {
+            Some(format) => format,
+            None => {
+                default_format = TimeFormat::default();
+                &default_format
+            }
+        };
+        // Con alguna tecla de acceso rápido, las etiquetas se alinean dejando su lugar
+        let hotkeys = self.items.iter().any(|item| item.hotkey.is_some());
+        let lines: Vec<(Line, Style)> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let symbol = match item.action {
+                    ref action if action.is_submenu() => self.submenu_symbol,
+                    MenuAction::Quit => self.quit_symbol,
+                    _ => "",
+                };
+                let mut spans = Vec::new();
+                match item.hotkey {
+                    Some(key) => spans.push(Span::styled(
+                        format!(" [{}]", key),
+                        Style::default().add_modifier(Modifier::DIM),
+                    )),
+                    None if hotkeys => spans.push(Span::raw("    ")),
+                    None => {}
+                }
+                spans.push(Span::raw(format!(" {}", item.label)));
+                if let Some(count) = child_count(item) {
+                    spans.push(Span::styled(
+                        format!(" ({})", count),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                if !symbol.is_empty() {
+                    spans.push(Span::raw(format!(" {}", symbol)));
+                }
+                if let Some((badge, style)) = self.badges.get(i).copied().flatten() {
+                    spans.push(Span::raw("  "));
+                    spans.push(Span::styled(badge, style));
+                }
+                if let Some(location) = self.locations.get(i).filter(|l| !l.is_empty()) {
+                    spans.push(Span::styled(
+                        format!("  {}", location),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                if self.running.get(i).copied().unwrap_or(false) {
+                    spans.push(Span::raw(format!("  {}", spinner)));
+                }
+                // Ítems programados: próxima ejecución atenuada junto a la etiqueta
+                if let Some(next) = item
+                    .schedule
+                    .as_deref()
+                    .and_then(|schedule| schedule::next_run_label(schedule, now, time_format))
+                {
+                    spans.push(Span::styled(
+                        format!("  {} {}", schedule_icon, next),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                match self.cooldowns.get(i).copied().flatten() {
+                    Some(remaining) => {
+                        spans.push(Span::raw(format!(
+                            "  {} {}s",
+                            cooldown_icon,
+                            remaining.as_secs() + 1
+                        )));
+                        (
+                            Line::from(spans),
+                            Style::default().add_modifier(Modifier::DIM),
+                        )
+                    }
+                    None if self.disabled.get(i).copied().unwrap_or(false) => (
+                        Line::from(spans),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    None => (Line::from(spans), Style::default()),
+                }
+            })
+            .collect();
+
+        let mut block = Block::default()
+            .title_alignment(Alignment::Center)
+            .borders(Borders::ALL)
+            .border_set(self.border_set)
+            .border_style(self.border_style)
+            .padding(Padding::new(0, 0, self.padding, self.padding));
+        if !self.title.is_empty() {
+            block = block.title(format!(" {} ", self.title));
+        }
+        if !self.hint.is_empty() {
+            block = block.title_bottom(Line::from(self.hint).right_aligned());
+        }
+        // Filas para ítems: sin los bordes ni el relleno de arriba y abajo
+        let rows = area.height.saturating_sub(2 + 2 * self.padding) as usize;
+        let overflow = self.items.len() > rows * self.columns;
+        if overflow {
+            let position = state.selected().map_or(0, |i| i + 1);
+            block = block.title_bottom(format!(" {}/{} ", position, self.items.len()));
+        }
+
+        if self.columns > 1 {
+            let inner = block.inner(area);
+            block.render(area, buf);
+            self.render_grid(&lines, inner, buf, state);
+            return;
+        }
+
+        let list_items: Vec<ListItem> = lines
+            .into_iter()
+            .map(|(line, style)| ListItem::new(line).style(style))
+            .collect();
+        let list = List::new(list_items)
+            .block(block)
+            .highlight_style(self.highlight_style)
+            .highlight_symbol(self.highlight_symbol);
+
+        StatefulWidget::render(list, area, buf, state);
+
+        if overflow {
+            // La barra va sobre el borde derecho, a la altura de los ítems
+            let mut scrollbar = ScrollbarState::new(self.items.len() - rows)
+                .viewport_content_length(rows)
+                .position(state.offset());
+            let mut scrollbar_widget = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None);
+            if self.ascii {
+                scrollbar_widget = scrollbar_widget.thumb_symbol("#").track_symbol(Some("|"));
+            }
+            scrollbar_widget.style(self.border_style).render(
+                area.inner(Margin::new(0, 1 + self.padding)),
+                buf,
+                &mut scrollbar,
+            );
+        }
+    }
+}
+
+impl TmenuWidget<'_> {
+    /// Dibuja las líneas en columnas dentro de `inner`, desplazando las
+    /// columnas visibles para que la seleccionada siempre entre.
+    fn render_grid(
+        &self,
+        lines: &[(Line, Style)],
+        inner: Rect,
+        buf: &mut Buffer,
+        state: &mut ListState,
+    ) {
+        let rows = (inner.height as usize).max(1);
+        let column_width = inner.width / self.columns as u16;
+        let symbol_width = self.highlight_symbol.width() as u16;
+
+        let mut first = state.offset() / rows;
+        if let Some(selected) = state.selected().map(|i| i / rows) {
+            first = first
+                .min(selected)
+                .max((selected + 1).saturating_sub(self.columns));
+        }
+        *state.offset_mut() = first * rows;
+
+        for (i, (line, style)) in lines.iter().enumerate().skip(first * rows) {
+            let column = (i / rows - first) as u16;
+            if column as usize >= self.columns {
+                break;
+            }
+            let cell = Rect::new(
+                inner.x + column * column_width,
+                inner.y + (i % rows) as u16,
+                column_width,
+                1,
+            );
+            let selected = state.selected() == Some(i);
+            let style = if selected {
+                style.patch(self.highlight_style)
+            } else {
+                *style
+            };
+            buf.set_style(cell, style);
+            if selected {
+                buf.set_stringn(
+                    cell.x,
+                    cell.y,
+                    self.highlight_symbol,
+                    cell.width as usize,
+                    style,
+                );
+            }
+            let x = cell.x + symbol_width.min(cell.width);
+            buf.set_line(x, cell.y, line, cell.right().saturating_sub(x));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_widget_renders_labels_and_markers() {
+        let items = vec![
+            MenuItem::new(
+                "Git",
+                MenuAction::OpenSubmenu(Rc::new(vec![
+                    MenuItem::new("Status", MenuAction::Execute("git status".into())),
+                    MenuItem::new("Log", MenuAction::Execute("git log".into())),
+                ])),
+            ),
+            MenuItem::new("Salir", MenuAction::Quit),
+        ];
+        let mut state = ListState::default();
+        state.select(Some(0));
+
+        let area = Rect::new(0, 0, 20, 6);
+        let mut buf = Buffer::empty(area);
+        TmenuWidget::new(&items)
+            .title("Menu")
+            .render(area, &mut buf, &mut state);
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert!(rows[0].contains(" Menu "));
+        assert!(rows[2].contains("Git (2) \u{25b6}"));
+        assert!(rows[2].contains("\u{27a4}"));
+        assert!(rows[3].contains("Salir \u{2717}"));
+    }
+
+    #[test]
+    fn test_widget_scrolls_with_the_selection() {
+        let items: Vec<MenuItem> = (1..=10)
+            .map(|i| MenuItem::new(format!("Item {}", i), MenuAction::Execute("true".into())))
+            .collect();
+        let mut state = ListState::default();
+        state.select(Some(9));
+
+        let area = Rect::new(0, 0, 24, 8);
+        let mut buf = Buffer::empty(area);
+        TmenuWidget::new(&items).render(area, &mut buf, &mut state);
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        // Cuatro filas visibles: la última es la seleccionada
+        assert!(rows[2].contains("Item 7"));
+        assert!(rows[5].contains("Item 10"));
+        assert!(rows[7].contains(" 10/10 "));
+        // La barra está abajo del todo: sus primeras filas quedan vacías
+        let bar: Vec<&str> = (2..6).map(|y| buf[(23, y)].symbol()).collect();
+        assert_ne!(bar[0], bar[3]);
+    }
+
+    #[test]
+    fn test_widget_renders_a_grid() {
+        let items: Vec<MenuItem> = (1..=7)
+            .map(|i| MenuItem::new(format!("Item {}", i), MenuAction::Execute("true".into())))
+            .collect();
+        let mut state = ListState::default();
+        state.select(Some(6));
+
+        // Tres filas y dos columnas visibles: la tercera columna obliga a desplazar
+        let area = Rect::new(0, 0, 30, 7);
+        let mut buf = Buffer::empty(area);
+        TmenuWidget::new(&items)
+            .columns(2)
+            .render(area, &mut buf, &mut state);
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(state.offset(), 3);
+        assert!(rows[2].contains("Item 4"));
+        assert!(rows[2].contains("\u{27a4}  Item 7"));
+        assert!(rows[4].contains("Item 6"));
+        assert!(rows[6].contains(" 7/7 "));
+    }
+
+    #[test]
+    fn test_truncate_to_width_counts_terminal_columns() {
+        assert_eq!(truncate_to_width("Configuración", 20, "…"), "Configuración");
+        assert_eq!(truncate_to_width("Configuración", 8, "…"), "Configu…");
+        // Cada ideograma ocupa dos columnas: no se parte uno a la mitad
+        assert_eq!(truncate_to_width("日本語のメニュー", 7, "…"), "日本語…");
+        assert_eq!(truncate_to_width("🚀 Deploy", 5, "..."), "🚀...");
+        assert_eq!(truncate_to_width("abc", 2, ""), "ab");
+    }
+
+    #[test]
+    fn test_widget_renders_hotkeys() {
+        let mut deploy = MenuItem::new("Deploy", MenuAction::Execute("./deploy".into()));
+        deploy.hotkey = Some('2');
+        let items = vec![deploy, MenuItem::new("Salir", MenuAction::Quit)];
+        let mut state = ListState::default();
+
+        let area = Rect::new(0, 0, 24, 6);
+        let mut buf = Buffer::empty(area);
+        TmenuWidget::new(&items).render(area, &mut buf, &mut state);
+
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert!(rows[2].contains("[2] Deploy"));
+        // Sin tecla propia la etiqueta queda alineada con las demás
+        let column = |row: &str, label: &str| row.find(label).unwrap();
+        assert_eq!(column(&rows[2], "Deploy"), column(&rows[3], "Salir"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Tamaño y ubicación del menú en la pantalla, configurables en la sección
+//! `[window]` de `config.toml`:
+//!
+//! ```toml
+//! [window]
+//! width = "60%"
+//! min_width = 40
+//! max_width = 100
+//! anchor = "top"
+//! ```
+//!
+//! Sin `width`, el menú es tan ancho como el ítem más largo más `extra_width`
+//! columnas (bordes, marcas y margen); sin `height`, tan alto como los ítems
+//! del nivel. Los anchos y altos se dan en celdas (`80`) o como porcentaje de
+//! la pantalla (`"60%"`), y nunca pasan del tamaño de la terminal.
+use ratatui::layout::Rect;
+
+use crate::settings::{Settings, Value};
+
+/// Un ancho o alto fijo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    /// Celdas de la terminal (`80`)
+    Cells(u16),
+    /// Porcentaje del espacio disponible (`"60%"`)
+    Percent(u16),
+}
+
+impl Size {
+    /// Celdas que ocupa en un espacio de `total` celdas.
+    fn resolve(self, total: u16) -> u16 {
+        match self {
+            Size::Cells(cells) => cells,
+            Size::Percent(percent) => (u32::from(total) * u32::from(percent) / 100) as u16,
+        }
+    }
+}
+
+/// Dónde se ubica el menú en vertical; en horizontal siempre va centrado.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Tamaño y ubicación del menú principal.
+#[derive(Clone, Debug)]
+pub struct Window {
+    /// Ancho fijo; `None` = según las etiquetas
+    pub width: Option<Size>,
+    /// Alto fijo; `None` = según la cantidad de ítems
+    pub height: Option<Size>,
+    pub min_width: u16,
+    pub max_width: Option<u16>,
+    pub min_height: u16,
+    /// Columnas que se suman al ítem más ancho cuando el ancho no es fijo
+    pub extra_width: u16,
+    pub anchor: Anchor,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window {
+            width: None,
+            height: None,
+            min_width: 24,
+            max_width: None,
+            min_height: 8,
+            extra_width: 14,
+            anchor: Anchor::Center,
+        }
+    }
+}
+
+impl Window {
+    /// Valores por defecto con los de `[window]` aplicados encima. El segundo
+    /// valor describe las claves desconocidas o con valores inválidos.
+    pub fn from_settings(settings: &Settings) -> (Self, Vec<String>) {
+        let mut window = Window::default();
+        let mut errors = Vec::new();
+        for (key, value) in settings.section("window") {
+            if let Err(e) = window.set(key, value) {
+                errors.push(format!("[window] {}: {}", key, e));
+            }
+        }
+        (window, errors)
+    }
+
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        match key {
+            "width" => self.width = Some(parse_size(value)?),
+            "height" => self.height = Some(parse_size(value)?),
+            "min_width" => self.min_width = cells(value)?,
+            "max_width" => self.max_width = Some(cells(value)?),
+            "min_height" => self.min_height = cells(value)?,
+            "extra_width" => self.extra_width = cells(value)?,
+            "anchor" => {
+                self.anchor = match value {
+                    Value::Str(anchor) => match anchor.to_lowercase().as_str() {
+                        "top" => Anchor::Top,
+                        "center" => Anchor::Center,
+                        "bottom" => Anchor::Bottom,
+                        _ => return Err(format!("ubicación inválida '{}'", anchor)),
+                    },
+                    _ => return Err("se esperaba \"top\", \"center\" o \"bottom\"".to_string()),
+                }
+            }
+            _ => return Err("clave desconocida".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Ancho del menú: `natural` (el de las etiquetas sin `extra_width`) o el
+    /// fijo, acotado por `min_width` y `max_width`, en una pantalla de
+    /// `screen` columnas.
+    pub fn box_width(&self, natural: u16, screen: u16) -> u16 {
+        let width = match self.width {
+            Some(size) => size.resolve(screen),
+            None => natural.saturating_add(self.extra_width),
+        };
+        width
+            .min(self.max_width.unwrap_or(u16::MAX))
+            .max(self.min_width)
+    }
+
+    /// Alto del menú: `natural` (el de los ítems del nivel) o el fijo, al
+    /// menos `min_height`, en una pantalla de `screen` filas.
+    pub fn box_height(&self, natural: u16, screen: u16) -> u16 {
+        self.height
+            .map_or(natural, |size| size.resolve(screen))
+            .max(self.min_height)
+    }
+
+    /// Rectángulo de `width`×`height` dentro de `r`, centrado en horizontal y
+    /// ubicado en vertical según `anchor`.
+    pub fn place(&self, width: u16, height: u16, r: Rect) -> Rect {
+        let w = width.min(r.width);
+        let h = height.min(r.height);
+        let free = r.height - h;
+        let y = match self.anchor {
+            Anchor::Top => r.y,
+            Anchor::Center => r.y + free / 2,
+            Anchor::Bottom => r.y + free,
+        };
+        Rect::new(r.x + (r.width - w) / 2, y, w, h)
+    }
+}
+
+/// `80` (celdas) o `"60%"`.
+fn parse_size(value: &Value) -> Result<Size, String> {
+    let invalid = || "se esperaba un número o un porcentaje (\"60%\")".to_string();
+    match value {
+        Value::Int(_) => Ok(Size::Cells(cells(value)?)),
+        Value::Str(text) => {
+            let percent: u16 = text
+                .trim()
+                .strip_suffix('%')
+                .and_then(|n| n.trim().parse().ok())
+                .ok_or_else(invalid)?;
+            Ok(Size::Percent(percent.clamp(1, 100)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn cells(value: &Value) -> Result<u16, String> {
+    match value {
+        Value::Int(n) => Ok((*n).clamp(0, i64::from(u16::MAX)) as u16),
+        _ => Err("se esperaba un número".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_from_settings_and_placement() {
+        let screen = Rect::new(0, 0, 100, 40);
+        let window = Window::default();
+        assert_eq!(window.box_width(20, screen.width), 34);
+        assert_eq!(window.box_width(2, screen.width), 24);
+        assert_eq!(window.box_height(5, screen.height), 8);
+        assert_eq!(window.place(34, 10, screen), Rect::new(33, 15, 34, 10));
+
+        let (settings, _) = Settings::parse(
+            "[window]\n\
+             width = \"60%\"\n\
+             max_width = 50\n\
+             height = 12\n\
+             anchor = \"top\"\n\
+             min_height = \"alto\"\n\
+             shadow = true\n",
+        );
+        let (window, errors) = Window::from_settings(&settings);
+        assert_eq!(
+            errors,
+            vec![
+                "[window] min_height: se esperaba un número",
+                "[window] shadow: clave desconocida"
+            ]
+        );
+        assert_eq!(window.width, Some(Size::Percent(60)));
+        // El porcentaje queda acotado por `max_width`
+        assert_eq!(window.box_width(20, screen.width), 50);
+        assert_eq!(window.box_height(30, screen.height), 12);
+        assert_eq!(window.place(50, 12, screen), Rect::new(25, 0, 50, 12));
+
+        let (settings, _) = Settings::parse("[window]\nanchor = \"bottom\"\nextra_width = 4\n");
+        let (window, errors) = Window::from_settings(&settings);
+        assert!(errors.is_empty());
+        assert_eq!(window.box_width(30, screen.width), 34);
+        // No pasa del tamaño de la pantalla
+        assert_eq!(window.place(120, 10, screen), Rect::new(0, 30, 100, 10));
+    }
+}
@@ -0,0 +1,553 @@
+use crate::model::{
+    Columns, ExecutionMode, ItemHelp, MenuAction, MenuItem, QuickSelect, Shell, SortOrder,
+};
+use crate::parser::{DEFAULT_HISTORY_LIMIT, DEFAULT_RECENT_LIMIT, GlobalConfig};
+use std::time::Duration;
+
+/// Indentación usada por nivel al escribir un `.toon`.
+const INDENT: &str = "    ";
+
+/// Serializa un menú en memoria al formato `.toon`.
+///
+/// El resultado vuelve a parsearse al mismo árbol con `parse_toon_str`:
+/// la sección `config:` solo se escribe si tiene valores distintos de los
+/// por defecto, y cada ítem lleva explícitos sus flags (`defaults:` no se
+/// reconstruye, ya que al parsear se aplicó a cada ítem).
+pub fn write_toon(config: &GlobalConfig, title: &str, items: &[MenuItem]) -> String {
+    let mut out = String::new();
+
+    let default = GlobalConfig::default();
+    let mut options = Vec::new();
+    if config.execution_mode != ExecutionMode::Inherit {
+        options.push(format!(
+            "execution_mode: {}",
+            config.execution_mode.as_str()
+        ));
+    }
+    if config.tick_rate != default.tick_rate {
+        options.push(format!("tick_rate: {}", config.tick_rate.as_millis()));
+    }
+    if config.shell != Shell::None {
+        options.push(format!("shell: {}", config.shell.to_toon()));
+    }
+    if !config.wait {
+        options.push("wait: false".to_string());
+    }
+    if config.quick_select != QuickSelect::Run {
+        options.push(format!("quick_select: {}", config.quick_select.as_str()));
+    }
+    if config.columns != Columns::Fixed(1) {
+        options.push(format!("columns: {}", config.columns.to_toon()));
+    }
+    if !config.wrap {
+        options.push("wrap: false".to_string());
+    }
+    if !config.status_bar {
+        options.push("status_bar: false".to_string());
+    }
+    if config.tabs {
+        options.push("tabs: true".to_string());
+    }
+    if config.sort != SortOrder::File {
+        options.push(format!("sort: {}", config.sort.as_str()));
+    }
+    if !config.notify {
+        options.push("notify: false".to_string());
+    }
+    if !config.history {
+        options.push("history: false".to_string());
+    }
+    if !config.restore {
+        options.push("restore: false".to_string());
+    }
+    if config.stats {
+        options.push("stats: true".to_string());
+    }
+    if let Some(words) = &config.elevate_with {
+        let line =
+            shlex::try_join(words.iter().map(String::as_str)).unwrap_or_else(|_| words.join(" "));
+        options.push(format!("elevate_with: {}", line));
+    }
+    if let Some(words) = &config.terminal {
+        let line =
+            shlex::try_join(words.iter().map(String::as_str)).unwrap_or_else(|_| words.join(" "));
+        options.push(format!("terminal: {}", line));
+    }
+    if let Some(hook) = &config.before_exec {
+        options.push(format!("before_exec: {}", quote(hook)));
+    }
+    if let Some(hook) = &config.after_exec {
+        options.push(format!("after_exec: {}", quote(hook)));
+    }
+    if let Some(format) = &config.date_format {
+        options.push(format!("date_format: {}", quote(format)));
+    }
+    if let Some(format) = &config.time_format {
+        options.push(format!("time_format: {}", quote(format)));
+    }
+    if !options.is_empty() {
+        out.push_str("config:\n");
+        for option in options {
+            out.push_str(INDENT);
+            out.push_str(&option);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    if !config.env.is_empty() {
+        out.push_str("env:\n");
+        for (name, value) in &config.env {
+            out.push_str(&format!("{}{}: {}\n", INDENT, name, quote(value)));
+        }
+        out.push('\n');
+    }
+    if !config.keys.is_empty() {
+        out.push_str("keys:\n");
+        for (action, keys) in &config.keys {
+            out.push_str(&format!("{}{}: {}\n", INDENT, action, keys));
+        }
+        out.push('\n');
+    }
+
+    out.push_str(&format!("{}:\n", quote(title)));
+    write_items(&mut out, items, 1);
+    out
+}
+
+/// Nombre del ítem antes de `:`, con su tecla de acceso rápido (`"Deploy"[d]`).
+fn item_key(item: &MenuItem) -> String {
+    match item.hotkey {
+        Some(c) => format!("{}[{}]", quote(&item.label), c),
+        None => quote(&item.label),
+    }
+}
+
+/// El valor de un ítem en el `.toon`, sin comillas: el comando, `exit` o
+/// el origen de un submenú generado (`dir:ruta`, `list:comando`…). `None`
+/// para un submenú con sus ítems en el archivo.
+pub fn action_value(action: &MenuAction) -> Option<String> {
+    Some(match action {
+        MenuAction::OpenSubmenu(_) => return None,
+        MenuAction::Quit => "exit".to_string(),
+        MenuAction::Execute(cmd) => cmd.clone(),
+        MenuAction::Directory(dir) => format!("dir:{}", dir.display()),
+        MenuAction::MenuFile(path) => format!("menu:{}", path.display()),
+        MenuAction::Dynamic { source, .. } => format!("list:{}", source),
+        MenuAction::Plugin(command) => format!("plugin:{}", command),
+        MenuAction::SshHosts => "ssh:".to_string(),
+        MenuAction::Script(script) => format!("script:{}", script.source()),
+        MenuAction::History(DEFAULT_HISTORY_LIMIT) => "history:".to_string(),
+        MenuAction::History(limit) => format!("history:{}", limit),
+        MenuAction::Favorites => "favorites:".to_string(),
+        MenuAction::Recent(DEFAULT_RECENT_LIMIT) => "recent:".to_string(),
+        MenuAction::Recent(limit) => format!("recent:{}", limit),
+    })
+}
+
+/// Escribe recursivamente los ítems de un nivel con la indentación `depth`.
+fn write_items(out: &mut String, items: &[MenuItem], depth: usize) {
+    let indent = INDENT.repeat(depth);
+    for item in items {
+        let value = match &item.action {
+            MenuAction::OpenSubmenu(children) => {
+                let flags = item_flags(item);
+                if flags.is_empty() {
+                    out.push_str(&format!("{}{}:\n", indent, item_key(item)));
+                } else {
+                    out.push_str(&format!(
+                        "{}{}: [{}]\n",
+                        indent,
+                        item_key(item),
+                        flags.join(", ")
+                    ));
+                }
+                write_items(out, children, depth + 1);
+                continue;
+            }
+            MenuAction::Quit => "exit".to_string(),
+            action => quote(&action_value(action).unwrap_or_default()),
+        };
+
+        let flags = item_flags(item);
+        if flags.is_empty() {
+            out.push_str(&format!("{}{}: {}\n", indent, item_key(item), value));
+        } else {
+            out.push_str(&format!(
+                "{}{}: {} [{}]\n",
+                indent,
+                item_key(item),
+                value,
+                flags.join(", ")
+            ));
+        }
+        for step in &item.steps {
+            out.push_str(&format!("{}{}- {}\n", indent, INDENT, quote(step)));
+        }
+    }
+}
+
+/// Flags `clave=valor` de un ítem que difieren de los valores por defecto,
+/// tal como se escriben entre corchetes.
+pub fn item_flags(item: &MenuItem) -> Vec<String> {
+    let mut flags = Vec::new();
+    match &item.confirm_message {
+        Some(message) => flags.push(format!("confirm={}", flag_value(message))),
+        None if item.require_confirmation => flags.push("confirm=true".to_string()),
+        None => {}
+    }
+    if let Some(text) = &item.confirm_text {
+        flags.push(format!("confirm_text={}", flag_value(text)));
+    }
+    if let Some(cwd) = &item.cwd {
+        flags.push(format!("cwd={}", flag_value(&cwd.display().to_string())));
+    }
+    if let Some(shell) = &item.shell {
+        flags.push(format!("shell={}", flag_value(&shell.to_toon())));
+    }
+    if let Some(wait) = item.wait {
+        flags.push(format!("wait={}", wait));
+    }
+    if let Some(mode) = item.execution_mode {
+        flags.push(format!("execution_mode={}", mode.as_str()));
+    }
+    if item.background {
+        flags.push("background=true".to_string());
+    }
+    if let Some(detach) = item.detach {
+        flags.push(format!("detach={}", detach.as_str()));
+    }
+    if let Some(script) = &item.output_filter {
+        flags.push(format!(
+            "output_filter={}",
+            flag_value(&format!("script:{}", script.source()))
+        ));
+    }
+    if let Some(words) = &item.terminal {
+        let line =
+            shlex::try_join(words.iter().map(String::as_str)).unwrap_or_else(|_| words.join(" "));
+        flags.push(format!("terminal={}", flag_value(&line)));
+    }
+    if let Some(target) = item.tmux {
+        flags.push(format!("tmux={}", target.as_str()));
+    }
+    if item.elevate {
+        flags.push("elevate=true".to_string());
+    }
+    if item.exit_after {
+        flags.push("exit=true".to_string());
+    }
+    if item.disabled {
+        flags.push("disabled=true".to_string());
+    }
+    if item.hidden {
+        flags.push("hidden=true".to_string());
+    }
+    if let Some(condition) = &item.enabled_if {
+        flags.push(format!("enabled_if={}", flag_value(condition)));
+    }
+    if let Some(probe) = &item.status {
+        flags.push(format!("status={}", flag_value(probe)));
+    }
+    if let Some(order) = item.sort {
+        flags.push(format!("sort={}", order.as_str()));
+    }
+    if let Some(cooldown) = item.cooldown {
+        flags.push(format!("cooldown={}", format_duration(cooldown)));
+    }
+    if let Some(timeout) = item.timeout {
+        flags.push(format!("timeout={}", format_duration(timeout)));
+    }
+    if let Some(follow_up) = &item.on_success {
+        flags.push(format!("on_success={}", flag_value(&follow_up.to_toon())));
+    }
+    if let Some(follow_up) = &item.on_failure {
+        flags.push(format!("on_failure={}", flag_value(&follow_up.to_toon())));
+    }
+    if let Some(schedule) = &item.schedule {
+        flags.push(format!("schedule={}", flag_value(schedule)));
+    }
+    if !item.env.is_empty() {
+        flags.push(format!(
+            "env={}",
+            quote(&env_assignments(
+                item.env.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+            ))
+        ));
+    }
+    match &item.help {
+        Some(ItemHelp::Text(text)) => {
+            flags.push(format!("help={}", flag_value(&text.replace('\n', "\\n"))))
+        }
+        Some(ItemHelp::File(path)) => flags.push(format!(
+            "help_file={}",
+            flag_value(&path.display().to_string())
+        )),
+        None => {}
+    }
+    if let MenuAction::Dynamic {
+        template: Some(template),
+        ..
+    } = &item.action
+    {
+        flags.push(format!("run={}", flag_value(template)));
+    }
+    flags
+}
+
+/// Duración en la forma que acepta `parse_duration` (`60s` o `250ms`).
+fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+/// Asignaciones `NOMBRE=valor` de `[env=...]`, con comillas de shell en los
+/// valores que las necesiten.
+pub fn env_assignments<'a>(env: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    env.into_iter()
+        .map(|(name, value)| {
+            let value = shlex::try_quote(value).unwrap_or(value.into());
+            format!("{}={}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Envuelve un texto entre comillas para etiquetas y comandos, escapando las
+/// comillas y barras propias (`\"`, `\\`) como las lee el parser.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Cita el valor de un flag solo si contiene caracteres que lo cortarían o
+/// que el parser quitaría (comillas, espacios en los extremos).
+fn flag_value(s: &str) -> String {
+    if s.contains([',', '[', ']', '"', '=', '\\']) || s.trim() != s || s.is_empty() {
+        quote(s)
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Script;
+    use crate::parser::parse_toon_str;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_write_toon_round_trip() {
+        let source = r#"config:
+    execution_mode: clean
+    stats: true
+    shell: fish
+    wait: false
+    notify: false
+    history: false
+    restore: false
+    quick_select: select
+    columns: auto
+    wrap: false
+    status_bar: false
+    tabs: true
+    elevate_with: doas -n
+    terminal: wezterm start --
+    after_exec: "logger -t tmenu ok"
+    time_format: "%I:%M %p"
+
+env:
+    AWS_REGION: eu-west-1
+
+keys:
+    quit: ctrl+x
+    back: esc, backspace
+
+"Mi proyecto":
+    Git: [sort=file, hidden=true]
+        Estado: "git status" [schedule=0,30 * * * *, execution_mode=capture]
+        Log: "git log --oneline" [shell=bash -lc]
+        Pods: "kubectl get pods" [env="KUBECONFIG=/etc/kube/prod MSG='hola mundo'"]
+        Fetch: "git fetch" [wait=false]
+        Failover: "./failover.sh" [cooldown=2m]
+        Tests: "cargo test" [timeout=90s, on_success=notify-send 'Tests OK', on_failure=goto:Git/Log]
+        Release: "cargo test" [confirm=true]
+            - "cargo build --release"
+            - "git tag v{{text: Versión}}"
+        Build: "cargo build --release" [background=true]
+        "Editor"[e]: "nvim" [exit=true]
+        Firefox: "firefox" [detach=true]
+        Htop: "htop" [detach=terminal, terminal=kitty -e]
+        Logs: "journalctl -f" [tmux=pane, wait=false]
+        Deploy: "./deploy.sh" [tmux=window]
+        "Editar hosts": "nvim /etc/hosts" [elevate=true]
+        Rebase: "git rebase -i main" [help="Reordená los commits.\nGuardá y cerrá el editor para aplicar."]
+        Bisect: "git bisect start" [help_file=docs/bisect.md]
+        "Nuevo branch": "git checkout -b {{text: Branch name}}" [confirm=true]
+        Reset: "git reset --hard" [confirm="¿Descartar los cambios, incluso los no guardados?"]
+        Borrar: "git push origin --delete main" [confirm_text=borrar main]
+        Bisectar: "git bisect run make" [disabled=true, hidden=true]
+        Continuar: "git rebase --continue" [enabled_if=test -d .git/rebase-merge]
+    Hosts: list:cat hosts [run=ssh {line}, cwd=/tmp]
+    K8s: plugin:tmenu-k8s --context prod
+    Servidores: ssh: [tmux=window]
+    Historial: history:20 [confirm=true]
+    Recientes: history:
+    Favoritos: favorites:
+    Usados: recent:
+    Ultimos: recent:3
+    Scripts: dir:/opt/scripts [sort=alpha]
+    Nginx: "systemctl restart nginx" [status=systemctl is-active nginx]
+    Salir: exit
+"#;
+        let (config, title, items) = parse_toon_str(source, Path::new("/"));
+        let written = write_toon(&config, &title, &items);
+        let (config2, title2, items2) = parse_toon_str(&written, Path::new("/"));
+
+        assert_eq!(config2.execution_mode, ExecutionMode::Clean);
+        assert!(config2.stats);
+        assert!(!config2.wait);
+        assert!(!config2.notify);
+        assert!(!config2.history);
+        assert_eq!(
+            config2.keys,
+            vec![
+                ("quit".to_string(), "ctrl+x".to_string()),
+                ("back".to_string(), "esc, backspace".to_string())
+            ]
+        );
+        assert_eq!(config2.quick_select, QuickSelect::Select);
+        assert_eq!(config2.columns, Columns::Auto);
+        assert!(!config2.status_bar);
+        assert!(config2.tabs);
+        assert!(!config2.wrap);
+        assert_eq!(
+            config2.terminal,
+            Some(vec![
+                "wezterm".to_string(),
+                "start".to_string(),
+                "--".to_string()
+            ])
+        );
+        assert_eq!(config2.shell, Shell::parse("fish"));
+        assert_eq!(config2.env, config.env);
+        assert_eq!(
+            config2.elevate_with,
+            Some(vec!["doas".to_string(), "-n".to_string()])
+        );
+        assert_eq!(config2.time_format.as_deref(), Some("%I:%M %p"));
+        assert_eq!(config2.after_exec.as_deref(), Some("logger -t tmenu ok"));
+        assert_eq!(title2, title);
+        assert_eq!(items2, items);
+
+        // `sort:` reordena el árbol al parsear, así que se prueba aparte
+        let sorted = GlobalConfig {
+            sort: SortOrder::Alpha,
+            ..GlobalConfig::default()
+        };
+        let written = write_toon(&sorted, "Menu", &[]);
+        assert_eq!(
+            parse_toon_str(&written, Path::new("/")).0.sort,
+            SortOrder::Alpha
+        );
+    }
+
+    #[test]
+    fn test_write_toon_omits_default_config() {
+        let written = write_toon(&GlobalConfig::default(), "Menu", &[]);
+        assert_eq!(written, "\"Menu\":\n");
+    }
+
+    /// Lee `source`, lo escribe y lo vuelve a leer.
+    fn round_trip(source: &str) -> (Vec<MenuItem>, Vec<MenuItem>) {
+        let (config, title, items) = parse_toon_str(source, Path::new("/"));
+        let written = write_toon(&config, &title, &items);
+        let (_, title2, items2) = parse_toon_str(&written, Path::new("/"));
+        assert_eq!(title2, title);
+        (items.to_vec(), items2.to_vec())
+    }
+
+    #[test]
+    fn test_round_trip_quotes_in_labels_and_commands() {
+        let (before, after) = round_trip(
+            r#""Menu \"raíz\"":
+    "Decir \"hola: mundo\" \\ ya": "echo \"a:b\" 'c\d' \\"
+        - "printf \"%s\\n\" \"fin\""
+"#,
+        );
+        assert_eq!(before[0].label, r#"Decir "hola: mundo" \ ya"#);
+        assert_eq!(
+            before[0].action,
+            MenuAction::Execute(r#"echo "a:b" 'c\d' \"#.to_string())
+        );
+        assert_eq!(before[0].steps, vec![r#"printf "%s\n" "fin""#.to_string()]);
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_round_trip_flag_values_with_quotes_and_equals() {
+        let (before, after) = round_trip(
+            r#""Menu":
+    Deploy: "./deploy.sh" [confirm="¿Seguro de \"prod\"?", enabled_if="test \"$MODO\" = prod", status="echo [a], b"]
+    Borrar: "rm -rf build" [confirm_text="modo=prod"]
+"#,
+        );
+        assert_eq!(
+            before[0].confirm_message.as_deref(),
+            Some(r#"¿Seguro de "prod"?"#)
+        );
+        assert_eq!(
+            before[0].enabled_if.as_deref(),
+            Some(r#"test "$MODO" = prod"#)
+        );
+        assert_eq!(before[0].status.as_deref(), Some("echo [a], b"));
+        assert_eq!(before[1].confirm_text.as_deref(), Some("modo=prod"));
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_round_trip_command_ending_in_brackets() {
+        let (before, after) = round_trip(
+            r#""Menu":
+    Uno: "echo [x=y]"
+    Dos: "echo [x=y]" [confirm=true]
+    Tres: "true"
+        - "echo [x=y]"
+"#,
+        );
+        assert_eq!(
+            before[0].action,
+            MenuAction::Execute("echo [x=y]".to_string())
+        );
+        assert!(!before[0].require_confirmation);
+        assert!(before[1].require_confirmation);
+        assert_eq!(before[2].steps, vec!["echo [x=y]".to_string()]);
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_write_toon_round_trip_scripts() {
+        let source = "\"Menu\":\n    Ramas: script:ramas.rhai [enabled_if=script:1 < 2]\n    Fijos: \"script:range(1, 3).map(|n| `echo ${n}`)\"\n    Log: \"cat app.log\" [output_filter=output.to_upper()]\n";
+        let (config, title, items) = parse_toon_str(source, Path::new("/"));
+        assert_eq!(
+            items[0].action,
+            MenuAction::Script(Script::File(PathBuf::from("/ramas.rhai")))
+        );
+        assert_eq!(items[0].enabled_if.as_deref(), Some("script:1 < 2"));
+        assert_eq!(
+            items[1].action,
+            MenuAction::Script(Script::Inline(
+                "range(1, 3).map(|n| `echo ${n}`)".to_string()
+            ))
+        );
+        assert_eq!(
+            items[2].output_filter,
+            Some(Script::Inline("output.to_upper()".to_string()))
+        );
+
+        let written = write_toon(&config, &title, &items);
+        assert_eq!(parse_toon_str(&written, Path::new("/")).2, items);
+    }
+}
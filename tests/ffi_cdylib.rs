@@ -0,0 +1,118 @@
+//! La API C enlazada desde un programa C contra la cdylib, con el header de
+//! `include/tmenu.h` (solo con `--features ffi`, en unix y con `cc` instalado).
+#![cfg(unix)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PROGRAM: &str = r#"
+#include <stdio.h>
+#include <string.h>
+#include "tmenu.h"
+
+#define CHECK(cond) do { if (!(cond)) { fprintf(stderr, "falló: %s\n", #cond); return 1; } } while (0)
+
+int main(int argc, char **argv) {
+    CHECK(argc == 2);
+    CHECK(tmenu_load("/no/existe.toon") == NULL);
+
+    TmenuApp *app = tmenu_load(argv[1]);
+    CHECK(app != NULL);
+    CHECK(tmenu_item_count(app) == 2);
+    CHECK(tmenu_selected(app) == 0);
+
+    char *label = tmenu_item_label(app, 0);
+    CHECK(label != NULL && strcmp(label, "Sub") == 0);
+    tmenu_string_free(label);
+    CHECK(tmenu_item_label(app, 9) == NULL);
+
+    CHECK(tmenu_enter(app) == 1);
+    CHECK(tmenu_item_count(app) == 2);
+    CHECK(tmenu_run_selected(app) == 0);
+    tmenu_next(app);
+    CHECK(tmenu_selected(app) == 1);
+    CHECK(tmenu_run_selected(app) == 3);
+    tmenu_prev(app);
+    CHECK(tmenu_selected(app) == 0);
+    CHECK(tmenu_enter(app) == 0);
+    CHECK(tmenu_back(app) == 1);
+    CHECK(tmenu_back(app) == 0);
+
+    tmenu_free(app);
+    tmenu_free(NULL);
+    return 0;
+}
+"#;
+
+/// Compila la cdylib con `ffi`, como indica `include/tmenu.h`, en un
+/// directorio propio y retorna la carpeta donde quedó, para no mezclarla con
+/// el build de `cargo test`.
+fn build_cdylib() -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cdylib");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "ffi",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "no se pudo compilar la cdylib");
+    target_dir.join("debug")
+}
+
+#[test]
+fn test_c_program_links_against_the_cdylib() {
+    let dir = std::env::temp_dir().join(format!("tmenu-ffi-c-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let menu = dir.join("menu.toon");
+    fs::write(
+        &menu,
+        "\"M\":\n    Sub:\n        Bien: \"true\"\n        Mal: \"sh -c 'exit 3'\"\n    Salir: exit\n",
+    )
+    .unwrap();
+    let source = dir.join("main.c");
+    fs::write(&source, PROGRAM).unwrap();
+    let program = dir.join("main");
+
+    let include = Path::new(env!("CARGO_MANIFEST_DIR")).join("include");
+    let lib_dir = build_cdylib();
+    let compiled = Command::new("cc")
+        .arg(&source)
+        .arg("-I")
+        .arg(&include)
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-ltmenu")
+        .arg("-o")
+        .arg(&program)
+        .output()
+        .expect("hace falta un compilador C (cc)");
+    assert!(
+        compiled.status.success(),
+        "cc: {}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+
+    // cargo agrega target/debug/deps a la ruta de bibliotecas, que le ganaría al rpath
+    let run = Command::new(&program)
+        .arg(&menu)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}